@@ -1,7 +1,13 @@
-pub mod boxless_polymorphism;
+pub mod adhesion;
+#[cfg(test)]
+mod boxless_polymorphism;
 pub mod cell;
-pub mod changes;
+pub(crate) mod changes;
 pub mod control;
 pub mod control_requests;
+pub mod genealogy;
 pub mod genome;
+pub mod invasion;
 pub mod layers;
+pub mod selection;
+pub mod tissue_multipliers;
@@ -0,0 +1,113 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+// A cell's surface adhesion proteins, as bits that children inherit, possibly mutated. Under an
+// AdhesionRule, cells recognize each other as self by how few bits their tags differ in.
+// The cell carries its tag directly rather than in its control's genome, since a control need
+// not have a genome and controls are not saved. The tag is still heritable state like a gene:
+// children inherit it with mutations, cell documents save it, and the state hashes count it
+// with the cell's genome.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AdhesionTag(u16);
+
+impl AdhesionTag {
+    pub const BITS: u32 = 16;
+
+    pub fn new(bits: u16) -> Self {
+        AdhesionTag(bits)
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    // The number of bits the tags differ in.
+    pub fn distance(self, other: AdhesionTag) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    // Flips each bit with the given chance. The flips depend only on the seed, so that runs
+    // are repeatable.
+    pub fn mutated(self, bit_flip_rate: f64, seed: u64) -> Self {
+        if bit_flip_rate <= 0.0 {
+            return self;
+        }
+        let mut rng = Pcg64Mcg::seed_from_u64(seed);
+        let mut bits = self.0;
+        for bit in 0..Self::BITS {
+            if rng.gen::<f64>() < bit_flip_rate {
+                bits ^= 1 << bit;
+            }
+        }
+        AdhesionTag(bits)
+    }
+}
+
+// Limits adhesion bonds to cells whose tags differ in at most max_distance bits, so that
+// aggregates can tell self from non-self and evolve boundaries between tissues. Children's
+// tags are their parents' with each bit flipped with a chance of bit_flip_rate. Bonds between
+// parents and their buds form regardless of tags.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdhesionRule {
+    pub max_distance: u32,
+    pub bit_flip_rate: f64,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl AdhesionRule {
+    pub fn allows(&self, tag1: AdhesionTag, tag2: AdhesionTag) -> bool {
+        tag1.distance(tag2) <= self.max_distance
+    }
+
+    // The tag of a newborn cell, whose ID seeds its mutations.
+    pub fn child_tag(&self, parent_tag: AdhesionTag, child_id: u64) -> AdhesionTag {
+        parent_tag.mutated(self.bit_flip_rate, self.seed ^ child_id.rotate_left(32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_counts_differing_bits() {
+        assert_eq!(
+            AdhesionTag::new(0b1010).distance(AdhesionTag::new(0b0110)),
+            2
+        );
+    }
+
+    #[test]
+    fn rule_allows_tags_within_max_distance() {
+        let rule = AdhesionRule {
+            max_distance: 1,
+            bit_flip_rate: 0.0,
+            seed: 0,
+        };
+
+        assert!(rule.allows(AdhesionTag::new(0b1), AdhesionTag::new(0b0)));
+        assert!(!rule.allows(AdhesionTag::new(0b11), AdhesionTag::new(0b0)));
+    }
+
+    #[test]
+    fn child_tags_mutate_repeatably() {
+        let rule = AdhesionRule {
+            max_distance: 0,
+            bit_flip_rate: 0.5,
+            seed: 7,
+        };
+        let parent_tag = AdhesionTag::new(0);
+
+        assert_eq!(rule.child_tag(parent_tag, 3), rule.child_tag(parent_tag, 3));
+        assert_ne!(rule.child_tag(parent_tag, 3), parent_tag);
+    }
+
+    #[test]
+    fn zero_rate_keeps_tag() {
+        let tag = AdhesionTag::new(0xbeef);
+        assert_eq!(tag.mutated(0.0, 1), tag);
+    }
+}
@@ -1,46 +1,207 @@
+use crate::biology::adhesion::AdhesionTag;
 use crate::biology::changes::*;
 use crate::biology::control::*;
 use crate::biology::control_requests::*;
 use crate::biology::layers::*;
+use crate::biology::tissue_multipliers::TissueMultipliers;
+use crate::document::*;
 use crate::environment::local_environment::*;
 use crate::physics::newtonian::*;
 use crate::physics::quantities::*;
 use crate::physics::shapes::*;
 use crate::physics::sortable_graph::*;
 use crate::physics::util::*;
+use crate::state_hash::StateHasher;
 use evo_domain_derive::*;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::f64::consts::PI;
 use std::ptr;
 
+// Identifies a cell for as long as it lives, unlike its node handle, which changes when
+// other cells are removed. The world assigns it when the cell is added.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CellId(u64);
+
+impl CellId {
+    pub const UNSET: CellId = CellId(0);
+
+    pub fn new(value: u64) -> Self {
+        CellId(value)
+    }
+
+    pub fn value(self) -> u64 {
+        self.0
+    }
+}
+
+// A dormant cell neither metabolizes nor runs its control. It hatches into a normal cell when
+// its light reaches `hatch_light_intensity` or when `remaining_ticks` runs out, whichever is
+// first; a condition that is None never triggers.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spore {
+    pub hatch_light_intensity: Option<f64>,
+    pub remaining_ticks: Option<u32>,
+}
+
+impl Spore {
+    // Zero means no condition, and with no conditions there is no spore.
+    pub fn from_hatch_conditions(hatch_light_intensity: f64, hatch_ticks: u32) -> Option<Spore> {
+        if hatch_light_intensity <= 0.0 && hatch_ticks == 0 {
+            return None;
+        }
+        Some(Spore {
+            hatch_light_intensity: if hatch_light_intensity > 0.0 {
+                Some(hatch_light_intensity)
+            } else {
+                None
+            },
+            remaining_ticks: if hatch_ticks > 0 {
+                Some(hatch_ticks)
+            } else {
+                None
+            },
+        })
+    }
+
+    fn tick_and_check_hatching(&mut self, light_intensity: f64) -> bool {
+        if let Some(remaining_ticks) = &mut self.remaining_ticks {
+            *remaining_ticks = remaining_ticks.saturating_sub(1);
+            if *remaining_ticks == 0 {
+                return true;
+            }
+        }
+        self.hatch_light_intensity
+            .is_some_and(|hatch_intensity| light_intensity >= hatch_intensity)
+    }
+}
+
+// Lets small or low-energy cells run their controls only every `interval` ticks, repeating
+// their last requests in between, to save time in huge populations. A cell is small if its
+// radius is below `max_radius`, and low-energy if its energy is below `max_energy`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ControlLod {
+    pub interval: u64,
+    pub max_radius: Length,
+    pub max_energy: BioEnergy,
+}
+
+impl ControlLod {
+    // Cells take turns by ID, so that they don't all run on the same ticks.
+    fn runs_control(&self, cell: &Cell) -> bool {
+        let is_low_detail = cell.radius < self.max_radius || cell.energy < self.max_energy;
+        !is_low_detail || (cell.age + cell.id.value()).is_multiple_of(self.interval)
+    }
+}
+
+// Where a budding cell puts its child, which always touches the parent's surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChildPlacementStrategy {
+    // At the budding angle the control requested
+    BuddingAngle,
+    // At the budding angle, turned a random amount up to `max_angle` either way. The turn
+    // depends only on the seed and the parent, so that runs are repeatable.
+    Jitter { max_angle: Angle, seed: u64 },
+    // Opposite the side where the parent's overlaps with other cells and walls push it from, or
+    // at the budding angle if nothing overlaps it
+    AwayFromCrowding,
+}
+
+// A user label for tracking a cell, e.g. a founder whose descendants compete with others'.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellTag {
+    pub name: String,
+    // Whether the cell's children get the tag too
+    pub inherited: bool,
+}
+
 #[allow(clippy::vec_box)]
 #[derive(Debug, GraphNode, HasLocalEnvironment, NewtonianBody)]
 pub struct Cell {
     graph_node_data: GraphNodeData,
+    id: CellId,
+    // The cell this one budded from, if any. Not saved, since loading assigns new IDs.
+    parent_id: Option<CellId>,
     radius: Length,
+    // The length of the cell's axis, along its heading, if its layers stretch it into a capsule
+    elongation: Length,
     newtonian_state: NewtonianState,
     environment: LocalEnvironment,
     layers: Vec<CellLayer>, // TODO array? smallvec?
     control: Box<dyn CellControl>,
     energy: BioEnergy,
+    // Identifies the founder the cell descends from; children inherit it. Zero means none.
+    lineage: u32,
+    spore: Option<Spore>,
+    tags: Vec<CellTag>,
+    // Children inherit it, mutated under the world's adhesion rule
+    adhesion_tag: AdhesionTag,
+    // The layer whose health is the chance that each of the control's requests is carried out,
+    // so that damage to it degrades the cell's behavior. None means every request is.
+    neural_layer_index: Option<usize>,
+    // Seeds the next tick's neural dropouts and is moved on each time it is used. Saved, so that
+    // a resumed run drops the same requests. Set from the cell's ID if it has none when it gets
+    // one, and from its parent's seed for a child.
+    dropout_seed: Option<u64>,
+    // Which way the cell faces. Thruster layers turn it. Children face their parent's heading
+    // plus an offset the parent chooses, so that the parts of a structure can share a frame of
+    // reference.
+    heading: Angle,
+    // How to combine the control's requests with the same ID. Not saved, like the control.
+    merge_policies: RequestMergePolicies,
+    // Overrides the world's child placement; children inherit it. Not saved.
+    child_placement: Option<ChildPlacementStrategy>,
+    // What the cell's energy went to during the last tick
+    energy_budget: EnergyBudget,
+    // Ticks since the cell was added to its world. Not saved.
+    age: u64,
     selected: bool,
+    // The control's most recent requests, oldest first, kept only while the cell is
+    // selected, for inspecting it
+    request_history: VecDeque<RecordedControlRequest>,
+    // The control's last requests, kept only under a ControlLod, for repeating on the ticks
+    // when the control does not run
+    held_requests: Option<Vec<ControlRequest>>,
 }
 
 impl Cell {
+    const DEFAULT_CHILD_LAYER_AREA: f64 = 10.0 * PI;
+    pub const REQUEST_HISTORY_LENGTH: usize = 256;
+
     pub fn new(position: Position, velocity: Velocity, mut layers: Vec<CellLayer>) -> Self {
         if layers.is_empty() {
             panic!("Cell must have at least one layer");
         }
 
-        let radius = Self::update_layer_outer_radii(&mut layers);
+        let elongation = Self::calc_elongation(&layers);
+        let radius = Self::update_layer_outer_radii(&mut layers, elongation);
         Cell {
             graph_node_data: GraphNodeData::new(),
+            id: CellId::UNSET,
+            parent_id: None,
             radius,
+            elongation,
             newtonian_state: NewtonianState::new(Self::calc_mass(&layers), position, velocity),
             environment: LocalEnvironment::new(),
             layers,
             control: Box::new(NullControl::new()),
             energy: BioEnergy::new(0.0),
+            lineage: 0,
+            spore: None,
+            tags: vec![],
+            adhesion_tag: AdhesionTag::default(),
+            neural_layer_index: None,
+            dropout_seed: None,
+            heading: Angle::ZERO,
+            merge_policies: RequestMergePolicies::new(),
+            child_placement: None,
+            energy_budget: EnergyBudget::ZERO,
+            age: 0,
             selected: false,
+            request_history: VecDeque::new(),
+            held_requests: None,
         }
     }
 
@@ -62,6 +223,44 @@ impl Cell {
         ])))
     }
 
+    pub fn from_document(document: &CellDocument) -> Self {
+        let mut cell = Self::new(
+            to_position(document.position),
+            Velocity::new(document.velocity[0], document.velocity[1]),
+            document
+                .layers
+                .iter()
+                .map(CellLayer::from_document)
+                .collect(),
+        )
+        .with_initial_energy(BioEnergy::new(document.energy))
+        .with_lineage(document.lineage)
+        .with_spore(document.spore)
+        .with_tags(document.tags.clone())
+        .with_adhesion_tag(document.adhesion_tag)
+        .with_neural_layer(document.neural_layer_index)
+        .with_heading(Angle::from_radians(document.heading));
+        cell.dropout_seed = document.dropout_seed;
+        cell
+    }
+
+    pub fn to_document(&self) -> CellDocument {
+        CellDocument {
+            position: from_position(self.position()),
+            velocity: [self.velocity().x(), self.velocity().y()],
+            energy: self.energy.value(),
+            lineage: self.lineage,
+            spore: self.spore,
+            tags: self.tags.clone(),
+            adhesion_tag: self.adhesion_tag,
+            neural_layer_index: self.neural_layer_index,
+            dropout_seed: self.dropout_seed,
+            heading: self.heading.radians(),
+            has_control: !self.control.is_null(),
+            layers: self.layers.iter().map(CellLayer::to_document).collect(),
+        }
+    }
+
     pub fn with_control(mut self, control: Box<dyn CellControl>) -> Self {
         self.control = control;
         self
@@ -77,16 +276,93 @@ impl Cell {
         self
     }
 
+    pub fn with_lineage(mut self, lineage: u32) -> Self {
+        self.lineage = lineage;
+        self
+    }
+
+    pub fn with_spore(mut self, spore: Option<Spore>) -> Self {
+        self.spore = spore;
+        self
+    }
+
+    pub fn with_tag(mut self, name: &str) -> Self {
+        self.add_tag(name);
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<CellTag>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_adhesion_tag(mut self, tag: AdhesionTag) -> Self {
+        self.adhesion_tag = tag;
+        self
+    }
+
+    pub fn with_neural_layer(mut self, layer_index: Option<usize>) -> Self {
+        if let Some(index) = layer_index {
+            assert!(index < self.layers.len(), "Invalid neural layer index");
+        }
+        self.neural_layer_index = layer_index;
+        self
+    }
+
+    pub fn with_request_merge_policy(
+        mut self,
+        layer_index: usize,
+        channel_index: usize,
+        policy: MergePolicy,
+    ) -> Self {
+        self.merge_policies
+            .set_policy(layer_index, channel_index, policy);
+        self
+    }
+
+    pub fn with_heading(mut self, heading: Angle) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    pub fn with_child_placement(mut self, placement: ChildPlacementStrategy) -> Self {
+        self.child_placement = Some(placement);
+        self
+    }
+
     pub fn spawn(&mut self, layer_area: Area) -> Self {
-        let mut layers = self
+        let layers = self
             .layers
             .iter()
             .map(|layer| layer.spawn(layer_area))
             .collect();
-        let radius = Self::update_layer_outer_radii(&mut layers);
+        self.spawn_with_layers(layers)
+    }
+
+    // A new, unplaced cell with this cell's layer sizes, energy and tags, and its control
+    // spawned as for a child, but with no parent.
+    pub fn duplicate(&mut self) -> Self {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.spawn(layer.area()))
+            .collect();
+        let mut copy = self.spawn_with_layers(layers);
+        copy.parent_id = None;
+        copy.energy = self.energy;
+        copy.tags = self.tags.clone();
+        copy
+    }
+
+    fn spawn_with_layers(&mut self, mut layers: Vec<CellLayer>) -> Self {
+        let elongation = Self::calc_elongation(&layers);
+        let radius = Self::update_layer_outer_radii(&mut layers, elongation);
         Cell {
             graph_node_data: GraphNodeData::new(),
+            id: CellId::UNSET,
+            parent_id: Some(self.id),
             radius,
+            elongation,
             newtonian_state: NewtonianState::new(
                 Self::calc_mass(&layers),
                 Position::ORIGIN,
@@ -96,22 +372,195 @@ impl Cell {
             layers,
             control: self.control.spawn(),
             energy: BioEnergy::ZERO,
+            lineage: self.lineage,
+            spore: None,
+            tags: self
+                .tags
+                .iter()
+                .filter(|tag| tag.inherited)
+                .cloned()
+                .collect(),
+            adhesion_tag: self.adhesion_tag,
+            neural_layer_index: self.neural_layer_index,
+            dropout_seed: Some(self.split_dropout_seed()),
+            heading: self.heading,
+            merge_policies: self.merge_policies.clone(),
+            child_placement: self.child_placement,
+            energy_budget: EnergyBudget::ZERO,
+            age: 0,
             selected: false,
+            request_history: VecDeque::new(),
+            held_requests: None,
         }
     }
 
+    pub fn id(&self) -> CellId {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: CellId) {
+        self.id = id;
+        self.dropout_seed.get_or_insert(id.value().rotate_left(32));
+    }
+
+    pub fn parent_id(&self) -> Option<CellId> {
+        self.parent_id
+    }
+
     pub fn layers(&self) -> &[CellLayer] {
         &self.layers
     }
 
+    pub fn neural_layer_index(&self) -> Option<usize> {
+        self.neural_layer_index
+    }
+
+    pub fn lineage(&self) -> u32 {
+        self.lineage
+    }
+
+    pub fn age(&self) -> u64 {
+        self.age
+    }
+
+    pub fn spore(&self) -> Option<Spore> {
+        self.spore
+    }
+
+    pub fn is_spore(&self) -> bool {
+        self.spore.is_some()
+    }
+
+    pub fn heading(&self) -> Angle {
+        self.heading
+    }
+
+    pub fn set_heading(&mut self, heading: Angle) {
+        self.heading = heading;
+    }
+
+    pub fn set_sensor_reading(&mut self, layer_index: usize, reading: Option<SensorReading>) {
+        self.layers[layer_index].set_sensor_reading(reading);
+    }
+
+    pub fn set_spore(&mut self, spore: Option<Spore>) {
+        self.spore = spore;
+    }
+
+    pub fn adhesion_tag(&self) -> AdhesionTag {
+        self.adhesion_tag
+    }
+
+    pub fn set_adhesion_tag(&mut self, tag: AdhesionTag) {
+        self.adhesion_tag = tag;
+    }
+
+    pub fn tags(&self) -> &[CellTag] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, name: &str) -> bool {
+        self.tags.iter().any(|tag| tag.name == name)
+    }
+
+    // Adds a tag that the cell's children inherit.
+    pub fn add_tag(&mut self, name: &str) {
+        self.add_tag_with_inheritance(name, true);
+    }
+
+    // Replaces any existing tag with the same name.
+    pub fn add_tag_with_inheritance(&mut self, name: &str, inherited: bool) {
+        self.remove_tag(name);
+        self.tags.push(CellTag {
+            name: name.to_string(),
+            inherited,
+        });
+    }
+
+    pub fn remove_tag(&mut self, name: &str) {
+        self.tags.retain(|tag| tag.name != name);
+    }
+
     pub fn energy(&self) -> BioEnergy {
         self.energy
     }
 
+    pub fn use_fast_math(&mut self) {
+        self.control.use_fast_math();
+    }
+
+    pub fn mutation_rate(&self) -> Option<f64> {
+        self.control.mutation_rate()
+    }
+
+    pub fn set_mutation_rate(&mut self, rate: f64) {
+        self.control.set_mutation_rate(rate);
+    }
+
+    // Describes the first NaN or infinite value in the cell's state, if any.
+    pub fn non_finite_state(&self) -> Option<String> {
+        let position = self.position();
+        let velocity = self.velocity();
+        if !position.x().is_finite() || !position.y().is_finite() {
+            return Some(format!("position {:?}", position));
+        }
+        if !velocity.x().is_finite() || !velocity.y().is_finite() {
+            return Some(format!("velocity {:?}", velocity));
+        }
+        if !self.energy.value().is_finite() {
+            return Some(format!("energy {}", self.energy.value()));
+        }
+        for (index, layer) in self.layers.iter().enumerate() {
+            if !layer.area().value().is_finite() || !layer.health().is_finite() {
+                return Some(format!(
+                    "layer {} area {} health {}",
+                    index,
+                    layer.area().value(),
+                    layer.health()
+                ));
+            }
+        }
+        self.control
+            .non_finite_state()
+            .map(|state| format!("control {}", state))
+    }
+
+    // Hashes the cell's heritable state: its layers, adhesion tag, and control.
+    pub fn hash_genome(&self, hasher: &mut StateHasher) {
+        for layer in &self.layers {
+            hasher.write_bytes(&serde_json::to_vec(&layer.to_document()).unwrap());
+        }
+        hasher.write_u64(self.adhesion_tag.bits() as u64);
+        self.control.hash_state(hasher);
+    }
+
+    pub fn energy_budget(&self) -> &EnergyBudget {
+        &self.energy_budget
+    }
+
     pub fn add_energy(&mut self, energy: BioEnergy) {
         self.energy += energy;
     }
 
+    // Removes up to max_energy from the cell, and returns what it removed.
+    pub fn take_energy(&mut self, max_energy: BioEnergy) -> BioEnergy {
+        let energy = max_energy.min(self.energy);
+        self.energy -= energy;
+        energy
+    }
+
+    // How the cell siphons energy from a host, if it is a parasite.
+    pub fn siphon(&self) -> Option<Siphon> {
+        self.layers.iter().find_map(|layer| layer.siphon())
+    }
+
+    // The strength of the cell's outer layer against parasites.
+    pub fn shell_strength(&self) -> f64 {
+        self.layers
+            .last()
+            .map_or(0.0, |outer_layer| outer_layer.shell_strength())
+    }
+
     pub fn is_alive(&self) -> bool {
         self.layers.iter().any(|layer| layer.is_alive())
     }
@@ -122,6 +571,13 @@ impl Cell {
 
     pub fn set_selected(&mut self, is_selected: bool) {
         self.selected = is_selected;
+        if !is_selected {
+            self.request_history.clear();
+        }
+    }
+
+    pub fn request_history(&self) -> &VecDeque<RecordedControlRequest> {
+        &self.request_history
     }
 
     pub fn set_initial_position(&mut self, position: Position) {
@@ -145,32 +601,145 @@ impl Cell {
                 <= sqr(self.radius.value())
     }
 
-    pub fn after_influences(&mut self, _changes: &mut CellChanges) {
+    pub fn after_influences(&mut self, changes: &mut CellChanges) {
+        self.after_influences_with_multipliers(changes, &TissueMultipliers::new());
+    }
+
+    // Each layer's energy gain is scaled by its tissue type's multiplier.
+    pub fn after_influences_with_multipliers(
+        &mut self,
+        _changes: &mut CellChanges,
+        tissue_multipliers: &TissueMultipliers,
+    ) {
+        self.age += 1;
+        self.energy_budget = EnergyBudget::ZERO;
+        if let Some(spore) = &mut self.spore {
+            if spore.tick_and_check_hatching(self.environment.light_intensity()) {
+                self.spore = None;
+            }
+            return;
+        }
+
+        for layer in &self.layers {
+            self.heading += layer.turn();
+        }
+        self.environment.set_heading(self.heading);
+
         let forces = self.newtonian_state.forces_mut();
         for layer in &mut self.layers {
             let (energy, force) = layer.after_influences(&self.environment);
+            let energy = energy * tissue_multipliers.get(layer.color()).energy_gain;
             self.energy += energy;
+            self.energy_budget.influences += BioEnergyDelta::new(energy.value());
             // TODO changes.energy += energy;
             forces.add_force(force);
         }
     }
 
+    // A spore makes no requests, so it lets go of all its bonds.
     pub fn run_control(&mut self, bond_requests: &mut BondRequests, changes: &mut CellChanges) {
-        let (end_energy, budgeted_control_requests) = self.get_budgeted_control_requests();
+        self.run_control_with_lod(bond_requests, changes, None, &TissueMultipliers::new());
+    }
+
+    pub fn run_control_with_lod(
+        &mut self,
+        bond_requests: &mut BondRequests,
+        changes: &mut CellChanges,
+        lod: Option<&ControlLod>,
+        tissue_multipliers: &TissueMultipliers,
+    ) {
+        if self.is_spore() {
+            return;
+        }
+        let (end_energy, budgeted_control_requests) =
+            self.get_budgeted_control_requests(lod, tissue_multipliers);
         //self._print_selected_cell_status(end_energy, &budgeted_control_requests);
         self.energy = end_energy;
         self.execute_control_requests(&budgeted_control_requests, bond_requests, changes);
+        if self.selected {
+            self.record_requests(&budgeted_control_requests);
+        }
         //self._print_selected_cell_bond_requests(bond_requests);
         self.reset_layers();
     }
 
-    fn get_budgeted_control_requests(&mut self) -> (BioEnergy, Vec<BudgetedControlRequest>) {
-        let cell_state = self.get_state_snapshot();
-        let control_requests = self.control.run(&cell_state);
-        let costed_requests = self.cost_control_requests(&control_requests);
+    fn record_requests(&mut self, requests: &[BudgetedControlRequest]) {
+        for &request in requests {
+            if self.request_history.len() == Self::REQUEST_HISTORY_LENGTH {
+                self.request_history.pop_front();
+            }
+            self.request_history.push_back(RecordedControlRequest {
+                cell_age: self.age,
+                request,
+            });
+        }
+    }
+
+    // Cells that the control LOD skips this tick leave the energy waiting in their bonds for the
+    // next tick they run.
+    pub fn claims_bond_energy(&self, lod: Option<&ControlLod>) -> bool {
+        lod.is_none_or(|lod| lod.runs_control(self))
+    }
+
+    fn get_budgeted_control_requests(
+        &mut self,
+        lod: Option<&ControlLod>,
+        tissue_multipliers: &TissueMultipliers,
+    ) -> (BioEnergy, Vec<BudgetedControlRequest>) {
+        let mut control_requests = self.get_control_requests(lod);
+        self.drop_control_requests_for_neural_damage(&mut control_requests);
+        let control_requests = self.merge_policies.merge(&control_requests);
+        let costed_requests = self.cost_control_requests(&control_requests, tissue_multipliers);
         Self::budget_control_requests(self.energy, &costed_requests)
     }
 
+    fn get_control_requests(&mut self, lod: Option<&ControlLod>) -> Vec<ControlRequest> {
+        let lod = match lod {
+            Some(lod) => lod,
+            None => return self.control.run(&self.get_state_snapshot()),
+        };
+        if let Some(held_requests) = &self.held_requests {
+            if !lod.runs_control(self) {
+                return held_requests.clone();
+            }
+        }
+        let control_requests = self.control.run(&self.get_state_snapshot());
+        self.held_requests = Some(control_requests.clone());
+        control_requests
+    }
+
+    // The dropouts depend only on the cell's own dropout seed, so that runs are repeatable however
+    // many threads run the cells, and across saves.
+    fn drop_control_requests_for_neural_damage(
+        &mut self,
+        control_requests: &mut Vec<ControlRequest>,
+    ) {
+        let health = match self.neural_layer_index {
+            Some(index) => self.layers[index].health(),
+            None => return,
+        };
+        if health >= 1.0 {
+            return;
+        }
+        let mut rng = Pcg64Mcg::seed_from_u64(self.next_dropout_seed());
+        control_requests.retain(|_| rng.gen::<f64>() < health);
+    }
+
+    // Moves the dropout seed on and returns its previous value.
+    fn next_dropout_seed(&mut self) -> u64 {
+        let seed = self.dropout_seed.unwrap_or(0);
+        self.dropout_seed = Some(Pcg64Mcg::seed_from_u64(seed).gen());
+        seed
+    }
+
+    // Moves the dropout seed on and returns the seed of a new stream, e.g. a child's, drawn
+    // after the one the seed moved on to, so that it does not replay this cell's dropouts.
+    fn split_dropout_seed(&mut self) -> u64 {
+        let mut rng = Pcg64Mcg::seed_from_u64(self.dropout_seed.unwrap_or(0));
+        self.dropout_seed = Some(rng.gen());
+        rng.gen()
+    }
+
     fn get_state_snapshot(&self) -> CellStateSnapshot {
         CellStateSnapshot {
             radius: self.radius(),
@@ -178,6 +747,7 @@ impl Cell {
             mass: self.mass(),
             center: self.center(),
             velocity: self.velocity(),
+            heading: self.heading,
             energy: self.energy(),
             layers: self.get_layer_state_snapshots(),
         }
@@ -190,18 +760,27 @@ impl Cell {
                 area: layer.area(),
                 mass: layer.mass(),
                 health: layer.health(),
+                sensor_reading: layer.sensor_reading(),
             });
         }
         result
     }
 
+    // Each request's cost is scaled by its layer's tissue type's multiplier.
     fn cost_control_requests(
         &mut self,
         control_requests: &[ControlRequest],
+        tissue_multipliers: &TissueMultipliers,
     ) -> Vec<CostedControlRequest> {
         control_requests
             .iter()
-            .map(|req| self.layers[req.layer_index()].cost_control_request(*req))
+            .map(|req| {
+                let layer = &mut self.layers[req.layer_index()];
+                let cost_multiplier = tissue_multipliers.get(layer.color()).cost;
+                layer
+                    .cost_control_request(*req)
+                    .with_cost_scaled(cost_multiplier)
+            })
             .collect()
     }
 
@@ -253,9 +832,12 @@ impl Cell {
         // TODO do healing first
         for request in budgeted_control_requests {
             let layer = &mut self.layers[request.layer_index()];
+            self.energy_budget
+                .add_request(layer.energy_use(request.channel_index()), request);
             layer.execute_control_request(*request, bond_requests, changes);
         }
-        self.radius = Self::update_layer_outer_radii(&mut self.layers);
+        self.elongation = Self::calc_elongation(&self.layers);
+        self.radius = Self::update_layer_outer_radii(&mut self.layers, self.elongation);
         self.newtonian_state.mass = Self::calc_mass(&self.layers);
     }
 
@@ -267,7 +849,7 @@ impl Cell {
         self._print_selected_cell_basics();
         self._print_selected_cell_layers();
         self._print_selected_cell_energy(end_energy);
-        self._print_selected_cell_control_requests(&budgeted_control_requests);
+        self._print_selected_cell_control_requests(budgeted_control_requests);
     }
 
     fn _print_selected_cell_basics(&self) {
@@ -324,19 +906,79 @@ impl Cell {
         }
     }
 
+    // A zero `layer_area` gets the default, which the child doesn't pay for. Otherwise the child
+    // pays to grow its layers out of `energy`, and they are only as big as it can afford. The
+    // cell's own child placement, if any, overrides `default_placement`.
     pub fn create_and_place_child_cell(
         &mut self,
         budding_angle: Angle,
-        donation_energy: BioEnergy,
+        layer_area: Area,
+        energy: BioEnergy,
+        default_placement: ChildPlacementStrategy,
     ) -> Cell {
-        let mut child = self.spawn(Area::new(10.0 * PI));
-        let offset = Displacement::from_polar(self.radius + child.radius(), budding_angle);
+        let (layer_area, energy) = if layer_area == Area::ZERO {
+            (Area::new(Self::DEFAULT_CHILD_LAYER_AREA), energy)
+        } else {
+            self.afford_child_layer_area(layer_area, energy)
+        };
+        let mut child = self.spawn(layer_area);
+        let placement = self.child_placement.unwrap_or(default_placement);
+        let offset = Displacement::from_polar(
+            self.radius + child.radius(),
+            self.child_angle(placement, budding_angle),
+        );
         child.set_initial_position(self.center() + offset);
         child.set_initial_velocity(self.velocity());
-        child.set_initial_energy(donation_energy);
+        child.set_initial_energy(energy);
         child
     }
 
+    fn child_angle(&self, placement: ChildPlacementStrategy, budding_angle: Angle) -> Angle {
+        match placement {
+            ChildPlacementStrategy::BuddingAngle => budding_angle,
+            ChildPlacementStrategy::Jitter { max_angle, seed } => {
+                let mut rng = Pcg64Mcg::seed_from_u64(
+                    seed ^ self.id.value().rotate_left(32)
+                        ^ self.age
+                        ^ budding_angle.radians().to_bits(),
+                );
+                let turn = max_angle.radians() * rng.gen_range(-1.0, 1.0);
+                Angle::from_radians(budding_angle.radians() + turn)
+            }
+            ChildPlacementStrategy::AwayFromCrowding => {
+                let push = self
+                    .environment
+                    .overlaps()
+                    .iter()
+                    .fold(Displacement::ZERO, |push, overlap| {
+                        push + overlap.incursion()
+                    });
+                if push == Displacement::ZERO {
+                    budding_angle
+                } else {
+                    push.angle()
+                }
+            }
+        }
+    }
+
+    fn afford_child_layer_area(&self, layer_area: Area, energy: BioEnergy) -> (Area, BioEnergy) {
+        // growing a unit of area in every layer
+        let cost_per_area: f64 = self
+            .layers
+            .iter()
+            .map(|layer| -layer.resize_parameters().growth_energy_delta.value())
+            .sum();
+        if cost_per_area == 0.0 {
+            return (layer_area, energy);
+        }
+        let affordable_area = layer_area.value().min(energy.value() / cost_per_area);
+        (
+            Area::new(affordable_area),
+            BioEnergy::new((energy.value() - affordable_area * cost_per_area).max(0.0)),
+        )
+    }
+
     fn reset_layers(&mut self) {
         for layer in &mut self.layers {
             layer.reset();
@@ -344,15 +986,28 @@ impl Cell {
     }
 
     #[allow(clippy::vec_box)]
-    fn update_layer_outer_radii(layers: &mut Vec<CellLayer>) -> Length {
+    fn update_layer_outer_radii(layers: &mut [CellLayer], elongation: Length) -> Length {
         layers
             .iter_mut()
             .fold(Length::new(0.0), |inner_radius, layer| {
-                layer.update_outer_radius(inner_radius);
+                layer.update_outer_radius(inner_radius, elongation);
                 layer.outer_radius()
             })
     }
 
+    fn calc_elongation(layers: &[CellLayer]) -> Length {
+        layers
+            .iter()
+            .map(|layer| layer.elongation())
+            .fold(Length::ZERO, |max, elongation| {
+                if elongation > max {
+                    elongation
+                } else {
+                    max
+                }
+            })
+    }
+
     fn calc_mass(layers: &[CellLayer]) -> Mass {
         layers
             .iter()
@@ -382,12 +1037,21 @@ impl Circle for Cell {
     fn center(&self) -> Position {
         self.newtonian_state.position
     }
+
+    fn half_axis(&self) -> Displacement {
+        if self.elongation == Length::ZERO {
+            Displacement::ZERO
+        } else {
+            Displacement::from_polar(self.elongation * 0.5, self.heading)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::physics::overlap::Overlap;
+    use approx::assert_abs_diff_eq;
 
     #[test]
     fn cells_use_pointer_equality() {
@@ -479,6 +1143,182 @@ mod tests {
         assert_eq!(Mass::new(10.5), cell.mass());
     }
 
+    #[test]
+    fn elongated_cell_is_capsule_along_its_heading() {
+        let mut cell = simple_layered_cell(vec![CellLayer::new(
+            Area::new(PI),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(ElongatorCellLayerSpecialty::new(Length::new(10.0), 0.0)),
+        )])
+        .with_heading(Angle::from_radians(PI / 2.0))
+        .with_control(Box::new(ContinuousRequestsControl::new(vec![
+            ElongatorCellLayerSpecialty::elongation_request(0, 4.0),
+        ])));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        assert_eq!(cell.half_axis(), Displacement::ZERO);
+
+        cell.run_control(&mut bond_requests, &mut CellChanges::new(1));
+
+        assert_abs_diff_eq!(cell.half_axis().x(), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(cell.half_axis().y(), 2.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(cell.max_y(), 2.0 + cell.radius().value(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn elongating_cell_keeps_its_area_and_buoyancy_to_weight_ratio() {
+        let mut cell = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(PI), Density::new(1.0)),
+            CellLayer::new(
+                Area::new(2.0 * PI),
+                Density::new(2.0),
+                Color::Green,
+                Box::new(ElongatorCellLayerSpecialty::new(Length::new(10.0), 0.0)),
+            ),
+        ])
+        .with_control(Box::new(ContinuousRequestsControl::new(vec![
+            ElongatorCellLayerSpecialty::elongation_request(1, 4.0),
+        ])));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let round_ratio = cell.area().value() / cell.mass().value();
+
+        cell.run_control(&mut bond_requests, &mut CellChanges::new(2));
+
+        assert!(cell.radius() < Length::new(3.0_f64.sqrt()));
+        assert_abs_diff_eq!(cell.area().value(), 3.0 * PI, epsilon = 1e-9);
+        assert_abs_diff_eq!(
+            cell.area().value() / cell.mass().value(),
+            round_ratio,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn duplicate_control_requests_merge_by_channel_policy() {
+        let requests = vec![
+            CellLayer::resize_request(0, AreaDelta::new(0.5)),
+            CellLayer::resize_request(0, AreaDelta::new(1.0)),
+        ];
+        let mut last_wins_cell =
+            simple_layered_cell(vec![simple_cell_layer(Area::new(10.0), Density::new(1.0))])
+                .with_control(Box::new(ContinuousRequestsControl::new(requests.clone())));
+        let mut sum_cell =
+            simple_layered_cell(vec![simple_cell_layer(Area::new(10.0), Density::new(1.0))])
+                .with_request_merge_policy(0, CellLayer::RESIZE_CHANNEL_INDEX, MergePolicy::Sum)
+                .with_control(Box::new(ContinuousRequestsControl::new(requests)));
+
+        for cell in &mut [&mut last_wins_cell, &mut sum_cell] {
+            let mut bond_requests = NONE_BOND_REQUESTS;
+            let mut changes = CellChanges::new(cell.layers.len());
+            cell.run_control(&mut bond_requests, &mut changes);
+        }
+
+        assert_eq!(Mass::new(11.0), last_wins_cell.mass());
+        assert_eq!(Mass::new(11.5), sum_cell.mass());
+    }
+
+    #[test]
+    fn dead_neural_layer_drops_all_control_requests() {
+        let mut cell = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(10.0), Density::new(1.0)),
+            simple_cell_layer(Area::new(1.0), Density::new(1.0)).dead(),
+        ])
+        .with_neural_layer(Some(1))
+        .with_control(Box::new(ContinuousResizeControl::new(
+            0,
+            AreaDelta::new(0.5),
+        )));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(cell.layers.len());
+        cell.run_control(&mut bond_requests, &mut changes);
+        assert_eq!(Mass::new(11.0), cell.mass());
+    }
+
+    #[test]
+    fn damaged_neural_layer_drops_some_control_requests() {
+        let mut cell = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(10.0), Density::new(1.0)),
+            simple_cell_layer(Area::new(1.0), Density::new(1.0)).with_health(0.5),
+        ])
+        .with_neural_layer(Some(1))
+        .with_request_merge_policy(0, CellLayer::RESIZE_CHANNEL_INDEX, MergePolicy::Sum)
+        .with_control(Box::new(ContinuousRequestsControl::new(vec![
+            CellLayer::resize_request(0, AreaDelta::new(1.0));
+            100
+        ])));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(cell.layers.len());
+        cell.run_control(&mut bond_requests, &mut changes);
+        let growth = cell.layers()[0].area().value() - 10.0;
+        assert!(growth > 25.0 && growth < 75.0);
+    }
+
+    #[test]
+    fn saved_cell_continues_its_neural_dropouts() {
+        let control = || {
+            Box::new(ContinuousRequestsControl::new(vec![
+                CellLayer::resize_request(0, AreaDelta::new(1.0));
+                100
+            ]))
+        };
+        let mut cell = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(10.0), Density::new(1.0)),
+            simple_cell_layer(Area::new(1.0), Density::new(1.0)).with_health(0.5),
+        ])
+        .with_neural_layer(Some(1))
+        .with_request_merge_policy(0, CellLayer::RESIZE_CHANNEL_INDEX, MergePolicy::Sum)
+        .with_control(control());
+        cell.set_id(CellId::new(3));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(cell.layers.len());
+        cell.run_control(&mut bond_requests, &mut changes);
+
+        let mut loaded = Cell::from_document(&cell.to_document())
+            .with_request_merge_policy(0, CellLayer::RESIZE_CHANNEL_INDEX, MergePolicy::Sum)
+            .with_control(control());
+        loaded.set_id(CellId::new(4));
+        let area_before = cell.layers()[0].area().value();
+        cell.run_control(&mut bond_requests, &mut CellChanges::new(2));
+        loaded.run_control(&mut bond_requests, &mut CellChanges::new(2));
+
+        assert_eq!(
+            cell.layers()[0].area().value() - area_before,
+            loaded.layers()[0].area().value() - area_before
+        );
+    }
+
+    #[test]
+    fn child_does_not_replay_parents_dropouts() {
+        let mut parent = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(10.0), Density::new(1.0)),
+            simple_cell_layer(Area::new(1.0), Density::new(1.0)).with_health(0.5),
+        ])
+        .with_neural_layer(Some(1));
+        parent.set_id(CellId::new(3));
+        let mut child = parent.spawn(Area::new(1.0));
+        child.set_id(CellId::new(4));
+        child.layers[1] = simple_cell_layer(Area::new(1.0), Density::new(1.0)).with_health(0.5);
+        let requests: Vec<ControlRequest> = (0..64)
+            .map(|index| ControlRequest::new(0, 0, index, 1.0))
+            .collect();
+        let dropouts = |cell: &mut Cell| -> Vec<Vec<ControlRequest>> {
+            (0..3)
+                .map(|_| {
+                    let mut kept = requests.clone();
+                    cell.drop_control_requests_for_neural_damage(&mut kept);
+                    kept
+                })
+                .collect()
+        };
+
+        let parent_dropouts = dropouts(&mut parent);
+        let child_dropouts = dropouts(&mut child);
+
+        for kept in &child_dropouts {
+            assert!(!parent_dropouts.contains(kept));
+        }
+    }
+
     #[test]
     fn layer_growth_cost_reduces_cell_energy() {
         const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
@@ -488,7 +1328,7 @@ mod tests {
 
         let mut cell =
             simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))
-                .with_resize_parameters(&LAYER_RESIZE_PARAMS)])
+                .with_resize_parameters(LAYER_RESIZE_PARAMS)])
             .with_control(Box::new(ContinuousResizeControl::new(
                 0,
                 AreaDelta::new(2.0),
@@ -502,24 +1342,85 @@ mod tests {
         assert_eq!(BioEnergy::new(8.0), cell.energy());
     }
 
+    #[test]
+    fn energy_budget_records_spending_by_use() {
+        const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
+            growth_energy_delta: BioEnergyDelta::new(-1.0),
+            ..LayerResizeParameters::UNLIMITED
+        };
+
+        let mut cell = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(1.0), Density::new(1.0))
+                .with_resize_parameters(LAYER_RESIZE_PARAMS),
+            CellLayer::new(
+                Area::new(1.0),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(BondingCellLayerSpecialty::new()),
+            ),
+        ])
+        .with_control(Box::new(ContinuousRequestsControl::new(vec![
+            CellLayer::resize_request(0, AreaDelta::new(2.0)),
+            BondingCellLayerSpecialty::donation_energy_request(1, 0, BioEnergy::new(3.0)),
+        ])))
+        .with_initial_energy(BioEnergy::new(10.0));
+
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(cell.layers.len());
+        cell.run_control(&mut bond_requests, &mut changes);
+
+        let budget = cell.energy_budget();
+        assert_eq!(budget.growth, BioEnergyDelta::new(-2.0));
+        assert_eq!(budget.donation, BioEnergyDelta::new(-3.0));
+        assert_eq!(budget.net(), BioEnergyDelta::new(-5.0));
+    }
+
     #[test]
     fn thruster_layer_adds_force_to_cell() {
         let mut cell = simple_layered_cell(vec![CellLayer::new(
             Area::new(1.0),
             Density::new(1.0),
             Color::Green,
-            Box::new(ThrusterCellLayerSpecialty::new()),
+            Box::new(ThrusterCellLayerSpecialty::new(0.0)),
         )])
+        .with_heading(Angle::from_radians(PI / 2.0))
         .with_control(Box::new(SimpleThrusterControl::new(
             0,
-            Force::new(1.0, -1.0),
+            1.0,
+            Deflection::ZERO,
         )));
         let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(cell.layers.len());
         cell.run_control(&mut bond_requests, &mut changes);
         let mut changes = CellChanges::new(cell.layers.len());
         cell.after_influences(&mut changes);
-        assert_eq!(Force::new(1.0, -1.0), cell.forces().net_force());
+        let force = cell.forces().net_force();
+        assert_abs_diff_eq!(force.x(), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(force.y(), 1.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn thruster_layer_turns_cell_before_thrusting() {
+        let mut cell = simple_layered_cell(vec![CellLayer::new(
+            Area::new(1.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(ThrusterCellLayerSpecialty::new(0.0)),
+        )])
+        .with_control(Box::new(SimpleThrusterControl::new(
+            0,
+            2.0,
+            Deflection::from_radians(PI / 2.0),
+        )));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(cell.layers.len());
+        cell.run_control(&mut bond_requests, &mut changes);
+        cell.after_influences(&mut changes);
+
+        assert_abs_diff_eq!(cell.heading().radians(), PI / 2.0, epsilon = 1e-12);
+        let force = cell.forces().net_force();
+        assert_abs_diff_eq!(force.x(), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(force.y(), 2.0, epsilon = 1e-12);
     }
 
     #[test]
@@ -538,6 +1439,31 @@ mod tests {
         assert_eq!(BioEnergy::new(20.0), cell.energy());
     }
 
+    #[test]
+    fn child_layer_area_is_limited_by_its_energy() {
+        const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
+            growth_energy_delta: BioEnergyDelta::new(-1.0),
+            ..LayerResizeParameters::UNLIMITED
+        };
+        let mut cell = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(10.0), Density::new(1.0))
+                .with_resize_parameters(LAYER_RESIZE_PARAMS),
+            simple_cell_layer(Area::new(10.0), Density::new(1.0))
+                .with_resize_parameters(LAYER_RESIZE_PARAMS),
+        ]);
+
+        let child = cell.create_and_place_child_cell(
+            Angle::ZERO,
+            Area::new(5.0),
+            BioEnergy::new(4.0),
+            ChildPlacementStrategy::BuddingAngle,
+        );
+
+        assert_eq!(child.layers()[0].area(), Area::new(2.0));
+        assert_eq!(child.layers()[1].area(), Area::new(2.0));
+        assert_eq!(child.energy(), BioEnergy::ZERO);
+    }
+
     #[test]
     fn budding_creates_child_with_right_state() {
         let mut cell = Cell::new(
@@ -546,7 +1472,12 @@ mod tests {
             vec![simple_cell_layer(Area::new(10.0), Density::new(1.0))],
         );
 
-        let child = cell.create_and_place_child_cell(Angle::from_radians(0.0), BioEnergy::new(1.0));
+        let child = cell.create_and_place_child_cell(
+            Angle::from_radians(0.0),
+            Area::ZERO,
+            BioEnergy::new(1.0),
+            ChildPlacementStrategy::BuddingAngle,
+        );
 
         assert_eq!(
             child.center(),
@@ -559,13 +1490,216 @@ mod tests {
         assert_eq!(child.energy(), BioEnergy::new(1.0));
     }
 
+    #[test]
+    fn crowded_cell_places_child_away_from_crowding() {
+        let mut cell = Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![simple_cell_layer(Area::new(PI), Density::new(1.0))],
+        );
+        cell.environment_mut()
+            .add_overlap(Overlap::new(Displacement::new(0.0, -0.5), 1.0));
+
+        let child = cell.create_and_place_child_cell(
+            Angle::ZERO,
+            Area::new(PI),
+            BioEnergy::ZERO,
+            ChildPlacementStrategy::AwayFromCrowding,
+        );
+
+        assert_abs_diff_eq!(child.center(), Position::new(0.0, -2.0), epsilon = 1e-9);
+    }
+
+    #[test]
+    fn cell_child_placement_overrides_default() {
+        let mut cell = Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![simple_cell_layer(Area::new(PI), Density::new(1.0))],
+        )
+        .with_child_placement(ChildPlacementStrategy::Jitter {
+            max_angle: Angle::from_radians(0.5),
+            seed: 7,
+        });
+
+        let child = cell.create_and_place_child_cell(
+            Angle::ZERO,
+            Area::new(PI),
+            BioEnergy::ZERO,
+            ChildPlacementStrategy::BuddingAngle,
+        );
+
+        let angle = child.center().y().atan2(child.center().x());
+        assert!(angle != 0.0 && angle.abs() <= 0.5);
+        assert_abs_diff_eq!(
+            (child.center() - cell.center()).length(),
+            Length::new(2.0),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn child_inherits_only_inherited_tags() {
+        let mut cell = Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![simple_cell_layer(Area::new(10.0), Density::new(1.0))],
+        )
+        .with_tag("founderA");
+        cell.add_tag_with_inheritance("marked", false);
+
+        let child = cell.create_and_place_child_cell(
+            Angle::ZERO,
+            Area::ZERO,
+            BioEnergy::ZERO,
+            ChildPlacementStrategy::BuddingAngle,
+        );
+
+        assert!(child.has_tag("founderA"));
+        assert!(!child.has_tag("marked"));
+        assert!(cell.has_tag("marked"));
+    }
+
+    #[test]
+    fn spore_does_not_photosynthesize_until_it_hatches_in_light() {
+        let mut cell = simple_layered_cell(vec![CellLayer::new(
+            Area::new(4.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(PhotoCellLayerSpecialty::new(0.5)),
+        )])
+        .with_spore(Spore::from_hatch_conditions(2.0, 0));
+
+        cell.environment_mut().add_light_intensity(1.0);
+        cell.after_influences(&mut CellChanges::new(1));
+        cell.environment_mut().clear();
+        assert!(cell.is_spore());
+        assert_eq!(cell.energy(), BioEnergy::ZERO);
+
+        cell.environment_mut().add_light_intensity(2.0);
+        cell.after_influences(&mut CellChanges::new(1));
+        assert!(!cell.is_spore());
+        assert_eq!(cell.energy(), BioEnergy::ZERO);
+
+        cell.after_influences(&mut CellChanges::new(1));
+        assert_eq!(cell.energy(), BioEnergy::new(4.0));
+    }
+
+    #[test]
+    fn spore_hatches_after_its_ticks_run_out() {
+        let mut cell =
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_spore(Spore::from_hatch_conditions(0.0, 2));
+
+        cell.after_influences(&mut CellChanges::new(1));
+        assert!(cell.is_spore());
+        cell.after_influences(&mut CellChanges::new(1));
+        assert!(!cell.is_spore());
+    }
+
+    #[test]
+    fn cell_ages_every_tick_even_as_spore() {
+        let mut cell =
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_spore(Spore::from_hatch_conditions(0.0, 5));
+
+        cell.after_influences(&mut CellChanges::new(1));
+        cell.after_influences(&mut CellChanges::new(1));
+
+        assert_eq!(cell.age(), 2);
+        assert_eq!(cell.spawn(Area::new(1.0)).age(), 0);
+    }
+
+    #[test]
+    fn spore_runs_no_control() {
+        let mut cell =
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_control(Box::new(ContinuousResizeControl::new(
+                    0,
+                    AreaDelta::new(1.0),
+                )))
+                .with_spore(Spore::from_hatch_conditions(0.0, 5));
+
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        cell.run_control(&mut bond_requests, &mut CellChanges::new(1));
+
+        assert_eq!(cell.layers()[0].area(), Area::new(1.0));
+    }
+
+    #[test]
+    fn selected_cell_keeps_its_request_history() {
+        let mut cell =
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_control(Box::new(ContinuousResizeControl::new(
+                    0,
+                    AreaDelta::new(1.0),
+                )));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+
+        cell.run_control(&mut bond_requests, &mut CellChanges::new(1));
+        assert!(cell.request_history().is_empty());
+
+        cell.set_selected(true);
+        cell.run_control(&mut bond_requests, &mut CellChanges::new(1));
+        cell.run_control(&mut bond_requests, &mut CellChanges::new(1));
+        assert_eq!(cell.request_history().len(), 2);
+        assert_eq!(cell.request_history()[0].request.requested_value(), 1.0);
+
+        cell.set_selected(false);
+        assert!(cell.request_history().is_empty());
+    }
+
+    #[test]
+    fn request_history_drops_oldest_requests() {
+        let mut cell =
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]);
+        cell.set_selected(true);
+        let request = BudgetedControlRequest::new(
+            CostedControlRequest::unlimited(
+                ControlRequest::new(0, 1, 0, 1.0),
+                BioEnergyDelta::new(-0.5),
+            ),
+            0.5,
+        );
+
+        cell.age = 1;
+        cell.record_requests(&vec![request; Cell::REQUEST_HISTORY_LENGTH]);
+        cell.age = 2;
+        cell.record_requests(&[request]);
+
+        let history = cell.request_history();
+        assert_eq!(history.len(), Cell::REQUEST_HISTORY_LENGTH);
+        assert_eq!(history.front().unwrap().cell_age, 1);
+        assert_eq!(history.back().unwrap().cell_age, 2);
+        assert_eq!(history.back().unwrap().request, request);
+    }
+
+    #[test]
+    fn child_inherits_lineage() {
+        let mut cell = Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![simple_cell_layer(Area::new(10.0), Density::new(1.0))],
+        )
+        .with_lineage(3);
+
+        let child = cell.create_and_place_child_cell(
+            Angle::ZERO,
+            Area::ZERO,
+            BioEnergy::ZERO,
+            ChildPlacementStrategy::BuddingAngle,
+        );
+
+        assert_eq!(child.lineage(), 3);
+    }
+
     #[test]
     fn zero_cost_request_gets_fully_budgeted() {
         let costed_request =
             CostedControlRequest::unlimited(ControlRequest::NULL_REQUEST, BioEnergyDelta::new(0.0));
 
         let (_, budgeted_requests) =
-            Cell::budget_control_requests(BioEnergy::new(0.0), &vec![costed_request]);
+            Cell::budget_control_requests(BioEnergy::new(0.0), &[costed_request]);
 
         assert_eq!(budgeted_requests[0].budgeted_fraction(), 1.0);
     }
@@ -576,7 +1710,7 @@ mod tests {
             CostedControlRequest::unlimited(ControlRequest::NULL_REQUEST, BioEnergyDelta::new(1.0));
 
         let (_, budgeted_requests) =
-            Cell::budget_control_requests(BioEnergy::new(0.0), &vec![costed_request]);
+            Cell::budget_control_requests(BioEnergy::new(0.0), &[costed_request]);
 
         assert_eq!(budgeted_requests[0].budgeted_fraction(), 1.0);
     }
@@ -589,7 +1723,7 @@ mod tests {
         );
 
         let (_, budgeted_requests) =
-            Cell::budget_control_requests(BioEnergy::new(1.0), &vec![costed_request]);
+            Cell::budget_control_requests(BioEnergy::new(1.0), &[costed_request]);
 
         assert_eq!(budgeted_requests[0].budgeted_fraction(), 1.0);
     }
@@ -602,7 +1736,7 @@ mod tests {
         );
 
         let (_, budgeted_requests) =
-            Cell::budget_control_requests(BioEnergy::new(1.0), &vec![costed_request]);
+            Cell::budget_control_requests(BioEnergy::new(1.0), &[costed_request]);
 
         assert_eq!(budgeted_requests[0].budgeted_fraction(), 0.5);
     }
@@ -614,7 +1748,7 @@ mod tests {
             BioEnergyDelta::new(-1.0),
         );
 
-        let (energy, _) = Cell::budget_control_requests(BioEnergy::new(2.0), &vec![costed_request]);
+        let (energy, _) = Cell::budget_control_requests(BioEnergy::new(2.0), &[costed_request]);
 
         assert_eq!(energy, BioEnergy::new(1.0));
     }
@@ -676,9 +1810,9 @@ mod tests {
 
         let mut cell = simple_layered_cell(vec![
             simple_cell_layer(Area::new(1.0), Density::new(1.0))
-                .with_health_parameters(&LAYER0_HEALTH_PARAMS),
+                .with_health_parameters(LAYER0_HEALTH_PARAMS),
             simple_cell_layer(Area::new(1.0), Density::new(1.0))
-                .with_health_parameters(&LAYER1_HEALTH_PARAMS),
+                .with_health_parameters(LAYER1_HEALTH_PARAMS),
         ]);
 
         cell.environment_mut()
@@ -705,9 +1839,9 @@ mod tests {
 
         let mut cell = simple_layered_cell(vec![
             simple_cell_layer(Area::new(10.0), Density::new(1.0))
-                .with_resize_parameters(&LAYER0_RESIZE_PARAMS),
+                .with_resize_parameters(LAYER0_RESIZE_PARAMS),
             simple_cell_layer(Area::new(5.0), Density::new(1.0))
-                .with_resize_parameters(&LAYER1_RESIZE_PARAMS),
+                .with_resize_parameters(LAYER1_RESIZE_PARAMS),
         ])
         .with_control(Box::new(ContinuousRequestsControl::new(vec![
             CellLayer::resize_request(0, AreaDelta::new(-100.0)),
@@ -723,6 +1857,30 @@ mod tests {
         assert_eq!(BioEnergy::new(5.0), cell.energy());
     }
 
+    #[test]
+    fn duplicate_keeps_layer_sizes_energy_and_tags_without_parent() {
+        let mut cell = simple_layered_cell(vec![
+            simple_cell_layer(Area::new(1.0), Density::new(1.0)),
+            simple_cell_layer(Area::new(2.0), Density::new(1.0)),
+        ])
+        .with_initial_energy(BioEnergy::new(3.0))
+        .with_tags(vec![CellTag {
+            name: "founder".to_string(),
+            inherited: false,
+        }]);
+        cell.set_id(CellId::new(7));
+
+        let copy = cell.duplicate();
+
+        assert_eq!(copy.layers()[0].area(), Area::new(1.0));
+        assert_eq!(copy.layers()[1].area(), Area::new(2.0));
+        assert_eq!(copy.radius(), cell.radius());
+        assert_eq!(copy.energy(), BioEnergy::new(3.0));
+        assert!(copy.has_tag("founder"));
+        assert_eq!(copy.parent_id(), None);
+        assert_eq!(copy.id(), CellId::UNSET);
+    }
+
     fn simple_layered_cell(layers: Vec<CellLayer>) -> Cell {
         Cell::new(Position::ORIGIN, Velocity::ZERO, layers)
     }
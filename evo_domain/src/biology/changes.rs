@@ -12,6 +12,15 @@ impl WorldChanges {
             cells: vec![CellChanges::new(num_layers); num_cells],
         }
     }
+
+    // Same as replacing self with WorldChanges::new, but reuses the existing allocations.
+    pub fn reset(&mut self, num_cells: usize, num_layers: usize) {
+        self.cells.truncate(num_cells);
+        for cell_changes in &mut self.cells {
+            cell_changes.reset(num_layers);
+        }
+        self.cells.resize(num_cells, CellChanges::new(num_layers));
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +38,13 @@ impl CellChanges {
             layers: vec![CellLayerChanges::new(); num_layers],
         }
     }
+
+    pub fn reset(&mut self, num_layers: usize) {
+        self.energy = BioEnergyDelta::ZERO;
+        self.thrust = Force::ZERO;
+        self.layers.clear();
+        self.layers.resize(num_layers, CellLayerChanges::new());
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -38,6 +54,7 @@ pub struct CellLayerChanges {
 }
 
 impl CellLayerChanges {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         CellLayerChanges {
             health: 0.0,
@@ -45,3 +62,25 @@ impl CellLayerChanges {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_clears_and_resizes_changes() {
+        let mut changes = WorldChanges::new(3, 2);
+        changes.cells[0].energy = BioEnergyDelta::new(1.0);
+        changes.cells[2].layers[1].health = 0.5;
+
+        changes.reset(2, 3);
+
+        assert_eq!(changes.cells.len(), 2);
+        assert_eq!(changes.cells[0].energy, BioEnergyDelta::ZERO);
+        assert_eq!(changes.cells[1].layers.len(), 3);
+        assert!(changes.cells[1]
+            .layers
+            .iter()
+            .all(|layer| layer.health == 0.0));
+    }
+}
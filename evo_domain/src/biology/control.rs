@@ -1,12 +1,43 @@
 use crate::biology::control_requests::*;
-use crate::biology::layers::CellLayer;
+use crate::biology::layers::{CellLayer, SensorReading, ThrusterCellLayerSpecialty};
 use crate::physics::quantities::*;
+use crate::state_hash::StateHasher;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
-pub trait CellControl: Debug {
+pub trait CellControl: Debug + Send {
     fn run(&mut self, cell_state: &CellStateSnapshot) -> Vec<ControlRequest>;
 
     fn spawn(&mut self) -> Box<dyn CellControl>;
+
+    // Switches to faster, approximate math where the control has any, e.g. for transfer
+    // functions. Spawned controls should keep using it.
+    fn use_fast_math(&mut self) {}
+
+    // The chance that each weight of the control's genome mutates when the control is spawned,
+    // for controls with a genome. Spawned controls keep the chance they were set to.
+    fn mutation_rate(&self) -> Option<f64> {
+        None
+    }
+
+    fn set_mutation_rate(&mut self, _rate: f64) {}
+
+    // Describes the first NaN or infinite value in the control's state, if any, e.g. a node
+    // value, for the world's finite-value checks.
+    fn non_finite_state(&self) -> Option<String> {
+        None
+    }
+
+    // Whether the control never makes requests, so that losing it loses nothing, e.g. when
+    // the cell is saved to a document.
+    fn is_null(&self) -> bool {
+        false
+    }
+
+    // Hashes the control's evolved or remembered state, e.g. a nnet's weights and node values,
+    // for the world's state hashes. Controls without such state hash nothing.
+    fn hash_state(&self, _hasher: &mut StateHasher) {}
 }
 
 #[derive(Debug)]
@@ -16,6 +47,7 @@ pub struct CellStateSnapshot {
     pub mass: Mass,
     pub center: Position,
     pub velocity: Velocity,
+    pub heading: Angle,
     pub energy: BioEnergy,
     pub layers: Vec<CellLayerStateSnapshot>,
 }
@@ -27,6 +59,7 @@ impl CellStateSnapshot {
         mass: Mass::ZERO,
         center: Position::ORIGIN,
         velocity: Velocity::ZERO,
+        heading: Angle::ZERO,
         energy: BioEnergy::ZERO,
         layers: Vec::new(),
     };
@@ -37,6 +70,8 @@ pub struct CellLayerStateSnapshot {
     pub area: Area,
     pub mass: Mass,
     pub health: f64,
+    // The nearest cell the layer sensed, if it is a sensor layer and sensed one
+    pub sensor_reading: Option<SensorReading>,
 }
 
 #[derive(Debug)]
@@ -57,6 +92,10 @@ impl CellControl for NullControl {
     fn spawn(&mut self) -> Box<dyn CellControl> {
         Box::new(Self::new())
     }
+
+    fn is_null(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -111,14 +150,16 @@ impl CellControl for ContinuousResizeControl {
 #[derive(Clone, Debug)]
 pub struct SimpleThrusterControl {
     thruster_layer_index: usize,
-    force: Force,
+    forward: f64,
+    turn: Deflection,
 }
 
 impl SimpleThrusterControl {
-    pub fn new(thruster_layer_index: usize, force: Force) -> Self {
+    pub fn new(thruster_layer_index: usize, forward: f64, turn: Deflection) -> Self {
         SimpleThrusterControl {
             thruster_layer_index,
-            force,
+            forward,
+            turn,
         }
     }
 }
@@ -126,8 +167,8 @@ impl SimpleThrusterControl {
 impl CellControl for SimpleThrusterControl {
     fn run(&mut self, _cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
         vec![
-            ControlRequest::new(self.thruster_layer_index, 2, 0, self.force.x()),
-            ControlRequest::new(self.thruster_layer_index, 3, 0, self.force.y()),
+            ThrusterCellLayerSpecialty::forward_request(self.thruster_layer_index, self.forward),
+            ThrusterCellLayerSpecialty::turn_request(self.thruster_layer_index, self.turn),
         ]
     }
 
@@ -136,6 +177,358 @@ impl CellControl for SimpleThrusterControl {
     }
 }
 
+// Runs several child controls, such as a hand-written reflex layer over an evolved nnet, and
+// merges their requests. Children are in priority order: a request from a child overrides any
+// request for the same layer, channel and value from the children after it.
+#[derive(Debug, Default)]
+pub struct CompositeCellControl {
+    children: Vec<Box<dyn CellControl>>,
+}
+
+impl CompositeCellControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds a child with lower priority than the ones already added.
+    pub fn with_child(mut self, child: Box<dyn CellControl>) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+impl CellControl for CompositeCellControl {
+    fn run(&mut self, cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+        let mut requests: Vec<ControlRequest> = vec![];
+        for child in &mut self.children {
+            let num_higher_priority_requests = requests.len();
+            for request in child.run(cell_state) {
+                if !requests[..num_higher_priority_requests]
+                    .iter()
+                    .any(|higher| higher.id() == request.id())
+                {
+                    requests.push(request);
+                }
+            }
+        }
+        requests
+    }
+
+    fn spawn(&mut self) -> Box<dyn CellControl> {
+        Box::new(CompositeCellControl {
+            children: self
+                .children
+                .iter_mut()
+                .map(|child| child.spawn())
+                .collect(),
+        })
+    }
+
+    fn use_fast_math(&mut self) {
+        for child in &mut self.children {
+            child.use_fast_math();
+        }
+    }
+
+    fn mutation_rate(&self) -> Option<f64> {
+        self.children.iter().find_map(|child| child.mutation_rate())
+    }
+
+    fn set_mutation_rate(&mut self, rate: f64) {
+        for child in &mut self.children {
+            child.set_mutation_rate(rate);
+        }
+    }
+
+    fn non_finite_state(&self) -> Option<String> {
+        self.children
+            .iter()
+            .find_map(|child| child.non_finite_state())
+    }
+
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        for child in &self.children {
+            child.hash_state(hasher);
+        }
+    }
+}
+
+// Smooths the requests of another control on chosen layer channels, so that jittery outputs of
+// an evolved control, e.g. ones that alternate between growing and shrinking a layer, do not
+// waste energy. Each request value is smoothed against the one last requested with the same ID.
+// A spawned control starts without any previous values.
+#[derive(Debug)]
+pub struct SmoothedControl {
+    inner: Box<dyn CellControl>,
+    smoothings: HashMap<(usize, usize), Smoothing>,
+    previous_values: HashMap<ControlRequestId, f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Smoothing {
+    // Weight, from 0 to 1, of the new value against the previous smoothed value
+    ExponentialMovingAverage { weight: f64 },
+    // Largest change from the previous value in one run
+    MaxSlewRate { max_change: f64 },
+}
+
+impl Smoothing {
+    fn apply(self, previous: f64, value: f64) -> f64 {
+        match self {
+            Smoothing::ExponentialMovingAverage { weight } => {
+                weight * value + (1.0 - weight) * previous
+            }
+            Smoothing::MaxSlewRate { max_change } => {
+                value.clamp(previous - max_change, previous + max_change)
+            }
+        }
+    }
+}
+
+impl SmoothedControl {
+    pub fn new(inner: Box<dyn CellControl>) -> Self {
+        SmoothedControl {
+            inner,
+            smoothings: HashMap::new(),
+            previous_values: HashMap::new(),
+        }
+    }
+
+    pub fn with_smoothing(
+        mut self,
+        layer_index: usize,
+        channel_index: usize,
+        smoothing: Smoothing,
+    ) -> Self {
+        match smoothing {
+            Smoothing::ExponentialMovingAverage { weight } => {
+                assert!((0.0..=1.0).contains(&weight))
+            }
+            Smoothing::MaxSlewRate { max_change } => assert!(max_change >= 0.0),
+        }
+        self.smoothings
+            .insert((layer_index, channel_index), smoothing);
+        self
+    }
+}
+
+impl CellControl for SmoothedControl {
+    fn run(&mut self, cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+        let mut requests = self.inner.run(cell_state);
+        for request in &mut requests {
+            let smoothing = match self
+                .smoothings
+                .get(&(request.layer_index(), request.channel_index()))
+            {
+                Some(smoothing) => *smoothing,
+                None => continue,
+            };
+            let value = match self.previous_values.get(&request.id()) {
+                Some(&previous) => smoothing.apply(previous, request.requested_value()),
+                None => request.requested_value(),
+            };
+            self.previous_values.insert(request.id(), value);
+            *request = ControlRequest::new(
+                request.layer_index(),
+                request.channel_index(),
+                request.value_index(),
+                value,
+            );
+        }
+        requests
+    }
+
+    fn spawn(&mut self) -> Box<dyn CellControl> {
+        Box::new(SmoothedControl {
+            inner: self.inner.spawn(),
+            smoothings: self.smoothings.clone(),
+            previous_values: HashMap::new(),
+        })
+    }
+
+    fn use_fast_math(&mut self) {
+        self.inner.use_fast_math();
+    }
+
+    fn mutation_rate(&self) -> Option<f64> {
+        self.inner.mutation_rate()
+    }
+
+    fn set_mutation_rate(&mut self, rate: f64) {
+        self.inner.set_mutation_rate(rate);
+    }
+
+    fn non_finite_state(&self) -> Option<String> {
+        self.inner.non_finite_state()
+    }
+
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.inner.hash_state(hasher);
+        let mut previous_values: Vec<_> = self.previous_values.iter().collect();
+        previous_values.sort_by_key(|(id, _)| **id);
+        for (id, value) in previous_values {
+            hasher.write_u64(id.layer_index() as u64);
+            hasher.write_u64(id.channel_index() as u64);
+            hasher.write_u64(id.value_index() as u64);
+            hasher.write_f64(*value);
+        }
+    }
+}
+
+// A scripted control, easier to read than an evolved nnet. Each run ticks the tree from the
+// root and returns the requests of the actions it reaches. Trees can be loaded from JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BehaviorTreeControl {
+    root: BehaviorNode,
+}
+
+impl BehaviorTreeControl {
+    pub fn new(root: BehaviorNode) -> Self {
+        BehaviorTreeControl { root }
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        Ok(Self::new(serde_json::from_str(json)?))
+    }
+}
+
+impl CellControl for BehaviorTreeControl {
+    fn run(&mut self, cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+        let mut requests = vec![];
+        self.root.tick(cell_state, &mut requests);
+        requests
+    }
+
+    fn spawn(&mut self) -> Box<dyn CellControl> {
+        Box::new(self.clone())
+    }
+
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_bytes(&serde_json::to_vec(&self.root).unwrap());
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BehaviorNode {
+    // Ticks the children in order until one fails
+    Sequence {
+        children: Vec<BehaviorNode>,
+    },
+    // Ticks the children in order until one succeeds
+    Selector {
+        children: Vec<BehaviorNode>,
+    },
+    // Fails if the value is of a layer the cell does not have
+    Condition {
+        value: SnapshotValue,
+        comparison: Comparison,
+        threshold: f64,
+    },
+    // Always succeeds
+    Action {
+        requests: Vec<ActionRequest>,
+    },
+}
+
+impl BehaviorNode {
+    // Returns whether the node succeeded.
+    fn tick(&self, cell_state: &CellStateSnapshot, requests: &mut Vec<ControlRequest>) -> bool {
+        match self {
+            BehaviorNode::Sequence { children } => children
+                .iter()
+                .all(|child| child.tick(cell_state, requests)),
+            BehaviorNode::Selector { children } => children
+                .iter()
+                .any(|child| child.tick(cell_state, requests)),
+            BehaviorNode::Condition {
+                value,
+                comparison,
+                threshold,
+            } => value
+                .get(cell_state)
+                .is_some_and(|value| comparison.compare(value, *threshold)),
+            BehaviorNode::Action {
+                requests: action_requests,
+            } => {
+                requests.extend(
+                    action_requests
+                        .iter()
+                        .copied()
+                        .map(ActionRequest::to_control_request),
+                );
+                true
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SnapshotValue {
+    Radius,
+    Area,
+    Mass,
+    Energy,
+    Speed,
+    LayerArea { layer_index: usize },
+    LayerHealth { layer_index: usize },
+}
+
+impl SnapshotValue {
+    // None if the value is of a layer the cell does not have.
+    fn get(self, cell_state: &CellStateSnapshot) -> Option<f64> {
+        match self {
+            SnapshotValue::Radius => Some(cell_state.radius.value()),
+            SnapshotValue::Area => Some(cell_state.area.value()),
+            SnapshotValue::Mass => Some(cell_state.mass.value()),
+            SnapshotValue::Energy => Some(cell_state.energy.value()),
+            SnapshotValue::Speed => Some(cell_state.velocity.value().magnitude()),
+            SnapshotValue::LayerArea { layer_index } => cell_state
+                .layers
+                .get(layer_index)
+                .map(|layer| layer.area.value()),
+            SnapshotValue::LayerHealth { layer_index } => {
+                cell_state.layers.get(layer_index).map(|layer| layer.health)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Comparison {
+    LessThan,
+    GreaterThan,
+}
+
+impl Comparison {
+    fn compare(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::LessThan => value < threshold,
+            Comparison::GreaterThan => value > threshold,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActionRequest {
+    pub layer_index: usize,
+    pub channel_index: usize,
+    pub value_index: usize,
+    pub value: f64,
+}
+
+impl ActionRequest {
+    fn to_control_request(self) -> ControlRequest {
+        ControlRequest::new(
+            self.layer_index,
+            self.channel_index,
+            self.value_index,
+            self.value,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,8 +544,8 @@ mod tests {
     }
 
     #[test]
-    fn simple_thruster_control_returns_requests_for_force() {
-        let mut control = SimpleThrusterControl::new(2, Force::new(1.0, -1.0));
+    fn simple_thruster_control_returns_requests_for_forward_thrust_and_turn() {
+        let mut control = SimpleThrusterControl::new(2, 1.0, Deflection::from_radians(-1.0));
         let requests = control.run(&CellStateSnapshot::ZEROS);
         assert_eq!(
             requests,
@@ -162,4 +555,201 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn behavior_tree_control_takes_first_branch_whose_conditions_hold() {
+        let mut control = BehaviorTreeControl::new(BehaviorNode::Selector {
+            children: vec![
+                BehaviorNode::Sequence {
+                    children: vec![
+                        BehaviorNode::Condition {
+                            value: SnapshotValue::Energy,
+                            comparison: Comparison::LessThan,
+                            threshold: 1.0,
+                        },
+                        BehaviorNode::Action {
+                            requests: vec![ActionRequest {
+                                layer_index: 1,
+                                channel_index: 1,
+                                value_index: 0,
+                                value: -1.0,
+                            }],
+                        },
+                    ],
+                },
+                BehaviorNode::Action {
+                    requests: vec![ActionRequest {
+                        layer_index: 1,
+                        channel_index: 1,
+                        value_index: 0,
+                        value: 1.0,
+                    }],
+                },
+            ],
+        });
+
+        let mut cell_state = CellStateSnapshot::ZEROS;
+        assert_eq!(
+            control.run(&cell_state),
+            vec![ControlRequest::new(1, 1, 0, -1.0)]
+        );
+
+        cell_state.energy = BioEnergy::new(2.0);
+        assert_eq!(
+            control.run(&cell_state),
+            vec![ControlRequest::new(1, 1, 0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn behavior_tree_condition_on_missing_layer_fails() {
+        let mut control = BehaviorTreeControl::new(BehaviorNode::Sequence {
+            children: vec![
+                BehaviorNode::Condition {
+                    value: SnapshotValue::LayerHealth { layer_index: 3 },
+                    comparison: Comparison::LessThan,
+                    threshold: 1.0,
+                },
+                BehaviorNode::Action {
+                    requests: vec![ActionRequest {
+                        layer_index: 3,
+                        channel_index: 1,
+                        value_index: 0,
+                        value: 1.0,
+                    }],
+                },
+            ],
+        });
+
+        assert_eq!(control.run(&CellStateSnapshot::ZEROS), vec![]);
+    }
+
+    #[test]
+    fn behavior_tree_control_loads_from_json() {
+        let control = BehaviorTreeControl::from_json(
+            r#"{
+                "type": "Sequence",
+                "children": [
+                    {
+                        "type": "Condition",
+                        "value": { "type": "LayerHealth", "layer_index": 0 },
+                        "comparison": "LessThan",
+                        "threshold": 0.5
+                    },
+                    {
+                        "type": "Action",
+                        "requests": [
+                            { "layer_index": 0, "channel_index": 0, "value_index": 0, "value": 1.0 }
+                        ]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            control,
+            BehaviorTreeControl::new(BehaviorNode::Sequence {
+                children: vec![
+                    BehaviorNode::Condition {
+                        value: SnapshotValue::LayerHealth { layer_index: 0 },
+                        comparison: Comparison::LessThan,
+                        threshold: 0.5,
+                    },
+                    BehaviorNode::Action {
+                        requests: vec![ActionRequest {
+                            layer_index: 0,
+                            channel_index: 0,
+                            value_index: 0,
+                            value: 1.0,
+                        }],
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn smoothed_control_averages_requests_on_chosen_channels() {
+        let mut control = SmoothedControl::new(Box::new(AlternatingControl { sign: 1.0 }))
+            .with_smoothing(1, 1, Smoothing::ExponentialMovingAverage { weight: 0.25 });
+
+        assert_eq!(
+            control.run(&CellStateSnapshot::ZEROS),
+            vec![
+                ControlRequest::new(1, 1, 0, 1.0),
+                ControlRequest::new(1, 2, 0, 1.0)
+            ]
+        );
+        assert_eq!(
+            control.run(&CellStateSnapshot::ZEROS),
+            vec![
+                ControlRequest::new(1, 1, 0, 0.5),
+                ControlRequest::new(1, 2, 0, -1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn smoothed_control_limits_slew_rate() {
+        let mut control = SmoothedControl::new(Box::new(AlternatingControl { sign: 1.0 }))
+            .with_smoothing(1, 2, Smoothing::MaxSlewRate { max_change: 0.5 });
+
+        control.run(&CellStateSnapshot::ZEROS);
+        let requests = control.run(&CellStateSnapshot::ZEROS);
+
+        assert_eq!(requests[1], ControlRequest::new(1, 2, 0, 0.5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn smoothed_control_rejects_negative_slew_rate() {
+        SmoothedControl::new(Box::new(AlternatingControl { sign: 1.0 })).with_smoothing(
+            1,
+            2,
+            Smoothing::MaxSlewRate { max_change: -0.5 },
+        );
+    }
+
+    // Requests +1 and -1 in turn on two channels of layer 1.
+    #[derive(Debug)]
+    struct AlternatingControl {
+        sign: f64,
+    }
+
+    impl CellControl for AlternatingControl {
+        fn run(&mut self, _cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+            let requests = vec![
+                ControlRequest::new(1, 1, 0, self.sign),
+                ControlRequest::new(1, 2, 0, self.sign),
+            ];
+            self.sign = -self.sign;
+            requests
+        }
+
+        fn spawn(&mut self) -> Box<dyn CellControl> {
+            Box::new(AlternatingControl { sign: 1.0 })
+        }
+    }
+
+    #[test]
+    fn composite_control_lets_higher_priority_children_override_requests() {
+        let mut control = CompositeCellControl::new()
+            .with_child(Box::new(ContinuousRequestsControl::new(vec![
+                ControlRequest::new(2, 3, 0, 5.0),
+            ])))
+            .with_child(Box::new(SimpleThrusterControl::new(
+                2,
+                1.0,
+                Deflection::from_radians(-1.0),
+            )));
+        let requests = control.run(&CellStateSnapshot::ZEROS);
+        assert_eq!(
+            requests,
+            vec![
+                ControlRequest::new(2, 3, 0, 5.0),
+                ControlRequest::new(2, 2, 0, 1.0)
+            ]
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use crate::physics::quantities::*;
+use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -25,6 +26,10 @@ impl ControlRequest {
         }
     }
 
+    pub fn id(&self) -> ControlRequestId {
+        self.id
+    }
+
     pub fn layer_index(&self) -> usize {
         self.id.layer_index()
     }
@@ -42,7 +47,7 @@ impl ControlRequest {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct ControlRequestId {
     layer_index: u16,
     channel_index: u16,
@@ -87,6 +92,73 @@ impl fmt::Display for ControlRequestId {
     }
 }
 
+// How a cell combines several requests with the same ID from one run of its control, e.g. from
+// the sources of a composite control, before costing them:
+//   LastWins: the value of the last request
+//   Sum: the sum of the values, e.g. for several sources that each add some growth
+//   Max: the largest value
+// The combined request takes the place of the first one, so requests keep their order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MergePolicy {
+    LastWins,
+    Sum,
+    Max,
+}
+
+// Merge policies by layer and channel. Channels without one use LastWins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestMergePolicies {
+    policies: HashMap<(usize, usize), MergePolicy>,
+}
+
+impl RequestMergePolicies {
+    pub const DEFAULT_POLICY: MergePolicy = MergePolicy::LastWins;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(
+        mut self,
+        layer_index: usize,
+        channel_index: usize,
+        policy: MergePolicy,
+    ) -> Self {
+        self.set_policy(layer_index, channel_index, policy);
+        self
+    }
+
+    pub fn set_policy(&mut self, layer_index: usize, channel_index: usize, policy: MergePolicy) {
+        self.policies.insert((layer_index, channel_index), policy);
+    }
+
+    pub fn policy(&self, layer_index: usize, channel_index: usize) -> MergePolicy {
+        self.policies
+            .get(&(layer_index, channel_index))
+            .copied()
+            .unwrap_or(Self::DEFAULT_POLICY)
+    }
+
+    pub fn merge(&self, requests: &[ControlRequest]) -> Vec<ControlRequest> {
+        // Controls make few requests, so a linear search beats hashing.
+        let mut merged: Vec<ControlRequest> = Vec::with_capacity(requests.len());
+        for request in requests {
+            match merged.iter_mut().find(|earlier| earlier.id == request.id) {
+                Some(earlier) => {
+                    let policy = self.policy(request.layer_index(), request.channel_index());
+                    earlier.requested_value = match policy {
+                        MergePolicy::LastWins => request.requested_value,
+                        MergePolicy::Sum => earlier.requested_value + request.requested_value,
+                        MergePolicy::Max => earlier.requested_value.max(request.requested_value),
+                    };
+                }
+                None => merged.push(*request),
+            }
+        }
+        merged
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CostedControlRequest {
     id: ControlRequestId,
@@ -152,6 +224,14 @@ impl CostedControlRequest {
     pub fn energy_delta(&self) -> BioEnergyDelta {
         self.energy_delta
     }
+
+    // Scales the request's cost, leaving any energy it yields as it is.
+    pub fn with_cost_scaled(mut self, factor: f64) -> Self {
+        if self.energy_delta.value() < 0.0 {
+            self.energy_delta = self.energy_delta * factor;
+        }
+        self
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -224,3 +304,126 @@ impl fmt::Display for BudgetedControlRequest {
         )
     }
 }
+
+// A budgeted control request, stamped with the age of the cell that made it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecordedControlRequest {
+    pub cell_age: u64,
+    pub request: BudgetedControlRequest,
+}
+
+// What a control request's energy goes to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EnergyUse {
+    Healing,
+    Growth,
+    Donation,
+    Thrust,
+    Other,
+}
+
+// Where a cell's energy came from and went to during one tick. Each entry is a net delta:
+// costs are negative and yields, like energy recovered by shrinking, are positive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnergyBudget {
+    pub influences: BioEnergyDelta,
+    pub healing: BioEnergyDelta,
+    pub growth: BioEnergyDelta,
+    pub donation: BioEnergyDelta,
+    pub thrust: BioEnergyDelta,
+    pub other: BioEnergyDelta,
+}
+
+impl EnergyBudget {
+    pub const ZERO: EnergyBudget = EnergyBudget {
+        influences: BioEnergyDelta::ZERO,
+        healing: BioEnergyDelta::ZERO,
+        growth: BioEnergyDelta::ZERO,
+        donation: BioEnergyDelta::ZERO,
+        thrust: BioEnergyDelta::ZERO,
+        other: BioEnergyDelta::ZERO,
+    };
+
+    pub fn add_request(&mut self, energy_use: EnergyUse, request: &BudgetedControlRequest) {
+        let delta = request.energy_delta() * request.budgeted_fraction();
+        let entry = match energy_use {
+            EnergyUse::Healing => &mut self.healing,
+            EnergyUse::Growth => &mut self.growth,
+            EnergyUse::Donation => &mut self.donation,
+            EnergyUse::Thrust => &mut self.thrust,
+            EnergyUse::Other => &mut self.other,
+        };
+        *entry += delta;
+    }
+
+    pub fn add(&mut self, other: &EnergyBudget) {
+        self.influences += other.influences;
+        self.healing += other.healing;
+        self.growth += other.growth;
+        self.donation += other.donation;
+        self.thrust += other.thrust;
+        self.other += other.other;
+    }
+
+    pub fn net(&self) -> BioEnergyDelta {
+        self.influences + self.healing + self.growth + self.donation + self.thrust + self.other
+    }
+}
+
+impl fmt::Display for EnergyBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "influences: {:.4}, healing: {:.4}, growth: {:.4}, donation: {:.4}, thrust: {:.4}, other: {:.4}",
+            self.influences.value(),
+            self.healing.value(),
+            self.growth.value(),
+            self.donation.value(),
+            self.thrust.value(),
+            self.other.value(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_keeps_last_request_by_default() {
+        let merged = RequestMergePolicies::new().merge(&[
+            ControlRequest::new(0, 1, 0, 1.0),
+            ControlRequest::new(1, 0, 0, 5.0),
+            ControlRequest::new(0, 1, 0, 2.0),
+        ]);
+        assert_eq!(
+            merged,
+            vec![
+                ControlRequest::new(0, 1, 0, 2.0),
+                ControlRequest::new(1, 0, 0, 5.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_sums_or_maxes_requests_on_chosen_channels() {
+        let policies = RequestMergePolicies::new()
+            .with_policy(0, 1, MergePolicy::Sum)
+            .with_policy(0, 2, MergePolicy::Max);
+        let merged = policies.merge(&[
+            ControlRequest::new(0, 1, 0, 1.0),
+            ControlRequest::new(0, 2, 0, 3.0),
+            ControlRequest::new(0, 1, 0, 2.0),
+            ControlRequest::new(0, 2, 0, -1.0),
+            ControlRequest::new(0, 1, 1, 4.0),
+        ]);
+        assert_eq!(
+            merged,
+            vec![
+                ControlRequest::new(0, 1, 0, 3.0),
+                ControlRequest::new(0, 2, 0, 3.0),
+                ControlRequest::new(0, 1, 1, 4.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::biology::cell::CellId;
+use std::collections::{HashMap, HashSet};
+use std::iter;
+
+// Who budded whom, for every birth since the genealogy was attached to its world, so that a
+// cell's ancestors and descendants can be found after some of them have died. Dead cells are
+// forgotten once none of their descendants are alive, so the genealogy grows with the living
+// population and its ancestry, not with every birth.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Genealogy {
+    parents: HashMap<CellId, CellId>,
+    children: HashMap<CellId, Vec<CellId>>,
+    // Dead cells with living descendants
+    dead: HashSet<CellId>,
+    // Counts changes, so that views can tell when to redraw
+    revision: u64,
+}
+
+impl Genealogy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_birth(&mut self, parent: CellId, child: CellId) {
+        self.parents.insert(child, parent);
+        self.children.entry(parent).or_default().push(child);
+        self.revision += 1;
+    }
+
+    // Forgets the cell, and then any of its ancestors, that has no living descendants.
+    pub fn record_death(&mut self, id: CellId) {
+        self.revision += 1;
+        let mut current = id;
+        while self.children(current).is_empty() {
+            self.dead.remove(&current);
+            self.children.remove(&current);
+            let parent = match self.parents.remove(&current) {
+                Some(parent) => parent,
+                None => return,
+            };
+            let siblings = self.children.get_mut(&parent).unwrap();
+            siblings.retain(|&sibling| sibling != current);
+            if !siblings.is_empty() || !self.dead.contains(&parent) {
+                return;
+            }
+            current = parent;
+        }
+        self.dead.insert(current);
+    }
+
+    pub fn is_dead(&self, id: CellId) -> bool {
+        self.dead.contains(&id)
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    pub fn parent(&self, id: CellId) -> Option<CellId> {
+        self.parents.get(&id).copied()
+    }
+
+    // In birth order.
+    pub fn children(&self, id: CellId) -> &[CellId] {
+        self.children.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    // Parent first, as far back as births were recorded.
+    pub fn ancestors(&self, id: CellId) -> impl Iterator<Item = CellId> + '_ {
+        iter::successors(self.parent(id), move |&ancestor| self.parent(ancestor))
+    }
+
+    // Depth first, each with its generation below the cell, i.e. 1 for its children. Every
+    // descendant has living cells in its branch, since dead branches are forgotten. The
+    // children of cells the filter rejects, e.g. collapsed ones in a view, are left out.
+    pub fn descendants<F>(&self, id: CellId, expand: F) -> Descendants<'_, F>
+    where
+        F: Fn(CellId) -> bool,
+    {
+        let mut descendants = Descendants {
+            genealogy: self,
+            expand,
+            stack: vec![],
+        };
+        descendants.push_children(id, 1);
+        descendants
+    }
+}
+
+pub struct Descendants<'a, F> {
+    genealogy: &'a Genealogy,
+    expand: F,
+    stack: Vec<(CellId, usize)>,
+}
+
+impl<'a, F> Descendants<'a, F> {
+    fn push_children(&mut self, id: CellId, generation: usize) {
+        self.stack.extend(
+            self.genealogy
+                .children(id)
+                .iter()
+                .rev()
+                .map(|&child| (child, generation)),
+        );
+    }
+}
+
+impl<'a, F> Iterator for Descendants<'a, F>
+where
+    F: Fn(CellId) -> bool,
+{
+    type Item = (CellId, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (current, generation) = self.stack.pop()?;
+        if (self.expand)(current) {
+            self.push_children(current, generation + 1);
+        }
+        Some((current, generation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ancestors_are_nearest_first() {
+        let mut genealogy = Genealogy::new();
+        genealogy.record_birth(id(1), id(2));
+        genealogy.record_birth(id(2), id(3));
+
+        assert_eq!(ancestors(&genealogy, id(3)), vec![id(2), id(1)]);
+        assert!(ancestors(&genealogy, id(1)).is_empty());
+    }
+
+    #[test]
+    fn descendants_are_depth_first_with_generations() {
+        let mut genealogy = Genealogy::new();
+        genealogy.record_birth(id(1), id(2));
+        genealogy.record_birth(id(1), id(3));
+        genealogy.record_birth(id(2), id(4));
+
+        assert_eq!(
+            genealogy.descendants(id(1), |_| true).collect::<Vec<_>>(),
+            vec![(id(2), 1), (id(4), 2), (id(3), 1)]
+        );
+    }
+
+    #[test]
+    fn descendants_leave_out_children_of_unexpanded_cells() {
+        let mut genealogy = Genealogy::new();
+        genealogy.record_birth(id(1), id(2));
+        genealogy.record_birth(id(1), id(3));
+        genealogy.record_birth(id(3), id(4));
+
+        assert_eq!(
+            genealogy
+                .descendants(id(1), |cell_id| cell_id != id(3))
+                .collect::<Vec<_>>(),
+            vec![(id(2), 1), (id(3), 1)]
+        );
+    }
+
+    #[test]
+    fn long_lineages_are_walked_lazily_without_recursion() {
+        let mut genealogy = Genealogy::new();
+        for value in 1..100_000 {
+            genealogy.record_birth(id(value), id(value + 1));
+        }
+
+        assert_eq!(genealogy.ancestors(id(100_000)).take(2).count(), 2);
+        assert_eq!(genealogy.descendants(id(1), |_| true).count(), 99_999);
+    }
+
+    #[test]
+    fn dead_cells_are_forgotten_once_no_descendants_live() {
+        let mut genealogy = Genealogy::new();
+        genealogy.record_birth(id(1), id(2));
+        genealogy.record_birth(id(2), id(3));
+        genealogy.record_birth(id(1), id(4));
+
+        genealogy.record_death(id(1));
+        genealogy.record_death(id(2));
+
+        assert!(genealogy.is_dead(id(2)));
+        assert_eq!(ancestors(&genealogy, id(3)), vec![id(2), id(1)]);
+
+        genealogy.record_death(id(3));
+
+        assert!(!genealogy.is_dead(id(2)));
+        assert_eq!(genealogy.children(id(1)), &[id(4)]);
+        assert_eq!(genealogy.len(), 1);
+
+        genealogy.record_death(id(4));
+
+        assert!(genealogy.is_empty());
+        assert!(!genealogy.is_dead(id(1)));
+    }
+
+    fn ancestors(genealogy: &Genealogy, cell_id: CellId) -> Vec<CellId> {
+        genealogy.ancestors(cell_id).collect()
+    }
+
+    fn id(value: u64) -> CellId {
+        CellId::new(value)
+    }
+}
@@ -2,9 +2,11 @@
 // by Kenneth O. Stanley and Risto Miikkulainen
 // http://nn.cs.utexas.edu/downloads/papers/stanley.ec02.pdf
 
+use crate::state_hash::StateHasher;
 use rand::{Rng, SeedableRng};
 use rand_distr::StandardNormal;
 use rand_pcg::Pcg64Mcg;
+use std::collections::HashSet;
 use std::f32;
 use std::fmt;
 use std::fmt::{Error, Formatter};
@@ -12,6 +14,7 @@ use std::fmt::{Error, Formatter};
 type Coefficient = f32;
 type VecIndex = u16;
 type NodeValue = f32;
+pub type InnovationNumber = u32;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct SparseNeuralNet {
@@ -40,9 +43,48 @@ impl SparseNeuralNet {
         self.node_values[index as usize]
     }
 
+    // All the node values, which carry a recurrent net's state from one run to the next.
+    pub fn node_values(&self) -> &[NodeValue] {
+        &self.node_values
+    }
+
+    pub fn restore_node_values(&mut self, node_values: &[NodeValue]) {
+        assert_eq!(node_values.len(), self.node_values.len());
+        self.node_values.copy_from_slice(node_values);
+    }
+
+    // The first node whose value is NaN or infinite, if any.
+    pub fn first_non_finite_node(&self) -> Option<(VecIndex, NodeValue)> {
+        self.node_values
+            .iter()
+            .position(|value| !value.is_finite())
+            .map(|index| (index as VecIndex, self.node_values[index]))
+    }
+
+    // Zeroes all the node values, as in a new net.
+    pub fn reset(&mut self) {
+        for value in &mut self.node_values {
+            *value = 0.0;
+        }
+    }
+
     pub fn run(&mut self) {
         self.genome.run(&mut self.node_values);
     }
+
+    pub fn use_fast_transfer_fns(&mut self) {
+        self.genome.use_fast_transfer_fns();
+    }
+
+    // Hashes the genome and the node values, for the world's state hashes.
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        for op in &self.genome.ops {
+            op.hash_state(hasher);
+        }
+        for &value in &self.node_values {
+            hasher.write_f64(value as f64);
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -50,6 +92,8 @@ pub struct SparseNeuralNetGenome {
     ops: Vec<Op>,
     transfer_fn: TransferFn,
     num_nodes: VecIndex,
+    // Nodes whose values are read from outside the net, which pruning must keep
+    output_nodes: Vec<VecIndex>,
 }
 
 impl SparseNeuralNetGenome {
@@ -58,14 +102,35 @@ impl SparseNeuralNetGenome {
             ops: vec![],
             transfer_fn,
             num_nodes: 0,
+            output_nodes: vec![],
         }
     }
 
+    pub fn set_output_nodes(&mut self, output_nodes: &[VecIndex]) {
+        self.output_nodes = output_nodes.to_vec();
+    }
+
+    // Connects a node that uses the genome's transfer function.
     pub fn connect_node(
         &mut self,
         to_value_index: VecIndex,
         bias: Coefficient,
         from_value_weights: &[(VecIndex, Coefficient)],
+    ) {
+        self.connect_node_with_transfer_fn(
+            to_value_index,
+            self.transfer_fn,
+            bias,
+            from_value_weights,
+        );
+    }
+
+    pub fn connect_node_with_transfer_fn(
+        &mut self,
+        to_value_index: VecIndex,
+        transfer_fn: TransferFn,
+        bias: Coefficient,
+        from_value_weights: &[(VecIndex, Coefficient)],
     ) {
         self.grow_num_nodes_if_needed(to_value_index);
         self.ops.push(Op::Bias {
@@ -78,12 +143,126 @@ impl SparseNeuralNetGenome {
                 from_value_index: *from_value_index,
                 to_value_index,
                 weight: *weight,
+                innovation: Self::innovation_number(*from_value_index, to_value_index),
             });
         }
         self.ops.push(Op::Transfer {
             value_index: to_value_index,
-            transfer_fn: self.transfer_fn,
+            transfer_fn,
+        });
+    }
+
+    // Identifies a connection gene across genomes, to line genes up for crossover. There is no
+    // add-node mutation that could renumber nodes, so a connection's endpoints identify it, and
+    // the same connection gets the same number in every genome without a shared registry.
+    pub fn innovation_number(
+        from_value_index: VecIndex,
+        to_value_index: VecIndex,
+    ) -> InnovationNumber {
+        (InnovationNumber::from(from_value_index) << 16) | InnovationNumber::from(to_value_index)
+    }
+
+    // The connection genes' innovation numbers and weights, in innovation number order.
+    pub fn connection_genes(&self) -> Vec<(InnovationNumber, Coefficient)> {
+        let mut genes: Vec<(InnovationNumber, Coefficient)> = self
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Connection {
+                    innovation, weight, ..
+                } => Some((*innovation, *weight)),
+                _ => None,
+            })
+            .collect();
+        genes.sort_by_key(|(innovation, _)| *innovation);
+        genes
+    }
+
+    // NEAT's compatibility distance, with excess and disjoint genes weighted the same: the
+    // fraction of genes that only one genome has, times unmatched_coefficient, plus the mean
+    // weight difference of the matching genes, times weight_coefficient.
+    pub fn compatibility_distance(
+        &self,
+        other: &SparseNeuralNetGenome,
+        unmatched_coefficient: f32,
+        weight_coefficient: f32,
+    ) -> f32 {
+        let genes = self.connection_genes();
+        let other_genes = other.connection_genes();
+        let num_genes = genes.len().max(other_genes.len());
+        if num_genes == 0 {
+            return 0.0;
+        }
+
+        let mut num_matching = 0;
+        let mut total_weight_difference = 0.0;
+        let mut other_iter = other_genes.iter().peekable();
+        for (innovation, weight) in &genes {
+            while let Some((other_innovation, other_weight)) = other_iter.peek() {
+                if other_innovation > innovation {
+                    break;
+                }
+                if other_innovation == innovation {
+                    num_matching += 1;
+                    total_weight_difference += (weight - other_weight).abs();
+                }
+                other_iter.next();
+            }
+        }
+
+        let num_unmatched = genes.len() + other_genes.len() - 2 * num_matching;
+        let mean_weight_difference = if num_matching == 0 {
+            0.0
+        } else {
+            total_weight_difference / num_matching as f32
+        };
+        unmatched_coefficient * num_unmatched as f32 / num_genes as f32
+            + weight_coefficient * mean_weight_difference
+    }
+
+    // Replaces the transfer functions with their fast approximations, where they have them.
+    pub fn use_fast_transfer_fns(&mut self) {
+        self.transfer_fn = self.transfer_fn.fast_approximation();
+        for op in &mut self.ops {
+            if let Op::Transfer { transfer_fn, .. } = op {
+                *transfer_fn = transfer_fn.fast_approximation();
+            }
+        }
+    }
+
+    // Removes connections weaker than min_abs_weight, then the nodes whose values are no longer
+    // used, i.e. that are not outputs and that no connection reads. Without output nodes, it
+    // can't tell which nodes are used, so it only removes connections.
+    pub fn prune(&mut self, min_abs_weight: Coefficient) {
+        self.ops.retain(|op| match op {
+            Op::Connection { weight, .. } => weight.abs() >= min_abs_weight,
+            _ => true,
         });
+        if self.output_nodes.is_empty() {
+            return;
+        }
+
+        loop {
+            let read_nodes: HashSet<VecIndex> = self
+                .ops
+                .iter()
+                .filter_map(|op| match op {
+                    Op::Connection {
+                        from_value_index, ..
+                    } => Some(*from_value_index),
+                    _ => None,
+                })
+                .collect();
+            let output_nodes = &self.output_nodes;
+            let num_ops = self.ops.len();
+            self.ops.retain(|op| {
+                let node = op.target_index();
+                read_nodes.contains(&node) || output_nodes.contains(&node)
+            });
+            if self.ops.len() == num_ops {
+                break;
+            }
+        }
     }
 
     fn grow_num_nodes_if_needed(&mut self, new_index: VecIndex) {
@@ -97,16 +276,30 @@ impl SparseNeuralNetGenome {
     }
 
     pub fn spawn(&self, randomness: &mut dyn MutationRandomness) -> Self {
-        SparseNeuralNetGenome {
-            ops: Self::copy_with_mutated_weights(&self.ops, randomness),
+        let mut genome = SparseNeuralNetGenome {
+            ops: Self::copy_with_mutations(&self.ops, randomness),
             transfer_fn: self.transfer_fn,
             num_nodes: self.num_nodes,
+            output_nodes: self.output_nodes.clone(),
+        };
+        if let Some(min_abs_weight) = randomness.prune_min_abs_weight() {
+            genome.prune(min_abs_weight);
         }
+        genome
     }
 
-    fn copy_with_mutated_weights(ops: &[Op], randomness: &mut dyn MutationRandomness) -> Vec<Op> {
+    fn copy_with_mutations(ops: &[Op], randomness: &mut dyn MutationRandomness) -> Vec<Op> {
         ops.iter()
-            .map(|op| op.copy_with_mutated_weight(|weight| randomness.mutate_weight(weight)))
+            .map(|op| match op {
+                Op::Transfer {
+                    value_index,
+                    transfer_fn,
+                } => Op::Transfer {
+                    value_index: *value_index,
+                    transfer_fn: randomness.mutate_transfer_fn(*transfer_fn),
+                },
+                _ => op.copy_with_mutated_weight(|weight| randomness.mutate_weight(weight)),
+            })
             .collect()
     }
 }
@@ -121,6 +314,7 @@ enum Op {
         from_value_index: VecIndex,
         to_value_index: VecIndex,
         weight: Coefficient,
+        innovation: InnovationNumber,
     },
     Transfer {
         value_index: VecIndex,
@@ -129,6 +323,15 @@ enum Op {
 }
 
 impl Op {
+    // The node whose value the op sets.
+    fn target_index(&self) -> VecIndex {
+        match self {
+            Self::Bias { value_index, .. } => *value_index,
+            Self::Connection { to_value_index, .. } => *to_value_index,
+            Self::Transfer { value_index, .. } => *value_index,
+        }
+    }
+
     fn run(&self, node_values: &mut [NodeValue]) {
         match self {
             Self::Bias { value_index, bias } => {
@@ -140,6 +343,7 @@ impl Op {
                 from_value_index,
                 to_value_index,
                 weight,
+                ..
             } => {
                 let from_value = node_values[*from_value_index as usize];
                 let to_value = &mut node_values[*to_value_index as usize];
@@ -156,6 +360,38 @@ impl Op {
         }
     }
 
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        match self {
+            Self::Bias { value_index, bias } => {
+                hasher.write_u64(0);
+                hasher.write_u64(*value_index as u64);
+                hasher.write_f64(*bias as f64);
+            }
+
+            Self::Connection {
+                from_value_index,
+                to_value_index,
+                weight,
+                innovation,
+            } => {
+                hasher.write_u64(1);
+                hasher.write_u64(*from_value_index as u64);
+                hasher.write_u64(*to_value_index as u64);
+                hasher.write_f64(*weight as f64);
+                hasher.write_u64(*innovation as u64);
+            }
+
+            Self::Transfer {
+                value_index,
+                transfer_fn,
+            } => {
+                hasher.write_u64(2);
+                hasher.write_u64(*value_index as u64);
+                hasher.write_bytes(format!("{:?}", transfer_fn).as_bytes());
+            }
+        }
+    }
+
     fn copy_with_mutated_weight<F>(&self, mut mutate_weight: F) -> Self
     where
         F: FnMut(Coefficient) -> Coefficient,
@@ -170,10 +406,12 @@ impl Op {
                 from_value_index,
                 to_value_index,
                 weight,
+                innovation,
             } => Self::Connection {
                 from_value_index: *from_value_index,
                 to_value_index: *to_value_index,
                 weight: mutate_weight(*weight),
+                innovation: *innovation,
             },
 
             Self::Transfer {
@@ -199,6 +437,35 @@ impl TransferFn {
     pub const SIGMOIDAL: TransferFn = TransferFn {
         the_fn: Self::sigmoidal,
     };
+    pub const TANH: TransferFn = TransferFn { the_fn: Self::tanh };
+    pub const RELU: TransferFn = TransferFn { the_fn: Self::relu };
+    pub const SINE: TransferFn = TransferFn { the_fn: Self::sine };
+    // Approximations without exp, within 0.012 of SIGMOIDAL and 0.024 of TANH
+    pub const FAST_SIGMOIDAL: TransferFn = TransferFn {
+        the_fn: Self::fast_sigmoidal,
+    };
+    pub const FAST_TANH: TransferFn = TransferFn {
+        the_fn: Self::fast_tanh,
+    };
+
+    // The ones that structural mutation chooses among
+    pub const MUTATION_CHOICES: [TransferFn; 5] = [
+        Self::IDENTITY,
+        Self::SIGMOIDAL,
+        Self::TANH,
+        Self::RELU,
+        Self::SINE,
+    ];
+
+    const NAMED: [(&'static str, TransferFn); 7] = [
+        ("identity", Self::IDENTITY),
+        ("sigmoidal", Self::SIGMOIDAL),
+        ("tanh", Self::TANH),
+        ("relu", Self::RELU),
+        ("sine", Self::SINE),
+        ("fast_sigmoidal", Self::FAST_SIGMOIDAL),
+        ("fast_tanh", Self::FAST_TANH),
+    ];
 
     pub fn new(the_fn: fn(&mut NodeValue)) -> Self {
         TransferFn { the_fn }
@@ -217,6 +484,58 @@ impl TransferFn {
     fn sigmoidal_fn(val: NodeValue) -> NodeValue {
         1.0_f32 / (1.0_f32 + (-4.9_f32 * val).exp())
     }
+
+    fn tanh(value: &mut NodeValue) {
+        *value = value.tanh();
+    }
+
+    fn relu(value: &mut NodeValue) {
+        *value = value.max(0.0);
+    }
+
+    fn sine(value: &mut NodeValue) {
+        *value = value.sin();
+    }
+
+    fn fast_sigmoidal(value: &mut NodeValue) {
+        *value = Self::fast_sigmoidal_fn(*value);
+    }
+
+    // sigmoid(x) = (1 + tanh(x / 2)) / 2
+    fn fast_sigmoidal_fn(val: NodeValue) -> NodeValue {
+        0.5_f32 + 0.5_f32 * Self::fast_tanh_fn(2.45_f32 * val)
+    }
+
+    fn fast_tanh(value: &mut NodeValue) {
+        *value = Self::fast_tanh_fn(*value);
+    }
+
+    // Pade approximation, which reaches +/-1 at +/-3
+    fn fast_tanh_fn(val: NodeValue) -> NodeValue {
+        if val.abs() >= 3.0_f32 {
+            val.signum()
+        } else {
+            let val_sqr = val * val;
+            val * (27.0_f32 + val_sqr) / (27.0_f32 + 9.0_f32 * val_sqr)
+        }
+    }
+
+    pub fn name(self) -> Option<&'static str> {
+        Self::NAMED
+            .iter()
+            .find(|(_, transfer_fn)| *transfer_fn == self)
+            .map(|(name, _)| *name)
+    }
+
+    pub fn fast_approximation(self) -> TransferFn {
+        if self == Self::SIGMOIDAL {
+            Self::FAST_SIGMOIDAL
+        } else if self == Self::TANH {
+            Self::FAST_TANH
+        } else {
+            self
+        }
+    }
 }
 
 impl Clone for TransferFn {
@@ -227,8 +546,10 @@ impl Clone for TransferFn {
 
 impl fmt::Debug for TransferFn {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        // TODO match against constants and print name?
-        write!(f, "{}", self.the_fn as usize)
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", self.the_fn as usize),
+        }
     }
 }
 
@@ -243,6 +564,11 @@ pub struct MutationParameters {
     pub weight_mutation_probability: f32,
     pub weight_mutation_stdev: f32,
     pub add_node_probability: f32,
+    // Chance that a node's transfer function is replaced by one of TransferFn::MUTATION_CHOICES
+    pub transfer_fn_mutation_probability: f32,
+    // Chance that a spawned genome is pruned of weights weaker than prune_min_abs_weight
+    pub prune_probability: f32,
+    pub prune_min_abs_weight: f32,
 }
 
 impl MutationParameters {
@@ -250,6 +576,9 @@ impl MutationParameters {
         weight_mutation_probability: 0.0,
         weight_mutation_stdev: 0.0,
         add_node_probability: 0.0,
+        transfer_fn_mutation_probability: 0.0,
+        prune_probability: 0.0,
+        prune_min_abs_weight: 0.0,
     };
 
     fn _validate(&self) {
@@ -257,30 +586,48 @@ impl MutationParameters {
     }
 
     fn _is_probability(num: f32) -> bool {
-        0.0 <= num && num <= 1.0
+        (0.0..=1.0).contains(&num)
     }
 }
 
 pub trait MutationRandomness {
     fn mutate_weight(&mut self, weight: Coefficient) -> Coefficient;
+
+    fn mutate_transfer_fn(&mut self, transfer_fn: TransferFn) -> TransferFn {
+        transfer_fn
+    }
+
+    // If a spawned genome should be pruned, the weight below which to prune it.
+    fn prune_min_abs_weight(&mut self) -> Option<Coefficient> {
+        None
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct SeededMutationRandomness {
     rng: Pcg64Mcg,
-    mutation_parameters: &'static MutationParameters,
+    mutation_parameters: MutationParameters,
 }
 
 impl SeededMutationRandomness {
-    pub fn new(seed: u64, mutation_parameters: &'static MutationParameters) -> Self {
+    pub fn new(seed: u64, mutation_parameters: &MutationParameters) -> Self {
         SeededMutationRandomness {
             rng: rand_pcg::Pcg64Mcg::seed_from_u64(seed),
-            mutation_parameters,
+            mutation_parameters: *mutation_parameters,
         }
     }
 
     pub fn spawn(&mut self) -> Self {
-        Self::new(self.child_seed(), self.mutation_parameters)
+        Self::new(self.child_seed(), &self.mutation_parameters)
+    }
+
+    pub fn weight_mutation_probability(&self) -> f32 {
+        self.mutation_parameters.weight_mutation_probability
+    }
+
+    // Clamps the probability to [0, 1], e.g. for a rate set while the world is running.
+    pub fn set_weight_mutation_probability(&mut self, probability: f32) {
+        self.mutation_parameters.weight_mutation_probability = probability.clamp(0.0, 1.0);
     }
 
     pub fn child_seed(&mut self) -> u64 {
@@ -302,6 +649,27 @@ impl MutationRandomness for SeededMutationRandomness {
         let gaussian = self.rng.sample::<f32, _>(StandardNormal);
         weight + gaussian * self.mutation_parameters.weight_mutation_stdev * weight
     }
+
+    // Draws nothing when the mutation is off, so that seeded runs without it stay the same.
+    fn mutate_transfer_fn(&mut self, transfer_fn: TransferFn) -> TransferFn {
+        let probability = self.mutation_parameters.transfer_fn_mutation_probability;
+        if probability == 0.0 || !self.rng.gen_bool(probability as f64) {
+            return transfer_fn;
+        }
+
+        let choices = &TransferFn::MUTATION_CHOICES;
+        choices[self.rng.gen_range(0, choices.len())]
+    }
+
+    // Like mutate_transfer_fn, draws nothing when pruning is off.
+    fn prune_min_abs_weight(&mut self) -> Option<Coefficient> {
+        let probability = self.mutation_parameters.prune_probability;
+        if probability != 0.0 && self.rng.gen_bool(probability as f64) {
+            Some(self.mutation_parameters.prune_min_abs_weight)
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -372,6 +740,39 @@ mod tests {
         assert_eq!(nnet.node_value(2), 2.0);
     }
 
+    #[test]
+    fn restoring_node_values_restores_recurrent_state() {
+        let mut genome = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome.connect_node(1, 0.0, &[(0, 1.0), (2, 1.0)]);
+        genome.connect_node(2, 0.0, &[(1, 1.0)]);
+        let mut nnet = SparseNeuralNet::new(genome);
+        nnet.set_node_value(0, 1.0);
+        nnet.run();
+        let snapshot = nnet.node_values().to_vec();
+
+        nnet.run();
+        assert_eq!(nnet.node_value(1), 2.0);
+        nnet.run();
+        assert_eq!(nnet.node_value(1), 3.0);
+
+        nnet.restore_node_values(&snapshot);
+        nnet.run();
+        assert_eq!(nnet.node_value(1), 2.0);
+    }
+
+    #[test]
+    fn reset_zeroes_node_values() {
+        let mut genome = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome.connect_node(1, 0.5, &[(0, 1.0)]);
+        let mut nnet = SparseNeuralNet::new(genome);
+        nnet.set_node_value(0, 1.0);
+        nnet.run();
+
+        nnet.reset();
+
+        assert_eq!(nnet.node_values(), &[0.0, 0.0]);
+    }
+
     #[test]
     fn spawn_unmutated() {
         let mut genome = SparseNeuralNetGenome::new(TransferFn::SIGMOIDAL);
@@ -408,11 +809,13 @@ mod tests {
                     from_value_index: 0,
                     to_value_index: 2,
                     weight: 1.0,
+                    innovation: 2,
                 },
                 Op::Connection {
                     from_value_index: 1,
                     to_value_index: 2,
                     weight: 2.25,
+                    innovation: (1 << 16) | 2,
                 },
                 Op::Transfer {
                     value_index: 2,
@@ -422,6 +825,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn connection_genes_have_the_same_innovation_numbers_in_every_genome() {
+        let mut genome1 = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome1.connect_node(2, 0.0, &[(0, 1.0), (1, 1.0)]);
+        let mut genome2 = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome2.connect_node(3, 0.0, &[(0, 1.0)]);
+        genome2.connect_node(2, 0.0, &[(1, 0.5)]);
+
+        let innovation = SparseNeuralNetGenome::innovation_number(1, 2);
+        assert!(genome1.connection_genes().contains(&(innovation, 1.0)));
+        assert!(genome2.connection_genes().contains(&(innovation, 0.5)));
+    }
+
+    #[test]
+    fn compatibility_distance_counts_unmatched_genes_and_weight_differences() {
+        let mut genome1 = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome1.connect_node(2, 0.0, &[(0, 1.0), (1, 1.0)]);
+        let mut genome2 = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome2.connect_node(2, 0.0, &[(1, 0.5)]);
+        genome2.connect_node(3, 0.0, &[(0, 1.0)]);
+
+        assert_eq!(genome1.compatibility_distance(&genome1, 1.0, 1.0), 0.0);
+        // one matching gene, two unmatched genes out of two
+        assert_eq!(genome1.compatibility_distance(&genome2, 1.0, 2.0), 2.0);
+    }
+
     #[test]
     fn seeded_mutation_randomness_leaves_weight_unmutated() {
         let mut randomness = SeededMutationRandomness::new(0, &MutationParameters::NO_MUTATION);
@@ -440,6 +869,144 @@ mod tests {
         assert_ne!(randomness.mutate_weight(1.0), 1.0);
     }
 
+    #[test]
+    fn nodes_can_have_their_own_transfer_fns() {
+        let mut genome = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome.connect_node_with_transfer_fn(1, TransferFn::RELU, 0.0, &[(0, 1.0)]);
+        genome.connect_node(2, 0.0, &[(0, 1.0)]);
+
+        let mut nnet = SparseNeuralNet::new(genome);
+        nnet.set_node_value(0, -2.0);
+        nnet.run();
+
+        assert_eq!(nnet.node_value(1), 0.0);
+        assert_eq!(nnet.node_value(2), -2.0);
+    }
+
+    #[test]
+    fn spawn_can_mutate_transfer_fns() {
+        const ALWAYS_MUTATE_TRANSFER_FNS: MutationParameters = MutationParameters {
+            transfer_fn_mutation_probability: 1.0,
+            ..MutationParameters::NO_MUTATION
+        };
+
+        let mut genome = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome.connect_node(1, 0.0, &[(0, 1.0)]);
+        let mut randomness = SeededMutationRandomness::new(0, &ALWAYS_MUTATE_TRANSFER_FNS);
+
+        let transfer_fns: Vec<TransferFn> = (0..20)
+            .map(|_| match genome.spawn(&mut randomness).ops[2] {
+                Op::Transfer { transfer_fn, .. } => transfer_fn,
+                _ => panic!("Expected a transfer op"),
+            })
+            .collect();
+
+        assert!(transfer_fns
+            .iter()
+            .all(|transfer_fn| TransferFn::MUTATION_CHOICES.contains(transfer_fn)));
+        assert!(transfer_fns.contains(&TransferFn::SINE));
+    }
+
+    #[test]
+    fn disabled_transfer_fn_mutation_draws_nothing() {
+        let mut randomness = SeededMutationRandomness::new(0, &MutationParameters::NO_MUTATION);
+        let mut untouched = SeededMutationRandomness::new(0, &MutationParameters::NO_MUTATION);
+
+        assert_eq!(
+            randomness.mutate_transfer_fn(TransferFn::IDENTITY),
+            TransferFn::IDENTITY
+        );
+        assert_eq!(randomness.child_seed(), untouched.child_seed());
+    }
+
+    #[test]
+    fn disabled_pruning_draws_nothing() {
+        let mut randomness = SeededMutationRandomness::new(0, &MutationParameters::NO_MUTATION);
+        let mut untouched = SeededMutationRandomness::new(0, &MutationParameters::NO_MUTATION);
+
+        assert_eq!(randomness.prune_min_abs_weight(), None);
+        assert_eq!(randomness.child_seed(), untouched.child_seed());
+    }
+
+    #[test]
+    fn prune_removes_weak_connections_and_unused_nodes() {
+        let mut genome = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome.connect_node(1, 0.0, &[(0, 1.0)]);
+        genome.connect_node(2, 0.0, &[(1, 1.0)]);
+        genome.connect_node(3, 0.5, &[(1, 2.0), (2, 0.001)]);
+        genome.set_output_nodes(&[3]);
+
+        genome.prune(0.01);
+
+        let mut expected = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        expected.connect_node(1, 0.0, &[(0, 1.0)]);
+        expected.connect_node(3, 0.5, &[(1, 2.0)]);
+        expected.set_output_nodes(&[3]);
+        assert_eq!(genome, expected);
+    }
+
+    #[test]
+    fn prune_without_output_nodes_keeps_nodes() {
+        let mut genome = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+        genome.connect_node(1, 0.0, &[(0, 0.001)]);
+
+        genome.prune(0.01);
+
+        assert_eq!(
+            genome.ops,
+            vec![
+                Op::Bias {
+                    value_index: 1,
+                    bias: 0.0,
+                },
+                Op::Transfer {
+                    value_index: 1,
+                    transfer_fn: TransferFn::IDENTITY,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_fn_debug_prints_name() {
+        assert_eq!(format!("{:?}", TransferFn::TANH), "tanh");
+    }
+
+    #[test]
+    fn fast_sigmoidal_is_close_to_sigmoidal() {
+        let max_error = (-6000..=6000)
+            .map(|i| i as f32 / 1000.0)
+            .map(|val| (TransferFn::fast_sigmoidal_fn(val) - TransferFn::sigmoidal_fn(val)).abs())
+            .fold(0.0_f32, f32::max);
+        assert!(max_error < 0.012, "max error {}", max_error);
+    }
+
+    #[test]
+    fn fast_tanh_is_close_to_tanh() {
+        let max_error = (-6000..=6000)
+            .map(|i| i as f32 / 1000.0)
+            .map(|val| (TransferFn::fast_tanh_fn(val) - val.tanh()).abs())
+            .fold(0.0_f32, f32::max);
+        assert!(max_error < 0.024, "max error {}", max_error);
+    }
+
+    #[test]
+    fn fast_transfer_fns_replace_approximated_fns() {
+        let mut genome = SparseNeuralNetGenome::new(TransferFn::SIGMOIDAL);
+        genome.connect_node(1, 0.0, &[(0, 1.0)]);
+
+        genome.use_fast_transfer_fns();
+
+        assert_eq!(genome.transfer_fn, TransferFn::FAST_SIGMOIDAL);
+        assert_eq!(
+            genome.ops[2],
+            Op::Transfer {
+                value_index: 1,
+                transfer_fn: TransferFn::FAST_SIGMOIDAL,
+            }
+        );
+    }
+
     fn plus_one(value: &mut NodeValue) {
         *value += 1.0;
     }
@@ -0,0 +1,153 @@
+use crate::biology::cell::Cell;
+use crate::physics::quantities::*;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+
+// A batch of cells with the same genome -- the genome cell's layers, control, energy and
+// tags -- dropped into a resident population to see whether they take over. The invaders go
+// at positions chosen uniformly in a rectangle, reproducibly from the seed, and get an
+// inherited tag so their descendants can be counted.
+#[derive(Debug)]
+pub struct Invasion {
+    genome: Cell,
+    count: usize,
+    min_corner: Position,
+    max_corner: Position,
+    tag: String,
+    seed: u64,
+}
+
+impl Invasion {
+    pub const DEFAULT_TAG: &'static str = "invader";
+
+    pub fn new(genome: Cell, count: usize, min_corner: Position, max_corner: Position) -> Self {
+        assert!(min_corner.x() <= max_corner.x() && min_corner.y() <= max_corner.y());
+        Invasion {
+            genome,
+            count,
+            min_corner,
+            max_corner,
+            tag: Self::DEFAULT_TAG.to_string(),
+            seed: 0,
+        }
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = tag.to_string();
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    // The invading cells, placed and tagged. Makes the same cells every time.
+    pub fn create_invaders(&mut self) -> Vec<Cell> {
+        let mut rng = Pcg64Mcg::seed_from_u64(self.seed);
+        let mut invaders = Vec::with_capacity(self.count);
+        for _ in 0..self.count {
+            let mut invader = self.genome.duplicate();
+            invader.set_initial_position(Position::new(
+                Self::random_in_range(&mut rng, self.min_corner.x(), self.max_corner.x()),
+                Self::random_in_range(&mut rng, self.min_corner.y(), self.max_corner.y()),
+            ));
+            invader.add_tag_with_inheritance(&self.tag, true);
+            invaders.push(invader);
+        }
+        invaders
+    }
+
+    fn random_in_range(rng: &mut Pcg64Mcg, min: f64, max: f64) -> f64 {
+        if min == max {
+            min
+        } else {
+            rng.gen_range(min, max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::newtonian::NewtonianBody;
+    use crate::physics::shapes::Circle;
+
+    #[test]
+    fn invaders_are_tagged_copies_of_genome_inside_region() {
+        let genome = Cell::ball(
+            Length::new(2.0),
+            Mass::new(3.0),
+            Position::ORIGIN,
+            Velocity::ZERO,
+        )
+        .with_initial_energy(BioEnergy::new(5.0));
+        let mut invasion = Invasion::new(
+            genome,
+            10,
+            Position::new(-10.0, 0.0),
+            Position::new(-5.0, 20.0),
+        );
+
+        let invaders = invasion.create_invaders();
+
+        assert_eq!(invaders.len(), 10);
+        for invader in &invaders {
+            assert_eq!(invader.radius(), Length::new(2.0));
+            assert_eq!(invader.energy(), BioEnergy::new(5.0));
+            assert!(invader.has_tag(Invasion::DEFAULT_TAG));
+            assert!(invader.tags()[0].inherited);
+            let position = invader.position();
+            assert!(-10.0 <= position.x() && position.x() <= -5.0);
+            assert!(0.0 <= position.y() && position.y() <= 20.0);
+        }
+    }
+
+    #[test]
+    fn same_seed_places_invaders_the_same() {
+        let mut invasion1 = point_invasion().with_seed(3);
+        let mut invasion2 = point_invasion().with_seed(3);
+        let mut invasion3 = point_invasion().with_seed(4);
+
+        let positions1: Vec<Position> = invasion1
+            .create_invaders()
+            .iter()
+            .map(|cell| cell.position())
+            .collect();
+        let positions2: Vec<Position> = invasion2
+            .create_invaders()
+            .iter()
+            .map(|cell| cell.position())
+            .collect();
+        let positions3: Vec<Position> = invasion3
+            .create_invaders()
+            .iter()
+            .map(|cell| cell.position())
+            .collect();
+
+        assert_eq!(positions1, positions2);
+        assert_ne!(positions1, positions3);
+    }
+
+    fn point_invasion() -> Invasion {
+        Invasion::new(
+            Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ),
+            5,
+            Position::new(0.0, 0.0),
+            Position::new(10.0, 10.0),
+        )
+    }
+}
@@ -1,26 +1,36 @@
+use crate::biology::cell::Cell;
 use crate::biology::changes::*;
 use crate::biology::control_requests::*;
+use crate::document::*;
 use crate::environment::local_environment::LocalEnvironment;
 use crate::physics::overlap::Overlap;
 use crate::physics::quantities::*;
+use crate::physics::util::sqr;
+use serde::{Deserialize, Serialize};
 use std::f64;
 use std::f64::consts::PI;
 use std::fmt;
 use std::fmt::Debug;
 
 // TODO rename as TissueType?
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum Color {
     Green,
     White,
     Yellow,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Color {
+    pub const ALL: [Color; 3] = [Color::Green, Color::White, Color::Yellow];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LayerHealthParameters {
     pub healing_energy_delta: BioEnergyDelta,
     pub entropic_damage_health_delta: f64,
     pub overlap_damage_health_delta: f64,
+    // Newborns start out overlapping their parents, so spawned layers ignore overlaps this long
+    pub newborn_overlap_immunity_ticks: u32,
 }
 
 impl LayerHealthParameters {
@@ -28,6 +38,7 @@ impl LayerHealthParameters {
         healing_energy_delta: BioEnergyDelta::ZERO,
         entropic_damage_health_delta: 0.0,
         overlap_damage_health_delta: 0.0,
+        newborn_overlap_immunity_ticks: 0,
     };
 
     fn validate(&self) {
@@ -37,7 +48,7 @@ impl LayerHealthParameters {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LayerResizeParameters {
     pub growth_energy_delta: BioEnergyDelta,
     pub max_growth_rate: f64,
@@ -68,8 +79,8 @@ pub struct CellLayer {
 }
 
 impl CellLayer {
-    const HEALING_CHANNEL_INDEX: usize = 0;
-    const RESIZE_CHANNEL_INDEX: usize = 1;
+    pub const HEALING_CHANNEL_INDEX: usize = 0;
+    pub const RESIZE_CHANNEL_INDEX: usize = 1;
     const LIVING_BRAIN: LivingCellLayerBrain = LivingCellLayerBrain {};
     const DEAD_BRAIN: DeadCellLayerBrain = DeadCellLayerBrain {};
 
@@ -85,24 +96,50 @@ impl CellLayer {
         }
     }
 
-    pub fn with_health_parameters(
-        mut self,
-        health_parameters: &'static LayerHealthParameters,
-    ) -> Self {
+    pub fn with_health_parameters(mut self, health_parameters: LayerHealthParameters) -> Self {
         health_parameters.validate();
         self.body.health_parameters = health_parameters;
         self
     }
 
-    pub fn with_resize_parameters(
-        mut self,
-        resize_parameters: &'static LayerResizeParameters,
-    ) -> Self {
+    pub fn with_resize_parameters(mut self, resize_parameters: LayerResizeParameters) -> Self {
         resize_parameters.validate();
         self.body.resize_parameters = resize_parameters;
         self
     }
 
+    pub fn from_document(document: &LayerDocument) -> Self {
+        let layer = Self::new(
+            Area::new(document.area),
+            Density::new(document.density),
+            document.color,
+            document.specialty.to_specialty(),
+        )
+        .with_health_parameters(document.health_parameters.to_parameters())
+        .with_resize_parameters(document.resize_parameters.to_parameters());
+        if document.health > 0.0 {
+            layer.with_health(document.health)
+        } else {
+            layer.dead()
+        }
+    }
+
+    pub fn to_document(&self) -> LayerDocument {
+        LayerDocument {
+            area: self.body.area.value(),
+            density: self.body.density.value(),
+            color: self.body.color,
+            health: self.body.health,
+            health_parameters: LayerHealthParametersDocument::from_parameters(
+                &self.body.health_parameters,
+            ),
+            resize_parameters: LayerResizeParametersDocument::from_parameters(
+                &self.body.resize_parameters,
+            ),
+            specialty: self.specialty.to_document(),
+        }
+    }
+
     pub fn with_health(mut self, health: f64) -> Self {
         assert!(health >= 0.0);
         self.body.health = health;
@@ -129,10 +166,17 @@ impl CellLayer {
         self.body.outer_radius
     }
 
+    // The layer's tissue type.
     pub fn color(&self) -> Color {
         self.body.color
     }
 
+    // The color other cells see and the renderer draws: the layer's pigment, if it has one,
+    // or else its tissue type's color.
+    pub fn visible_color(&self) -> Color {
+        self.specialty.pigment().unwrap_or(self.body.color)
+    }
+
     pub fn health(&self) -> f64 {
         self.body.health
     }
@@ -145,12 +189,17 @@ impl CellLayer {
         self.body.mass
     }
 
+    pub fn resize_parameters(&self) -> &LayerResizeParameters {
+        &self.body.resize_parameters
+    }
+
     pub fn damage(&mut self, health_loss: f64) {
         self.body.brain.damage(&mut self.body, health_loss);
     }
 
-    pub fn update_outer_radius(&mut self, inner_radius: Length) {
-        self.body.update_outer_radius(inner_radius);
+    // The layer wraps the capsule inside it, whose axis is axis_length long.
+    pub fn update_outer_radius(&mut self, inner_radius: Length, axis_length: Length) {
+        self.body.update_outer_radius(inner_radius, axis_length);
     }
 
     pub fn after_influences(&mut self, env: &LocalEnvironment) -> (BioEnergy, Force) {
@@ -184,6 +233,62 @@ impl CellLayer {
         self.specialty.reset();
     }
 
+    pub fn siphon(&self) -> Option<Siphon> {
+        if self.is_alive() {
+            self.specialty.siphon(&self.body)
+        } else {
+            None
+        }
+    }
+
+    pub fn sensor(&self) -> Option<Sensor> {
+        if self.is_alive() {
+            self.specialty.sensor()
+        } else {
+            None
+        }
+    }
+
+    // Dead layers sense nothing, even during the tick they die in.
+    pub fn sensor_reading(&self) -> Option<SensorReading> {
+        if self.is_alive() {
+            self.specialty.sensor_reading()
+        } else {
+            None
+        }
+    }
+
+    // Dead layers keep their shape.
+    pub fn elongation(&self) -> Length {
+        self.specialty.elongation()
+    }
+
+    // Dead layers don't turn their cells.
+    pub fn turn(&self) -> Deflection {
+        if self.is_alive() {
+            self.specialty.turn()
+        } else {
+            Deflection::ZERO
+        }
+    }
+
+    pub fn set_sensor_reading(&mut self, reading: Option<SensorReading>) {
+        self.specialty.set_sensor_reading(reading);
+    }
+
+    // How much the layer resists parasites when it is a cell's outer layer.
+    pub fn shell_strength(&self) -> f64 {
+        self.body.area.value() * self.body.health
+    }
+
+    pub fn energy_use(&self, channel_index: usize) -> EnergyUse {
+        match channel_index {
+            Self::HEALING_CHANNEL_INDEX => EnergyUse::Healing,
+            Self::RESIZE_CHANNEL_INDEX => EnergyUse::Growth,
+            _ => self.specialty.energy_use(channel_index),
+        }
+    }
+
     pub fn healing_request(layer_index: usize, delta_health: f64) -> ControlRequest {
         ControlRequest::new(layer_index, Self::HEALING_CHANNEL_INDEX, 0, delta_health)
     }
@@ -212,10 +317,11 @@ pub struct CellLayerBody {
     outer_radius: Length,
     health: f64,
     color: Color,
+    overlap_immunity_ticks: u32,
     brain: &'static dyn CellLayerBrain,
     // TODO move to CellLayerParameters struct?
-    health_parameters: &'static LayerHealthParameters,
-    resize_parameters: &'static LayerResizeParameters,
+    health_parameters: LayerHealthParameters,
+    resize_parameters: LayerResizeParameters,
 }
 
 impl CellLayerBody {
@@ -227,9 +333,10 @@ impl CellLayerBody {
             outer_radius: Length::ZERO,
             health: 1.0,
             color,
+            overlap_immunity_ticks: 0,
             brain: &CellLayer::LIVING_BRAIN,
-            health_parameters: &LayerHealthParameters::DEFAULT,
-            resize_parameters: &LayerResizeParameters::UNLIMITED,
+            health_parameters: LayerHealthParameters::DEFAULT,
+            resize_parameters: LayerResizeParameters::UNLIMITED,
         };
         body.init_from_area();
         body
@@ -239,6 +346,7 @@ impl CellLayerBody {
         let mut copy = Self {
             area,
             health: 1.0,
+            overlap_immunity_ticks: self.health_parameters.newborn_overlap_immunity_ticks,
             brain: &CellLayer::LIVING_BRAIN,
             ..*self
         };
@@ -256,8 +364,17 @@ impl CellLayerBody {
         self.health = (self.health - health_loss).max(0.0);
     }
 
-    fn update_outer_radius(&mut self, inner_radius: Length) {
-        self.outer_radius = (inner_radius.sqr() + self.area / PI).sqrt();
+    // Solves PI * R^2 + 2 * R * L = PI * r^2 + 2 * r * L + area for the outer radius R, so the
+    // layer's area stays the same however long the axis grows; the capsule thins instead.
+    fn update_outer_radius(&mut self, inner_radius: Length, axis_length: Length) {
+        if axis_length == Length::ZERO {
+            self.outer_radius = (inner_radius.sqr() + self.area / PI).sqrt();
+            return;
+        }
+        let axis = axis_length.value();
+        let inner = inner_radius.value();
+        let total_area = PI * inner * inner + 2.0 * inner * axis + self.area.value();
+        self.outer_radius = Length::new(((axis * axis + PI * total_area).sqrt() - axis) / PI);
     }
 
     fn cost_restore_health(&self, request: ControlRequest) -> CostedControlRequest {
@@ -280,7 +397,7 @@ impl CellLayerBody {
     }
 
     fn restore_health(&mut self, delta_health: f64) {
-        self.health = self.health + delta_health;
+        self.health += delta_health;
     }
 
     fn actual_delta_health(&self, requested_delta_health: f64, budgeted_fraction: f64) -> f64 {
@@ -316,7 +433,7 @@ impl CellLayerBody {
     }
 }
 
-trait CellLayerBrain: Debug {
+trait CellLayerBrain: Debug + Sync {
     fn damage(&self, body: &mut CellLayerBody, health_loss: f64);
 
     fn after_influences(
@@ -353,8 +470,13 @@ impl LivingCellLayerBrain {
     }
 
     fn overlap_damage(&self, body: &mut CellLayerBody, overlaps: &[Overlap]) {
+        if body.overlap_immunity_ticks > 0 {
+            body.overlap_immunity_ticks -= 1;
+            return;
+        }
         let overlap_damage = overlaps.iter().fold(0.0, |total_damage, overlap| {
-            total_damage + body.health_parameters.overlap_damage_health_delta * overlap.magnitude()
+            total_damage
+                + body.health_parameters.overlap_damage_health_delta * overlap.depth().value()
         });
         self.damage(body, -overlap_damage);
     }
@@ -402,8 +524,8 @@ impl CellLayerBrain for LivingCellLayerBrain {
     ) {
         match request.channel_index() {
             CellLayer::HEALING_CHANNEL_INDEX => {
-                let delta_health =
-                    body.actual_delta_health(request.requested_value(), request.budgeted_fraction());
+                let delta_health = body
+                    .actual_delta_health(request.requested_value(), request.budgeted_fraction());
                 body.restore_health(delta_health);
 
                 let layer_changes = &mut changes.layers[request.layer_index()];
@@ -432,10 +554,11 @@ impl CellLayerBrain for DeadCellLayerBrain {
 
     fn after_influences(
         &self,
-        _specialty: &mut dyn CellLayerSpecialty,
+        specialty: &mut dyn CellLayerSpecialty,
         _body: &mut CellLayerBody,
         _env: &LocalEnvironment,
     ) -> (BioEnergy, Force) {
+        specialty.set_sensor_reading(None);
         (BioEnergy::ZERO, Force::ZERO)
     }
 
@@ -469,9 +592,11 @@ impl CellLayerSpecialtySpawn for Box<dyn CellLayerSpecialty> {
     }
 }
 
-pub trait CellLayerSpecialty: Debug {
+pub trait CellLayerSpecialty: Debug + Send {
     fn box_spawn(&self) -> Box<dyn CellLayerSpecialty>;
 
+    fn to_document(&self) -> SpecialtyDocument;
+
     fn after_influences(
         &mut self,
         _body: &CellLayerBody,
@@ -498,14 +623,102 @@ pub trait CellLayerSpecialty: Debug {
         panic!("Invalid control channel index: {}", request.channel_index());
     }
 
+    fn energy_use(&self, _channel_index: usize) -> EnergyUse {
+        EnergyUse::Other
+    }
+
+    // If the layer is a parasite's, how it siphons energy from its host.
+    fn siphon(&self, _body: &CellLayerBody) -> Option<Siphon> {
+        None
+    }
+
+    // If the layer senses other cells, what it senses.
+    fn sensor(&self) -> Option<Sensor> {
+        None
+    }
+
+    fn sensor_reading(&self) -> Option<SensorReading> {
+        None
+    }
+
+    fn set_sensor_reading(&mut self, _reading: Option<SensorReading>) {}
+
+    // How far the layer stretches its cell along the cell's heading.
+    fn elongation(&self) -> Length {
+        Length::ZERO
+    }
+
+    // How far the layer turns its cell's heading each tick.
+    fn turn(&self) -> Deflection {
+        Deflection::ZERO
+    }
+
+    // If the layer has taken on a pigment, its color.
+    fn pigment(&self) -> Option<Color> {
+        None
+    }
+
     fn reset(&mut self) {}
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Siphon {
+    // The energy siphoned per tick from a host without a shell
+    pub rate: BioEnergy,
+    // A host's shell divides the rate by 1 + shell_resistance * shell strength
+    pub shell_resistance: f64,
+}
+
+impl Siphon {
+    pub fn energy_from_host(&self, host_shell_strength: f64) -> BioEnergy {
+        self.rate * (1.0 / (1.0 + self.shell_resistance * host_shell_strength))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sensor {
+    pub range: Length,
+    // Only cells with a layer of this color, if any
+    pub target_color: Option<Color>,
+}
+
+impl Sensor {
+    pub fn senses(&self, cell: &Cell) -> bool {
+        self.target_color.is_none_or(|color| {
+            cell.layers()
+                .iter()
+                .any(|layer| layer.visible_color() == color)
+        })
+    }
+}
+
+// The nearest sensed cell: the distance between the cells' centers, and the direction to it
+// relative to the sensing cell's heading.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SensorReading {
+    pub distance: Length,
+    pub bearing: Angle,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct BondRequest {
     pub retain_bond: bool,
     pub budding_angle: Angle,
     pub donation_energy: BioEnergy,
+    // Offers this free bond slot for a bond with an overlapping, unrelated cell
+    pub adhere: bool,
+    // Area of each of a budded child's layers; zero means the default
+    pub child_layer_area: Area,
+    // Energy a budded child starts with, in addition to the donation through its bond
+    pub child_energy: BioEnergy,
+    // If either is nonzero, a budded child starts as a spore that hatches on that condition
+    pub child_hatch_light_intensity: f64,
+    pub child_hatch_ticks: u32,
+    // The budded child's slot for its bond to the parent. A request with a slot out of range
+    // buds no child.
+    pub child_bond_index: usize,
+    // A budded child's heading, relative to the parent's
+    pub child_heading_offset: Deflection,
 }
 
 impl BondRequest {
@@ -515,6 +728,13 @@ impl BondRequest {
         retain_bond: false,
         budding_angle: Angle::ZERO,
         donation_energy: BioEnergy::ZERO,
+        adhere: false,
+        child_layer_area: Area::ZERO,
+        child_energy: BioEnergy::ZERO,
+        child_hatch_light_intensity: 0.0,
+        child_hatch_ticks: 0,
+        child_bond_index: 0,
+        child_heading_offset: Deflection::ZERO,
     };
 
     pub fn reset(&mut self) {
@@ -526,10 +746,17 @@ impl fmt::Display for BondRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "(retain: {}, angle: {:.4}, energy: {:.4})",
+            "(retain: {}, angle: {:.4}, energy: {:.4}, adhere: {}, child area: {:.4}, child energy: {:.4}, hatch light: {:.4}, hatch ticks: {}, child bond: {}, child heading offset: {:.4})",
             self.retain_bond,
             self.budding_angle.radians(),
             self.donation_energy.value(),
+            self.adhere,
+            self.child_layer_area.value(),
+            self.child_energy.value(),
+            self.child_hatch_light_intensity,
+            self.child_hatch_ticks,
+            self.child_bond_index,
+            self.child_heading_offset.radians(),
         )
     }
 }
@@ -552,90 +779,156 @@ impl CellLayerSpecialty for NullCellLayerSpecialty {
     fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
         Box::new(NullCellLayerSpecialty::new())
     }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Null
+    }
 }
 
+// Thrusts in its cell's body frame: forward along the cell's heading, or backward for negative
+// values, and turning the heading counterclockwise by the requested radians per tick, or
+// clockwise for negative values. A control has to turn its cell to face where it wants to go.
+// Thrust costs energy_per_thrust_sqr times its square and lasts only for the tick after it is
+// paid for, so a cell that keeps moving keeps paying. Turning is free and holds until the next
+// turn request.
 #[derive(Debug)]
 pub struct ThrusterCellLayerSpecialty {
-    force_x: f64,
-    force_y: f64,
+    energy_per_thrust_sqr: f64,
+    forward: f64,
+    turn: f64,
 }
 
 impl ThrusterCellLayerSpecialty {
-    const FORCE_X_CHANNEL_INDEX: usize = 2;
-    const FORCE_Y_CHANNEL_INDEX: usize = 3;
+    const FORWARD_CHANNEL_INDEX: usize = 2;
+    const TURN_CHANNEL_INDEX: usize = 3;
 
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> Self {
+    pub fn new(energy_per_thrust_sqr: f64) -> Self {
+        assert!(energy_per_thrust_sqr >= 0.0);
         ThrusterCellLayerSpecialty {
-            force_x: 0.0,
-            force_y: 0.0,
+            energy_per_thrust_sqr,
+            forward: 0.0,
+            turn: 0.0,
         }
     }
 
-    pub fn force_x_request(layer_index: usize, value: f64) -> ControlRequest {
-        ControlRequest::new(layer_index, Self::FORCE_X_CHANNEL_INDEX, 0, value)
+    pub fn forward_request(layer_index: usize, value: f64) -> ControlRequest {
+        ControlRequest::new(layer_index, Self::FORWARD_CHANNEL_INDEX, 0, value)
     }
 
-    pub fn force_y_request(layer_index: usize, value: f64) -> ControlRequest {
-        ControlRequest::new(layer_index, Self::FORCE_Y_CHANNEL_INDEX, 0, value)
+    pub fn turn_request(layer_index: usize, turn: Deflection) -> ControlRequest {
+        ControlRequest::new(layer_index, Self::TURN_CHANNEL_INDEX, 0, turn.radians())
     }
 }
 
 impl CellLayerSpecialty for ThrusterCellLayerSpecialty {
     fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
-        Box::new(ThrusterCellLayerSpecialty::new())
+        Box::new(ThrusterCellLayerSpecialty::new(self.energy_per_thrust_sqr))
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Thruster {
+            energy_per_thrust_sqr: self.energy_per_thrust_sqr,
+        }
     }
 
     fn after_influences(
         &mut self,
         _body: &CellLayerBody,
-        _env: &LocalEnvironment,
+        env: &LocalEnvironment,
     ) -> (BioEnergy, Force) {
-        (BioEnergy::ZERO, Force::new(self.force_x, self.force_y))
+        // thrust lasts only for the tick it was paid for
+        let force = Value2D::new(self.forward, 0.0).rotate(env.heading()).into();
+        self.forward = 0.0;
+        (BioEnergy::ZERO, force)
     }
 
     fn cost_control_request(&self, request: ControlRequest) -> CostedControlRequest {
         match request.channel_index() {
-            // TODO cost forces based on a parameter struct(?)
-            Self::FORCE_X_CHANNEL_INDEX | Self::FORCE_Y_CHANNEL_INDEX => {
-                CostedControlRequest::free(request)
-            }
+            Self::FORWARD_CHANNEL_INDEX => CostedControlRequest::unlimited(
+                request,
+                BioEnergyDelta::new(-self.energy_per_thrust_sqr * sqr(request.requested_value())),
+            ),
+            Self::TURN_CHANNEL_INDEX => CostedControlRequest::free(request),
             _ => panic!("Invalid control channel index: {}", request.channel_index()),
         }
     }
 
+    fn energy_use(&self, _channel_index: usize) -> EnergyUse {
+        EnergyUse::Thrust
+    }
+
     fn execute_control_request(
         &mut self,
         body: &CellLayerBody,
         request: BudgetedControlRequest,
         _bond_requests: &mut BondRequests,
     ) {
+        let value = body.health * request.budgeted_fraction() * request.requested_value();
         match request.channel_index() {
-            Self::FORCE_X_CHANNEL_INDEX => {
-                self.force_x = body.health * request.budgeted_fraction() * request.requested_value()
-            }
-            Self::FORCE_Y_CHANNEL_INDEX => {
-                self.force_y = body.health * request.budgeted_fraction() * request.requested_value()
-            }
+            Self::FORWARD_CHANNEL_INDEX => self.forward = value,
+            Self::TURN_CHANNEL_INDEX => self.turn = value,
             _ => panic!("Invalid control channel index: {}", request.channel_index()),
         }
     }
+
+    fn turn(&self) -> Deflection {
+        Deflection::from_radians(self.turn)
+    }
 }
 
+// Propulsion limited by the layer's size: the maximum thrust scales with the layer's area and
+// health, and thrust costs energy proportional to its square. Like a thruster's, its channels
+// are in the cell's body frame: thrust along the cell's heading, backward for negative values,
+// and a counterclockwise turn in radians, clockwise for negative values. Both last only for the
+// tick after they are requested, so a cell that keeps moving keeps paying. Turning is free.
 #[derive(Clone, Debug)]
-pub struct PhotoCellLayerSpecialty {
-    efficiency: f64,
+pub struct CiliaCellLayerSpecialty {
+    max_thrust_per_area: f64,
+    energy_per_thrust_sqr: f64,
+    // From the layer body, updated every tick before the control requests are costed
+    max_thrust: f64,
+    forward: f64,
+    turn: f64,
 }
 
-impl PhotoCellLayerSpecialty {
-    pub fn new(efficiency: f64) -> Self {
-        PhotoCellLayerSpecialty { efficiency }
+impl CiliaCellLayerSpecialty {
+    const FORWARD_CHANNEL_INDEX: usize = 2;
+    const TURN_CHANNEL_INDEX: usize = 3;
+
+    pub fn new(max_thrust_per_area: f64, energy_per_thrust_sqr: f64) -> Self {
+        assert!(max_thrust_per_area >= 0.0);
+        assert!(energy_per_thrust_sqr >= 0.0);
+        CiliaCellLayerSpecialty {
+            max_thrust_per_area,
+            energy_per_thrust_sqr,
+            max_thrust: 0.0,
+            forward: 0.0,
+            turn: 0.0,
+        }
+    }
+
+    pub fn forward_request(layer_index: usize, value: f64) -> ControlRequest {
+        ControlRequest::new(layer_index, Self::FORWARD_CHANNEL_INDEX, 0, value)
+    }
+
+    pub fn turn_request(layer_index: usize, turn: Deflection) -> ControlRequest {
+        ControlRequest::new(layer_index, Self::TURN_CHANNEL_INDEX, 0, turn.radians())
     }
 }
 
-impl CellLayerSpecialty for PhotoCellLayerSpecialty {
+impl CellLayerSpecialty for CiliaCellLayerSpecialty {
     fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
-        Box::new(self.clone())
+        Box::new(CiliaCellLayerSpecialty::new(
+            self.max_thrust_per_area,
+            self.energy_per_thrust_sqr,
+        ))
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Cilia {
+            max_thrust_per_area: self.max_thrust_per_area,
+            energy_per_thrust_sqr: self.energy_per_thrust_sqr,
+        }
     }
 
     fn after_influences(
@@ -643,154 +936,711 @@ impl CellLayerSpecialty for PhotoCellLayerSpecialty {
         body: &CellLayerBody,
         env: &LocalEnvironment,
     ) -> (BioEnergy, Force) {
-        (
-            BioEnergy::new(
-                env.light_intensity() * self.efficiency * body.health * body.area.value(),
-            ),
-            Force::ZERO,
-        )
+        self.max_thrust = self.max_thrust_per_area * body.area.value() * body.health;
+        // thrust and turn last only for the tick they were requested; the cell has already read
+        // the turn by now
+        let force = Value2D::new(self.forward, 0.0).rotate(env.heading()).into();
+        self.forward = 0.0;
+        self.turn = 0.0;
+        (BioEnergy::ZERO, force)
     }
-}
 
-#[derive(Debug)]
-pub struct BondingCellLayerSpecialty {}
+    fn cost_control_request(&self, request: ControlRequest) -> CostedControlRequest {
+        match request.channel_index() {
+            Self::FORWARD_CHANNEL_INDEX => {
+                // the force is along the heading, so clamping the one axis clamps its length
+                let thrust = request
+                    .requested_value()
+                    .max(-self.max_thrust)
+                    .min(self.max_thrust);
+                CostedControlRequest::limited(
+                    request,
+                    thrust,
+                    BioEnergyDelta::new(-self.energy_per_thrust_sqr * sqr(thrust)),
+                )
+            }
+            Self::TURN_CHANNEL_INDEX => CostedControlRequest::free(request),
+            _ => panic!("Invalid control channel index: {}", request.channel_index()),
+        }
+    }
 
-impl BondingCellLayerSpecialty {
-    const RETAIN_BOND_CHANNEL_INDEX: usize = 2;
-    const BUDDING_ANGLE_CHANNEL_INDEX: usize = 3;
-    const DONATION_ENERGY_CHANNEL_INDEX: usize = 4;
+    fn energy_use(&self, _channel_index: usize) -> EnergyUse {
+        EnergyUse::Thrust
+    }
 
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> Self {
-        BondingCellLayerSpecialty {}
+    fn execute_control_request(
+        &mut self,
+        body: &CellLayerBody,
+        request: BudgetedControlRequest,
+        _bond_requests: &mut BondRequests,
+    ) {
+        match request.channel_index() {
+            Self::FORWARD_CHANNEL_INDEX => {
+                self.forward = request.budgeted_fraction() * request.allowed_value()
+            }
+            Self::TURN_CHANNEL_INDEX => {
+                self.turn = body.health * request.budgeted_fraction() * request.requested_value()
+            }
+            _ => panic!("Invalid control channel index: {}", request.channel_index()),
+        }
     }
 
-    pub fn retain_bond_request(
-        layer_index: usize,
-        bond_index: usize,
-        flag: bool,
-    ) -> ControlRequest {
-        ControlRequest::new(
-            layer_index,
-            Self::RETAIN_BOND_CHANNEL_INDEX,
-            bond_index,
-            if flag { 1.0 } else { 0.0 },
-        )
+    fn turn(&self) -> Deflection {
+        Deflection::from_radians(self.turn)
     }
+}
 
-    pub fn budding_angle_request(
-        layer_index: usize,
-        bond_index: usize,
-        angle: Angle,
-    ) -> ControlRequest {
-        ControlRequest::new(
-            layer_index,
-            Self::BUDDING_ANGLE_CHANNEL_INDEX,
-            bond_index,
-            angle.radians(),
-        )
+// Stretches its cell into a capsule, a rod with rounded ends whose axis lies along the cell's
+// heading. The axis's length grows or shrinks by the amount requested on the elongation
+// channel, between zero and max_length. Growth costs energy_per_length per unit of length,
+// scaled by the layer's health like resizing; shrinking is free. Budded children start round.
+// The cell's layers keep their areas, and so its mass, by thinning as the axis lengthens.
+#[derive(Clone, Debug)]
+pub struct ElongatorCellLayerSpecialty {
+    max_length: Length,
+    energy_per_length: f64,
+    length: Length,
+}
+
+impl ElongatorCellLayerSpecialty {
+    const ELONGATION_CHANNEL_INDEX: usize = 2;
+
+    pub fn new(max_length: Length, energy_per_length: f64) -> Self {
+        assert!(max_length.value() >= 0.0);
+        assert!(energy_per_length >= 0.0);
+        ElongatorCellLayerSpecialty {
+            max_length,
+            energy_per_length,
+            length: Length::ZERO,
+        }
     }
 
-    pub fn donation_energy_request(
-        layer_index: usize,
-        bond_index: usize,
-        energy: BioEnergy,
-    ) -> ControlRequest {
-        ControlRequest::new(
-            layer_index,
-            Self::DONATION_ENERGY_CHANNEL_INDEX,
-            bond_index,
-            energy.value(),
-        )
+    pub fn with_length(mut self, length: Length) -> Self {
+        assert!(Length::ZERO <= length && length <= self.max_length);
+        self.length = length;
+        self
+    }
+
+    pub fn elongation_request(layer_index: usize, delta_length: f64) -> ControlRequest {
+        ControlRequest::new(layer_index, Self::ELONGATION_CHANNEL_INDEX, 0, delta_length)
     }
 }
 
-impl CellLayerSpecialty for BondingCellLayerSpecialty {
+impl CellLayerSpecialty for ElongatorCellLayerSpecialty {
     fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
-        Box::new(BondingCellLayerSpecialty::new())
+        Box::new(ElongatorCellLayerSpecialty::new(
+            self.max_length,
+            self.energy_per_length,
+        ))
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Elongator {
+            max_length: self.max_length.value(),
+            energy_per_length: self.energy_per_length,
+            length: self.length.value(),
+        }
     }
 
     fn cost_control_request(&self, request: ControlRequest) -> CostedControlRequest {
         match request.channel_index() {
-            Self::RETAIN_BOND_CHANNEL_INDEX => CostedControlRequest::free(request),
-            Self::BUDDING_ANGLE_CHANNEL_INDEX => CostedControlRequest::free(request),
-            Self::DONATION_ENERGY_CHANNEL_INDEX => CostedControlRequest::unlimited(
-                request,
-                BioEnergyDelta::new(-request.requested_value()),
-            ),
+            Self::ELONGATION_CHANNEL_INDEX => {
+                let delta_length = request
+                    .requested_value()
+                    .max(-self.length.value())
+                    .min(self.max_length.value() - self.length.value());
+                CostedControlRequest::limited(
+                    request,
+                    delta_length,
+                    BioEnergyDelta::new(-self.energy_per_length * delta_length.max(0.0)),
+                )
+            }
             _ => panic!("Invalid control channel index: {}", request.channel_index()),
         }
     }
 
+    fn energy_use(&self, _channel_index: usize) -> EnergyUse {
+        EnergyUse::Growth
+    }
+
     fn execute_control_request(
         &mut self,
         body: &CellLayerBody,
         request: BudgetedControlRequest,
-        bond_requests: &mut BondRequests,
+        _bond_requests: &mut BondRequests,
     ) {
-        let bond_request = &mut bond_requests[request.value_index()];
         match request.channel_index() {
-            Self::RETAIN_BOND_CHANNEL_INDEX => {
-                bond_request.retain_bond = request.requested_value() > 0.0
-            }
-            Self::BUDDING_ANGLE_CHANNEL_INDEX => {
-                bond_request.budding_angle = Angle::from_radians(request.requested_value())
-            }
-            Self::DONATION_ENERGY_CHANNEL_INDEX => {
-                bond_request.donation_energy = body.health
-                    * request.budgeted_fraction()
-                    * BioEnergy::new(request.requested_value())
+            Self::ELONGATION_CHANNEL_INDEX => {
+                let delta_length = if request.allowed_value() > 0.0 {
+                    body.health * request.budgeted_fraction() * request.allowed_value()
+                } else {
+                    request.allowed_value()
+                };
+                self.length = Length::new(
+                    (self.length.value() + delta_length)
+                        .max(0.0)
+                        .min(self.max_length.value()),
+                );
             }
             _ => panic!("Invalid control channel index: {}", request.channel_index()),
         }
     }
+
+    fn elongation(&self) -> Length {
+        self.length
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::biology::control_requests::BudgetedControlRequest;
-    use crate::environment::local_environment::LocalEnvironment;
-    use crate::physics::overlap::Overlap;
+// Lets a cell attach itself to an overlapping host cell and siphon the host's energy, at
+// siphon_rate per tick scaled by the layer's health. The world keeps the attachment for as long
+// as the cells overlap. The host's outer layer acts as a shell that resists the siphoning.
+#[derive(Clone, Debug)]
+pub struct ParasiteCellLayerSpecialty {
+    siphon_rate: f64,
+    shell_resistance: f64,
+}
 
-    #[test]
-    fn layer_calculates_mass() {
-        let layer = simple_cell_layer(Area::new(2.0 * PI), Density::new(3.0));
-        assert_eq!(layer.mass(), Mass::new(6.0 * PI));
+impl ParasiteCellLayerSpecialty {
+    pub fn new(siphon_rate: f64, shell_resistance: f64) -> Self {
+        assert!(siphon_rate >= 0.0);
+        assert!(shell_resistance >= 0.0);
+        ParasiteCellLayerSpecialty {
+            siphon_rate,
+            shell_resistance,
+        }
     }
+}
 
-    #[test]
-    fn single_layer_calculates_outer_radius() {
-        let layer = simple_cell_layer(Area::new(4.0 * PI), Density::new(1.0));
-        assert_eq!(layer.outer_radius(), Length::new(2.0));
+impl CellLayerSpecialty for ParasiteCellLayerSpecialty {
+    fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
+        Box::new(self.clone())
     }
 
-    #[test]
-    fn layer_updates_outer_radius_based_on_inner_radius() {
-        let mut layer = simple_cell_layer(Area::new(3.0 * PI), Density::new(1.0));
-        layer.update_outer_radius(Length::new(1.0));
-        assert_eq!(layer.outer_radius(), Length::new(2.0));
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Parasite {
+            siphon_rate: self.siphon_rate,
+            shell_resistance: self.shell_resistance,
+        }
     }
 
-    #[test]
-    fn layer_resize_updates_area_and_mass() {
-        let mut layer = simple_cell_layer(Area::new(1.0), Density::new(2.0));
-        let mut bond_requests = NONE_BOND_REQUESTS;
-        let mut changes = CellChanges::new(1);
-        layer.execute_control_request(
-            fully_budgeted_resize_request(0, 2.0),
-            &mut bond_requests,
-            &mut changes,
-        );
-        assert_eq!(layer.area(), Area::new(3.0));
-        assert_eq!(layer.mass(), Mass::new(6.0));
-        assert_eq!(changes.layers[0].area, AreaDelta::new(2.0));
+    fn siphon(&self, body: &CellLayerBody) -> Option<Siphon> {
+        Some(Siphon {
+            rate: BioEnergy::new(self.siphon_rate * body.health),
+            shell_resistance: self.shell_resistance,
+        })
     }
+}
 
-    #[test]
-    fn layer_resize_records_energy_change() {
-        let mut layer = simple_cell_layer(Area::new(1.0), Density::new(2.0));
-        let mut bond_requests = NONE_BOND_REQUESTS;
+// Senses the nearest other cell within range, optionally only cells with a layer of a given
+// color, so that a control can find prey or mates without knowing the whole world. The world
+// takes a reading before the controls run, and the control sees it in the layer's state
+// snapshot.
+#[derive(Clone, Debug)]
+pub struct SensorCellLayerSpecialty {
+    sensor: Sensor,
+    reading: Option<SensorReading>,
+}
+
+impl SensorCellLayerSpecialty {
+    pub fn new(range: Length, target_color: Option<Color>) -> Self {
+        assert!(range.value() > 0.0);
+        SensorCellLayerSpecialty {
+            sensor: Sensor {
+                range,
+                target_color,
+            },
+            reading: None,
+        }
+    }
+}
+
+impl CellLayerSpecialty for SensorCellLayerSpecialty {
+    fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
+        Box::new(SensorCellLayerSpecialty::new(
+            self.sensor.range,
+            self.sensor.target_color,
+        ))
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Sensor {
+            range: self.sensor.range.value(),
+            target_color: self.sensor.target_color,
+        }
+    }
+
+    fn sensor(&self) -> Option<Sensor> {
+        Some(self.sensor)
+    }
+
+    fn sensor_reading(&self) -> Option<SensorReading> {
+        self.reading
+    }
+
+    fn set_sensor_reading(&mut self, reading: Option<SensorReading>) {
+        self.reading = reading;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PhotoCellLayerSpecialty {
+    efficiency: f64,
+}
+
+impl PhotoCellLayerSpecialty {
+    pub fn new(efficiency: f64) -> Self {
+        PhotoCellLayerSpecialty { efficiency }
+    }
+}
+
+impl CellLayerSpecialty for PhotoCellLayerSpecialty {
+    fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
+        Box::new(self.clone())
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Photo {
+            efficiency: self.efficiency,
+        }
+    }
+
+    fn after_influences(
+        &mut self,
+        body: &CellLayerBody,
+        env: &LocalEnvironment,
+    ) -> (BioEnergy, Force) {
+        (
+            BioEnergy::new(
+                env.light_intensity() * self.efficiency * body.health * body.area.value(),
+            ),
+            Force::ZERO,
+        )
+    }
+}
+
+// A photosynthesizing layer whose pigment a control can change on the pigment channel, by
+// requesting the index of a color in Color::ALL, for energy_per_change per unit of the layer's
+// area. Only fully funded changes happen. The pigment is what sensors and the renderer see,
+// for camouflage or signaling, and sets how much light the layer absorbs: green the most and
+// white the least. Until its first change, the layer shows its tissue type's color. Budded
+// children start with their parent's pigment.
+#[derive(Clone, Debug)]
+pub struct PigmentCellLayerSpecialty {
+    efficiency: f64,
+    energy_per_change: f64,
+    pigment: Option<Color>,
+    // From the layer body, updated every tick before the control requests are costed
+    area: Area,
+    color: Color,
+}
+
+impl PigmentCellLayerSpecialty {
+    const PIGMENT_CHANNEL_INDEX: usize = 2;
+
+    pub fn new(efficiency: f64, energy_per_change: f64) -> Self {
+        assert!(efficiency >= 0.0);
+        assert!(energy_per_change >= 0.0);
+        PigmentCellLayerSpecialty {
+            efficiency,
+            energy_per_change,
+            pigment: None,
+            area: Area::ZERO,
+            color: Color::Green,
+        }
+    }
+
+    pub fn with_pigment(mut self, pigment: Color) -> Self {
+        self.pigment = Some(pigment);
+        self
+    }
+
+    pub fn pigment_request(layer_index: usize, pigment: Color) -> ControlRequest {
+        let pigment_index = Color::ALL
+            .iter()
+            .position(|&color| color == pigment)
+            .unwrap();
+        ControlRequest::new(
+            layer_index,
+            Self::PIGMENT_CHANNEL_INDEX,
+            0,
+            pigment_index as f64,
+        )
+    }
+
+    // The fraction of the light falling on the layer that it absorbs.
+    pub fn light_absorption(pigment: Color) -> f64 {
+        match pigment {
+            Color::Green => 1.0,
+            Color::Yellow => 0.5,
+            Color::White => 0.1,
+        }
+    }
+
+    fn pigment_from_value(value: f64) -> Color {
+        let max_index = (Color::ALL.len() - 1) as f64;
+        Color::ALL[value.round().clamp(0.0, max_index) as usize]
+    }
+}
+
+impl CellLayerSpecialty for PigmentCellLayerSpecialty {
+    fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
+        Box::new(PigmentCellLayerSpecialty {
+            pigment: self.pigment,
+            ..PigmentCellLayerSpecialty::new(self.efficiency, self.energy_per_change)
+        })
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Pigment {
+            efficiency: self.efficiency,
+            energy_per_change: self.energy_per_change,
+            pigment: self.pigment,
+        }
+    }
+
+    fn after_influences(
+        &mut self,
+        body: &CellLayerBody,
+        env: &LocalEnvironment,
+    ) -> (BioEnergy, Force) {
+        self.area = body.area;
+        self.color = body.color;
+        let absorption = Self::light_absorption(self.pigment.unwrap_or(body.color));
+        (
+            BioEnergy::new(
+                env.light_intensity()
+                    * self.efficiency
+                    * absorption
+                    * body.health
+                    * body.area.value(),
+            ),
+            Force::ZERO,
+        )
+    }
+
+    fn cost_control_request(&self, request: ControlRequest) -> CostedControlRequest {
+        match request.channel_index() {
+            Self::PIGMENT_CHANNEL_INDEX => {
+                let pigment = Self::pigment_from_value(request.requested_value());
+                let energy_delta = if pigment == self.pigment.unwrap_or(self.color) {
+                    BioEnergyDelta::ZERO
+                } else {
+                    BioEnergyDelta::new(-self.energy_per_change * self.area.value())
+                };
+                CostedControlRequest::unlimited(request, energy_delta)
+            }
+            _ => panic!("Invalid control channel index: {}", request.channel_index()),
+        }
+    }
+
+    fn execute_control_request(
+        &mut self,
+        _body: &CellLayerBody,
+        request: BudgetedControlRequest,
+        _bond_requests: &mut BondRequests,
+    ) {
+        match request.channel_index() {
+            Self::PIGMENT_CHANNEL_INDEX => {
+                if request.budgeted_fraction() >= 1.0 {
+                    self.pigment = Some(Self::pigment_from_value(request.allowed_value()));
+                }
+            }
+            _ => panic!("Invalid control channel index: {}", request.channel_index()),
+        }
+    }
+
+    fn pigment(&self) -> Option<Color> {
+        self.pigment
+    }
+}
+
+#[derive(Debug)]
+pub struct BondingCellLayerSpecialty {}
+
+impl BondingCellLayerSpecialty {
+    const RETAIN_BOND_CHANNEL_INDEX: usize = 2;
+    const BUDDING_ANGLE_CHANNEL_INDEX: usize = 3;
+    const DONATION_ENERGY_CHANNEL_INDEX: usize = 4;
+    const ADHERE_CHANNEL_INDEX: usize = 5;
+    const CHILD_LAYER_AREA_CHANNEL_INDEX: usize = 6;
+    const CHILD_ENERGY_CHANNEL_INDEX: usize = 7;
+    const CHILD_HATCH_LIGHT_CHANNEL_INDEX: usize = 8;
+    const CHILD_HATCH_TICKS_CHANNEL_INDEX: usize = 9;
+    const CHILD_BOND_INDEX_CHANNEL_INDEX: usize = 10;
+    const CHILD_HEADING_OFFSET_CHANNEL_INDEX: usize = 11;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        BondingCellLayerSpecialty {}
+    }
+
+    pub fn retain_bond_request(
+        layer_index: usize,
+        bond_index: usize,
+        flag: bool,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::RETAIN_BOND_CHANNEL_INDEX,
+            bond_index,
+            if flag { 1.0 } else { 0.0 },
+        )
+    }
+
+    pub fn budding_angle_request(
+        layer_index: usize,
+        bond_index: usize,
+        angle: Angle,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::BUDDING_ANGLE_CHANNEL_INDEX,
+            bond_index,
+            angle.radians(),
+        )
+    }
+
+    pub fn donation_energy_request(
+        layer_index: usize,
+        bond_index: usize,
+        energy: BioEnergy,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::DONATION_ENERGY_CHANNEL_INDEX,
+            bond_index,
+            energy.value(),
+        )
+    }
+
+    // A bond forms only if the other cell also offers a free slot, so an adhering cell
+    // consents to being bonded by offering. Like any bond, the new one lasts only as long as
+    // the cell keeps requesting to retain it.
+    pub fn adhere_request(layer_index: usize, bond_index: usize, flag: bool) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::ADHERE_CHANNEL_INDEX,
+            bond_index,
+            if flag { 1.0 } else { 0.0 },
+        )
+    }
+
+    // The child pays to grow its layers to this area out of its starting energy.
+    pub fn child_layer_area_request(
+        layer_index: usize,
+        bond_index: usize,
+        area: Area,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::CHILD_LAYER_AREA_CHANNEL_INDEX,
+            bond_index,
+            area.value(),
+        )
+    }
+
+    // Like donation energy, the parent pays for this whether or not it buds a child this tick,
+    // but it gets back whatever isn't used.
+    pub fn child_energy_request(
+        layer_index: usize,
+        bond_index: usize,
+        energy: BioEnergy,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::CHILD_ENERGY_CHANNEL_INDEX,
+            bond_index,
+            energy.value(),
+        )
+    }
+
+    // Buds the child as a spore that hatches once its light reaches `intensity`.
+    pub fn child_hatch_light_request(
+        layer_index: usize,
+        bond_index: usize,
+        intensity: f64,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::CHILD_HATCH_LIGHT_CHANNEL_INDEX,
+            bond_index,
+            intensity,
+        )
+    }
+
+    // Buds the child as a spore that hatches after `ticks` ticks.
+    pub fn child_hatch_ticks_request(
+        layer_index: usize,
+        bond_index: usize,
+        ticks: u32,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::CHILD_HATCH_TICKS_CHANNEL_INDEX,
+            bond_index,
+            ticks as f64,
+        )
+    }
+
+    // Buds the child with its bond to the parent in slot `child_bond_index`, instead of the
+    // first slot, so that the child's own buds can go in the slots it expects.
+    pub fn child_bond_index_request(
+        layer_index: usize,
+        bond_index: usize,
+        child_bond_index: usize,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::CHILD_BOND_INDEX_CHANNEL_INDEX,
+            bond_index,
+            child_bond_index as f64,
+        )
+    }
+
+    pub fn child_heading_offset_request(
+        layer_index: usize,
+        bond_index: usize,
+        offset: Deflection,
+    ) -> ControlRequest {
+        ControlRequest::new(
+            layer_index,
+            Self::CHILD_HEADING_OFFSET_CHANNEL_INDEX,
+            bond_index,
+            offset.radians(),
+        )
+    }
+}
+
+impl CellLayerSpecialty for BondingCellLayerSpecialty {
+    fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
+        Box::new(BondingCellLayerSpecialty::new())
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Bonding
+    }
+
+    fn cost_control_request(&self, request: ControlRequest) -> CostedControlRequest {
+        match request.channel_index() {
+            Self::RETAIN_BOND_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::BUDDING_ANGLE_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::ADHERE_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::CHILD_LAYER_AREA_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::CHILD_HATCH_LIGHT_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::CHILD_HATCH_TICKS_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::CHILD_BOND_INDEX_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::CHILD_HEADING_OFFSET_CHANNEL_INDEX => CostedControlRequest::free(request),
+            Self::DONATION_ENERGY_CHANNEL_INDEX | Self::CHILD_ENERGY_CHANNEL_INDEX => {
+                CostedControlRequest::unlimited(
+                    request,
+                    BioEnergyDelta::new(-request.requested_value()),
+                )
+            }
+            _ => panic!("Invalid control channel index: {}", request.channel_index()),
+        }
+    }
+
+    fn energy_use(&self, channel_index: usize) -> EnergyUse {
+        match channel_index {
+            Self::DONATION_ENERGY_CHANNEL_INDEX | Self::CHILD_ENERGY_CHANNEL_INDEX => {
+                EnergyUse::Donation
+            }
+            _ => EnergyUse::Other,
+        }
+    }
+
+    fn execute_control_request(
+        &mut self,
+        body: &CellLayerBody,
+        request: BudgetedControlRequest,
+        bond_requests: &mut BondRequests,
+    ) {
+        let bond_request = &mut bond_requests[request.value_index()];
+        match request.channel_index() {
+            Self::RETAIN_BOND_CHANNEL_INDEX => {
+                bond_request.retain_bond = request.requested_value() > 0.0
+            }
+            Self::BUDDING_ANGLE_CHANNEL_INDEX => {
+                bond_request.budding_angle = Angle::from_radians(request.requested_value())
+            }
+            Self::DONATION_ENERGY_CHANNEL_INDEX => {
+                bond_request.donation_energy = body.health
+                    * request.budgeted_fraction()
+                    * BioEnergy::new(request.requested_value())
+            }
+            Self::ADHERE_CHANNEL_INDEX => bond_request.adhere = request.requested_value() > 0.0,
+            Self::CHILD_LAYER_AREA_CHANNEL_INDEX => {
+                bond_request.child_layer_area = Area::new(request.requested_value().max(0.0))
+            }
+            Self::CHILD_ENERGY_CHANNEL_INDEX => {
+                bond_request.child_energy = body.health
+                    * request.budgeted_fraction()
+                    * BioEnergy::new(request.requested_value())
+            }
+            Self::CHILD_HATCH_LIGHT_CHANNEL_INDEX => {
+                bond_request.child_hatch_light_intensity = request.requested_value()
+            }
+            Self::CHILD_HATCH_TICKS_CHANNEL_INDEX => {
+                bond_request.child_hatch_ticks = request.requested_value().max(0.0).round() as u32
+            }
+            Self::CHILD_BOND_INDEX_CHANNEL_INDEX => {
+                bond_request.child_bond_index = request.requested_value().max(0.0).round() as usize
+            }
+            Self::CHILD_HEADING_OFFSET_CHANNEL_INDEX => {
+                bond_request.child_heading_offset =
+                    Deflection::from_radians(request.requested_value())
+            }
+            _ => panic!("Invalid control channel index: {}", request.channel_index()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::control_requests::BudgetedControlRequest;
+    use crate::environment::local_environment::LocalEnvironment;
+    use crate::physics::overlap::Overlap;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn layer_calculates_mass() {
+        let layer = simple_cell_layer(Area::new(2.0 * PI), Density::new(3.0));
+        assert_eq!(layer.mass(), Mass::new(6.0 * PI));
+    }
+
+    #[test]
+    fn single_layer_calculates_outer_radius() {
+        let layer = simple_cell_layer(Area::new(4.0 * PI), Density::new(1.0));
+        assert_eq!(layer.outer_radius(), Length::new(2.0));
+    }
+
+    #[test]
+    fn layer_updates_outer_radius_based_on_inner_radius() {
+        let mut layer = simple_cell_layer(Area::new(3.0 * PI), Density::new(1.0));
+        layer.update_outer_radius(Length::new(1.0), Length::ZERO);
+        assert_eq!(layer.outer_radius(), Length::new(2.0));
+    }
+
+    #[test]
+    fn layer_resize_updates_area_and_mass() {
+        let mut layer = simple_cell_layer(Area::new(1.0), Density::new(2.0));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            fully_budgeted_resize_request(0, 2.0),
+            &mut bond_requests,
+            &mut changes,
+        );
+        assert_eq!(layer.area(), Area::new(3.0));
+        assert_eq!(layer.mass(), Mass::new(6.0));
+        assert_eq!(changes.layers[0].area, AreaDelta::new(2.0));
+    }
+
+    #[test]
+    fn layer_resize_records_energy_change() {
+        let mut layer = simple_cell_layer(Area::new(1.0), Density::new(2.0));
+        let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(1);
         layer.execute_control_request(
             budgeted(
@@ -840,7 +1690,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(1.0), Density::new(1.0))
-            .with_resize_parameters(&LAYER_RESIZE_PARAMS);
+            .with_resize_parameters(LAYER_RESIZE_PARAMS);
         let costed_request =
             layer.cost_control_request(CellLayer::resize_request(0, AreaDelta::new(3.0)));
         assert_eq!(
@@ -852,6 +1702,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn spawned_layer_keeps_runtime_resize_parameters() {
+        let max_growth_rate = 0.5;
+        let layer = simple_cell_layer(Area::new(1.0), Density::new(1.0)).with_resize_parameters(
+            LayerResizeParameters {
+                max_growth_rate,
+                ..LayerResizeParameters::UNLIMITED
+            },
+        );
+
+        let child = layer.spawn(Area::new(2.0));
+
+        assert_eq!(child.resize_parameters().max_growth_rate, 0.5);
+    }
+
     #[test]
     fn layer_growth_is_limited_by_budgeted_fraction() {
         let mut layer = simple_cell_layer(Area::new(2.0), Density::new(1.0));
@@ -878,7 +1743,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(2.0), Density::new(1.0))
-            .with_resize_parameters(&LAYER_RESIZE_PARAMS);
+            .with_resize_parameters(LAYER_RESIZE_PARAMS);
         let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(1);
         layer.execute_control_request(
@@ -899,7 +1764,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(1.0), Density::new(1.0))
-            .with_resize_parameters(&LAYER_RESIZE_PARAMS);
+            .with_resize_parameters(LAYER_RESIZE_PARAMS);
         let control_request = CellLayer::resize_request(0, AreaDelta::new(2.0));
         let costed_request = layer.cost_control_request(control_request);
         assert_eq!(
@@ -916,7 +1781,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(2.0), Density::new(1.0))
-            .with_resize_parameters(&LAYER_RESIZE_PARAMS);
+            .with_resize_parameters(LAYER_RESIZE_PARAMS);
         let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(1);
         layer.execute_control_request(
@@ -937,7 +1802,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(4.0), Density::new(1.0))
-            .with_resize_parameters(&LAYER_RESIZE_PARAMS);
+            .with_resize_parameters(LAYER_RESIZE_PARAMS);
         let control_request = CellLayer::resize_request(0, AreaDelta::new(-10.0));
         let costed_request = layer.cost_control_request(control_request);
         assert_eq!(
@@ -968,7 +1833,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(1.0), Density::new(1.0))
-            .with_resize_parameters(&LAYER_RESIZE_PARAMS)
+            .with_resize_parameters(LAYER_RESIZE_PARAMS)
             .with_health(0.5);
         let control_request = CellLayer::resize_request(0, AreaDelta::new(1.0));
         let costed_request = layer.cost_control_request(control_request);
@@ -1032,7 +1897,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(2.0), Density::new(1.0))
-            .with_health_parameters(&LAYER_HEALTH_PARAMS)
+            .with_health_parameters(LAYER_HEALTH_PARAMS)
             .with_health(0.5);
         let control_request = CellLayer::healing_request(0, 0.25);
         let costed_request = layer.cost_control_request(control_request);
@@ -1050,7 +1915,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(1.0), Density::new(1.0))
-            .with_health_parameters(&LAYER_HEALTH_PARAMS);
+            .with_health_parameters(LAYER_HEALTH_PARAMS);
 
         let env = LocalEnvironment::new();
         layer.after_influences(&env);
@@ -1066,12 +1931,34 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(1.0), Density::new(1.0))
-            .with_health_parameters(&LAYER_HEALTH_PARAMS);
+            .with_health_parameters(LAYER_HEALTH_PARAMS);
+
+        let mut env = LocalEnvironment::new();
+        env.add_overlap(Overlap::new(Displacement::new(0.5, 0.0), 1.0));
+        layer.after_influences(&env);
+
+        assert_eq!(layer.health(), 0.875);
+    }
+
+    #[test]
+    fn spawned_layer_is_immune_to_overlap_damage_for_a_while() {
+        const LAYER_HEALTH_PARAMS: LayerHealthParameters = LayerHealthParameters {
+            overlap_damage_health_delta: -0.25,
+            newborn_overlap_immunity_ticks: 2,
+            ..LayerHealthParameters::DEFAULT
+        };
+
+        let parent = simple_cell_layer(Area::new(1.0), Density::new(1.0))
+            .with_health_parameters(LAYER_HEALTH_PARAMS);
+        let mut layer = parent.spawn(Area::new(1.0));
 
         let mut env = LocalEnvironment::new();
         env.add_overlap(Overlap::new(Displacement::new(0.5, 0.0), 1.0));
         layer.after_influences(&env);
+        layer.after_influences(&env);
+        assert_eq!(layer.health(), 1.0);
 
+        layer.after_influences(&env);
         assert_eq!(layer.health(), 0.875);
     }
 
@@ -1083,7 +1970,7 @@ mod tests {
         };
 
         let mut layer = simple_cell_layer(Area::new(1.0), Density::new(1.0))
-            .with_health_parameters(&LAYER_HEALTH_PARAMS)
+            .with_health_parameters(LAYER_HEALTH_PARAMS)
             .dead();
         let control_request = CellLayer::healing_request(0, 1.0);
         let costed_request = layer.cost_control_request(control_request);
@@ -1104,30 +1991,211 @@ mod tests {
     }
 
     #[test]
-    fn thruster_layer_adds_force() {
+    fn thruster_layer_adds_force_along_heading_and_turns() {
         let mut layer = CellLayer::new(
             Area::new(1.0),
             Density::new(1.0),
             Color::Green,
-            Box::new(ThrusterCellLayerSpecialty::new()),
+            Box::new(ThrusterCellLayerSpecialty::new(0.0)),
+        );
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            fully_budgeted(ThrusterCellLayerSpecialty::forward_request(0, 1.0)),
+            &mut bond_requests,
+            &mut changes,
+        );
+        layer.execute_control_request(
+            fully_budgeted(ThrusterCellLayerSpecialty::turn_request(
+                0,
+                Deflection::from_radians(-1.0),
+            )),
+            &mut bond_requests,
+            &mut changes,
+        );
+
+        let mut env = LocalEnvironment::new();
+        env.set_heading(Angle::from_radians(PI / 2.0));
+        let (_, force) = layer.after_influences(&env);
+
+        assert!((force.x() - 0.0).abs() < 1e-12);
+        assert!((force.y() - 1.0).abs() < 1e-12);
+        assert_eq!(layer.turn(), Deflection::from_radians(-1.0));
+    }
+
+    #[test]
+    fn cilia_thrust_is_limited_by_area_and_health_and_costs_its_square() {
+        let mut layer = CellLayer::new(
+            Area::new(2.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(CiliaCellLayerSpecialty::new(1.5, 0.5)),
+        );
+        let env = LocalEnvironment::new();
+        layer.after_influences(&env);
+
+        let costed_request =
+            layer.cost_control_request(CiliaCellLayerSpecialty::forward_request(0, 5.0));
+
+        assert_eq!(costed_request.allowed_value(), 3.0);
+        assert_eq!(costed_request.energy_delta(), BioEnergyDelta::new(-4.5));
+
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            BudgetedControlRequest::new(costed_request, 0.5),
+            &mut bond_requests,
+            &mut changes,
+        );
+        let (_, force) = layer.after_influences(&env);
+
+        assert_eq!(force, Force::new(1.5, 0.0));
+    }
+
+    #[test]
+    fn cilia_thrust_lasts_only_one_tick() {
+        let mut layer = CellLayer::new(
+            Area::new(2.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(CiliaCellLayerSpecialty::new(1.5, 0.5)),
+        );
+        let env = LocalEnvironment::new();
+        layer.after_influences(&env);
+        let costed_request =
+            layer.cost_control_request(CiliaCellLayerSpecialty::forward_request(0, 1.0));
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            BudgetedControlRequest::new(costed_request, 1.0),
+            &mut bond_requests,
+            &mut changes,
+        );
+        layer.after_influences(&env);
+
+        let (_, force) = layer.after_influences(&env);
+
+        assert_eq!(force, Force::ZERO);
+    }
+
+    #[test]
+    fn cilia_thrust_along_a_diagonal_heading_is_limited_in_length() {
+        let mut layer = CellLayer::new(
+            Area::new(2.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(CiliaCellLayerSpecialty::new(1.5, 0.5)),
         );
+        let mut env = LocalEnvironment::new();
+        env.set_heading(Angle::from_radians(PI / 4.0));
+        layer.after_influences(&env);
+        let costed_request =
+            layer.cost_control_request(CiliaCellLayerSpecialty::forward_request(0, 5.0));
         let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(1);
         layer.execute_control_request(
-            fully_budgeted(ThrusterCellLayerSpecialty::force_x_request(0, 1.0)),
+            BudgetedControlRequest::new(costed_request, 1.0),
             &mut bond_requests,
             &mut changes,
         );
+
+        let (_, force) = layer.after_influences(&env);
+
+        assert!((force.x() - 3.0 / 2.0_f64.sqrt()).abs() < 1e-12);
+        assert!((force.y() - 3.0 / 2.0_f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cilia_turn_is_free_and_lasts_only_one_tick() {
+        let mut layer = CellLayer::new(
+            Area::new(2.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(CiliaCellLayerSpecialty::new(1.5, 0.5)),
+        );
+        let costed_request = layer.cost_control_request(CiliaCellLayerSpecialty::turn_request(
+            0,
+            Deflection::from_radians(-1.0),
+        ));
+        assert_eq!(costed_request.energy_delta(), BioEnergyDelta::ZERO);
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
         layer.execute_control_request(
-            fully_budgeted(ThrusterCellLayerSpecialty::force_y_request(0, -1.0)),
+            BudgetedControlRequest::new(costed_request, 1.0),
             &mut bond_requests,
             &mut changes,
         );
 
+        assert_eq!(layer.turn(), Deflection::from_radians(-1.0));
+        layer.after_influences(&LocalEnvironment::new());
+        assert_eq!(layer.turn(), Deflection::ZERO);
+    }
+
+    #[test]
+    fn thruster_thrust_costs_its_square_and_lasts_only_one_tick() {
+        let mut layer = CellLayer::new(
+            Area::new(1.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(ThrusterCellLayerSpecialty::new(0.5)),
+        );
+        let costed_request =
+            layer.cost_control_request(ThrusterCellLayerSpecialty::forward_request(0, -2.0));
+        assert_eq!(costed_request.energy_delta(), BioEnergyDelta::new(-2.0));
+        let costed_turn = layer.cost_control_request(ThrusterCellLayerSpecialty::turn_request(
+            0,
+            Deflection::from_radians(1.0),
+        ));
+        assert_eq!(costed_turn.energy_delta(), BioEnergyDelta::ZERO);
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            BudgetedControlRequest::new(costed_request, 1.0),
+            &mut bond_requests,
+            &mut changes,
+        );
         let env = LocalEnvironment::new();
         let (_, force) = layer.after_influences(&env);
+        assert_eq!(force, Force::new(-2.0, 0.0));
+
+        let (_, force) = layer.after_influences(&env);
+
+        assert_eq!(force, Force::ZERO);
+    }
+
+    #[test]
+    fn elongation_is_limited_to_max_length_and_only_growth_costs() {
+        let mut layer = CellLayer::new(
+            Area::new(2.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(
+                ElongatorCellLayerSpecialty::new(Length::new(4.0), 0.5)
+                    .with_length(Length::new(1.0)),
+            ),
+        );
+
+        let costed_request =
+            layer.cost_control_request(ElongatorCellLayerSpecialty::elongation_request(0, 5.0));
+
+        assert_eq!(costed_request.allowed_value(), 3.0);
+        assert_eq!(costed_request.energy_delta(), BioEnergyDelta::new(-1.5));
+
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            BudgetedControlRequest::new(costed_request, 0.5),
+            &mut bond_requests,
+            &mut changes,
+        );
+
+        assert_eq!(layer.elongation(), Length::new(2.5));
 
-        assert_eq!(force, Force::new(1.0, -1.0));
+        let costed_request =
+            layer.cost_control_request(ElongatorCellLayerSpecialty::elongation_request(0, -5.0));
+
+        assert_eq!(costed_request.allowed_value(), -2.5);
+        assert_eq!(costed_request.energy_delta(), BioEnergyDelta::new(0.0));
     }
 
     #[test]
@@ -1136,13 +2204,13 @@ mod tests {
             Area::new(1.0),
             Density::new(1.0),
             Color::Green,
-            Box::new(ThrusterCellLayerSpecialty::new()),
+            Box::new(ThrusterCellLayerSpecialty::new(0.0)),
         );
         let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(1);
         layer.execute_control_request(
             budgeted(
-                ThrusterCellLayerSpecialty::force_x_request(0, 1.0),
+                ThrusterCellLayerSpecialty::forward_request(0, 1.0),
                 BioEnergyDelta::new(1.0),
                 0.5,
             ),
@@ -1151,7 +2219,7 @@ mod tests {
         );
         layer.execute_control_request(
             budgeted(
-                ThrusterCellLayerSpecialty::force_y_request(0, -1.0),
+                ThrusterCellLayerSpecialty::turn_request(0, Deflection::from_radians(-1.0)),
                 BioEnergyDelta::new(1.0),
                 0.25,
             ),
@@ -1162,7 +2230,8 @@ mod tests {
         let env = LocalEnvironment::new();
         let (_, force) = layer.after_influences(&env);
 
-        assert_eq!(force, Force::new(0.5, -0.25));
+        assert_eq!(force, Force::new(0.5, 0.0));
+        assert_eq!(layer.turn(), Deflection::from_radians(-0.25));
     }
 
     #[test]
@@ -1171,18 +2240,21 @@ mod tests {
             Area::new(1.0),
             Density::new(1.0),
             Color::Green,
-            Box::new(ThrusterCellLayerSpecialty::new()),
+            Box::new(ThrusterCellLayerSpecialty::new(0.0)),
         )
         .with_health(0.5);
         let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(1);
         layer.execute_control_request(
-            fully_budgeted(ThrusterCellLayerSpecialty::force_x_request(0, 1.0)),
+            fully_budgeted(ThrusterCellLayerSpecialty::forward_request(0, 1.0)),
             &mut bond_requests,
             &mut changes,
         );
         layer.execute_control_request(
-            fully_budgeted(ThrusterCellLayerSpecialty::force_y_request(0, -1.0)),
+            fully_budgeted(ThrusterCellLayerSpecialty::turn_request(
+                0,
+                Deflection::from_radians(-1.0),
+            )),
             &mut bond_requests,
             &mut changes,
         );
@@ -1190,26 +2262,30 @@ mod tests {
         let env = LocalEnvironment::new();
         let (_, force) = layer.after_influences(&env);
 
-        assert_eq!(force, Force::new(0.5, -0.5));
+        assert_eq!(force, Force::new(0.5, 0.0));
+        assert_eq!(layer.turn(), Deflection::from_radians(-0.5));
     }
 
     #[test]
-    fn dead_thruster_layer_adds_no_force() {
+    fn dead_thruster_layer_adds_no_force_or_turn() {
         let mut layer = CellLayer::new(
             Area::new(1.0),
             Density::new(1.0),
             Color::Green,
-            Box::new(ThrusterCellLayerSpecialty::new()),
+            Box::new(ThrusterCellLayerSpecialty::new(0.0)),
         );
         let mut bond_requests = NONE_BOND_REQUESTS;
         let mut changes = CellChanges::new(1);
         layer.execute_control_request(
-            fully_budgeted(ThrusterCellLayerSpecialty::force_x_request(0, 1.0)),
+            fully_budgeted(ThrusterCellLayerSpecialty::forward_request(0, 1.0)),
             &mut bond_requests,
             &mut changes,
         );
         layer.execute_control_request(
-            fully_budgeted(ThrusterCellLayerSpecialty::force_y_request(0, -1.0)),
+            fully_budgeted(ThrusterCellLayerSpecialty::turn_request(
+                0,
+                Deflection::from_radians(-1.0),
+            )),
             &mut bond_requests,
             &mut changes,
         );
@@ -1219,6 +2295,26 @@ mod tests {
         let (_, force) = layer.after_influences(&env);
 
         assert_eq!(force, Force::new(0.0, 0.0));
+        assert_eq!(layer.turn(), Deflection::ZERO);
+    }
+
+    #[test]
+    fn dead_sensor_layer_has_no_reading() {
+        let mut layer = CellLayer::new(
+            Area::new(1.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(SensorCellLayerSpecialty::new(Length::new(5.0), None)),
+        );
+        layer.set_sensor_reading(Some(SensorReading {
+            distance: Length::new(1.0),
+            bearing: Angle::from_radians(0.0),
+        }));
+        layer.damage(1.0);
+
+        assert_eq!(layer.sensor_reading(), None);
+        layer.after_influences(&LocalEnvironment::new());
+        assert_eq!(layer.specialty.sensor_reading(), None);
     }
 
     #[test]
@@ -1256,6 +2352,95 @@ mod tests {
         assert_eq!(energy, BioEnergy::new(0.75));
     }
 
+    #[test]
+    fn pigment_change_costs_energy_per_area_and_changes_visible_color() {
+        let mut layer = CellLayer::new(
+            Area::new(2.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(PigmentCellLayerSpecialty::new(1.0, 0.5)),
+        );
+        layer.after_influences(&LocalEnvironment::new());
+
+        let unchanged_request =
+            layer.cost_control_request(PigmentCellLayerSpecialty::pigment_request(0, Color::Green));
+        let costed_request =
+            layer.cost_control_request(PigmentCellLayerSpecialty::pigment_request(0, Color::White));
+
+        assert_eq!(unchanged_request.energy_delta(), BioEnergyDelta::ZERO);
+        assert_eq!(costed_request.energy_delta(), BioEnergyDelta::new(-1.0));
+
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            BudgetedControlRequest::new(costed_request, 1.0),
+            &mut bond_requests,
+            &mut changes,
+        );
+
+        assert_eq!(layer.color(), Color::Green);
+        assert_eq!(layer.visible_color(), Color::White);
+    }
+
+    #[test]
+    fn partly_funded_pigment_change_does_not_happen() {
+        let mut layer = CellLayer::new(
+            Area::new(1.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(PigmentCellLayerSpecialty::new(1.0, 1.0)),
+        );
+        layer.after_influences(&LocalEnvironment::new());
+        let costed_request = layer
+            .cost_control_request(PigmentCellLayerSpecialty::pigment_request(0, Color::Yellow));
+
+        let mut bond_requests = NONE_BOND_REQUESTS;
+        let mut changes = CellChanges::new(1);
+        layer.execute_control_request(
+            BudgetedControlRequest::new(costed_request, 0.5),
+            &mut bond_requests,
+            &mut changes,
+        );
+
+        assert_eq!(layer.visible_color(), Color::Green);
+    }
+
+    #[test]
+    fn pigment_sets_light_absorption() {
+        let mut layer = CellLayer::new(
+            Area::new(2.0),
+            Density::new(1.0),
+            Color::Green,
+            Box::new(PigmentCellLayerSpecialty::new(0.5, 0.0).with_pigment(Color::Yellow)),
+        );
+        let mut env = LocalEnvironment::new();
+        env.add_light_intensity(10.0);
+
+        let (energy, _) = layer.after_influences(&env);
+
+        assert_eq!(energy, BioEnergy::new(5.0));
+    }
+
+    #[test]
+    fn sensor_targets_visible_color() {
+        let sensor = Sensor {
+            range: Length::new(1.0),
+            target_color: Some(Color::White),
+        };
+        let cell = Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![CellLayer::new(
+                Area::new(1.0),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(PigmentCellLayerSpecialty::new(1.0, 0.0).with_pigment(Color::White)),
+            )],
+        );
+
+        assert!(sensor.senses(&cell));
+    }
+
     #[test]
     fn dead_photo_layer_adds_no_energy() {
         let mut layer = CellLayer::new(
@@ -0,0 +1,130 @@
+use crate::biology::cell::Cell;
+use crate::physics::newtonian::NewtonianBody;
+use crate::physics::shapes::Circle;
+use rand::seq::index;
+use rand::SeedableRng;
+use rand_pcg::Pcg64Mcg;
+
+// How to choose which cells survive a population bottleneck.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CullSelection {
+    // Keep the cells that score highest on the metric. Ties go to the lower cell index, and NaN
+    // scores, as from a blown-up cell, rank last.
+    Fittest(FitnessMetric),
+    // Keep cells chosen uniformly at random, reproducibly from the seed.
+    Random { seed: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FitnessMetric {
+    Energy,
+    Mass,
+    Radius,
+}
+
+impl FitnessMetric {
+    pub fn score(self, cell: &Cell) -> f64 {
+        match self {
+            FitnessMetric::Energy => cell.energy().value(),
+            FitnessMetric::Mass => cell.mass().value(),
+            FitnessMetric::Radius => cell.radius().value(),
+        }
+    }
+}
+
+// Returns the indexes of the surviving cells in ascending order.
+pub fn select_survivors(
+    cells: &[Cell],
+    num_survivors: usize,
+    selection: CullSelection,
+) -> Vec<usize> {
+    if num_survivors >= cells.len() {
+        return (0..cells.len()).collect();
+    }
+
+    let mut survivors = match selection {
+        CullSelection::Fittest(metric) => {
+            let mut indexes: Vec<usize> = (0..cells.len()).collect();
+            let score = |index: usize| {
+                let score = metric.score(&cells[index]);
+                if score.is_nan() {
+                    f64::NEG_INFINITY
+                } else {
+                    score
+                }
+            };
+            indexes.sort_by(|&i1, &i2| score(i2).total_cmp(&score(i1)));
+            indexes.truncate(num_survivors);
+            indexes
+        }
+        CullSelection::Random { seed } => {
+            let mut rng = Pcg64Mcg::seed_from_u64(seed);
+            index::sample(&mut rng, cells.len(), num_survivors).into_vec()
+        }
+    };
+    survivors.sort_unstable();
+    survivors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::quantities::*;
+
+    #[test]
+    fn fittest_selection_keeps_highest_scores() {
+        let cells = vec![
+            ball_with_energy(1.0),
+            ball_with_energy(5.0),
+            ball_with_energy(3.0),
+            ball_with_energy(4.0),
+        ];
+
+        let survivors = select_survivors(&cells, 2, CullSelection::Fittest(FitnessMetric::Energy));
+
+        assert_eq!(survivors, vec![1, 3]);
+    }
+
+    #[test]
+    fn fittest_selection_ranks_nan_scores_last() {
+        let cells = vec![
+            ball_with_energy(f64::NAN),
+            ball_with_energy(1.0),
+            ball_with_energy(2.0),
+        ];
+
+        let survivors = select_survivors(&cells, 2, CullSelection::Fittest(FitnessMetric::Energy));
+
+        assert_eq!(survivors, vec![1, 2]);
+    }
+
+    #[test]
+    fn random_selection_is_reproducible() {
+        let cells: Vec<Cell> = (0..10).map(|_| ball_with_energy(1.0)).collect();
+
+        let survivors1 = select_survivors(&cells, 4, CullSelection::Random { seed: 7 });
+        let survivors2 = select_survivors(&cells, 4, CullSelection::Random { seed: 7 });
+
+        assert_eq!(survivors1.len(), 4);
+        assert_eq!(survivors1, survivors2);
+    }
+
+    #[test]
+    fn selecting_more_survivors_than_cells_keeps_all() {
+        let cells = vec![ball_with_energy(1.0), ball_with_energy(2.0)];
+        assert_eq!(
+            select_survivors(&cells, 5, CullSelection::Random { seed: 0 }),
+            vec![0, 1]
+        );
+    }
+
+    fn ball_with_energy(energy: f64) -> Cell {
+        Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            Position::ORIGIN,
+            Velocity::ZERO,
+        )
+        .with_initial_energy(BioEnergy::new(energy))
+    }
+}
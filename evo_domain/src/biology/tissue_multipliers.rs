@@ -0,0 +1,148 @@
+use crate::biology::layers::Color;
+use crate::environment::influences::TunableParameter;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// World-wide multipliers for one tissue type: of the energy its layers gain from their
+// environment, e.g. by photosynthesis, and of the energy its layers' control requests cost.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TissueMultiplier {
+    pub energy_gain: f64,
+    pub cost: f64,
+}
+
+impl TissueMultiplier {
+    pub const NEUTRAL: TissueMultiplier = TissueMultiplier {
+        energy_gain: 1.0,
+        cost: 1.0,
+    };
+}
+
+impl Default for TissueMultiplier {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+// Multipliers for shifting the whole environment for or against one strategy, e.g. halving
+// photosynthesis or doubling the cost of bonding, without touching genomes. Tissue types
+// without a multiplier are unaffected. They are tunable parameters, so they can be changed
+// while the world is running.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TissueMultipliers {
+    multipliers: BTreeMap<Color, TissueMultiplier>,
+}
+
+impl TissueMultipliers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_multiplier(mut self, color: Color, multiplier: TissueMultiplier) -> Self {
+        self.multipliers.insert(color, multiplier);
+        self
+    }
+
+    pub fn get(&self, color: Color) -> TissueMultiplier {
+        self.multipliers.get(&color).copied().unwrap_or_default()
+    }
+
+    pub fn is_neutral(&self) -> bool {
+        self.multipliers
+            .values()
+            .all(|&multiplier| multiplier == TissueMultiplier::NEUTRAL)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Color, TissueMultiplier)> + '_ {
+        self.multipliers
+            .iter()
+            .map(|(&color, &multiplier)| (color, multiplier))
+    }
+
+    pub fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        match parameter {
+            TunableParameter::TissueEnergyGain(color) => Some(self.get(color).energy_gain),
+            TunableParameter::TissueCost(color) => Some(self.get(color).cost),
+            _ => None,
+        }
+    }
+
+    // Tissue types whose multipliers return to neutral are forgotten.
+    pub fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        let (color, mut multiplier) = match parameter {
+            TunableParameter::TissueEnergyGain(color) | TunableParameter::TissueCost(color) => {
+                (color, self.get(color))
+            }
+            _ => return,
+        };
+        if let TunableParameter::TissueEnergyGain(_) = parameter {
+            multiplier.energy_gain = value;
+        } else {
+            multiplier.cost = value;
+        }
+        if multiplier == TissueMultiplier::NEUTRAL {
+            self.multipliers.remove(&color);
+        } else {
+            self.multipliers.insert(color, multiplier);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tissue_without_multiplier_is_unaffected() {
+        let multipliers = TissueMultipliers::new().with_multiplier(
+            Color::Green,
+            TissueMultiplier {
+                energy_gain: 0.5,
+                cost: 1.0,
+            },
+        );
+
+        assert_eq!(multipliers.get(Color::Green).energy_gain, 0.5);
+        assert_eq!(multipliers.get(Color::Yellow), TissueMultiplier::NEUTRAL);
+    }
+
+    #[test]
+    fn multipliers_are_tunable_parameters() {
+        let mut multipliers = TissueMultipliers::new();
+
+        multipliers.set_tunable_parameter(TunableParameter::TissueCost(Color::Yellow), 2.0);
+
+        assert_eq!(
+            multipliers.tunable_parameter(TunableParameter::TissueCost(Color::Yellow)),
+            Some(2.0)
+        );
+        assert_eq!(
+            multipliers.tunable_parameter(TunableParameter::TissueEnergyGain(Color::Yellow)),
+            Some(1.0)
+        );
+        assert_eq!(
+            multipliers.tunable_parameter(TunableParameter::Gravity),
+            None
+        );
+        assert!(!multipliers.is_neutral());
+    }
+
+    #[test]
+    fn multipliers_round_trip_through_json() {
+        let multipliers = TissueMultipliers::new().with_multiplier(
+            Color::White,
+            TissueMultiplier {
+                energy_gain: 1.5,
+                cost: 0.25,
+            },
+        );
+
+        let json = serde_json::to_string(&multipliers).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<TissueMultipliers>(&json).unwrap(),
+            multipliers
+        );
+    }
+}
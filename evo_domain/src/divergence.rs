@@ -0,0 +1,167 @@
+use crate::state_hash::StateHash;
+use crate::world::World;
+
+// Runs two worlds that should stay identical in lockstep, e.g. worlds built from the same seed
+// but ticked with different parallelism, and finds the first tick, and the phase within it,
+// where their states differ.
+pub struct TwinRun {
+    worlds: [World; 2],
+}
+
+// Where a twin run diverged: the tick in which the worlds' state hashes first differed, the
+// first phase of the tick after which they differed, and the parts of the state that differed
+// then. Tick 0 and phase "start" mean the worlds differed before the first tick.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    pub tick: u64,
+    pub phase: &'static str,
+    pub parts: Vec<&'static str>,
+    pub hashes: [StateHash; 2],
+}
+
+impl TwinRun {
+    pub fn new(world1: World, world2: World) -> Self {
+        TwinRun {
+            worlds: [world1.with_phase_hashes(), world2.with_phase_hashes()],
+        }
+    }
+
+    pub fn worlds(&self) -> &[World; 2] {
+        &self.worlds
+    }
+
+    // Checks the worlds before the first tick and after every phase of every tick. Stops at
+    // the first divergence, leaving the worlds as they were at the end of its tick.
+    pub fn run(&mut self, num_ticks: u64) -> Option<Divergence> {
+        let hashes = [self.worlds[0].state_hashes(), self.worlds[1].state_hashes()];
+        if let Some(divergence) = Self::check(0, "start", hashes) {
+            return Some(divergence);
+        }
+        for _ in 0..num_ticks {
+            for world in &mut self.worlds {
+                world.tick();
+            }
+            if let Some(divergence) = self.check_tick() {
+                return Some(divergence);
+            }
+        }
+        None
+    }
+
+    fn check_tick(&self) -> Option<Divergence> {
+        let tick = self.worlds[0].current_tick();
+        let phase_hashes = self.worlds[1].phase_hashes();
+        for (&(phase, hashes1), &(_, hashes2)) in
+            self.worlds[0].phase_hashes().iter().zip(phase_hashes)
+        {
+            if let Some(divergence) = Self::check(tick, phase, [hashes1, hashes2]) {
+                return Some(divergence);
+            }
+        }
+        // Some state, e.g. culls that enforce resource limits, changes after the last phase
+        let hashes = [self.worlds[0].state_hashes(), self.worlds[1].state_hashes()];
+        Self::check(tick, "end of tick", hashes)
+    }
+
+    fn check(tick: u64, phase: &'static str, hashes: [StateHash; 2]) -> Option<Divergence> {
+        if hashes[0] == hashes[1] {
+            return None;
+        }
+        Some(Divergence {
+            tick,
+            phase,
+            parts: hashes[0].differing_parts(&hashes[1]),
+            hashes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::cell::Cell;
+    use crate::biology::invasion::Invasion;
+    use crate::physics::quantities::*;
+    use crate::plugins::WorldPlugin;
+
+    #[test]
+    fn identical_twins_do_not_diverge() {
+        let mut twins = TwinRun::new(falling_ball_world(1.0), falling_ball_world(1.0));
+
+        assert_eq!(twins.run(10), None);
+        assert_eq!(twins.worlds()[0].current_tick(), 10);
+    }
+
+    #[test]
+    fn reports_first_tick_and_parts_that_differ() {
+        let invasion = Invasion::new(
+            Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ),
+            1,
+            Position::new(5.0, 5.0),
+            Position::new(5.0, 5.0),
+        );
+        let mut twins = TwinRun::new(
+            falling_ball_world(1.0),
+            falling_ball_world(1.0).with_scheduled_invasion(3, invasion),
+        );
+
+        let divergence = twins.run(10).unwrap();
+
+        assert_eq!(divergence.tick, 4);
+        assert_eq!(divergence.phase, "influences");
+        assert_eq!(divergence.parts, vec!["motion", "energies", "genomes"]);
+        assert_eq!(twins.worlds()[0].current_tick(), 4);
+    }
+
+    #[test]
+    fn reports_first_phase_that_differs() {
+        struct MoveAfterControls {}
+
+        impl WorldPlugin for MoveAfterControls {
+            fn after_controls(&mut self, world: &mut World) {
+                if world.current_tick() == 2 {
+                    let id = world.cells()[0].id();
+                    world.move_cell(id, Position::new(5.0, 5.0));
+                }
+            }
+        }
+
+        let mut world2 = falling_ball_world(1.0);
+        world2.add_plugin(Box::new(MoveAfterControls {}));
+        let mut twins = TwinRun::new(falling_ball_world(1.0), world2);
+
+        let divergence = twins.run(10).unwrap();
+
+        assert_eq!(divergence.tick, 3);
+        assert_eq!(divergence.phase, "movement");
+        assert_eq!(divergence.parts, vec!["motion"]);
+    }
+
+    #[test]
+    fn twins_that_start_different_diverge_at_first_check() {
+        let mut twins = TwinRun::new(falling_ball_world(1.0), falling_ball_world(2.0));
+
+        let divergence = twins.run(10).unwrap();
+
+        assert_eq!(divergence.tick, 0);
+        assert_eq!(divergence.phase, "start");
+        assert_eq!(divergence.parts, vec!["energies"]);
+    }
+
+    fn falling_ball_world(energy: f64) -> World {
+        World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0)).with_cell(
+            Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::new(0.5, 0.0),
+            )
+            .with_initial_energy(BioEnergy::new(energy)),
+        )
+    }
+}
@@ -0,0 +1,1193 @@
+use crate::biology::adhesion::*;
+use crate::biology::cell::{CellTag, Spore};
+use crate::biology::layers::*;
+use crate::biology::tissue_multipliers::*;
+use crate::environment::influences::*;
+use crate::physics::overlap::Overlap;
+use crate::physics::quantities::*;
+use crate::physics::sortable_graph::MAX_NODE_EDGES;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// A human-readable description of a world, for external tools that generate or analyze worlds.
+// Cell indexes are positions in `cells`, bond indexes are positions in `bonds`.
+// Cell controls are not part of the document: loaded cells get a NullControl, and each cell
+// records whether it had a control, so that tools can refuse to resume worlds that need them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldDocument {
+    pub version: u32,
+    // The tick the world was saved at, which a world loaded from the document continues from
+    #[serde(default)]
+    pub tick: u64,
+    pub min_corner: [f64; 2],
+    pub max_corner: [f64; 2],
+    pub influences: Vec<InfluenceDocument>,
+    pub cells: Vec<CellDocument>,
+    pub bonds: Vec<BondDocument>,
+    pub angle_gussets: Vec<AngleGussetDocument>,
+    #[serde(default, skip_serializing_if = "TissueMultipliers::is_neutral")]
+    pub tissue_multipliers: TissueMultipliers,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adhesion_rule: Option<AdhesionRule>,
+}
+
+impl WorldDocument {
+    pub const VERSION: u32 = 1;
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    // Checks everything World::from_document would otherwise panic on.
+    pub fn check(&self) -> Result<(), DocumentError> {
+        if self.version != Self::VERSION {
+            return Err(DocumentError::UnsupportedVersion(self.version));
+        }
+        check_influences(&self.influences)?;
+        check_bonds(self.cells.len(), &self.bonds)?;
+        check_angle_gussets(&self.bonds, &self.angle_gussets)
+    }
+}
+
+// Why a document cannot be turned into a world, or a patch cannot be applied to one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DocumentError {
+    UnsupportedVersion(u32),
+    InvalidKeyframeCellIndex(usize),
+    InvalidCellIndex {
+        bond: usize,
+        cell: usize,
+    },
+    SelfBond {
+        bond: usize,
+    },
+    InvalidBondSlot {
+        bond: usize,
+        slot: usize,
+    },
+    BondSlotInUse {
+        bond: usize,
+        cell: usize,
+        slot: usize,
+    },
+    InvalidBondIndex {
+        gusset: usize,
+        bond: usize,
+    },
+    UnjoinedGussetBonds {
+        gusset: usize,
+    },
+    UnknownInfluence(String),
+    UnsupportedScheduledParameter {
+        influence: usize,
+        parameter: TunableParameter,
+    },
+    ZeroPulsePeriod {
+        influence: usize,
+    },
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DocumentError::UnsupportedVersion(version) => {
+                write!(f, "unsupported document version {}", version)
+            }
+            DocumentError::InvalidKeyframeCellIndex(index) => {
+                write!(f, "delta refers to missing keyframe cell {}", index)
+            }
+            DocumentError::InvalidCellIndex { bond, cell } => {
+                write!(f, "bond {} refers to missing cell {}", bond, cell)
+            }
+            DocumentError::SelfBond { bond } => write!(f, "bond {} joins a cell to itself", bond),
+            DocumentError::InvalidBondSlot { bond, slot } => {
+                write!(f, "bond {} uses invalid bond slot {}", bond, slot)
+            }
+            DocumentError::BondSlotInUse { bond, cell, slot } => write!(
+                f,
+                "bond {} uses bond slot {} of cell {}, which is already in use",
+                bond, slot, cell
+            ),
+            DocumentError::InvalidBondIndex { gusset, bond } => {
+                write!(f, "angle gusset {} refers to missing bond {}", gusset, bond)
+            }
+            DocumentError::UnjoinedGussetBonds { gusset } => write!(
+                f,
+                "angle gusset {} joins bonds that do not meet at a cell",
+                gusset
+            ),
+            DocumentError::UnknownInfluence(id) => {
+                write!(f, "patch removes missing influence {:?}", id)
+            }
+            DocumentError::UnsupportedScheduledParameter {
+                influence,
+                parameter,
+            } => write!(
+                f,
+                "influence {} schedules {:?}, which its inner influence does not have",
+                influence, parameter
+            ),
+            DocumentError::ZeroPulsePeriod { influence } => {
+                write!(f, "influence {} pulses with a period of 0 ticks", influence)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+// Checks everything InfluenceDocument::to_influence would otherwise panic on. Errors give the
+// index of the influence in the list.
+pub(crate) fn check_influences(influences: &[InfluenceDocument]) -> Result<(), DocumentError> {
+    for (index, influence) in influences.iter().enumerate() {
+        check_influence(index, influence)?;
+    }
+    Ok(())
+}
+
+fn check_influence(index: usize, influence: &InfluenceDocument) -> Result<(), DocumentError> {
+    if let InfluenceDocument::Scheduled {
+        influence,
+        parameter,
+        schedule,
+        ..
+    } = influence
+    {
+        check_influence(index, influence)?;
+        if influence
+            .to_influence()
+            .tunable_parameter(*parameter)
+            .is_none()
+        {
+            return Err(DocumentError::UnsupportedScheduledParameter {
+                influence: index,
+                parameter: *parameter,
+            });
+        }
+        if let Schedule::Pulses {
+            period_ticks: 0, ..
+        } = schedule
+        {
+            return Err(DocumentError::ZeroPulsePeriod { influence: index });
+        }
+    }
+    Ok(())
+}
+
+fn check_bonds(num_cells: usize, bonds: &[BondDocument]) -> Result<(), DocumentError> {
+    let mut used_slots = HashSet::new();
+    for (index, bond) in bonds.iter().enumerate() {
+        for &(cell, slot) in &[
+            (bond.cell1, bond.bond_index_on_cell1),
+            (bond.cell2, bond.bond_index_on_cell2),
+        ] {
+            if cell >= num_cells {
+                return Err(DocumentError::InvalidCellIndex { bond: index, cell });
+            }
+            if slot >= MAX_NODE_EDGES {
+                return Err(DocumentError::InvalidBondSlot { bond: index, slot });
+            }
+            if !used_slots.insert((cell, slot)) {
+                return Err(DocumentError::BondSlotInUse {
+                    bond: index,
+                    cell,
+                    slot,
+                });
+            }
+        }
+        if bond.cell1 == bond.cell2 {
+            return Err(DocumentError::SelfBond { bond: index });
+        }
+    }
+    Ok(())
+}
+
+fn check_angle_gussets(
+    bonds: &[BondDocument],
+    gussets: &[AngleGussetDocument],
+) -> Result<(), DocumentError> {
+    for (index, gusset) in gussets.iter().enumerate() {
+        for &bond in &[gusset.bond1, gusset.bond2] {
+            if bond >= bonds.len() {
+                return Err(DocumentError::InvalidBondIndex {
+                    gusset: index,
+                    bond,
+                });
+            }
+        }
+        if gusset.bond1 == gusset.bond2 || bonds[gusset.bond1].cell2 != bonds[gusset.bond2].cell1 {
+            return Err(DocumentError::UnjoinedGussetBonds { gusset: index });
+        }
+    }
+    Ok(())
+}
+
+// A world checkpoint stored as its differences from an earlier full checkpoint, its keyframe,
+// to save space in long runs. Cells are matched to the keyframe's cells by cell ID. A cell whose
+// only changes are to its motion, energy, and layer areas and healths stores just those; any
+// other cell, including a newborn, is stored in full. Dead cells are simply left out.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldDeltaDocument {
+    pub version: u32,
+    #[serde(default)]
+    pub tick: u64,
+    pub keyframe_tick: u64,
+    pub cells: Vec<CellDeltaDocument>,
+    // None means the same as in the keyframe
+    #[serde(default)]
+    pub influences: Option<Vec<InfluenceDocument>>,
+    #[serde(default)]
+    pub bonds: Option<Vec<BondDocument>>,
+    #[serde(default)]
+    pub angle_gussets: Option<Vec<AngleGussetDocument>>,
+    #[serde(default)]
+    pub tissue_multipliers: Option<TissueMultipliers>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CellDeltaDocument {
+    Updated {
+        keyframe_index: usize,
+        position: [f64; 2],
+        velocity: [f64; 2],
+        energy: f64,
+        // Area and health of each layer
+        layers: Vec<[f64; 2]>,
+    },
+    Full(CellDocument),
+}
+
+impl WorldDeltaDocument {
+    pub const VERSION: u32 = 1;
+
+    // The cell IDs are those of the documents' cells, in order.
+    pub fn new(
+        keyframe: &WorldDocument,
+        keyframe_tick: u64,
+        keyframe_cell_ids: &[u64],
+        document: &WorldDocument,
+        cell_ids: &[u64],
+    ) -> Self {
+        assert_eq!(keyframe.cells.len(), keyframe_cell_ids.len());
+        assert_eq!(document.cells.len(), cell_ids.len());
+        let keyframe_indexes: HashMap<u64, usize> = keyframe_cell_ids
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| (id, index))
+            .collect();
+        WorldDeltaDocument {
+            version: Self::VERSION,
+            tick: document.tick,
+            keyframe_tick,
+            cells: document
+                .cells
+                .iter()
+                .zip(cell_ids)
+                .map(|(cell, id)| {
+                    keyframe_indexes
+                        .get(id)
+                        .map(|&index| CellDeltaDocument::updated(index, cell))
+                        .filter(|delta| &delta.apply_to(keyframe) == cell)
+                        .unwrap_or_else(|| CellDeltaDocument::Full(cell.clone()))
+                })
+                .collect(),
+            influences: Self::changed(&keyframe.influences, &document.influences),
+            bonds: Self::changed(&keyframe.bonds, &document.bonds),
+            angle_gussets: Self::changed(&keyframe.angle_gussets, &document.angle_gussets),
+            tissue_multipliers: Some(document.tissue_multipliers.clone())
+                .filter(|multipliers| *multipliers != keyframe.tissue_multipliers),
+        }
+    }
+
+    fn changed<T: Clone + PartialEq>(keyframe_items: &[T], items: &[T]) -> Option<Vec<T>> {
+        if items == keyframe_items {
+            None
+        } else {
+            Some(items.to_vec())
+        }
+    }
+
+    pub fn apply_to(&self, keyframe: &WorldDocument) -> Result<WorldDocument, DocumentError> {
+        if self.version != Self::VERSION {
+            return Err(DocumentError::UnsupportedVersion(self.version));
+        }
+        for delta in &self.cells {
+            if let CellDeltaDocument::Updated { keyframe_index, .. } = *delta {
+                if keyframe_index >= keyframe.cells.len() {
+                    return Err(DocumentError::InvalidKeyframeCellIndex(keyframe_index));
+                }
+            }
+        }
+        Ok(WorldDocument {
+            version: keyframe.version,
+            tick: self.tick,
+            min_corner: keyframe.min_corner,
+            max_corner: keyframe.max_corner,
+            influences: self
+                .influences
+                .clone()
+                .unwrap_or_else(|| keyframe.influences.clone()),
+            cells: self
+                .cells
+                .iter()
+                .map(|delta| delta.apply_to(keyframe))
+                .collect(),
+            bonds: self.bonds.clone().unwrap_or_else(|| keyframe.bonds.clone()),
+            angle_gussets: self
+                .angle_gussets
+                .clone()
+                .unwrap_or_else(|| keyframe.angle_gussets.clone()),
+            tissue_multipliers: self
+                .tissue_multipliers
+                .clone()
+                .unwrap_or_else(|| keyframe.tissue_multipliers.clone()),
+            adhesion_rule: keyframe.adhesion_rule,
+        })
+    }
+
+    // Compact rather than pretty, since deltas are for saving space.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+impl CellDeltaDocument {
+    fn updated(keyframe_index: usize, cell: &CellDocument) -> Self {
+        CellDeltaDocument::Updated {
+            keyframe_index,
+            position: cell.position,
+            velocity: cell.velocity,
+            energy: cell.energy,
+            layers: cell
+                .layers
+                .iter()
+                .map(|layer| [layer.area, layer.health])
+                .collect(),
+        }
+    }
+
+    fn apply_to(&self, keyframe: &WorldDocument) -> CellDocument {
+        match self {
+            CellDeltaDocument::Updated {
+                keyframe_index,
+                position,
+                velocity,
+                energy,
+                layers,
+            } => {
+                let mut cell = keyframe.cells[*keyframe_index].clone();
+                cell.position = *position;
+                cell.velocity = *velocity;
+                cell.energy = *energy;
+                for (layer, [area, health]) in cell.layers.iter_mut().zip(layers) {
+                    layer.area = *area;
+                    layer.health = *health;
+                }
+                cell
+            }
+            CellDeltaDocument::Full(cell) => cell.clone(),
+        }
+    }
+}
+
+// A connected group of bonded cells, for moving organisms between worlds. Cell positions are
+// relative to the organism's center; cell and bond indexes are as in WorldDocument.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OrganismDocument {
+    pub version: u32,
+    pub cells: Vec<CellDocument>,
+    pub bonds: Vec<BondDocument>,
+    pub angle_gussets: Vec<AngleGussetDocument>,
+}
+
+impl OrganismDocument {
+    pub const VERSION: u32 = 1;
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+// What a recorded run was and where it came from, saved in its run directory.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub version: u32,
+    pub started_at_unix_secs: u64,
+    // The command line that started the run
+    pub command: Vec<String>,
+    pub seed: Option<u64>,
+    // `git describe` of the source tree the program was built from, if known
+    pub git_describe: Option<String>,
+    pub num_ticks: u64,
+    pub checkpoint_interval: u64,
+    // Every this many checkpoints is a full world document, and the rest are deltas from the
+    // last full one. Zero, as in older runs, means the same as one: no deltas.
+    #[serde(default)]
+    pub keyframe_interval: u64,
+    // The checkpoint the run was resumed from, if it did not start from a fresh world
+    #[serde(default)]
+    pub resumed_from: Option<String>,
+    // Changes applied to the starting world, in order
+    #[serde(default)]
+    pub patches: Vec<WorldPatch>,
+    // Names of the plugins the run added to the world, in order
+    #[serde(default)]
+    pub plugins: Vec<String>,
+}
+
+impl RunMetadata {
+    pub const VERSION: u32 = 1;
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+// A change to a running or saved world, e.g. to a checkpoint before resuming it for the next
+// stage of an experiment. Influences are removed by ID, then added, then parameters are set.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldPatch {
+    #[serde(default)]
+    pub remove_influences: Vec<String>,
+    #[serde(default)]
+    pub add_influences: Vec<InfluenceDocument>,
+    #[serde(default)]
+    pub set_parameters: Vec<ParameterSetting>,
+}
+
+impl WorldPatch {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParameterSetting {
+    pub parameter: TunableParameter,
+    pub value: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellDocument {
+    pub position: [f64; 2],
+    pub velocity: [f64; 2],
+    pub energy: f64,
+    #[serde(default)]
+    pub lineage: u32,
+    #[serde(default)]
+    pub spore: Option<Spore>,
+    #[serde(default)]
+    pub tags: Vec<CellTag>,
+    #[serde(default)]
+    pub adhesion_tag: AdhesionTag,
+    #[serde(default)]
+    pub neural_layer_index: Option<usize>,
+    #[serde(default)]
+    pub dropout_seed: Option<u64>,
+    // In radians
+    #[serde(default)]
+    pub heading: f64,
+    // Whether the cell had a control other than a NullControl, which the document leaves out
+    #[serde(default)]
+    pub has_control: bool,
+    pub layers: Vec<LayerDocument>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayerDocument {
+    pub area: f64,
+    pub density: f64,
+    pub color: Color,
+    pub health: f64,
+    pub health_parameters: LayerHealthParametersDocument,
+    pub resize_parameters: LayerResizeParametersDocument,
+    pub specialty: SpecialtyDocument,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayerHealthParametersDocument {
+    pub healing_energy_delta: f64,
+    pub entropic_damage_health_delta: f64,
+    pub overlap_damage_health_delta: f64,
+    #[serde(default)]
+    pub newborn_overlap_immunity_ticks: u32,
+}
+
+impl LayerHealthParametersDocument {
+    pub fn from_parameters(parameters: &LayerHealthParameters) -> Self {
+        LayerHealthParametersDocument {
+            healing_energy_delta: parameters.healing_energy_delta.value(),
+            entropic_damage_health_delta: parameters.entropic_damage_health_delta,
+            overlap_damage_health_delta: parameters.overlap_damage_health_delta,
+            newborn_overlap_immunity_ticks: parameters.newborn_overlap_immunity_ticks,
+        }
+    }
+
+    pub fn to_parameters(&self) -> LayerHealthParameters {
+        LayerHealthParameters {
+            healing_energy_delta: BioEnergyDelta::new(self.healing_energy_delta),
+            entropic_damage_health_delta: self.entropic_damage_health_delta,
+            overlap_damage_health_delta: self.overlap_damage_health_delta,
+            newborn_overlap_immunity_ticks: self.newborn_overlap_immunity_ticks,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayerResizeParametersDocument {
+    pub growth_energy_delta: f64,
+    // None means unlimited, since JSON has no infinity
+    pub max_growth_rate: Option<f64>,
+    pub shrinkage_energy_delta: f64,
+    pub max_shrinkage_rate: f64,
+}
+
+impl LayerResizeParametersDocument {
+    pub fn from_parameters(parameters: &LayerResizeParameters) -> Self {
+        LayerResizeParametersDocument {
+            growth_energy_delta: parameters.growth_energy_delta.value(),
+            max_growth_rate: if parameters.max_growth_rate.is_finite() {
+                Some(parameters.max_growth_rate)
+            } else {
+                None
+            },
+            shrinkage_energy_delta: parameters.shrinkage_energy_delta.value(),
+            max_shrinkage_rate: parameters.max_shrinkage_rate,
+        }
+    }
+
+    pub fn to_parameters(&self) -> LayerResizeParameters {
+        LayerResizeParameters {
+            growth_energy_delta: BioEnergyDelta::new(self.growth_energy_delta),
+            max_growth_rate: self.max_growth_rate.unwrap_or(f64::INFINITY),
+            shrinkage_energy_delta: BioEnergyDelta::new(self.shrinkage_energy_delta),
+            max_shrinkage_rate: self.max_shrinkage_rate,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SpecialtyDocument {
+    Null,
+    Thruster {
+        #[serde(default)]
+        energy_per_thrust_sqr: f64,
+    },
+    Photo {
+        efficiency: f64,
+    },
+    Bonding,
+    Cilia {
+        max_thrust_per_area: f64,
+        energy_per_thrust_sqr: f64,
+    },
+    Parasite {
+        siphon_rate: f64,
+        shell_resistance: f64,
+    },
+    Sensor {
+        range: f64,
+        target_color: Option<Color>,
+    },
+    Elongator {
+        max_length: f64,
+        energy_per_length: f64,
+        #[serde(default)]
+        length: f64,
+    },
+    Pigment {
+        efficiency: f64,
+        energy_per_change: f64,
+        #[serde(default)]
+        pigment: Option<Color>,
+    },
+}
+
+impl SpecialtyDocument {
+    pub fn to_specialty(&self) -> Box<dyn CellLayerSpecialty> {
+        match self {
+            SpecialtyDocument::Null => Box::new(NullCellLayerSpecialty::new()),
+            SpecialtyDocument::Thruster {
+                energy_per_thrust_sqr,
+            } => Box::new(ThrusterCellLayerSpecialty::new(*energy_per_thrust_sqr)),
+            SpecialtyDocument::Photo { efficiency } => {
+                Box::new(PhotoCellLayerSpecialty::new(*efficiency))
+            }
+            SpecialtyDocument::Bonding => Box::new(BondingCellLayerSpecialty::new()),
+            SpecialtyDocument::Cilia {
+                max_thrust_per_area,
+                energy_per_thrust_sqr,
+            } => Box::new(CiliaCellLayerSpecialty::new(
+                *max_thrust_per_area,
+                *energy_per_thrust_sqr,
+            )),
+            SpecialtyDocument::Parasite {
+                siphon_rate,
+                shell_resistance,
+            } => Box::new(ParasiteCellLayerSpecialty::new(
+                *siphon_rate,
+                *shell_resistance,
+            )),
+            SpecialtyDocument::Sensor {
+                range,
+                target_color,
+            } => Box::new(SensorCellLayerSpecialty::new(
+                Length::new(*range),
+                *target_color,
+            )),
+            SpecialtyDocument::Elongator {
+                max_length,
+                energy_per_length,
+                length,
+            } => Box::new(
+                ElongatorCellLayerSpecialty::new(Length::new(*max_length), *energy_per_length)
+                    .with_length(Length::new(*length)),
+            ),
+            SpecialtyDocument::Pigment {
+                efficiency,
+                energy_per_change,
+                pigment,
+            } => {
+                let specialty = PigmentCellLayerSpecialty::new(*efficiency, *energy_per_change);
+                match pigment {
+                    Some(pigment) => Box::new(specialty.with_pigment(*pigment)),
+                    None => Box::new(specialty),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BondDocument {
+    pub cell1: usize,
+    pub cell2: usize,
+    pub bond_index_on_cell1: usize,
+    pub bond_index_on_cell2: usize,
+    pub energy_for_cell1: f64,
+    pub energy_for_cell2: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AngleGussetDocument {
+    pub bond1: usize,
+    pub bond2: usize,
+    pub angle: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum InfluenceDocument {
+    WallCollisions {
+        min_corner: [f64; 2],
+        max_corner: [f64; 2],
+        #[serde(default)]
+        static_friction: f64,
+        #[serde(default)]
+        kinetic_friction: f64,
+    },
+    PairCollisions,
+    BondForces,
+    ElasticBondForces {
+        spring_constant: f64,
+        loss_factor: f64,
+    },
+    BondAngleForces,
+    ConstantForce {
+        force: [f64; 2],
+    },
+    WeightForce {
+        gravity: f64,
+    },
+    BuoyancyForce {
+        gravity: f64,
+        fluid_density: f64,
+    },
+    DragForce {
+        viscosity: f64,
+        #[serde(default)]
+        speed_law: DragSpeedLaw,
+        #[serde(default)]
+        size_measure: DragSizeMeasure,
+    },
+    UniversalOverlap {
+        incursion: [f64; 2],
+        width: f64,
+    },
+    ThermalNoise {
+        magnitude: f64,
+        seed: u64,
+    },
+    EnergyTax {
+        rate: f64,
+    },
+    Sunlight {
+        min_y: f64,
+        max_y: f64,
+        min_intensity: f64,
+        max_intensity: f64,
+        // The direction the light travels, in radians.
+        #[serde(default)]
+        direction: Option<f64>,
+        #[serde(default)]
+        shading: f64,
+    },
+    LightPatch {
+        min_corner: [f64; 2],
+        max_corner: [f64; 2],
+        intensity: f64,
+    },
+    // An influence with one of its tunable parameters varied over time: base_value times the
+    // schedule's factor for the tick.
+    Scheduled {
+        influence: Box<InfluenceDocument>,
+        parameter: TunableParameter,
+        base_value: f64,
+        schedule: Schedule,
+    },
+}
+
+impl InfluenceDocument {
+    pub fn default_id(&self) -> &'static str {
+        match self {
+            InfluenceDocument::WallCollisions { .. } => "wall_collisions",
+            InfluenceDocument::PairCollisions => "pair_collisions",
+            InfluenceDocument::BondForces => "bond_forces",
+            InfluenceDocument::ElasticBondForces { .. } => "elastic_bond_forces",
+            InfluenceDocument::BondAngleForces => "bond_angle_forces",
+            InfluenceDocument::ConstantForce { .. } => "constant_force",
+            InfluenceDocument::WeightForce { .. } => "weight",
+            InfluenceDocument::BuoyancyForce { .. } => "buoyancy",
+            InfluenceDocument::DragForce { .. } => "drag",
+            InfluenceDocument::UniversalOverlap { .. } => "universal_overlap",
+            InfluenceDocument::ThermalNoise { .. } => "thermal_noise",
+            InfluenceDocument::EnergyTax { .. } => "energy_tax",
+            InfluenceDocument::Sunlight { .. } => "sunlight",
+            InfluenceDocument::LightPatch { .. } => "light_patch",
+            InfluenceDocument::Scheduled { influence, .. } => influence.default_id(),
+        }
+    }
+
+    pub fn to_influence(&self) -> Box<dyn Influence> {
+        match *self {
+            InfluenceDocument::WallCollisions {
+                min_corner,
+                max_corner,
+                static_friction,
+                kinetic_friction,
+            } => Box::new(
+                WallCollisions::new(to_position(min_corner), to_position(max_corner))
+                    .with_friction(WallFriction {
+                        static_coefficient: static_friction,
+                        kinetic_coefficient: kinetic_friction,
+                    }),
+            ),
+            InfluenceDocument::PairCollisions => Box::new(PairCollisions::new()),
+            InfluenceDocument::BondForces => Box::new(BondForces::new()),
+            InfluenceDocument::ElasticBondForces {
+                spring_constant,
+                loss_factor,
+            } => Box::new(ElasticBondForces::new(spring_constant, loss_factor)),
+            InfluenceDocument::BondAngleForces => Box::new(BondAngleForces::new()),
+            InfluenceDocument::ConstantForce { force } => Box::new(SimpleForceInfluence::new(
+                Box::new(ConstantForce::new(Force::new(force[0], force[1]))),
+            )),
+            InfluenceDocument::WeightForce { gravity } => Box::new(SimpleForceInfluence::new(
+                Box::new(WeightForce::new(gravity)),
+            )),
+            InfluenceDocument::BuoyancyForce {
+                gravity,
+                fluid_density,
+            } => Box::new(SimpleForceInfluence::new(Box::new(BuoyancyForce::new(
+                gravity,
+                fluid_density,
+            )))),
+            InfluenceDocument::DragForce {
+                viscosity,
+                speed_law,
+                size_measure,
+            } => Box::new(SimpleForceInfluence::new(Box::new(
+                DragForce::new(viscosity)
+                    .with_speed_law(speed_law)
+                    .with_size_measure(size_measure),
+            ))),
+            InfluenceDocument::UniversalOverlap { incursion, width } => {
+                Box::new(UniversalOverlap::new(Overlap::new(
+                    Displacement::new(incursion[0], incursion[1]),
+                    width,
+                )))
+            }
+            InfluenceDocument::ThermalNoise { magnitude, seed } => {
+                Box::new(ThermalNoise::new(magnitude, seed))
+            }
+            InfluenceDocument::EnergyTax { rate } => Box::new(EnergyTax::new(rate)),
+            InfluenceDocument::Sunlight {
+                min_y,
+                max_y,
+                min_intensity,
+                max_intensity,
+                direction,
+                shading,
+            } => {
+                let sunlight = Sunlight::new(min_y, max_y, min_intensity, max_intensity);
+                match direction {
+                    Some(direction) => {
+                        Box::new(sunlight.with_direction(Angle::from_radians(direction), shading))
+                    }
+                    None => Box::new(sunlight),
+                }
+            }
+            InfluenceDocument::LightPatch {
+                min_corner,
+                max_corner,
+                intensity,
+            } => Box::new(LightPatch::new(
+                to_position(min_corner),
+                to_position(max_corner),
+                intensity,
+            )),
+            InfluenceDocument::Scheduled {
+                ref influence,
+                parameter,
+                base_value,
+                schedule,
+            } => {
+                // older documents have the inner influence at its value for the tick they were
+                // saved at
+                let mut influence = influence.to_influence();
+                influence.set_tunable_parameter(parameter, base_value);
+                Box::new(ScheduledInfluence::new(influence, parameter, schedule))
+            }
+        }
+    }
+}
+
+pub fn from_position(position: Position) -> [f64; 2] {
+    [position.x(), position.y()]
+}
+
+pub fn to_position(xy: [f64; 2]) -> Position {
+    Position::new(xy[0], xy[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::cell::Cell;
+
+    #[test]
+    fn world_document_round_trips_through_json() {
+        let document = WorldDocument {
+            version: WorldDocument::VERSION,
+            tick: 7,
+            min_corner: [-10.0, -20.0],
+            max_corner: [10.0, 20.0],
+            influences: vec![
+                InfluenceDocument::PairCollisions,
+                InfluenceDocument::DragForce {
+                    viscosity: 0.5,
+                    speed_law: DragSpeedLaw::Linear,
+                    size_measure: DragSizeMeasure::Area,
+                },
+            ],
+            cells: vec![CellDocument {
+                position: [1.0, 2.0],
+                velocity: [-1.0, 0.5],
+                energy: 3.0,
+                lineage: 2,
+                spore: Some(Spore {
+                    hatch_light_intensity: Some(0.5),
+                    remaining_ticks: None,
+                }),
+                tags: vec![CellTag {
+                    name: "founderA".to_string(),
+                    inherited: true,
+                }],
+                adhesion_tag: AdhesionTag::new(0b101),
+                neural_layer_index: Some(0),
+                dropout_seed: Some(42),
+                heading: 0.5,
+                has_control: true,
+                layers: vec![LayerDocument {
+                    area: 4.0,
+                    density: 1.5,
+                    color: Color::Green,
+                    health: 0.75,
+                    health_parameters: LayerHealthParametersDocument::from_parameters(
+                        &LayerHealthParameters::DEFAULT,
+                    ),
+                    resize_parameters: LayerResizeParametersDocument::from_parameters(
+                        &LayerResizeParameters::UNLIMITED,
+                    ),
+                    specialty: SpecialtyDocument::Photo { efficiency: 0.25 },
+                }],
+            }],
+            bonds: vec![],
+            angle_gussets: vec![],
+            tissue_multipliers: TissueMultipliers::new().with_multiplier(
+                Color::Green,
+                TissueMultiplier {
+                    energy_gain: 0.5,
+                    cost: 2.0,
+                },
+            ),
+            adhesion_rule: Some(AdhesionRule {
+                max_distance: 2,
+                bit_flip_rate: 0.01,
+                seed: 3,
+            }),
+        };
+
+        let json = document.to_json();
+
+        assert_eq!(WorldDocument::from_json(&json).unwrap(), document);
+    }
+
+    #[test]
+    fn unlimited_growth_rate_round_trips() {
+        let document =
+            LayerResizeParametersDocument::from_parameters(&LayerResizeParameters::UNLIMITED);
+        assert_eq!(document.max_growth_rate, None);
+        assert_eq!(document.to_parameters().max_growth_rate, f64::INFINITY);
+    }
+
+    #[test]
+    fn influence_document_uses_type_tag() {
+        let json =
+            serde_json::to_string(&InfluenceDocument::WeightForce { gravity: -2.0 }).unwrap();
+        assert_eq!(json, r#"{"type":"WeightForce","gravity":-2.0}"#);
+    }
+
+    #[test]
+    fn drag_laws_default_to_quadratic_in_radius() {
+        let document: InfluenceDocument =
+            serde_json::from_str(r#"{"type":"DragForce","viscosity":0.5}"#).unwrap();
+        assert_eq!(
+            document,
+            InfluenceDocument::DragForce {
+                viscosity: 0.5,
+                speed_law: DragSpeedLaw::Quadratic,
+                size_measure: DragSizeMeasure::Radius,
+            }
+        );
+    }
+
+    #[test]
+    fn thruster_defaults_to_free_thrust() {
+        let document: SpecialtyDocument = serde_json::from_str(r#"{"type":"Thruster"}"#).unwrap();
+        assert_eq!(
+            document,
+            SpecialtyDocument::Thruster {
+                energy_per_thrust_sqr: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn sunlight_defaults_to_no_direction() {
+        let document: InfluenceDocument = serde_json::from_str(
+            r#"{"type":"Sunlight","min_y":0.0,"max_y":10.0,"min_intensity":0.0,"max_intensity":1.0}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            document,
+            InfluenceDocument::Sunlight {
+                min_y: 0.0,
+                max_y: 10.0,
+                min_intensity: 0.0,
+                max_intensity: 1.0,
+                direction: None,
+                shading: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn directional_sunlight_round_trips() {
+        let document = InfluenceDocument::Sunlight {
+            min_y: 0.0,
+            max_y: 10.0,
+            min_intensity: 0.0,
+            max_intensity: 1.0,
+            direction: Some(1.5),
+            shading: 0.25,
+        };
+        assert_eq!(document.to_influence().to_document(), document);
+    }
+
+    #[test]
+    fn scheduled_influence_reloads_with_base_value() {
+        let mut influence = ScheduledInfluence::new(
+            Box::new(LightPatch::new(
+                Position::new(0.0, 0.0),
+                Position::new(10.0, 10.0),
+                2.0,
+            )),
+            TunableParameter::LightIntensity,
+            Schedule::pulses(0, 10, 2, 1.0, 0.0),
+        );
+        influence.before_tick(5);
+
+        let document = influence.to_document();
+        let json = serde_json::to_string(&document).unwrap();
+        let mut reloaded = serde_json::from_str::<InfluenceDocument>(&json)
+            .unwrap()
+            .to_influence();
+
+        assert_eq!(document.default_id(), "light_patch");
+        assert_eq!(
+            reloaded.tunable_parameter(TunableParameter::LightIntensity),
+            Some(2.0)
+        );
+        reloaded.before_tick(5);
+        assert_eq!(reloaded.to_document(), document);
+    }
+
+    #[test]
+    fn scheduled_sunlight_saved_in_the_dark_lights_up_after_reload() {
+        let mut influence = ScheduledInfluence::new(
+            Box::new(Sunlight::new(0.0, 10.0, 1.0, 2.0)),
+            TunableParameter::LightIntensity,
+            Schedule::pulses(0, 10, 5, 1.0, 0.0),
+        );
+        influence.before_tick(5);
+        assert_eq!(influence.light_intensity_at(Position::new(0.0, 10.0)), 0.0);
+
+        let json = serde_json::to_string(&influence.to_document()).unwrap();
+        let mut reloaded = serde_json::from_str::<InfluenceDocument>(&json)
+            .unwrap()
+            .to_influence();
+        reloaded.before_tick(10);
+
+        assert_eq!(reloaded.light_intensity_at(Position::new(0.0, 0.0)), 1.0);
+        assert_eq!(reloaded.light_intensity_at(Position::new(0.0, 10.0)), 2.0);
+    }
+
+    #[test]
+    fn world_patch_fields_are_optional() {
+        let patch = WorldPatch::from_json(
+            r#"{"set_parameters": [{"parameter": "LightIntensity", "value": 40.0}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            patch,
+            WorldPatch {
+                set_parameters: vec![ParameterSetting {
+                    parameter: TunableParameter::LightIntensity,
+                    value: 40.0
+                }],
+                ..WorldPatch::default()
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        assert!(WorldDocument::from_json("{\"version\": 1").is_err());
+    }
+
+    #[test]
+    fn world_delta_rebuilds_world_from_keyframe() {
+        let keyframe = world_document_with_cells(vec![
+            ball_document([0.0, 0.0]),
+            ball_document([5.0, 0.0]),
+            ball_document([10.0, 0.0]),
+        ]);
+        let mut moved = ball_document([1.0, 0.0]);
+        moved.energy = 2.0;
+        moved.layers[0].health = 0.5;
+        let mut retagged = ball_document([10.0, 0.0]);
+        retagged.tags.push(CellTag {
+            name: "marked".to_string(),
+            inherited: false,
+        });
+        let document = world_document_with_cells(vec![moved, retagged, ball_document([15.0, 0.0])]);
+
+        let delta = WorldDeltaDocument::new(&keyframe, 10, &[1, 2, 3], &document, &[1, 3, 4]);
+
+        assert!(matches!(
+            delta.cells[0],
+            CellDeltaDocument::Updated {
+                keyframe_index: 0,
+                ..
+            }
+        ));
+        assert!(matches!(delta.cells[1], CellDeltaDocument::Full(_)));
+        assert!(matches!(delta.cells[2], CellDeltaDocument::Full(_)));
+        assert_eq!(delta.influences, None);
+        let delta = WorldDeltaDocument::from_json(&delta.to_json()).unwrap();
+        assert_eq!(delta.apply_to(&keyframe), Ok(document));
+    }
+
+    fn world_document_with_cells(cells: Vec<CellDocument>) -> WorldDocument {
+        WorldDocument {
+            version: WorldDocument::VERSION,
+            tick: 0,
+            min_corner: [-20.0, -20.0],
+            max_corner: [20.0, 20.0],
+            influences: vec![InfluenceDocument::PairCollisions],
+            cells,
+            bonds: vec![],
+            angle_gussets: vec![],
+            tissue_multipliers: TissueMultipliers::new(),
+            adhesion_rule: None,
+        }
+    }
+
+    fn ball_document(position: [f64; 2]) -> CellDocument {
+        Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            to_position(position),
+            Velocity::ZERO,
+        )
+        .to_document()
+    }
+
+    #[test]
+    fn run_metadata_round_trips_through_json() {
+        let metadata = RunMetadata {
+            version: RunMetadata::VERSION,
+            started_at_unix_secs: 1_600_000_000,
+            command: vec!["evo_main".to_string(), "record".to_string()],
+            seed: Some(42),
+            git_describe: None,
+            num_ticks: 100,
+            checkpoint_interval: 10,
+            keyframe_interval: 5,
+            resumed_from: Some("runs/run_1/checkpoints/tick_10.json".to_string()),
+            patches: vec![WorldPatch {
+                remove_influences: vec!["sunlight".to_string()],
+                ..WorldPatch::default()
+            }],
+            plugins: vec!["census".to_string()],
+        };
+
+        assert_eq!(
+            RunMetadata::from_json(&metadata.to_json()).unwrap(),
+            metadata
+        );
+    }
+}
@@ -1,2 +1,4 @@
 pub mod influences;
 pub mod local_environment;
+pub mod probes;
+pub mod trajectories;
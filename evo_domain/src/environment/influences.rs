@@ -1,4 +1,6 @@
 use crate::biology::cell::Cell;
+use crate::biology::layers::Color;
+use crate::document::*;
 use crate::environment::local_environment::*;
 use crate::physics::bond::*;
 use crate::physics::newtonian::*;
@@ -7,45 +9,198 @@ use crate::physics::quantities::*;
 use crate::physics::shapes::Circle;
 use crate::physics::sortable_graph::*;
 use crate::physics::util::*;
+use rand::{Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
 
 pub trait Influence {
     fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>);
+
+    fn to_document(&self) -> InfluenceDocument;
+
+    fn tunable_parameter(&self, _parameter: TunableParameter) -> Option<f64> {
+        None
+    }
+
+    fn set_tunable_parameter(&mut self, _parameter: TunableParameter, _value: f64) {}
+
+    // Called by the world at the start of every tick, before any influences are applied.
+    fn before_tick(&mut self, _tick: u64) {}
+
+    // The light this influence would add at a position, for probes.
+    fn light_intensity_at(&self, _position: Position) -> f64 {
+        0.0
+    }
+
+    // The walls this influence keeps cells inside, if any, for views.
+    fn walls(&self) -> Option<&Walls> {
+        None
+    }
+
+    // The energy this influence adds to or removes from the world in one tick. Called right
+    // after apply, before its forces have moved any cells.
+    fn energy_flux(&self, _cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> EnergyFlux {
+        EnergyFlux::ZERO
+    }
+}
+
+// Energy crossing the world's boundary in one tick: injected, e.g. sunlight falling on cells,
+// and dissipated, e.g. kinetic energy lost to drag as heat. Both are non-negative.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnergyFlux {
+    pub injected: f64,
+    pub dissipated: f64,
+}
+
+impl EnergyFlux {
+    pub const ZERO: EnergyFlux = EnergyFlux {
+        injected: 0.0,
+        dissipated: 0.0,
+    };
+
+    pub fn injected(injected: f64) -> Self {
+        EnergyFlux {
+            injected,
+            dissipated: 0.0,
+        }
+    }
+
+    pub fn dissipated(dissipated: f64) -> Self {
+        EnergyFlux {
+            injected: 0.0,
+            dissipated,
+        }
+    }
+
+    pub fn net(&self) -> f64 {
+        self.injected - self.dissipated
+    }
+}
+
+impl std::ops::AddAssign for EnergyFlux {
+    fn add_assign(&mut self, rhs: EnergyFlux) {
+        self.injected += rhs.injected;
+        self.dissipated += rhs.dissipated;
+    }
+}
+
+// World parameters that can be changed while the world is running. The tissue multipliers
+// belong to the world itself rather than to its influences, and the mutation rate to the
+// cells' controls.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum TunableParameter {
+    Gravity,
+    LightIntensity,
+    DragViscosity,
+    TissueEnergyGain(Color),
+    TissueCost(Color),
+    MutationRate,
+}
+
+impl TunableParameter {
+    pub const ALL: [TunableParameter; 10] = [
+        TunableParameter::Gravity,
+        TunableParameter::LightIntensity,
+        TunableParameter::DragViscosity,
+        TunableParameter::TissueEnergyGain(Color::Green),
+        TunableParameter::TissueEnergyGain(Color::White),
+        TunableParameter::TissueEnergyGain(Color::Yellow),
+        TunableParameter::TissueCost(Color::Green),
+        TunableParameter::TissueCost(Color::White),
+        TunableParameter::TissueCost(Color::Yellow),
+        TunableParameter::MutationRate,
+    ];
 }
 
 #[derive(Debug)]
 pub struct WallCollisions {
     walls: Walls,
+    friction: WallFriction,
+}
+
+// Coulomb friction between walls and the cells touching them, as multiples of the force the
+// wall pushes a cell with. Static friction stops a cell sliding along a wall if the force
+// needed to stop it in one tick is small enough; otherwise kinetic friction slows it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WallFriction {
+    pub static_coefficient: f64,
+    pub kinetic_coefficient: f64,
+}
+
+impl WallFriction {
+    pub const NONE: WallFriction = WallFriction {
+        static_coefficient: 0.0,
+        kinetic_coefficient: 0.0,
+    };
+
+    // The force along a wall, given the wall's push away from it.
+    fn force(&self, mass: Mass, tangential_velocity: f64, normal_force: f64) -> f64 {
+        let stopping_force = -mass.value() * tangential_velocity;
+        if stopping_force.abs() <= self.static_coefficient * normal_force.abs() {
+            stopping_force
+        } else {
+            (self.kinetic_coefficient * normal_force.abs()).min(stopping_force.abs())
+                * stopping_force.signum()
+        }
+    }
 }
 
 impl WallCollisions {
     pub fn new(min_corner: Position, max_corner: Position) -> Self {
         WallCollisions {
             walls: Walls::new(min_corner, max_corner),
+            friction: WallFriction::NONE,
         }
     }
 
+    pub fn with_friction(mut self, friction: WallFriction) -> Self {
+        assert!(friction.static_coefficient >= 0.0 && friction.kinetic_coefficient >= 0.0);
+        self.friction = friction;
+        self
+    }
+
     fn add_overlap_and_force(&self, cell: &mut Cell, overlap: Overlap) {
         cell.environment_mut().add_overlap(overlap);
-        let force = Self::collision_force(cell.mass(), cell.velocity(), -overlap.incursion());
+        let force = Self::collision_force(cell.mass(), cell.velocity(), &overlap);
         cell.forces_mut().set_net_force_if_stronger(force);
+        if self.friction != WallFriction::NONE {
+            let friction_force =
+                Self::friction_force(self.friction, cell.mass(), cell.velocity(), &overlap, force);
+            cell.forces_mut().add_force(friction_force);
+        }
     }
 
-    fn collision_force(mass: Mass, velocity: Velocity, overlap: Displacement) -> Force {
-        Force::new(
-            Self::x_or_y_collision_force(mass, velocity.x(), overlap.x()),
-            Self::x_or_y_collision_force(mass, velocity.y(), overlap.y()),
+    // Acts along the wall, perpendicular to the overlap's normal, so a cell in a corner gets one
+    // friction force rather than one from each wall.
+    fn friction_force(
+        friction: WallFriction,
+        mass: Mass,
+        velocity: Velocity,
+        overlap: &Overlap,
+        collision_force: Force,
+    ) -> Force {
+        let normal = overlap.normal().value();
+        let tangent = Value2D::new(-normal.y(), normal.x());
+        let tangential_velocity = velocity.value().dot(tangent);
+        Force::from(
+            friction.force(
+                mass,
+                tangential_velocity,
+                collision_force.value().magnitude(),
+            ) * tangent,
         )
     }
 
-    fn x_or_y_collision_force(mass: Mass, velocity: f64, overlap: f64) -> f64 {
-        let v = if overlap > 0.0 {
-            velocity.max(overlap)
-        } else if overlap < 0.0 {
-            velocity.min(overlap)
-        } else {
-            -velocity
-        };
-        -mass.value() * (velocity + v)
+    // Bounces the cell off the wall along the overlap's normal, reflecting its velocity into the
+    // wall, but at least as fast as the overlap is deep. A cell in a corner gets one bounce off
+    // both walls instead of a separate one per wall.
+    fn collision_force(mass: Mass, velocity: Velocity, overlap: &Overlap) -> Force {
+        let normal = overlap.normal().value();
+        let normal_velocity = velocity.value().dot(normal);
+        let new_normal_velocity = (-normal_velocity).max(overlap.depth().value());
+        Force::from(mass.value() * (new_normal_velocity - normal_velocity) * normal)
     }
 }
 
@@ -56,6 +211,19 @@ impl Influence for WallCollisions {
             self.add_overlap_and_force(cell_graph.node_mut(handle), overlap);
         }
     }
+
+    fn walls(&self) -> Option<&Walls> {
+        Some(&self.walls)
+    }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::WallCollisions {
+            min_corner: from_position(self.walls.min_corner()),
+            max_corner: from_position(self.walls.max_corner()),
+            static_friction: self.friction.static_coefficient,
+            kinetic_friction: self.friction.kinetic_coefficient,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -73,7 +241,7 @@ impl PairCollisions {
     }
 
     fn cell1_collision_force(cell1: &Cell, overlap1: Overlap, cell2: &Cell) -> Force {
-        if overlap1.incursion() == Displacement::ZERO {
+        if overlap1.depth() == Length::ZERO {
             return Force::ZERO;
         }
 
@@ -112,7 +280,8 @@ impl PairCollisions {
     fn body1_overlap_force(mass1: Mass, mass2: Mass, overlap1: Overlap) -> Force {
         Force::from(
             (mass1.value() * mass2.value() / (mass1 + mass2).value())
-                * overlap1.incursion().value(),
+                * overlap1.depth().value()
+                * overlap1.normal().value(),
         )
     }
 }
@@ -130,6 +299,10 @@ impl Influence for PairCollisions {
             Self::add_overlap_and_force(cell_graph.node_mut(handle2), overlap2, -force1);
         }
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::PairCollisions
+    }
 }
 
 #[derive(Debug)]
@@ -154,7 +327,7 @@ impl BondForces {
             cell1.position() - cell2.position(),
         );
         let strain_force = Self::body1_clear_strain_force(cell1.mass(), cell2.mass(), strain1);
-        Self::print_bond_force(&cell1, &cell2, velocity_force, strain_force);
+        Self::print_bond_force(cell1, cell2, velocity_force, strain_force);
         velocity_force + strain_force
     }
 
@@ -203,6 +376,59 @@ impl Influence for BondForces {
             Self::add_force(cell_graph.node_mut(handle2), -force1);
         }
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::BondForces
+    }
+}
+
+// An alternative to BondForces that makes bonds springs, which store elastic potential energy,
+// spring_constant * strain^2 / 2, as they stretch or compress. While a bond's strain relaxes,
+// it pushes with only (1 - loss_factor) of its full force, so it returns that fraction of its
+// stored energy to the cells' motion and loses the rest.
+#[derive(Debug)]
+pub struct ElasticBondForces {
+    spring_constant: f64,
+    loss_factor: f64,
+}
+
+impl ElasticBondForces {
+    pub fn new(spring_constant: f64, loss_factor: f64) -> Self {
+        assert!(spring_constant > 0.0);
+        assert!((0.0..=1.0).contains(&loss_factor));
+        ElasticBondForces {
+            spring_constant,
+            loss_factor,
+        }
+    }
+}
+
+impl Influence for ElasticBondForces {
+    fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>) {
+        let strains = calc_bond_strains(cell_graph);
+        for (index, ((handle1, strain1), (handle2, _strain2))) in strains.into_iter().enumerate() {
+            let strain = strain1.strain().value();
+            let energy = 0.5 * self.spring_constant * strain.dot_sqr();
+            let bond = cell_graph.edge_mut(cell_graph.edges()[index].edge_handle());
+            let relaxing = energy < bond.elastic_energy();
+            bond.set_elastic_energy(energy);
+            let stiffness = if relaxing {
+                self.spring_constant * (1.0 - self.loss_factor)
+            } else {
+                self.spring_constant
+            };
+            let force1 = Force::from(stiffness * strain);
+            cell_graph.node_mut(handle1).forces_mut().add_force(force1);
+            cell_graph.node_mut(handle2).forces_mut().add_force(-force1);
+        }
+    }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::ElasticBondForces {
+            spring_constant: self.spring_constant,
+            loss_factor: self.loss_factor,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -223,6 +449,103 @@ impl Influence for BondAngleForces {
             cell.forces_mut().add_force(force);
         }
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::BondAngleForces
+    }
+}
+
+// Small random forces, like Brownian motion, that keep cells from settling into perfectly
+// static arrangements. Each component of each cell's force is normally distributed with a
+// standard deviation of `magnitude`. The forces depend only on the seed, the tick, and the
+// cell's ID, so runs are repeatable.
+#[derive(Debug)]
+pub struct ThermalNoise {
+    magnitude: f64,
+    seed: u64,
+    tick: u64,
+}
+
+impl ThermalNoise {
+    pub fn new(magnitude: f64, seed: u64) -> Self {
+        assert!(magnitude >= 0.0);
+        ThermalNoise {
+            magnitude,
+            seed,
+            tick: 0,
+        }
+    }
+
+    fn force(&self, cell: &Cell) -> Force {
+        let mut rng = Pcg64Mcg::seed_from_u64(
+            self.seed ^ self.tick.rotate_left(32) ^ cell.id().value().wrapping_mul(0x9E37_79B9),
+        );
+        Force::new(
+            self.magnitude * rng.sample::<f64, _>(StandardNormal),
+            self.magnitude * rng.sample::<f64, _>(StandardNormal),
+        )
+    }
+}
+
+impl Influence for ThermalNoise {
+    fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>) {
+        for cell in cell_graph.nodes_mut() {
+            let force = self.force(cell);
+            cell.forces_mut().add_force(force);
+        }
+    }
+
+    fn before_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::ThermalNoise {
+            magnitude: self.magnitude,
+            seed: self.seed,
+        }
+    }
+}
+
+// Takes a fraction of every cell's energy each tick, as a simple knob for selection pressure:
+// the higher the rate, the harsher the world is on cells that hoard energy rather than use it.
+#[derive(Debug)]
+pub struct EnergyTax {
+    rate: f64,
+}
+
+impl EnergyTax {
+    // The rate must be less than 1, so that the tax can be worked out from what is left.
+    pub fn new(rate: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&rate),
+            "Invalid energy tax rate: {}",
+            rate
+        );
+        EnergyTax { rate }
+    }
+}
+
+impl Influence for EnergyTax {
+    fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>) {
+        for cell in cell_graph.nodes_mut() {
+            let tax = cell.energy() * self.rate;
+            cell.take_energy(tax);
+        }
+    }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::EnergyTax { rate: self.rate }
+    }
+
+    fn energy_flux(&self, cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> EnergyFlux {
+        let remaining_energy: f64 = cell_graph
+            .nodes()
+            .iter()
+            .map(|cell| cell.energy().value())
+            .sum();
+        EnergyFlux::dissipated(remaining_energy * self.rate / (1.0 - self.rate))
+    }
 }
 
 pub struct SimpleForceInfluence {
@@ -242,10 +565,42 @@ impl Influence for SimpleForceInfluence {
             cell.forces_mut().add_force(force);
         }
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        self.influence_force.to_document()
+    }
+
+    fn energy_flux(&self, cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> EnergyFlux {
+        let mut flux = EnergyFlux::ZERO;
+        for cell in cell_graph.nodes() {
+            flux += self.influence_force.energy_flux(cell);
+        }
+        flux
+    }
+
+    fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        self.influence_force.tunable_parameter(parameter)
+    }
+
+    fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        self.influence_force.set_tunable_parameter(parameter, value);
+    }
 }
 
 pub trait SimpleInfluenceForce {
     fn calc_force(&self, cell: &Cell) -> Force;
+
+    fn to_document(&self) -> InfluenceDocument;
+
+    fn energy_flux(&self, _cell: &Cell) -> EnergyFlux {
+        EnergyFlux::ZERO
+    }
+
+    fn tunable_parameter(&self, _parameter: TunableParameter) -> Option<f64> {
+        None
+    }
+
+    fn set_tunable_parameter(&mut self, _parameter: TunableParameter, _value: f64) {}
 }
 
 #[derive(Debug)]
@@ -263,6 +618,12 @@ impl SimpleInfluenceForce for ConstantForce {
     fn calc_force(&self, _ball: &Cell) -> Force {
         self.force
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::ConstantForce {
+            force: [self.force.x(), self.force.y()],
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -282,6 +643,25 @@ impl SimpleInfluenceForce for WeightForce {
     fn calc_force(&self, cell: &Cell) -> Force {
         cell.mass() * self.gravity
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::WeightForce {
+            gravity: self.gravity.y(),
+        }
+    }
+
+    fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        match parameter {
+            TunableParameter::Gravity => Some(self.gravity.y()),
+            _ => None,
+        }
+    }
+
+    fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        if parameter == TunableParameter::Gravity {
+            self.gravity = Acceleration::new(0.0, value);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -304,27 +684,93 @@ impl SimpleInfluenceForce for BuoyancyForce {
         let displaced_fluid_mass = cell.area() * self.fluid_density;
         -(displaced_fluid_mass * self.gravity)
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::BuoyancyForce {
+            gravity: self.gravity.y(),
+            fluid_density: self.fluid_density.value(),
+        }
+    }
+
+    fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        match parameter {
+            TunableParameter::Gravity => Some(self.gravity.y()),
+            _ => None,
+        }
+    }
+
+    fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        if parameter == TunableParameter::Gravity {
+            self.gravity = Acceleration::new(0.0, value);
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct DragForce {
     viscosity: f64,
+    speed_law: DragSpeedLaw,
+    size_measure: DragSizeMeasure,
+}
+
+// How drag grows with a cell's speed: linearly (Stokes drag, for small, slow cells) or with its
+// square (for large, fast ones).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum DragSpeedLaw {
+    Linear,
+    #[default]
+    Quadratic,
+}
+
+// How drag grows with a cell's size: with its radius, i.e. cross-sectional length, or with its
+// area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum DragSizeMeasure {
+    #[default]
+    Radius,
+    Area,
 }
 
 impl DragForce {
+    // Quadratic in speed and proportional to radius.
     pub fn new(viscosity: f64) -> Self {
-        DragForce { viscosity }
+        DragForce {
+            viscosity,
+            speed_law: DragSpeedLaw::default(),
+            size_measure: DragSizeMeasure::default(),
+        }
+    }
+
+    pub fn with_speed_law(mut self, speed_law: DragSpeedLaw) -> Self {
+        self.speed_law = speed_law;
+        self
     }
 
-    fn calc_drag(&self, mass: Mass, radius: Length, velocity: f64) -> f64 {
+    pub fn with_size_measure(mut self, size_measure: DragSizeMeasure) -> Self {
+        self.size_measure = size_measure;
+        self
+    }
+
+    fn calc_drag(&self, mass: Mass, size: f64, velocity: f64) -> f64 {
         -velocity.signum()
             * self
-                .instantaneous_abs_drag(radius, velocity)
+                .instantaneous_abs_drag(size, velocity)
                 .min(Self::abs_drag_that_will_stop_the_cell(mass, velocity))
     }
 
-    fn instantaneous_abs_drag(&self, radius: Length, velocity: f64) -> f64 {
-        self.viscosity * radius.value() * sqr(velocity)
+    fn size(&self, cell: &Cell) -> f64 {
+        match self.size_measure {
+            DragSizeMeasure::Radius => cell.radius().value(),
+            DragSizeMeasure::Area => cell.area().value(),
+        }
+    }
+
+    fn instantaneous_abs_drag(&self, size: f64, velocity: f64) -> f64 {
+        let speed_factor = match self.speed_law {
+            DragSpeedLaw::Linear => velocity.abs(),
+            DragSpeedLaw::Quadratic => sqr(velocity),
+        };
+        self.viscosity * size * speed_factor
     }
 
     fn abs_drag_that_will_stop_the_cell(mass: Mass, velocity: f64) -> f64 {
@@ -334,11 +780,40 @@ impl DragForce {
 
 impl SimpleInfluenceForce for DragForce {
     fn calc_force(&self, cell: &Cell) -> Force {
+        let size = self.size(cell);
         Force::new(
-            self.calc_drag(cell.mass(), cell.radius(), cell.velocity().x()),
-            self.calc_drag(cell.mass(), cell.radius(), cell.velocity().y()),
+            self.calc_drag(cell.mass(), size, cell.velocity().x()),
+            self.calc_drag(cell.mass(), size, cell.velocity().y()),
         )
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::DragForce {
+            viscosity: self.viscosity,
+            speed_law: self.speed_law,
+            size_measure: self.size_measure,
+        }
+    }
+
+    // The work drag does against the cell's motion over one tick.
+    fn energy_flux(&self, cell: &Cell) -> EnergyFlux {
+        let force = self.calc_force(cell);
+        let velocity = cell.velocity();
+        EnergyFlux::dissipated(-(force.x() * velocity.x() + force.y() * velocity.y()))
+    }
+
+    fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        match parameter {
+            TunableParameter::DragViscosity => Some(self.viscosity),
+            _ => None,
+        }
+    }
+
+    fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        if parameter == TunableParameter::DragViscosity {
+            self.viscosity = value;
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -358,34 +833,436 @@ impl Influence for UniversalOverlap {
             cell.environment_mut().add_overlap(self.overlap);
         }
     }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::UniversalOverlap {
+            incursion: [self.overlap.incursion().x(), self.overlap.incursion().y()],
+            width: self.overlap.width(),
+        }
+    }
 }
 
+// Light whose intensity varies linearly with height. With a direction, the light travels that
+// way and a cell bonded to a neighbor upstream of it, between it and the light, is shaded by
+// that neighbor: it gets 1 - shading of the light when the neighbor is straight upstream, and
+// less shade the further off to the side the neighbor is.
 #[derive(Debug)]
 pub struct Sunlight {
+    min_y: f64,
+    max_y: f64,
+    min_intensity: f64,
+    max_intensity: f64,
+    // The gradient as constructed, which tuning scales without losing
+    configured_min_intensity: f64,
+    configured_max_intensity: f64,
     slope: f64,
     intercept: f64,
+    direction: Option<Angle>,
+    shading: f64,
 }
 
 impl Sunlight {
     pub fn new(min_y: f64, max_y: f64, min_intensity: f64, max_intensity: f64) -> Self {
-        let slope = (max_intensity - min_intensity) / (max_y - min_y);
-        Sunlight {
-            slope,
-            intercept: max_intensity - slope * max_y,
-        }
+        let mut sunlight = Sunlight {
+            min_y,
+            max_y,
+            min_intensity,
+            max_intensity,
+            configured_min_intensity: min_intensity,
+            configured_max_intensity: max_intensity,
+            slope: 0.0,
+            intercept: 0.0,
+            direction: None,
+            shading: 0.0,
+        };
+        sunlight.init_slope_and_intercept();
+        sunlight
+    }
+
+    pub fn with_direction(mut self, direction: Angle, shading: f64) -> Self {
+        assert!((0.0..=1.0).contains(&shading));
+        self.direction = Some(direction);
+        self.shading = shading;
+        self
+    }
+
+    fn init_slope_and_intercept(&mut self) {
+        self.slope = (self.max_intensity - self.min_intensity) / (self.max_y - self.min_y);
+        self.intercept = self.max_intensity - self.slope * self.max_y;
     }
 
     fn calc_light_intensity(&self, y: f64) -> f64 {
         (self.slope * y + self.intercept).max(0.0)
     }
+
+    // The light reaching each cell, indexed by node handle.
+    fn calc_cell_light_intensities(
+        &self,
+        cell_graph: &SortableGraph<Cell, Bond, AngleGusset>,
+    ) -> Vec<f64> {
+        let shades = self.calc_shades(cell_graph);
+        cell_graph
+            .nodes()
+            .iter()
+            .zip(shades)
+            .map(|(cell, shade)| {
+                self.calc_light_intensity(cell.center().y()) * (1.0 - self.shading * shade)
+            })
+            .collect()
+    }
+
+    // How much each cell's bonded neighbors stand between it and the light, from 0 to 1,
+    // indexed by node handle.
+    fn calc_shades(&self, cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> Vec<f64> {
+        let mut shades = vec![0.0; cell_graph.nodes().len()];
+        let direction = match self.direction {
+            Some(direction) if self.shading > 0.0 => direction,
+            _ => return shades,
+        };
+        let toward_light = Value2D::new(-direction.cos(), -direction.sin());
+        for bond in cell_graph.edges() {
+            let cell1 = cell_graph.node(bond.node1_handle());
+            let cell2 = cell_graph.node(bond.node2_handle());
+            let offset = (cell2.center() - cell1.center()).value();
+            let distance = offset.magnitude();
+            if distance == 0.0 {
+                continue;
+            }
+            let upstream = offset.dot(toward_light) / distance;
+            let shade1 = &mut shades[bond.node1_handle().index()];
+            *shade1 = shade1.max(upstream);
+            let shade2 = &mut shades[bond.node2_handle().index()];
+            *shade2 = shade2.max(-upstream);
+        }
+        shades
+    }
+
+    // Scales the configured gradient so that its brightest point has the given intensity, e.g.
+    // down to zero for the night and back up again.
+    fn set_max_intensity(&mut self, max_intensity: f64) {
+        self.min_intensity = if self.configured_max_intensity == 0.0 {
+            self.configured_min_intensity
+        } else {
+            self.configured_min_intensity * max_intensity / self.configured_max_intensity
+        };
+        self.max_intensity = max_intensity;
+        self.init_slope_and_intercept();
+    }
 }
 
 impl Influence for Sunlight {
+    fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>) {
+        let intensities = self.calc_cell_light_intensities(cell_graph);
+        for (cell, intensity) in cell_graph.nodes_mut().iter_mut().zip(intensities) {
+            cell.environment_mut().add_light_intensity(intensity);
+        }
+    }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::Sunlight {
+            min_y: self.min_y,
+            max_y: self.max_y,
+            min_intensity: self.min_intensity,
+            max_intensity: self.max_intensity,
+            direction: self.direction.map(Angle::radians),
+            shading: self.shading,
+        }
+    }
+
+    fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        match parameter {
+            TunableParameter::LightIntensity => Some(self.max_intensity),
+            _ => None,
+        }
+    }
+
+    fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        if parameter == TunableParameter::LightIntensity {
+            self.set_max_intensity(value);
+        }
+    }
+
+    fn light_intensity_at(&self, position: Position) -> f64 {
+        self.calc_light_intensity(position.y())
+    }
+
+    // The light falling on the cells, whether or not they capture it.
+    fn energy_flux(&self, cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> EnergyFlux {
+        let mut flux = EnergyFlux::ZERO;
+        let intensities = self.calc_cell_light_intensities(cell_graph);
+        for (cell, intensity) in cell_graph.nodes().iter().zip(intensities) {
+            flux += EnergyFlux::injected(intensity * cell.area().value());
+        }
+        flux
+    }
+}
+
+// Extra light falling on the cells whose centers are inside a rectangle, e.g. to pulse on
+// a schedule as a seasonal bloom.
+#[derive(Debug)]
+pub struct LightPatch {
+    min_corner: Position,
+    max_corner: Position,
+    intensity: f64,
+}
+
+impl LightPatch {
+    pub fn new(min_corner: Position, max_corner: Position, intensity: f64) -> Self {
+        assert!(min_corner.x() <= max_corner.x() && min_corner.y() <= max_corner.y());
+        LightPatch {
+            min_corner,
+            max_corner,
+            intensity,
+        }
+    }
+
+    fn contains(&self, position: Position) -> bool {
+        self.min_corner.x() <= position.x()
+            && position.x() <= self.max_corner.x()
+            && self.min_corner.y() <= position.y()
+            && position.y() <= self.max_corner.y()
+    }
+}
+
+impl Influence for LightPatch {
     fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>) {
         for cell in cell_graph.nodes_mut() {
-            let y = cell.center().y();
-            cell.environment_mut()
-                .add_light_intensity(self.calc_light_intensity(y));
+            if self.contains(cell.center()) {
+                cell.environment_mut().add_light_intensity(self.intensity);
+            }
+        }
+    }
+
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::LightPatch {
+            min_corner: from_position(self.min_corner),
+            max_corner: from_position(self.max_corner),
+            intensity: self.intensity,
+        }
+    }
+
+    fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        match parameter {
+            TunableParameter::LightIntensity => Some(self.intensity),
+            _ => None,
+        }
+    }
+
+    fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        if parameter == TunableParameter::LightIntensity {
+            self.intensity = value;
+        }
+    }
+
+    fn light_intensity_at(&self, position: Position) -> f64 {
+        if self.contains(position) {
+            self.intensity
+        } else {
+            0.0
+        }
+    }
+
+    fn energy_flux(&self, cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> EnergyFlux {
+        let mut flux = EnergyFlux::ZERO;
+        for cell in cell_graph.nodes() {
+            if self.contains(cell.center()) {
+                flux += EnergyFlux::injected(self.intensity * cell.area().value());
+            }
+        }
+        flux
+    }
+}
+
+// Modulates one tunable parameter of an inner influence over time. The parameter's value at
+// each tick is its base value times the schedule's factor for that tick.
+pub struct ScheduledInfluence {
+    influence: Box<dyn Influence>,
+    // The inner influence as it was before any factor was applied. Documents are saved from it,
+    // since a factor of 0 can lose state the base value needs, e.g. a sunlight's gradient.
+    base_document: InfluenceDocument,
+    parameter: TunableParameter,
+    base_value: f64,
+    schedule: Schedule,
+}
+
+impl ScheduledInfluence {
+    pub fn new(
+        influence: Box<dyn Influence>,
+        parameter: TunableParameter,
+        schedule: Schedule,
+    ) -> Self {
+        let base_value = influence
+            .tunable_parameter(parameter)
+            .unwrap_or_else(|| panic!("Influence has no tunable parameter {:?}", parameter));
+        if let Schedule::Pulses { period_ticks, .. } = schedule {
+            assert!(period_ticks > 0, "Pulses must have a period");
+        }
+        ScheduledInfluence {
+            base_document: influence.to_document(),
+            influence,
+            parameter,
+            base_value,
+            schedule,
+        }
+    }
+}
+
+impl Influence for ScheduledInfluence {
+    fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>) {
+        self.influence.apply(cell_graph);
+    }
+
+    // Saves the inner influence at the base value, with its other parameters as they are now.
+    fn to_document(&self) -> InfluenceDocument {
+        let mut base = self.base_document.to_influence();
+        for &parameter in &TunableParameter::ALL {
+            let value = if parameter == self.parameter {
+                Some(self.base_value)
+            } else {
+                self.influence.tunable_parameter(parameter)
+            };
+            if let Some(value) = value {
+                base.set_tunable_parameter(parameter, value);
+            }
+        }
+        InfluenceDocument::Scheduled {
+            influence: Box::new(base.to_document()),
+            parameter: self.parameter,
+            base_value: self.base_value,
+            schedule: self.schedule,
+        }
+    }
+
+    fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        if parameter == self.parameter {
+            Some(self.base_value)
+        } else {
+            self.influence.tunable_parameter(parameter)
+        }
+    }
+
+    fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        if parameter == self.parameter {
+            self.base_value = value;
+        } else {
+            self.influence.set_tunable_parameter(parameter, value);
+        }
+    }
+
+    fn before_tick(&mut self, tick: u64) {
+        self.influence.before_tick(tick);
+        self.influence
+            .set_tunable_parameter(self.parameter, self.base_value * self.schedule.factor(tick));
+    }
+
+    fn light_intensity_at(&self, position: Position) -> f64 {
+        self.influence.light_intensity_at(position)
+    }
+
+    fn energy_flux(&self, cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> EnergyFlux {
+        self.influence.energy_flux(cell_graph)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Schedule {
+    // Changes linearly from start_factor to end_factor between the two ticks, holding steady
+    // before and after.
+    Ramp {
+        start_tick: u64,
+        end_tick: u64,
+        start_factor: f64,
+        end_factor: f64,
+    },
+    // Oscillates around mean_factor, e.g. for seasons.
+    Sine {
+        period_ticks: f64,
+        mean_factor: f64,
+        amplitude: f64,
+    },
+    // Jumps from before_factor to after_factor at the given tick.
+    Step {
+        tick: u64,
+        before_factor: f64,
+        after_factor: f64,
+    },
+    // Pulses to pulse_factor for duration_ticks, starting at first_tick and again every
+    // period_ticks after that, and rests at rest_factor the rest of the time, e.g. for
+    // seasonal booms and busts.
+    Pulses {
+        first_tick: u64,
+        period_ticks: u64,
+        duration_ticks: u64,
+        pulse_factor: f64,
+        rest_factor: f64,
+    },
+}
+
+impl Schedule {
+    pub fn pulses(
+        first_tick: u64,
+        period_ticks: u64,
+        duration_ticks: u64,
+        pulse_factor: f64,
+        rest_factor: f64,
+    ) -> Self {
+        assert!(period_ticks > 0, "Pulses must have a period");
+        Schedule::Pulses {
+            first_tick,
+            period_ticks,
+            duration_ticks,
+            pulse_factor,
+            rest_factor,
+        }
+    }
+
+    pub fn factor(&self, tick: u64) -> f64 {
+        match *self {
+            Schedule::Ramp {
+                start_tick,
+                end_tick,
+                start_factor,
+                end_factor,
+            } => {
+                if tick <= start_tick {
+                    start_factor
+                } else if tick >= end_tick {
+                    end_factor
+                } else {
+                    let fraction = (tick - start_tick) as f64 / (end_tick - start_tick) as f64;
+                    start_factor + fraction * (end_factor - start_factor)
+                }
+            }
+            Schedule::Sine {
+                period_ticks,
+                mean_factor,
+                amplitude,
+            } => mean_factor + amplitude * (2.0 * PI * tick as f64 / period_ticks).sin(),
+            Schedule::Step {
+                tick: step_tick,
+                before_factor,
+                after_factor,
+            } => {
+                if tick < step_tick {
+                    before_factor
+                } else {
+                    after_factor
+                }
+            }
+            Schedule::Pulses {
+                first_tick,
+                period_ticks,
+                duration_ticks,
+                pulse_factor,
+                rest_factor,
+            } => {
+                if tick >= first_tick && (tick - first_tick) % period_ticks < duration_ticks {
+                    pulse_factor
+                } else {
+                    rest_factor
+                }
+            }
         }
     }
 }
@@ -393,11 +1270,32 @@ impl Influence for Sunlight {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::biology::cell::CellId;
     use crate::biology::layers::*;
-    use std::f64::consts::PI;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn wall_collisions_add_overlap_and_force() {
+        let mut cell_graph = SortableGraph::new();
+        let wall_collisions =
+            WallCollisions::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
+        let ball_handle = cell_graph.add_node(Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            Position::new(9.5, 9.5),
+            Velocity::new(1.0, 1.0),
+        ));
+
+        wall_collisions.apply(&mut cell_graph);
+
+        let ball = cell_graph.node(ball_handle);
+        assert_eq!(ball.environment().overlaps().len(), 1);
+        assert_ne!(ball.forces().net_force().x(), 0.0);
+        assert_ne!(ball.forces().net_force().y(), 0.0);
+    }
 
     #[test]
-    fn wall_collisions_add_overlap_and_force() {
+    fn wall_collisions_in_corner_push_along_one_normal() {
         let mut cell_graph = SortableGraph::new();
         let wall_collisions =
             WallCollisions::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
@@ -405,15 +1303,66 @@ mod tests {
             Length::new(1.0),
             Mass::new(1.0),
             Position::new(9.5, 9.5),
-            Velocity::new(1.0, 1.0),
+            Velocity::new(1.0, -1.0),
         ));
 
         wall_collisions.apply(&mut cell_graph);
 
-        let ball = cell_graph.node(ball_handle);
-        assert_eq!(ball.environment().overlaps().len(), 1);
-        assert_ne!(ball.forces().net_force().x(), 0.0);
-        assert_ne!(ball.forces().net_force().y(), 0.0);
+        let force = cell_graph.node(ball_handle).forces().net_force();
+        assert!(force.x() < 0.0);
+        assert_abs_diff_eq!(force.x(), force.y(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn weak_sliding_is_stopped_by_static_wall_friction() {
+        let friction = WallFriction {
+            static_coefficient: 0.5,
+            kinetic_coefficient: 0.25,
+        };
+        assert_eq!(
+            WallCollisions::friction_force(
+                friction,
+                Mass::new(2.0),
+                Velocity::new(1.0, -1.0),
+                &Overlap::new(Displacement::new(0.0, 0.5), 1.0),
+                Force::new(0.0, 4.0)
+            ),
+            Force::new(-2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn strong_sliding_is_slowed_by_kinetic_wall_friction() {
+        let friction = WallFriction {
+            static_coefficient: 0.5,
+            kinetic_coefficient: 0.25,
+        };
+        assert_eq!(
+            WallCollisions::friction_force(
+                friction,
+                Mass::new(2.0),
+                Velocity::new(-3.0, -1.0),
+                &Overlap::new(Displacement::new(0.0, 0.5), 1.0),
+                Force::new(0.0, 4.0)
+            ),
+            Force::new(1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn corner_wall_friction_acts_once_along_combined_wall() {
+        let friction = WallFriction {
+            static_coefficient: 0.5,
+            kinetic_coefficient: 0.25,
+        };
+        let force = WallCollisions::friction_force(
+            friction,
+            Mass::new(1.0),
+            Velocity::new(1.0, -1.0),
+            &Overlap::new(Displacement::new(-0.5, -0.5), 1.0),
+            Force::new(-4.0, -4.0),
+        );
+        assert_abs_diff_eq!(force, Force::new(-1.0, 1.0), epsilon = 1e-12);
     }
 
     #[test]
@@ -422,58 +1371,79 @@ mod tests {
             WallCollisions::collision_force(
                 Mass::new(2.0),
                 Velocity::new(3.0, 2.0),
-                Displacement::new(0.0, 0.0)
+                &Overlap::new(Displacement::ZERO, 1.0)
             ),
             Force::new(0.0, 0.0)
         );
     }
 
     #[test]
-    fn top_right_walls_fast_collision_force() {
+    fn right_wall_fast_collision_force() {
         assert_eq!(
             WallCollisions::collision_force(
                 Mass::new(2.0),
                 Velocity::new(3.0, 4.0),
-                Displacement::new(2.0, 3.0)
+                &Overlap::new(Displacement::new(-2.0, 0.0), 1.0)
             ),
-            Force::new(-12.0, -16.0)
+            Force::new(-12.0, 0.0)
         );
     }
 
     #[test]
-    fn top_right_walls_slow_collision_force() {
+    fn right_wall_slow_collision_force() {
         assert_eq!(
             WallCollisions::collision_force(
                 Mass::new(2.0),
                 Velocity::new(1.0, 0.5),
-                Displacement::new(2.0, 1.5)
+                &Overlap::new(Displacement::new(-2.0, 0.0), 1.0)
             ),
-            Force::new(-6.0, -4.0)
+            Force::new(-6.0, 0.0)
         );
     }
 
     #[test]
-    fn bottom_left_walls_fast_collision_force() {
+    fn bottom_wall_fast_collision_force() {
         assert_eq!(
             WallCollisions::collision_force(
                 Mass::new(2.0),
                 Velocity::new(-3.0, -4.0),
-                Displacement::new(-2.0, -3.0)
+                &Overlap::new(Displacement::new(0.0, 3.0), 1.0)
             ),
-            Force::new(12.0, 16.0)
+            Force::new(0.0, 16.0)
         );
     }
 
     #[test]
-    fn bottom_left_walls_slow_collision_force() {
+    fn bottom_wall_slow_collision_force() {
         assert_eq!(
             WallCollisions::collision_force(
                 Mass::new(2.0),
                 Velocity::new(-1.0, -0.5),
-                Displacement::new(-2.0, -1.5)
+                &Overlap::new(Displacement::new(0.0, 1.5), 1.0)
             ),
-            Force::new(6.0, 4.0)
+            Force::new(0.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn corner_collision_force_reflects_only_velocity_into_corner() {
+        // moving straight into the top right corner at 5, along the combined normal
+        let force = WallCollisions::collision_force(
+            Mass::new(2.0),
+            Velocity::new(3.0, 4.0),
+            &Overlap::new(Displacement::new(-0.6, -0.8), 1.0),
+        );
+        assert_abs_diff_eq!(force, Force::new(-12.0, -16.0), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn corner_collision_force_leaves_sliding_along_corner_alone() {
+        let force = WallCollisions::collision_force(
+            Mass::new(1.0),
+            Velocity::new(1.0, -1.0),
+            &Overlap::new(Displacement::new(-1.0, -1.0), 1.0),
         );
+        assert_abs_diff_eq!(force, Force::new(-1.0, -1.0), epsilon = 1e-12);
     }
 
     #[test]
@@ -680,6 +1650,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn elastic_bond_stores_energy_and_returns_part_of_it() {
+        let mut cell_graph = SortableGraph::new();
+        let elastic_bond_forces = ElasticBondForces::new(2.0, 0.25);
+        let ball1_handle = cell_graph.add_node(Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            Position::new(0.0, 0.0),
+            Velocity::ZERO,
+        ));
+        let ball2_handle = cell_graph.add_node(Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            Position::new(3.0, 0.0),
+            Velocity::ZERO,
+        ));
+        let bond = Bond::new(cell_graph.node(ball1_handle), cell_graph.node(ball2_handle));
+        cell_graph.add_edge(bond, 1, 0);
+
+        elastic_bond_forces.apply(&mut cell_graph);
+
+        assert_eq!(cell_graph.edges()[0].elastic_energy(), 1.0);
+        assert_eq!(
+            cell_graph.node(ball1_handle).forces().net_force(),
+            Force::new(2.0, 0.0)
+        );
+        assert_eq!(
+            cell_graph.node(ball2_handle).forces().net_force(),
+            Force::new(-2.0, 0.0)
+        );
+
+        cell_graph.node_mut(ball1_handle).forces_mut().clear();
+        cell_graph
+            .node_mut(ball2_handle)
+            .set_initial_position(Position::new(2.5, 0.0));
+        elastic_bond_forces.apply(&mut cell_graph);
+
+        assert_eq!(cell_graph.edges()[0].elastic_energy(), 0.25);
+        assert_eq!(
+            cell_graph.node(ball1_handle).forces().net_force(),
+            Force::new(0.75, 0.0)
+        );
+    }
+
     #[test]
     fn bond_angle_forces_add_forces() {
         let mut cell_graph = SortableGraph::new();
@@ -777,6 +1791,30 @@ mod tests {
         assert_eq!(drag.calc_force(&ball), Force::new(-4.0, 9.0));
     }
 
+    #[test]
+    fn linear_drag_is_proportional_to_velocity() {
+        let drag = DragForce::new(0.5).with_speed_law(DragSpeedLaw::Linear);
+        let ball = Cell::ball(
+            Length::new(2.0),
+            Mass::new(10.0),
+            Position::new(0.0, 0.0),
+            Velocity::new(2.0, -3.0),
+        );
+        assert_eq!(drag.calc_force(&ball), Force::new(-2.0, 3.0));
+    }
+
+    #[test]
+    fn area_drag_is_proportional_to_area() {
+        let drag = DragForce::new(0.5).with_size_measure(DragSizeMeasure::Area);
+        let ball = Cell::ball(
+            Length::new(2.0),
+            Mass::new(100.0),
+            Position::new(0.0, 0.0),
+            Velocity::new(2.0, 0.0),
+        );
+        assert_eq!(drag.calc_force(&ball).x(), -0.5 * ball.area().value() * 4.0);
+    }
+
     #[test]
     fn drag_force_is_limited_to_force_that_will_stop_cell() {
         let drag = DragForce::new(0.5);
@@ -789,6 +1827,79 @@ mod tests {
         assert_eq!(drag.calc_force(&ball), Force::new(-0.1, 0.1));
     }
 
+    #[test]
+    fn drag_dissipates_work_against_cell_motion() {
+        let drag = DragForce::new(0.5);
+        let ball = Cell::ball(
+            Length::new(2.0),
+            Mass::new(10.0),
+            Position::new(0.0, 0.0),
+            Velocity::new(2.0, -3.0),
+        );
+        assert_eq!(drag.energy_flux(&ball), EnergyFlux::dissipated(35.0));
+    }
+
+    #[test]
+    fn thermal_noise_is_repeatable_per_tick_and_cell() {
+        let mut noise = ThermalNoise::new(0.1, 7);
+        let mut cell1 = Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            Position::ORIGIN,
+            Velocity::ZERO,
+        );
+        cell1.set_id(CellId::new(1));
+        let mut cell2 = Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            Position::ORIGIN,
+            Velocity::ZERO,
+        );
+        cell2.set_id(CellId::new(2));
+
+        noise.before_tick(5);
+        let force = noise.force(&cell1);
+
+        assert_ne!(force, Force::new(0.0, 0.0));
+        assert_eq!(noise.force(&cell1), force);
+        assert_ne!(noise.force(&cell2), force);
+        noise.before_tick(6);
+        assert_ne!(noise.force(&cell1), force);
+    }
+
+    #[test]
+    fn thermal_noise_of_zero_magnitude_adds_no_force() {
+        let mut cell_graph = SortableGraph::new();
+        let ball_handle = cell_graph.add_node(Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            Position::ORIGIN,
+            Velocity::ZERO,
+        ));
+
+        ThermalNoise::new(0.0, 7).apply(&mut cell_graph);
+
+        assert_eq!(
+            cell_graph.node(ball_handle).forces().net_force(),
+            Force::new(0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn energy_tax_takes_fraction_of_cell_energy() {
+        let mut cell_graph = SortableGraph::new();
+        let cell_handle = cell_graph.add_node(
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_initial_energy(BioEnergy::new(8.0)),
+        );
+        let tax = EnergyTax::new(0.25);
+
+        tax.apply(&mut cell_graph);
+
+        assert_eq!(cell_graph.node(cell_handle).energy(), BioEnergy::new(6.0));
+        assert_eq!(tax.energy_flux(&cell_graph), EnergyFlux::dissipated(2.0));
+    }
+
     #[test]
     fn sunlight_adds_light() {
         let sunlight = Sunlight::new(-10.0, 10.0, 10.0, 20.0);
@@ -804,6 +1915,158 @@ mod tests {
         assert_eq!(cell.environment().light_intensity(), 15.0);
     }
 
+    #[test]
+    fn sunlight_injects_light_falling_on_cells() {
+        let sunlight = Sunlight::new(-10.0, 10.0, 10.0, 20.0);
+        let mut cell_graph = SortableGraph::new();
+        cell_graph.add_node(simple_layered_cell(vec![simple_cell_layer(
+            Area::new(2.0),
+            Density::new(1.0),
+        )]));
+
+        assert_eq!(
+            sunlight.energy_flux(&cell_graph),
+            EnergyFlux::injected(30.0)
+        );
+    }
+
+    #[test]
+    fn tuning_sunlight_intensity_scales_gradient() {
+        let mut sunlight = Sunlight::new(-10.0, 10.0, 10.0, 20.0);
+
+        sunlight.set_tunable_parameter(TunableParameter::LightIntensity, 40.0);
+
+        assert_eq!(
+            sunlight.tunable_parameter(TunableParameter::LightIntensity),
+            Some(40.0)
+        );
+        assert_eq!(sunlight.calc_light_intensity(-10.0), 20.0);
+        assert_eq!(sunlight.calc_light_intensity(10.0), 40.0);
+    }
+
+    #[test]
+    fn tuning_sunlight_to_zero_and_back_restores_gradient() {
+        let mut sunlight = Sunlight::new(-10.0, 10.0, 10.0, 20.0);
+
+        sunlight.set_tunable_parameter(TunableParameter::LightIntensity, 0.0);
+        assert_eq!(sunlight.calc_light_intensity(-10.0), 0.0);
+        sunlight.set_tunable_parameter(TunableParameter::LightIntensity, 20.0);
+
+        assert_eq!(sunlight.calc_light_intensity(-10.0), 10.0);
+        assert_eq!(sunlight.calc_light_intensity(10.0), 20.0);
+    }
+
+    #[test]
+    fn sunlight_ignores_other_tunable_parameters() {
+        let mut sunlight = Sunlight::new(-10.0, 10.0, 10.0, 20.0);
+
+        sunlight.set_tunable_parameter(TunableParameter::Gravity, 40.0);
+
+        assert_eq!(sunlight.tunable_parameter(TunableParameter::Gravity), None);
+        assert_eq!(sunlight.calc_light_intensity(10.0), 20.0);
+    }
+
+    #[test]
+    fn directional_sunlight_shades_cell_behind_bonded_neighbor() {
+        let sunlight =
+            Sunlight::new(-10.0, 10.0, 10.0, 10.0).with_direction(Angle::from_degrees(270.0), 0.5);
+        let (mut cell_graph, lower_handle, upper_handle) =
+            bonded_balls(Position::new(0.0, 0.0), Position::new(0.0, 1.5));
+
+        sunlight.apply(&mut cell_graph);
+
+        assert_abs_diff_eq!(
+            cell_graph
+                .node(lower_handle)
+                .environment()
+                .light_intensity(),
+            5.0,
+            epsilon = 1e-9
+        );
+        assert_eq!(
+            cell_graph
+                .node(upper_handle)
+                .environment()
+                .light_intensity(),
+            10.0
+        );
+    }
+
+    #[test]
+    fn directional_sunlight_does_not_shade_side_by_side_cells() {
+        let sunlight =
+            Sunlight::new(-10.0, 10.0, 10.0, 10.0).with_direction(Angle::from_degrees(270.0), 0.5);
+        let (mut cell_graph, left_handle, right_handle) =
+            bonded_balls(Position::new(0.0, 0.0), Position::new(1.5, 0.0));
+
+        sunlight.apply(&mut cell_graph);
+
+        for handle in &[left_handle, right_handle] {
+            assert_abs_diff_eq!(
+                cell_graph.node(*handle).environment().light_intensity(),
+                10.0,
+                epsilon = 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn sunlight_without_direction_does_not_shade() {
+        let sunlight = Sunlight::new(-10.0, 10.0, 10.0, 10.0);
+        let (mut cell_graph, lower_handle, _) =
+            bonded_balls(Position::new(0.0, 0.0), Position::new(0.0, 1.5));
+
+        sunlight.apply(&mut cell_graph);
+
+        assert_eq!(
+            cell_graph
+                .node(lower_handle)
+                .environment()
+                .light_intensity(),
+            10.0
+        );
+    }
+
+    #[test]
+    fn shaded_light_is_not_injected() {
+        let sunlight =
+            Sunlight::new(-10.0, 10.0, 10.0, 10.0).with_direction(Angle::from_degrees(270.0), 1.0);
+        let (cell_graph, _, upper_handle) =
+            bonded_balls(Position::new(0.0, 0.0), Position::new(0.0, 1.5));
+
+        assert_abs_diff_eq!(
+            sunlight.energy_flux(&cell_graph).injected,
+            10.0 * cell_graph.node(upper_handle).area().value(),
+            epsilon = 1e-9
+        );
+    }
+
+    fn bonded_balls(
+        position1: Position,
+        position2: Position,
+    ) -> (
+        SortableGraph<Cell, Bond, AngleGusset>,
+        NodeHandle,
+        NodeHandle,
+    ) {
+        let mut cell_graph = SortableGraph::new();
+        let handle1 = cell_graph.add_node(Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            position1,
+            Velocity::ZERO,
+        ));
+        let handle2 = cell_graph.add_node(Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            position2,
+            Velocity::ZERO,
+        ));
+        let bond = Bond::new(cell_graph.node(handle1), cell_graph.node(handle2));
+        cell_graph.add_edge(bond, 1, 0);
+        (cell_graph, handle1, handle2)
+    }
+
     #[test]
     fn sunlight_never_negative() {
         let sunlight = Sunlight::new(-10.0, 0.0, 0.0, 10.0);
@@ -819,6 +2082,151 @@ mod tests {
         assert_eq!(cell.environment().light_intensity(), 0.0);
     }
 
+    #[test]
+    fn ramp_schedule_interpolates_between_ticks() {
+        let schedule = Schedule::Ramp {
+            start_tick: 10,
+            end_tick: 20,
+            start_factor: 1.0,
+            end_factor: 0.0,
+        };
+        assert_eq!(schedule.factor(0), 1.0);
+        assert_eq!(schedule.factor(15), 0.5);
+        assert_eq!(schedule.factor(30), 0.0);
+    }
+
+    #[test]
+    fn sine_schedule_oscillates_around_mean() {
+        let schedule = Schedule::Sine {
+            period_ticks: 100.0,
+            mean_factor: 1.0,
+            amplitude: 0.5,
+        };
+        assert_eq!(schedule.factor(0), 1.0);
+        assert!((schedule.factor(25) - 1.5).abs() < 1e-9);
+        assert!((schedule.factor(75) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn step_schedule_switches_at_tick() {
+        let schedule = Schedule::Step {
+            tick: 5,
+            before_factor: 1.0,
+            after_factor: 2.0,
+        };
+        assert_eq!(schedule.factor(4), 1.0);
+        assert_eq!(schedule.factor(5), 2.0);
+    }
+
+    #[test]
+    fn pulses_schedule_repeats_after_first_tick() {
+        let schedule = Schedule::pulses(10, 100, 20, 3.0, 0.5);
+        assert_eq!(schedule.factor(0), 0.5);
+        assert_eq!(schedule.factor(10), 3.0);
+        assert_eq!(schedule.factor(29), 3.0);
+        assert_eq!(schedule.factor(30), 0.5);
+        assert_eq!(schedule.factor(110), 3.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pulses_schedule_needs_a_period() {
+        Schedule::pulses(0, 0, 1, 1.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn scheduled_influence_rejects_pulses_without_a_period() {
+        ScheduledInfluence::new(
+            Box::new(Sunlight::new(-10.0, 0.0, 0.0, 10.0)),
+            TunableParameter::LightIntensity,
+            Schedule::Pulses {
+                first_tick: 0,
+                period_ticks: 0,
+                duration_ticks: 1,
+                pulse_factor: 1.0,
+                rest_factor: 0.0,
+            },
+        );
+    }
+
+    #[test]
+    fn light_patch_lights_only_cells_inside_it() {
+        let patch = LightPatch::new(Position::new(0.0, 0.0), Position::new(10.0, 10.0), 2.0);
+        let mut cell_graph = SortableGraph::new();
+        let inside_handle = cell_graph.add_node(
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_initial_position(Position::new(5.0, 5.0)),
+        );
+        let outside_handle = cell_graph.add_node(
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_initial_position(Position::new(-5.0, 5.0)),
+        );
+
+        patch.apply(&mut cell_graph);
+
+        assert_eq!(
+            cell_graph
+                .node(inside_handle)
+                .environment()
+                .light_intensity(),
+            2.0
+        );
+        assert_eq!(
+            cell_graph
+                .node(outside_handle)
+                .environment()
+                .light_intensity(),
+            0.0
+        );
+        assert_abs_diff_eq!(patch.energy_flux(&cell_graph).injected, 2.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn scheduled_light_patch_pulses() {
+        let mut influence = ScheduledInfluence::new(
+            Box::new(LightPatch::new(
+                Position::new(-10.0, -10.0),
+                Position::new(10.0, 10.0),
+                2.0,
+            )),
+            TunableParameter::LightIntensity,
+            Schedule::pulses(0, 10, 2, 1.0, 0.0),
+        );
+
+        influence.before_tick(1);
+        assert_eq!(influence.light_intensity_at(Position::ORIGIN), 2.0);
+        influence.before_tick(2);
+        assert_eq!(influence.light_intensity_at(Position::ORIGIN), 0.0);
+    }
+
+    #[test]
+    fn scheduled_influence_scales_inner_parameter() {
+        let mut influence = ScheduledInfluence::new(
+            Box::new(Sunlight::new(-10.0, 0.0, 0.0, 10.0)),
+            TunableParameter::LightIntensity,
+            Schedule::Step {
+                tick: 1,
+                before_factor: 1.0,
+                after_factor: 0.5,
+            },
+        );
+        let mut cell_graph = SortableGraph::new();
+        cell_graph.add_node(simple_layered_cell(vec![simple_cell_layer(
+            Area::new(PI),
+            Density::new(1.0),
+        )]));
+
+        influence.before_tick(1);
+        influence.apply(&mut cell_graph);
+
+        assert_eq!(cell_graph.nodes()[0].environment().light_intensity(), 5.0);
+        assert_eq!(
+            influence.tunable_parameter(TunableParameter::LightIntensity),
+            Some(10.0)
+        );
+    }
+
     fn simple_layered_cell(layers: Vec<CellLayer>) -> Cell {
         Cell::new(Position::ORIGIN, Velocity::ZERO, layers)
     }
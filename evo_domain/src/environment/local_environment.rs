@@ -1,4 +1,5 @@
 use crate::physics::overlap::*;
+use crate::physics::quantities::*;
 
 pub trait HasLocalEnvironment {
     fn environment(&self) -> &LocalEnvironment;
@@ -10,6 +11,8 @@ pub trait HasLocalEnvironment {
 pub struct LocalEnvironment {
     overlaps: Vec<Overlap>, // TODO smallvec?
     light_intensity: f64,   // TODO non-zero type?
+    // The cell's heading, for layers that act in its body frame
+    heading: Angle,
 }
 
 impl LocalEnvironment {
@@ -18,6 +21,7 @@ impl LocalEnvironment {
         LocalEnvironment {
             overlaps: vec![],
             light_intensity: 0.0,
+            heading: Angle::ZERO,
         }
     }
 
@@ -37,6 +41,14 @@ impl LocalEnvironment {
         self.light_intensity
     }
 
+    pub fn heading(&self) -> Angle {
+        self.heading
+    }
+
+    pub fn set_heading(&mut self, heading: Angle) {
+        self.heading = heading;
+    }
+
     pub fn clear(&mut self) {
         self.overlaps.clear();
         self.light_intensity = 0.0;
@@ -46,7 +58,6 @@ impl LocalEnvironment {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::physics::quantities::*;
 
     #[test]
     fn add_overlap() {
@@ -0,0 +1,204 @@
+use crate::biology::cell::Cell;
+use crate::physics::newtonian::NewtonianBody;
+use crate::physics::quantities::*;
+use std::collections::VecDeque;
+
+// A virtual instrument at a fixed place in the world that takes a reading at the end of
+// every tick. Keeps at most `capacity` readings, dropping the oldest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Probe {
+    name: String,
+    region: ProbeRegion,
+    capacity: usize,
+    readings: VecDeque<ProbeReading>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProbeRegion {
+    // Counts the cells that overlap the point.
+    Point(Position),
+    // Counts the cells whose centers are inside the rectangle.
+    Rect {
+        min_corner: Position,
+        max_corner: Position,
+    },
+}
+
+// `tick` is the number of ticks the world had run when the reading was taken.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbeReading {
+    pub tick: u64,
+    pub light_intensity: f64,
+    pub cell_count: usize,
+    pub average_velocity: Velocity,
+}
+
+impl Probe {
+    pub const DEFAULT_CAPACITY: usize = 1000;
+
+    pub fn new(name: &str, region: ProbeRegion) -> Self {
+        Probe {
+            name: name.to_string(),
+            region,
+            capacity: Self::DEFAULT_CAPACITY,
+            readings: VecDeque::new(),
+        }
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0);
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn region(&self) -> ProbeRegion {
+        self.region
+    }
+
+    // Oldest first.
+    pub fn readings(&self) -> &VecDeque<ProbeReading> {
+        &self.readings
+    }
+
+    pub fn latest_reading(&self) -> Option<&ProbeReading> {
+        self.readings.back()
+    }
+
+    // `light_intensity` is the light at the region's center.
+    pub fn take_reading(&mut self, tick: u64, cells: &[Cell], light_intensity: f64) {
+        let mut cell_count = 0;
+        let mut velocity_sum = (0.0, 0.0);
+        for cell in cells.iter().filter(|cell| self.region.contains(cell)) {
+            cell_count += 1;
+            velocity_sum.0 += cell.velocity().x();
+            velocity_sum.1 += cell.velocity().y();
+        }
+        let average_velocity = if cell_count == 0 {
+            Velocity::ZERO
+        } else {
+            Velocity::new(
+                velocity_sum.0 / cell_count as f64,
+                velocity_sum.1 / cell_count as f64,
+            )
+        };
+        if self.readings.len() == self.capacity {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(ProbeReading {
+            tick,
+            light_intensity,
+            cell_count,
+            average_velocity,
+        });
+    }
+}
+
+impl ProbeRegion {
+    pub fn center(&self) -> Position {
+        match *self {
+            ProbeRegion::Point(position) => position,
+            ProbeRegion::Rect {
+                min_corner,
+                max_corner,
+            } => Position::new(
+                (min_corner.x() + max_corner.x()) / 2.0,
+                (min_corner.y() + max_corner.y()) / 2.0,
+            ),
+        }
+    }
+
+    fn contains(&self, cell: &Cell) -> bool {
+        match *self {
+            ProbeRegion::Point(position) => cell.overlaps(position),
+            ProbeRegion::Rect {
+                min_corner,
+                max_corner,
+            } => {
+                let position = cell.position();
+                min_corner.x() <= position.x()
+                    && position.x() <= max_corner.x()
+                    && min_corner.y() <= position.y()
+                    && position.y() <= max_corner.y()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_probe_counts_cells_inside_and_averages_their_velocities() {
+        let mut probe = Probe::new(
+            "box",
+            ProbeRegion::Rect {
+                min_corner: Position::new(0.0, 0.0),
+                max_corner: Position::new(10.0, 10.0),
+            },
+        );
+        let cells = vec![
+            ball(Position::new(1.0, 1.0), Velocity::new(1.0, 0.0)),
+            ball(Position::new(5.0, 5.0), Velocity::new(3.0, 2.0)),
+            ball(Position::new(20.0, 5.0), Velocity::new(100.0, 100.0)),
+        ];
+
+        probe.take_reading(3, &cells, 0.5);
+
+        assert_eq!(
+            probe.latest_reading(),
+            Some(&ProbeReading {
+                tick: 3,
+                light_intensity: 0.5,
+                cell_count: 2,
+                average_velocity: Velocity::new(2.0, 1.0),
+            })
+        );
+    }
+
+    #[test]
+    fn point_probe_counts_overlapping_cells() {
+        let mut probe = Probe::new("point", ProbeRegion::Point(Position::new(0.5, 0.0)));
+        let cells = vec![
+            ball(Position::ORIGIN, Velocity::ZERO),
+            ball(Position::new(5.0, 0.0), Velocity::ZERO),
+        ];
+
+        probe.take_reading(0, &cells, 0.0);
+
+        assert_eq!(probe.latest_reading().unwrap().cell_count, 1);
+    }
+
+    #[test]
+    fn readings_beyond_capacity_are_dropped() {
+        let mut probe = Probe::new("point", ProbeRegion::Point(Position::ORIGIN)).with_capacity(2);
+
+        for tick in 1..=3 {
+            probe.take_reading(tick, &[], 0.0);
+        }
+
+        assert_eq!(probe.readings().len(), 2);
+        assert_eq!(probe.readings()[0].tick, 2);
+        assert_eq!(probe.latest_reading().unwrap().tick, 3);
+    }
+
+    #[test]
+    fn empty_region_has_zero_average_velocity() {
+        let mut probe = Probe::new("point", ProbeRegion::Point(Position::ORIGIN));
+
+        probe.take_reading(0, &[], 0.0);
+
+        assert_eq!(
+            probe.latest_reading().unwrap().average_velocity,
+            Velocity::ZERO
+        );
+    }
+
+    fn ball(position: Position, velocity: Velocity) -> Cell {
+        Cell::ball(Length::new(1.0), Mass::new(1.0), position, velocity)
+    }
+}
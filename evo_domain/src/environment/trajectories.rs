@@ -0,0 +1,118 @@
+use crate::biology::cell::{Cell, CellId};
+use crate::physics::newtonian::NewtonianBody;
+use crate::physics::quantities::*;
+use std::collections::{BTreeMap, VecDeque};
+
+// Records the recent positions of the cells with a tag, at the end of every tick, for
+// analyzing how they move. Keeps at most `capacity` positions per cell, dropping the oldest,
+// and forgets cells that have died or lost the tag.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrajectoryRecorder {
+    tag: String,
+    capacity: usize,
+    trajectories: BTreeMap<CellId, VecDeque<TrajectoryPoint>>,
+}
+
+// `tick` is the number of ticks the world had run when the position was recorded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrajectoryPoint {
+    pub tick: u64,
+    pub position: Position,
+}
+
+impl TrajectoryRecorder {
+    pub fn new(tag: &str, capacity: usize) -> Self {
+        assert!(capacity > 0);
+        TrajectoryRecorder {
+            tag: tag.to_string(),
+            capacity,
+            trajectories: BTreeMap::new(),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // In cell ID order, oldest position first.
+    pub fn trajectories(&self) -> impl Iterator<Item = (CellId, &VecDeque<TrajectoryPoint>)> {
+        self.trajectories
+            .iter()
+            .map(|(&cell_id, trajectory)| (cell_id, trajectory))
+    }
+
+    pub fn trajectory(&self, cell_id: CellId) -> Option<&VecDeque<TrajectoryPoint>> {
+        self.trajectories.get(&cell_id)
+    }
+
+    pub fn record(&mut self, tick: u64, cells: &[Cell]) {
+        let mut trajectories = BTreeMap::new();
+        let tag = &self.tag;
+        for cell in cells.iter().filter(|cell| cell.has_tag(tag)) {
+            let mut trajectory = self.trajectories.remove(&cell.id()).unwrap_or_default();
+            if trajectory.len() == self.capacity {
+                trajectory.pop_front();
+            }
+            trajectory.push_back(TrajectoryPoint {
+                tick,
+                position: cell.position(),
+            });
+            trajectories.insert(cell.id(), trajectory);
+        }
+        self.trajectories = trajectories;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_tagged_cells() {
+        let mut recorder = TrajectoryRecorder::new("tracked", 10);
+        let cells = vec![
+            ball(1, Position::ORIGIN).with_tag("tracked"),
+            ball(2, Position::ORIGIN),
+        ];
+
+        recorder.record(1, &cells);
+
+        assert_eq!(recorder.trajectories().count(), 1);
+        assert_eq!(recorder.trajectory(CellId::new(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn drops_oldest_positions_beyond_capacity() {
+        let mut recorder = TrajectoryRecorder::new("tracked", 2);
+
+        for tick in 1..=3 {
+            let cells = vec![ball(1, Position::new(tick as f64, 0.0)).with_tag("tracked")];
+            recorder.record(tick, &cells);
+        }
+
+        let trajectory = recorder.trajectory(CellId::new(1)).unwrap();
+        assert_eq!(trajectory.len(), 2);
+        assert_eq!(trajectory[0].tick, 2);
+        assert_eq!(trajectory[1].position, Position::new(3.0, 0.0));
+    }
+
+    #[test]
+    fn forgets_cells_that_are_gone() {
+        let mut recorder = TrajectoryRecorder::new("tracked", 10);
+        recorder.record(1, &[ball(1, Position::ORIGIN).with_tag("tracked")]);
+
+        recorder.record(2, &[]);
+
+        assert!(recorder.trajectory(CellId::new(1)).is_none());
+    }
+
+    fn ball(id: u64, position: Position) -> Cell {
+        let mut cell = Cell::ball(Length::new(1.0), Mass::new(1.0), position, Velocity::ZERO);
+        cell.set_id(CellId::new(id));
+        cell
+    }
+}
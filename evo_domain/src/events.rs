@@ -0,0 +1,416 @@
+use crate::biology::cell::CellId;
+use crate::biology::selection::CullSelection;
+use crate::environment::influences::TunableParameter;
+use crate::physics::quantities::{Force, Position, Velocity};
+use crate::world::LineageStats;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::time::Duration;
+
+// A record of interventions and other notable things that happened to a world, in tick order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventLog {
+    events: Vec<WorldEvent>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        EventLog { events: vec![] }
+    }
+
+    pub fn record(&mut self, tick: u64, kind: WorldEventKind) {
+        self.events.push(WorldEvent { tick, kind });
+    }
+
+    pub fn events(&self) -> &[WorldEvent] {
+        &self.events
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldEvent {
+    pub tick: u64,
+    pub kind: WorldEventKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorldEventKind {
+    Cull {
+        selection: CullSelection,
+        num_cells_before: usize,
+        num_survivors: usize,
+    },
+    InfluenceEnabled {
+        id: String,
+        enabled: bool,
+    },
+    Invasion {
+        tag: String,
+        num_cells: usize,
+    },
+    ParameterChanged {
+        parameter: TunableParameter,
+        old_value: f64,
+        new_value: f64,
+    },
+    Extinction {
+        lineage: u32,
+    },
+    PopulationCrash {
+        peak_cells: usize,
+        num_cells: usize,
+    },
+    Radiation {
+        fewest_lineages: usize,
+        num_lineages: usize,
+    },
+    LimitExceeded(LimitExceeded),
+    ChurnCapExceeded {
+        bond_changes: usize,
+    },
+    Blowup {
+        num_cells: usize,
+    },
+}
+
+// Hard limits that keep runaway reproduction from exhausting memory or stalling a long
+// experiment. None means no limit. The tick duration is wall-clock time, so culls for it are
+// not repeatable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResourceLimits {
+    pub max_cells: Option<usize>,
+    pub max_bonds: Option<usize>,
+    pub max_tick_duration: Option<Duration>,
+    pub action: LimitAction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LimitAction {
+    // Only report the limit, e.g. for the caller to save a checkpoint and pause the run
+    Pause,
+    // Cull the lowest-energy cells, down to a population that should fit within the limit
+    Cull,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LimitExceeded {
+    Cells(usize),
+    Bonds(usize),
+    TickDuration(Duration),
+}
+
+impl ResourceLimits {
+    pub const NONE: ResourceLimits = ResourceLimits {
+        max_cells: None,
+        max_bonds: None,
+        max_tick_duration: None,
+        action: LimitAction::Pause,
+    };
+
+    // Returns the first limit exceeded, if any.
+    pub fn check(
+        &self,
+        num_cells: usize,
+        num_bonds: usize,
+        tick_duration: Duration,
+    ) -> Option<LimitExceeded> {
+        if self.max_cells.is_some_and(|max| num_cells > max) {
+            Some(LimitExceeded::Cells(num_cells))
+        } else if self.max_bonds.is_some_and(|max| num_bonds > max) {
+            Some(LimitExceeded::Bonds(num_bonds))
+        } else if self
+            .max_tick_duration
+            .is_some_and(|max| tick_duration > max)
+        {
+            Some(LimitExceeded::TickDuration(tick_duration))
+        } else {
+            None
+        }
+    }
+
+    // How many cells a cull should leave to get back within the limit, assuming that bonds and
+    // tick duration are in proportion to the number of cells.
+    pub fn num_survivors(&self, exceeded: LimitExceeded, num_cells: usize) -> usize {
+        let fraction = match exceeded {
+            LimitExceeded::Cells(_) => return self.max_cells.unwrap(),
+            LimitExceeded::Bonds(num_bonds) => self.max_bonds.unwrap() as f64 / num_bonds as f64,
+            LimitExceeded::TickDuration(duration) => {
+                self.max_tick_duration.unwrap().as_secs_f64() / duration.as_secs_f64()
+            }
+        };
+        (fraction * num_cells as f64) as usize
+    }
+}
+
+// A cap on the bonds the cells' controls may form and break in one tick, counting each bud's
+// bond to its parent, so that controls that evolve to churn bonds cannot stall the world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChurnCap {
+    pub max_bond_changes: usize,
+    pub overflow: ChurnOverflow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChurnOverflow {
+    // Make every change, only reporting the overflow, e.g. to find which controls churn
+    Report,
+    // Make changes up to the cap, buds first in cell order, then bond breaks in bond order,
+    // then adhesions in cell order, and drop the rest: their bonds stay as they were, and
+    // dropped buds' energy goes back to their parents
+    Drop,
+}
+
+// Bounds beyond which a cell's motion is taken for a numerical blowup, e.g. from too stiff a
+// spring, rather than physics. Non-finite positions and velocities are always out of bounds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlowupBounds {
+    pub min_corner: Position,
+    pub max_corner: Position,
+    pub max_speed: f64,
+}
+
+impl BlowupBounds {
+    // A margin as big as the world all around it, and a speed that crosses the world in a tick.
+    pub fn for_world(min_corner: Position, max_corner: Position) -> Self {
+        let size = max_corner - min_corner;
+        BlowupBounds {
+            min_corner: Position::new(min_corner.x() - size.x(), min_corner.y() - size.y()),
+            max_corner: Position::new(max_corner.x() + size.x(), max_corner.y() + size.y()),
+            max_speed: size.length().value(),
+        }
+    }
+
+    pub fn contain(&self, position: Position, velocity: Velocity) -> bool {
+        self.min_corner.x() <= position.x()
+            && position.x() <= self.max_corner.x()
+            && self.min_corner.y() <= position.y()
+            && position.y() <= self.max_corner.y()
+            && velocity.x().hypot(velocity.y()) <= self.max_speed
+    }
+}
+
+// The cells that left their blowup bounds during a tick, with the forces that moved them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blowup {
+    pub tick: u64,
+    pub cells: Vec<BlownUpCell>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlownUpCell {
+    pub id: CellId,
+    pub position: Position,
+    pub velocity: Velocity,
+    pub net_force: Force,
+}
+
+impl fmt::Display for Blowup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Numerical blowup at tick {} in {} cells:",
+            self.tick,
+            self.cells.len()
+        )?;
+        for cell in &self.cells {
+            writeln!(
+                f,
+                "  cell {} position: {}, velocity: {}, force: {}",
+                cell.id.value(),
+                cell.position,
+                cell.velocity,
+                cell.net_force
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PopulationMonitorParameters {
+    // A crash is the population falling by at least this fraction from its peak within the
+    // window
+    pub crash_fraction: f64,
+    // A radiation is the number of living lineages growing by at least this many within the
+    // window
+    pub radiation_lineages: usize,
+    pub window_ticks: u64,
+}
+
+impl PopulationMonitorParameters {
+    pub const DEFAULT: PopulationMonitorParameters = PopulationMonitorParameters {
+        crash_fraction: 0.5,
+        radiation_lineages: 5,
+        window_ticks: 100,
+    };
+}
+
+// Watches the per-lineage stats, tick by tick, for extinctions, population crashes and rapid
+// diversification, so that long unattended runs document themselves in their event logs.
+// After reporting a crash or radiation, it starts a new window, so that one event is not
+// reported on every tick of the window.
+#[derive(Clone, Debug)]
+pub struct PopulationMonitor {
+    parameters: PopulationMonitorParameters,
+    // Ticks, populations and numbers of living lineages within the window
+    history: VecDeque<(u64, usize, usize)>,
+    living_lineages: HashSet<u32>,
+}
+
+impl PopulationMonitor {
+    pub fn new(parameters: PopulationMonitorParameters) -> Self {
+        assert!(parameters.crash_fraction > 0.0 && parameters.crash_fraction <= 1.0);
+        assert!(parameters.radiation_lineages > 0);
+        PopulationMonitor {
+            parameters,
+            history: VecDeque::new(),
+            living_lineages: HashSet::new(),
+        }
+    }
+
+    pub fn observe(&mut self, tick: u64, lineage_stats: &[LineageStats]) -> Vec<WorldEventKind> {
+        let mut events = vec![];
+        let living_lineages: HashSet<u32> = lineage_stats
+            .iter()
+            .filter(|stats| stats.cells > 0)
+            .map(|stats| stats.lineage)
+            .collect();
+        let mut extinct_lineages: Vec<u32> = self
+            .living_lineages
+            .difference(&living_lineages)
+            .cloned()
+            .collect();
+        extinct_lineages.sort_unstable();
+        for lineage in extinct_lineages {
+            events.push(WorldEventKind::Extinction { lineage });
+        }
+
+        let num_cells: usize = lineage_stats.iter().map(|stats| stats.cells).sum();
+        let num_lineages = living_lineages.len();
+        self.living_lineages = living_lineages;
+        while let Some(&(oldest_tick, _, _)) = self.history.front() {
+            if oldest_tick + self.parameters.window_ticks > tick {
+                break;
+            }
+            self.history.pop_front();
+        }
+        self.history.push_back((tick, num_cells, num_lineages));
+
+        let peak_cells = self.history.iter().map(|h| h.1).max().unwrap();
+        let fewest_lineages = self.history.iter().map(|h| h.2).min().unwrap();
+        let mut new_window = false;
+        if (num_cells as f64) <= (1.0 - self.parameters.crash_fraction) * peak_cells as f64
+            && num_cells < peak_cells
+        {
+            events.push(WorldEventKind::PopulationCrash {
+                peak_cells,
+                num_cells,
+            });
+            new_window = true;
+        }
+        if num_lineages >= fewest_lineages + self.parameters.radiation_lineages {
+            events.push(WorldEventKind::Radiation {
+                fewest_lineages,
+                num_lineages,
+            });
+            new_window = true;
+        }
+        if new_window {
+            self.history.clear();
+            self.history.push_back((tick, num_cells, num_lineages));
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::quantities::*;
+
+    #[test]
+    fn resource_limits_report_first_limit_exceeded() {
+        let limits = ResourceLimits {
+            max_cells: Some(100),
+            max_bonds: Some(50),
+            ..ResourceLimits::NONE
+        };
+
+        assert_eq!(limits.check(100, 50, std::time::Duration::ZERO), None);
+        assert_eq!(
+            limits.check(101, 60, std::time::Duration::ZERO),
+            Some(LimitExceeded::Cells(101))
+        );
+        assert_eq!(
+            limits.check(80, 60, std::time::Duration::ZERO),
+            Some(LimitExceeded::Bonds(60))
+        );
+    }
+
+    #[test]
+    fn bond_limit_culls_cells_in_proportion() {
+        let limits = ResourceLimits {
+            max_bonds: Some(50),
+            action: LimitAction::Cull,
+            ..ResourceLimits::NONE
+        };
+        assert_eq!(limits.num_survivors(LimitExceeded::Bonds(100), 80), 40);
+    }
+
+    #[test]
+    fn blowup_bounds_reject_far_fast_and_non_finite_cells() {
+        let bounds = BlowupBounds::for_world(Position::new(0.0, 0.0), Position::new(30.0, 40.0));
+
+        assert!(bounds.contain(Position::new(-20.0, 70.0), Velocity::new(30.0, 40.0)));
+        assert!(!bounds.contain(Position::new(-40.0, 0.0), Velocity::ZERO));
+        assert!(!bounds.contain(Position::ORIGIN, Velocity::new(30.0, 41.0)));
+        assert!(!bounds.contain(Position::new(f64::NAN, 0.0), Velocity::ZERO));
+    }
+
+    #[test]
+    fn monitor_reports_extinction_and_crash_once() {
+        let mut monitor = PopulationMonitor::new(PopulationMonitorParameters::DEFAULT);
+
+        assert!(monitor.observe(0, &[stats(1, 4), stats(2, 6)]).is_empty());
+        assert_eq!(
+            monitor.observe(1, &[stats(1, 4), stats(2, 0)]),
+            vec![
+                WorldEventKind::Extinction { lineage: 2 },
+                WorldEventKind::PopulationCrash {
+                    peak_cells: 10,
+                    num_cells: 4
+                }
+            ]
+        );
+        assert!(monitor.observe(2, &[stats(1, 3)]).is_empty());
+    }
+
+    #[test]
+    fn monitor_reports_radiation() {
+        let mut monitor = PopulationMonitor::new(PopulationMonitorParameters {
+            radiation_lineages: 2,
+            ..PopulationMonitorParameters::DEFAULT
+        });
+
+        monitor.observe(0, &[stats(1, 3)]);
+
+        assert_eq!(
+            monitor.observe(1, &[stats(1, 3), stats(2, 1), stats(3, 1)]),
+            vec![WorldEventKind::Radiation {
+                fewest_lineages: 1,
+                num_lineages: 3
+            }]
+        );
+    }
+
+    fn stats(lineage: u32, cells: usize) -> LineageStats {
+        LineageStats {
+            lineage,
+            cells,
+            biomass: Mass::new(cells as f64),
+            mean_energy: BioEnergy::ZERO,
+            births: 0,
+            deaths: 0,
+        }
+    }
+}
@@ -1,14 +1,75 @@
 pub mod biology;
+pub mod divergence;
+pub mod document;
 pub mod environment;
+pub mod events;
+pub mod parallelism;
 pub mod physics;
+pub mod plugins;
+pub mod prelude;
+pub mod state_hash;
+pub mod tournament;
+pub mod validation;
+pub mod view_model;
 pub mod world;
+pub mod worldgen;
+
+#[cfg(test)]
+mod test_doubles;
+#[cfg(test)]
+mod test_support;
+
+use biology::cell::CellId;
+use environment::influences::TunableParameter;
+use physics::quantities::Position;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum UserAction {
+    AddCell {
+        position: Position,
+    },
+    BondSelectedCells,
     DebugPrint,
     Exit,
+    ExportSelectedCells,
+    ExportWorld,
+    InspectLayer {
+        cell_id: CellId,
+        layer_index: usize,
+    },
+    KillSelectedCells,
+    MoveCell {
+        cell_id: CellId,
+        position: Position,
+    },
     None,
     PlayToggle,
-    SelectCellToggle { x: f64, y: f64 },
+    Screenshot,
+    SelectCellToggle {
+        cell_id: CellId,
+    },
+    SelectOnlyCell {
+        cell_id: CellId,
+    },
+    SelectCellsInRect {
+        min_corner: Position,
+        max_corner: Position,
+    },
+    SetTunableParameter {
+        parameter: TunableParameter,
+        value: f64,
+    },
     SingleTick,
+    TagSelectedCells,
+    ToggleBonds,
+    ToggleEditMode,
+    ToggleGenealogyBranch {
+        cell_id: CellId,
+    },
+    ToggleGenealogyPanel,
+    ToggleGussets,
+    ToggleHud,
+    ToggleMotionTrails,
+    ToggleParameterPanel,
+    ToggleParentLines,
 }
@@ -0,0 +1,46 @@
+use std::thread;
+
+// How the world spreads its per-cell work across threads.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Parallelism {
+    Serial,
+    // Splits the cells into the same contiguous chunks every tick, one per thread, and keeps
+    // each cell's results in its own slot, to be merged in cell order. Runs are reproducible
+    // and match Serial runs exactly.
+    Deterministic { num_threads: usize },
+}
+
+impl Parallelism {
+    pub fn for_each<T, F>(self, items: &mut [T], f: F)
+    where
+        T: Send,
+        F: Fn(&mut T) + Sync,
+    {
+        match self {
+            Parallelism::Deterministic { num_threads } if num_threads > 1 && items.len() > 1 => {
+                let chunk_size = items.len().div_ceil(num_threads);
+                let f = &f;
+                thread::scope(|scope| {
+                    for chunk in items.chunks_mut(chunk_size) {
+                        scope.spawn(move || chunk.iter_mut().for_each(f));
+                    }
+                });
+            }
+            _ => items.iter_mut().for_each(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_parallelism_visits_every_item_once() {
+        let mut items: Vec<usize> = (0..10).collect();
+
+        Parallelism::Deterministic { num_threads: 3 }.for_each(&mut items, |item| *item *= 2);
+
+        assert_eq!(items, (0..10).map(|i| i * 2).collect::<Vec<usize>>());
+    }
+}
@@ -3,6 +3,8 @@ pub mod newtonian;
 pub mod overlap;
 pub mod quantities;
 pub mod shapes;
-pub mod simple_graph_elements;
+#[cfg(test)]
+pub(crate) mod simple_graph_elements;
 pub mod sortable_graph;
-pub mod util;
+pub(crate) mod spatial_queries;
+pub(crate) mod util;
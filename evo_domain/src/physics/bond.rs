@@ -11,6 +11,8 @@ pub struct Bond {
     edge_data: GraphEdgeData,
     energy_for_cell1: BioEnergy,
     energy_for_cell2: BioEnergy,
+    // Potential energy stored in the bond as a spring; see ElasticBondForces. Not saved.
+    elastic_energy: f64,
 }
 
 impl Bond {
@@ -20,6 +22,7 @@ impl Bond {
             edge_data: GraphEdgeData::new(circle1.node_handle(), circle2.node_handle()),
             energy_for_cell1: BioEnergy::new(0.0),
             energy_for_cell2: BioEnergy::new(0.0),
+            elastic_energy: 0.0,
         }
     }
 
@@ -55,6 +58,23 @@ impl Bond {
         ret_energy
     }
 
+    // Loses `fraction` of the energy waiting in the bond for each cell, and returns the energy
+    // lost.
+    pub fn decay_energy(&mut self, fraction: f64) -> BioEnergy {
+        let lost_energy = (self.energy_for_cell1 + self.energy_for_cell2) * fraction;
+        self.energy_for_cell1 = self.energy_for_cell1 * (1.0 - fraction);
+        self.energy_for_cell2 = self.energy_for_cell2 * (1.0 - fraction);
+        lost_energy
+    }
+
+    pub fn elastic_energy(&self) -> f64 {
+        self.elastic_energy
+    }
+
+    pub fn set_elastic_energy(&mut self, energy: f64) {
+        self.elastic_energy = energy;
+    }
+
     pub fn calc_strain(&self) -> Displacement {
         Displacement::new(0.0, 0.0)
     }
@@ -96,12 +116,14 @@ where
     strains
 }
 
+// Capsules are bonded where their axes come closest, so their sides can lie against each other.
 fn calc_bond_strain<C>(circle1: &C, circle2: &C) -> Displacement
 where
     C: Circle,
 {
-    let x_offset = circle1.center().x() - circle2.center().x();
-    let y_offset = circle1.center().y() - circle2.center().y();
+    let (point1, point2) = closest_axis_points(circle1, circle2);
+    let x_offset = point1.x() - point2.x();
+    let y_offset = point1.y() - point2.y();
     let just_touching_center_sep = circle1.radius().value() + circle2.radius().value();
     let center_sep = (sqr(x_offset) + sqr(y_offset)).sqrt();
     if center_sep == 0.0 {
@@ -129,6 +151,10 @@ impl AngleGusset {
             angle,
         }
     }
+
+    pub fn angle(&self) -> Angle {
+        self.angle
+    }
 }
 
 pub fn calc_bond_angle_forces<C>(
@@ -182,12 +208,8 @@ where
 fn calc_bond_angle(origin: Position, point1: Position, point2: Position) -> Angle {
     let angle1 = point1.to_polar_angle(origin);
     let angle2 = point2.to_polar_angle(origin);
-    let radians = angle2.radians() - angle1.radians();
-    Angle::from_radians(if radians >= 0.0 {
-        radians
-    } else {
-        radians + 2.0 * PI
-    })
+    // counterclockwise from point1 to point2, not the shortest turn
+    Angle::from_radians(angle2.radians() - angle1.radians())
 }
 
 fn calc_torque_from_angle_deflection(deflection: Deflection) -> Torque {
@@ -216,6 +238,7 @@ fn calc_force_from_tangential_force(
 mod tests {
     use super::*;
     use crate::physics::simple_graph_elements::*;
+    use approx::assert_abs_diff_eq;
 
     #[test]
     #[should_panic]
@@ -225,6 +248,31 @@ mod tests {
         add_bond(&mut graph, node, node);
     }
 
+    #[test]
+    fn bond_energy_decays_for_both_cells() {
+        let mut graph: SortableGraph<SimpleCircleNode, Bond, AngleGusset> = SortableGraph::new();
+        let node1 = add_simple_circle_node(&mut graph, (0.0, 0.0), 1.0);
+        let node2 = add_simple_circle_node(&mut graph, (2.0, 0.0), 1.0);
+        let mut bond = Bond::new(graph.node(node1), graph.node(node2));
+        bond.set_energy_from_cell(node1, BioEnergy::new(2.0));
+        bond.set_energy_from_cell(node2, BioEnergy::new(4.0));
+
+        let lost_energy = bond.decay_energy(0.25);
+
+        assert_eq!(lost_energy, BioEnergy::new(1.5));
+        assert_eq!(bond.energy_for_cell1(), BioEnergy::new(3.0));
+        assert_eq!(bond.energy_for_cell2(), BioEnergy::new(1.5));
+    }
+
+    #[test]
+    fn angle_deflection_across_zero_takes_the_short_way() {
+        let deflection = Angle::from_radians(0.1) - Angle::from_radians(2.0 * PI - 0.1);
+
+        let torque = calc_torque_from_angle_deflection(deflection);
+
+        assert_abs_diff_eq!(torque, Torque::new(-0.2), epsilon = 1e-9);
+    }
+
     #[test]
     fn bond_calculates_strain() {
         // {3, 4, 5} triangle (as {6, 8, 10})
@@ -237,6 +285,25 @@ mod tests {
         assert_eq!(Displacement::new(3.0, 4.0), strain);
     }
 
+    #[test]
+    fn bonded_capsules_are_strained_where_their_axes_are_closest() {
+        let capsule1 = SimpleCircle::capsule(
+            Position::new(0.0, 0.0),
+            Length::new(1.0),
+            Displacement::new(0.0, 3.0),
+        );
+        let capsule2 = SimpleCircle::capsule(
+            Position::new(4.0, 2.0),
+            Length::new(1.0),
+            Displacement::new(3.0, 0.0),
+        );
+
+        let strain = calc_bond_strain(&capsule1, &capsule2);
+
+        // the axes come within 1 of each other, at (0, 2) and (1, 2)
+        assert_eq!(Displacement::new(-1.0, 0.0), strain);
+    }
+
     #[test]
     fn bonded_pair_with_matching_centers_has_no_strain() {
         let circle1 = SimpleCircle::new(Position::new(0.0, 0.0), Length::new(1.0));
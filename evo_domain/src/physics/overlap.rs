@@ -4,25 +4,70 @@ use crate::physics::sortable_graph::*;
 use crate::physics::util::*;
 use std::cmp::Ordering;
 
-// TODO add width to Overlap, or maybe make incursion magnitude an Area (incursion * min(diameter))
-
+// How far something has pushed into a circle, and which way. The normal is the unit vector
+// the circle is pushed along, or zero if it isn't pushed at all, and the depth is how far.
+// The contact point is halfway through the overlap along the normal, for overlaps that have a
+// place; an overlap applied to every cell, for example, has none.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Overlap {
-    incursion: Displacement,
+    normal: Displacement,
+    depth: Length,
+    contact_point: Option<Position>,
     width: f64,
 }
 
 impl Overlap {
     pub fn new(incursion: Displacement, width: f64) -> Self {
-        Overlap { incursion, width }
+        let depth = incursion.length();
+        Overlap {
+            normal: if depth.value() == 0.0 {
+                Displacement::ZERO
+            } else {
+                Displacement::new(incursion.x() / depth.value(), incursion.y() / depth.value())
+            },
+            depth,
+            contact_point: None,
+            width,
+        }
     }
 
+    // The overlap of a circle pushed by `incursion`.
+    pub fn of_circle<C: Circle>(circle: &C, incursion: Displacement, width: f64) -> Self {
+        let overlap = Self::new(incursion, width);
+        let inset = circle.radius().value() - overlap.depth.value() / 2.0;
+        overlap.with_contact_point(
+            circle.center()
+                + Displacement::new(-overlap.normal.x() * inset, -overlap.normal.y() * inset),
+        )
+    }
+
+    pub fn with_contact_point(mut self, contact_point: Position) -> Self {
+        self.contact_point = Some(contact_point);
+        self
+    }
+
+    pub fn normal(&self) -> Displacement {
+        self.normal
+    }
+
+    pub fn depth(&self) -> Length {
+        self.depth
+    }
+
+    pub fn contact_point(&self) -> Option<Position> {
+        self.contact_point
+    }
+
+    // The normal scaled by the depth.
     pub fn incursion(&self) -> Displacement {
-        self.incursion
+        Displacement::new(
+            self.normal.x() * self.depth.value(),
+            self.normal.y() * self.depth.value(),
+        )
     }
 
-    pub fn magnitude(&self) -> f64 {
-        self.incursion.length().value()
+    pub fn width(&self) -> f64 {
+        self.width
     }
 }
 
@@ -40,6 +85,14 @@ impl Walls {
         }
     }
 
+    pub fn min_corner(&self) -> Position {
+        self.min_corner
+    }
+
+    pub fn max_corner(&self) -> Position {
+        self.max_corner
+    }
+
     pub fn find_overlaps<C, E, ME>(
         &self,
         graph: &mut SortableGraph<C, E, ME>,
@@ -52,17 +105,54 @@ impl Walls {
         let mut overlaps: Vec<(NodeHandle, Overlap)> = Vec::with_capacity(graph.nodes().len() / 2);
 
         for circle in graph.nodes() {
-            if let Some(incursion) = self.calc_incursion(circle) {
-                overlaps.push((
-                    circle.node_handle(),
-                    Overlap::new(incursion, circle.radius().value()),
-                ));
+            if let Some(overlap) = self.calc_overlap(circle) {
+                overlaps.push((circle.node_handle(), overlap));
             }
         }
 
         overlaps
     }
 
+    pub fn count_overlaps<C, E, ME>(&self, graph: &SortableGraph<C, E, ME>) -> usize
+    where
+        C: Circle + GraphNode,
+        E: GraphEdge,
+        ME: GraphMetaEdge,
+    {
+        graph
+            .nodes()
+            .iter()
+            .filter(|circle| self.calc_incursion(*circle).is_some())
+            .count()
+    }
+
+    // A circle in a corner gets one overlap, pushing it out of both walls at once along the sum
+    // of their incursions, rather than one per wall. Its contact point is midway into each wall
+    // it touches, which is where the corner is, not on the line between the circle's center and
+    // the corner.
+    fn calc_overlap<C>(&self, circle: &C) -> Option<Overlap>
+    where
+        C: Circle + GraphNode,
+    {
+        let incursion = self.calc_incursion(circle)?;
+        let center = circle.center();
+        let contact_point = Position::new(
+            Self::contact_coordinate(center.x(), circle.min_x(), circle.max_x(), incursion.x()),
+            Self::contact_coordinate(center.y(), circle.min_y(), circle.max_y(), incursion.y()),
+        );
+        Some(Overlap::new(incursion, circle.radius().value()).with_contact_point(contact_point))
+    }
+
+    fn contact_coordinate(center: f64, min: f64, max: f64, incursion: f64) -> f64 {
+        if incursion > 0.0 {
+            min + incursion / 2.0
+        } else if incursion < 0.0 {
+            max + incursion / 2.0
+        } else {
+            center
+        }
+    }
+
     fn calc_incursion<C>(&self, circle: &C) -> Option<Displacement>
     where
         C: Circle + GraphNode,
@@ -109,11 +199,21 @@ where
                 continue;
             }
 
-            if let Some(incursion) = calc_incursion(circle1, circle2) {
+            // capsules collide like the circles at the closest points on their axes
+            let (point1, point2) = closest_axis_points(circle1, circle2);
+            let contact_circle1 = SimpleCircle::new(point1, circle1.radius());
+            let contact_circle2 = SimpleCircle::new(point2, circle2.radius());
+            if let Some(incursion) = calc_incursion(&contact_circle1, &contact_circle2) {
                 let width = circle1.radius().value().min(circle2.radius().value());
                 overlaps.push((
-                    (*handle1, Overlap::new(incursion, width)),
-                    (*handle2, Overlap::new(-incursion, width)),
+                    (
+                        *handle1,
+                        Overlap::of_circle(&contact_circle1, incursion, width),
+                    ),
+                    (
+                        *handle2,
+                        Overlap::of_circle(&contact_circle2, -incursion, width),
+                    ),
                 ));
             }
         }
@@ -122,7 +222,7 @@ where
     overlaps
 }
 
-fn cmp_by_min_x<C: Circle>(c1: &C, c2: &C) -> Ordering {
+pub fn cmp_by_min_x<C: Circle>(c1: &C, c2: &C) -> Ordering {
     c1.min_x().partial_cmp(&c2.min_x()).unwrap()
 }
 
@@ -176,6 +276,7 @@ impl PossibleCirclePairOverlap {
 mod tests {
     use super::*;
     use crate::physics::simple_graph_elements::*;
+    use approx::assert_abs_diff_eq;
 
     #[test]
     fn no_wall_overlaps() {
@@ -210,6 +311,7 @@ mod tests {
             (
                 graph.node_handles()[0],
                 Overlap::new(Displacement::new(1.5, 1.25), 2.0)
+                    .with_contact_point(Position::new(-10.75, -5.625))
             )
         );
     }
@@ -232,10 +334,38 @@ mod tests {
             (
                 graph.node_handles()[0],
                 Overlap::new(Displacement::new(-1.5, -1.75), 2.0)
+                    .with_contact_point(Position::new(10.75, 2.875))
             )
         );
     }
 
+    #[test]
+    fn corner_wall_overlap_normal_combines_both_walls() {
+        let mut graph: SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        graph.add_node(SimpleCircleNode::new(
+            Position::new(9.5, 1.5),
+            Length::new(1.0),
+        ));
+        let subject = Walls::new(Position::new(-10.0, -5.0), Position::new(10.0, 2.0));
+
+        let overlaps = subject.find_overlaps(&mut graph);
+
+        assert_eq!(overlaps.len(), 1);
+        let overlap = overlaps[0].1;
+        assert_abs_diff_eq!(
+            overlap.normal(),
+            Displacement::new(-0.5_f64.sqrt(), -0.5_f64.sqrt()),
+            epsilon = 1e-12
+        );
+        assert_abs_diff_eq!(
+            overlap.depth(),
+            Length::new(0.5_f64.sqrt()),
+            epsilon = 1e-12
+        );
+        assert_eq!(overlap.contact_point(), Some(Position::new(10.25, 2.25)));
+    }
+
     #[test]
     fn pair_overlap() {
         // {3, 4, 5} triangle (as {6, 8, 10})
@@ -289,18 +419,149 @@ mod tests {
             overlaps[0].0,
             (
                 graph.node_handles()[0],
-                Overlap::new(Displacement::new(-1.5, 0.0), 1.5)
+                Overlap::of_circle(&graph.nodes()[0], Displacement::new(-1.5, 0.0), 1.5)
             )
         );
         assert_eq!(
             overlaps[0].1,
             (
                 graph.node_handles()[1],
-                Overlap::new(Displacement::new(1.5, 0.0), 1.5)
+                Overlap::of_circle(&graph.nodes()[1], Displacement::new(1.5, 0.0), 1.5)
             )
         );
     }
 
+    #[test]
+    fn overlap_splits_incursion_into_normal_and_depth() {
+        let overlap = Overlap::new(Displacement::new(-3.0, 4.0), 1.0);
+
+        assert_eq!(overlap.normal(), Displacement::new(-0.6, 0.8));
+        assert_eq!(overlap.depth(), Length::new(5.0));
+        assert_eq!(overlap.incursion(), Displacement::new(-3.0, 4.0));
+        assert_eq!(overlap.contact_point(), None);
+    }
+
+    #[test]
+    fn zero_overlap_has_zero_normal() {
+        let overlap = Overlap::new(Displacement::ZERO, 1.0);
+
+        assert_eq!(overlap.normal(), Displacement::ZERO);
+        assert_eq!(overlap.depth(), Length::ZERO);
+    }
+
+    #[test]
+    fn pair_overlaps_share_contact_point() {
+        let mut graph: SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        graph.add_node(SimpleCircleNode::new(
+            Position::new(0.0, 0.0),
+            Length::new(1.5),
+        ));
+        graph.add_node(SimpleCircleNode::new(
+            Position::new(2.0, 0.0),
+            Length::new(2.0),
+        ));
+
+        let overlaps = find_pair_overlaps(&mut graph);
+
+        assert_eq!(
+            (overlaps[0].0).1.contact_point(),
+            Some(Position::new(0.75, 0.0))
+        );
+        assert_eq!(
+            (overlaps[0].1).1.contact_point(),
+            Some(Position::new(0.75, 0.0))
+        );
+    }
+
+    #[test]
+    fn wall_overlap_contact_point_is_midway_into_wall() {
+        let mut graph: SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        graph.add_node(SimpleCircleNode::new(
+            Position::new(9.5, 0.0),
+            Length::new(1.0),
+        ));
+        let subject = Walls::new(Position::new(-10.0, -5.0), Position::new(10.0, 2.0));
+
+        let overlaps = subject.find_overlaps(&mut graph);
+
+        assert_eq!(overlaps[0].1.normal(), Displacement::new(-1.0, 0.0));
+        assert_eq!(overlaps[0].1.depth(), Length::new(0.5));
+        assert_eq!(
+            overlaps[0].1.contact_point(),
+            Some(Position::new(10.25, 0.0))
+        );
+    }
+
+    #[test]
+    fn capsule_overlaps_circle_beside_its_axis() {
+        let mut graph: SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        graph.add_node(
+            SimpleCircleNode::new(Position::new(0.0, 0.0), Length::new(1.0))
+                .with_half_axis(Displacement::new(4.0, 0.0)),
+        );
+        graph.add_node(SimpleCircleNode::new(
+            Position::new(3.0, 1.5),
+            Length::new(1.0),
+        ));
+
+        let overlaps = find_pair_overlaps(&mut graph);
+
+        assert_eq!(overlaps.len(), 1);
+        let (capsule_overlap, circle_overlap) = ((overlaps[0].0).1, (overlaps[0].1).1);
+        assert_eq!(capsule_overlap.normal(), Displacement::new(0.0, -1.0));
+        assert_eq!(circle_overlap.normal(), Displacement::new(0.0, 1.0));
+        assert_eq!(capsule_overlap.depth(), Length::new(0.5));
+        assert_eq!(
+            capsule_overlap.contact_point(),
+            Some(Position::new(3.0, 0.75))
+        );
+    }
+
+    #[test]
+    fn capsules_end_to_end_do_not_overlap_until_their_ends_touch() {
+        let mut graph: SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        graph.add_node(
+            SimpleCircleNode::new(Position::new(0.0, 0.0), Length::new(1.0))
+                .with_half_axis(Displacement::new(2.0, 0.0)),
+        );
+        graph.add_node(
+            SimpleCircleNode::new(Position::new(6.5, 0.0), Length::new(1.0))
+                .with_half_axis(Displacement::new(2.0, 0.0)),
+        );
+
+        assert!(find_pair_overlaps(&mut graph).is_empty());
+
+        graph.nodes_mut()[1].set_center(Position::new(5.5, 0.0));
+        let overlaps = find_pair_overlaps(&mut graph);
+
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!((overlaps[0].0).1.depth(), Length::new(0.5));
+    }
+
+    #[test]
+    fn capsule_wall_overlap_reaches_along_axis() {
+        let mut graph: SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        graph.add_node(
+            SimpleCircleNode::new(Position::new(7.0, 0.0), Length::new(1.0))
+                .with_half_axis(Displacement::new(2.5, 0.0)),
+        );
+        let subject = Walls::new(Position::new(-10.0, -5.0), Position::new(10.0, 2.0));
+
+        let overlaps = subject.find_overlaps(&mut graph);
+
+        assert_eq!(overlaps[0].1.normal(), Displacement::new(-1.0, 0.0));
+        assert_eq!(overlaps[0].1.depth(), Length::new(0.5));
+        assert_eq!(
+            overlaps[0].1.contact_point(),
+            Some(Position::new(10.25, 0.0))
+        );
+    }
+
     #[test]
     fn bonded_graph_pair_overlap_is_ignored() {
         let mut graph: SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
@@ -1,3 +1,6 @@
+use approx::{AbsDiffEq, RelativeEq, UlpsEq};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::f64::consts::PI;
 use std::fmt;
 use std::ops::Add;
@@ -56,6 +59,12 @@ impl Value2D {
     pub fn magnitude(self) -> Value1D {
         self.x.hypot(self.y)
     }
+
+    // Rotates counterclockwise about the origin.
+    pub fn rotate(self, angle: Angle) -> Self {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
 }
 
 impl fmt::Display for Value2D {
@@ -140,6 +149,7 @@ impl DivAssign<Value1D> for Value2D {
     }
 }
 
+// A direction, normalized to [0, 2π).
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 pub struct Angle {
     radians: f64,
@@ -154,22 +164,42 @@ impl Angle {
         }
     }
 
+    pub fn from_degrees(degrees: f64) -> Self {
+        Self::from_radians(degrees.to_radians())
+    }
+
+    // The direction of the vector (x, y), which must not be zero.
+    pub fn from_xy(x: f64, y: f64) -> Self {
+        Self::from_radians(y.atan2(x))
+    }
+
+    // Non-finite radians stay non-finite, for the world's finite checks to catch.
     fn normalize_radians(radians: f64) -> f64 {
-        let mut normalized_radians = radians;
-        while normalized_radians < 0.0 {
-            normalized_radians += 2.0 * PI;
+        if (0.0..2.0 * PI).contains(&radians) {
+            return radians;
         }
-        while normalized_radians > 2.0 * PI {
-            normalized_radians -= 2.0 * PI;
+        let normalized_radians = radians.rem_euclid(2.0 * PI);
+        // rem_euclid can round a tiny negative up to 2π
+        if normalized_radians == 2.0 * PI {
+            0.0
+        } else {
+            normalized_radians
         }
-        normalized_radians
     }
 
-    #[allow(dead_code)]
     pub fn radians(self) -> f64 {
         self.radians
     }
 
+    // The same direction, in (-π, π].
+    pub fn signed_radians(self) -> f64 {
+        Deflection::normalize_radians(self.radians)
+    }
+
+    pub fn degrees(self) -> f64 {
+        self.radians.to_degrees()
+    }
+
     pub fn cos(self) -> f64 {
         self.radians.cos()
     }
@@ -179,6 +209,7 @@ impl Angle {
     }
 }
 
+// The shortest turn from `rhs` to `self`.
 impl Sub for Angle {
     type Output = Deflection;
 
@@ -197,24 +228,67 @@ impl Add<Deflection> for Angle {
 
 impl AddAssign<Deflection> for Angle {
     fn add_assign(&mut self, rhs: Deflection) {
-        self.radians = Self::normalize_radians(self.radians + rhs.radians);
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Deflection> for Angle {
+    type Output = Angle;
+
+    fn sub(self, rhs: Deflection) -> Self::Output {
+        Angle::from_radians(self.radians - rhs.radians)
+    }
+}
+
+impl SubAssign<Deflection> for Angle {
+    fn sub_assign(&mut self, rhs: Deflection) {
+        *self = *self - rhs;
     }
 }
 
+// A turn, counterclockwise if positive, normalized to (-π, π].
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 pub struct Deflection {
     radians: f64,
 }
 
 impl Deflection {
+    pub const ZERO: Deflection = Deflection { radians: 0.0 };
+
     pub fn from_radians(radians: f64) -> Self {
-        Deflection { radians }
+        Deflection {
+            radians: Self::normalize_radians(radians),
+        }
+    }
+
+    pub fn from_degrees(degrees: f64) -> Self {
+        Self::from_radians(degrees.to_radians())
+    }
+
+    // Limits the turn to `max_radians` either way.
+    pub fn from_radians_clamped(radians: f64, max_radians: f64) -> Self {
+        assert!((0.0..=PI).contains(&max_radians));
+        Self::from_radians(Self::normalize_radians(radians).clamp(-max_radians, max_radians))
+    }
+
+    fn normalize_radians(radians: f64) -> f64 {
+        if -PI < radians && radians <= PI {
+            return radians;
+        }
+        PI - (PI - radians).rem_euclid(2.0 * PI)
     }
 
-    #[allow(dead_code)]
     pub fn radians(self) -> f64 {
         self.radians
     }
+
+    pub fn degrees(self) -> f64 {
+        self.radians.to_degrees()
+    }
+
+    pub fn abs(self) -> Self {
+        Deflection::from_radians(self.radians.abs())
+    }
 }
 
 impl Add for Deflection {
@@ -225,6 +299,58 @@ impl Add for Deflection {
     }
 }
 
+impl AddAssign for Deflection {
+    fn add_assign(&mut self, rhs: Deflection) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Deflection {
+    type Output = Deflection;
+
+    fn sub(self, rhs: Deflection) -> Self::Output {
+        Deflection::from_radians(self.radians - rhs.radians)
+    }
+}
+
+impl SubAssign for Deflection {
+    fn sub_assign(&mut self, rhs: Deflection) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Deflection {
+    type Output = Deflection;
+
+    fn neg(self) -> Self::Output {
+        Deflection::from_radians(-self.radians)
+    }
+}
+
+impl Mul<f64> for Deflection {
+    type Output = Deflection;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Deflection::from_radians(self.radians * rhs)
+    }
+}
+
+impl Mul<Deflection> for f64 {
+    type Output = Deflection;
+
+    fn mul(self, rhs: Deflection) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Div<f64> for Deflection {
+    type Output = Deflection;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Deflection::from_radians(self.radians / rhs)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 pub struct Length {
     value: f64,
@@ -424,7 +550,7 @@ pub struct Position {
 impl Position {
     pub const ORIGIN: Position = Position { x: 0.0, y: 0.0 };
 
-    pub fn new(x: f64, y: f64) -> Self {
+    pub const fn new(x: f64, y: f64) -> Self {
         Position { x, y }
     }
 
@@ -447,13 +573,7 @@ impl Position {
     }
 
     pub fn to_polar_angle(&self, origin: Position) -> Angle {
-        let displacement = *self - origin;
-        let radians = displacement.y.atan2(displacement.x);
-        Angle::from_radians(if radians >= 0.0 {
-            radians
-        } else {
-            radians + 2.0 * PI
-        })
+        (*self - origin).angle()
     }
 }
 
@@ -530,6 +650,10 @@ impl Displacement {
     pub fn length(&self) -> Length {
         Length::new(self.x.hypot(self.y))
     }
+
+    pub fn angle(&self) -> Angle {
+        Angle::from_xy(self.x, self.y)
+    }
 }
 
 impl From<Value2D> for Displacement {
@@ -1189,9 +1313,278 @@ impl Neg for BioEnergyDelta {
     }
 }
 
+// Quantities are saved as plain numbers, or as [x, y] pairs. Loading a number that the
+// quantity's constructor would reject, e.g. a negative length, is an error. They compare
+// approximately, as with the approx crate's
+// assert_abs_diff_eq!, by comparing their numbers; 2D quantities compare each coordinate.
+
+macro_rules! impl_scalar_traits {
+    ($quantity:ident, $new:ident, $value:ident) => {
+        impl_scalar_traits!($quantity, $new, $value, is_any_value);
+    };
+    ($quantity:ident, $new:ident, $value:ident, $is_valid:ident) => {
+        impl Serialize for $quantity {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.$value().serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $quantity {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = f64::deserialize(deserializer)?;
+                if !$is_valid(value) {
+                    return Err(D::Error::custom(format!(
+                        "invalid {}: {}",
+                        stringify!($quantity),
+                        value
+                    )));
+                }
+                Ok($quantity::$new(value))
+            }
+        }
+
+        impl AbsDiffEq for $quantity {
+            type Epsilon = f64;
+
+            fn default_epsilon() -> f64 {
+                f64::default_epsilon()
+            }
+
+            fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+                self.$value().abs_diff_eq(&other.$value(), epsilon)
+            }
+        }
+
+        impl RelativeEq for $quantity {
+            fn default_max_relative() -> f64 {
+                f64::default_max_relative()
+            }
+
+            fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+                self.$value()
+                    .relative_eq(&other.$value(), epsilon, max_relative)
+            }
+        }
+
+        impl UlpsEq for $quantity {
+            fn default_max_ulps() -> u32 {
+                f64::default_max_ulps()
+            }
+
+            fn ulps_eq(&self, other: &Self, epsilon: f64, max_ulps: u32) -> bool {
+                self.$value().ulps_eq(&other.$value(), epsilon, max_ulps)
+            }
+        }
+    };
+}
+
+macro_rules! impl_vector_traits {
+    ($quantity:ident) => {
+        impl Serialize for $quantity {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                [self.x(), self.y()].serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $quantity {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <[f64; 2]>::deserialize(deserializer).map(|[x, y]| $quantity::new(x, y))
+            }
+        }
+
+        impl AbsDiffEq for $quantity {
+            type Epsilon = f64;
+
+            fn default_epsilon() -> f64 {
+                f64::default_epsilon()
+            }
+
+            fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+                self.x().abs_diff_eq(&other.x(), epsilon)
+                    && self.y().abs_diff_eq(&other.y(), epsilon)
+            }
+        }
+
+        impl RelativeEq for $quantity {
+            fn default_max_relative() -> f64 {
+                f64::default_max_relative()
+            }
+
+            fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+                self.x().relative_eq(&other.x(), epsilon, max_relative)
+                    && self.y().relative_eq(&other.y(), epsilon, max_relative)
+            }
+        }
+
+        impl UlpsEq for $quantity {
+            fn default_max_ulps() -> u32 {
+                f64::default_max_ulps()
+            }
+
+            fn ulps_eq(&self, other: &Self, epsilon: f64, max_ulps: u32) -> bool {
+                self.x().ulps_eq(&other.x(), epsilon, max_ulps)
+                    && self.y().ulps_eq(&other.y(), epsilon, max_ulps)
+            }
+        }
+    };
+}
+
+fn is_any_value(_value: f64) -> bool {
+    true
+}
+
+fn is_non_negative(value: f64) -> bool {
+    value >= 0.0
+}
+
+impl_scalar_traits!(Angle, from_radians, radians);
+impl_scalar_traits!(Deflection, from_radians, radians);
+impl_scalar_traits!(Length, new, value, is_non_negative);
+impl_scalar_traits!(Area, new, value, is_non_negative);
+impl_scalar_traits!(AreaDelta, new, value);
+impl_scalar_traits!(Duration, new, value);
+impl_scalar_traits!(Mass, new, value);
+impl_scalar_traits!(Density, new, value, is_non_negative);
+impl_scalar_traits!(Torque, new, value);
+impl_scalar_traits!(BioEnergy, new, value);
+impl_scalar_traits!(BioEnergyDelta, new, value);
+impl_vector_traits!(Value2D);
+impl_vector_traits!(Position);
+impl_vector_traits!(Displacement);
+impl_vector_traits!(Velocity);
+impl_vector_traits!(Acceleration);
+impl_vector_traits!(DeltaV);
+impl_vector_traits!(Momentum);
+impl_vector_traits!(Impulse);
+impl_vector_traits!(Force);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use approx::{assert_abs_diff_eq, assert_abs_diff_ne, assert_relative_eq};
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg64Mcg;
+
+    #[test]
+    fn scalar_quantity_saves_as_number() {
+        let json = serde_json::to_string(&Length::new(2.5)).unwrap();
+        assert_eq!(json, "2.5");
+        assert_eq!(
+            serde_json::from_str::<Length>(&json).unwrap(),
+            Length::new(2.5)
+        );
+    }
+
+    #[test]
+    fn vector_quantity_saves_as_pair() {
+        let json = serde_json::to_string(&Velocity::new(1.0, -2.0)).unwrap();
+        assert_eq!(json, "[1.0,-2.0]");
+        assert_eq!(
+            serde_json::from_str::<Velocity>(&json).unwrap(),
+            Velocity::new(1.0, -2.0)
+        );
+    }
+
+    #[test]
+    fn loading_invalid_quantity_fails() {
+        assert!(serde_json::from_str::<Length>("-1.0").is_err());
+    }
+
+    #[test]
+    fn quantities_compare_approximately() {
+        assert_abs_diff_eq!(Area::new(0.1 + 0.2), Area::new(0.3));
+        assert_relative_eq!(
+            Force::new(0.1 + 0.2, 1.0),
+            Force::new(0.3, 1.0 + 1e-12),
+            max_relative = 1e-9
+        );
+        assert_abs_diff_ne!(Mass::new(1.0), Mass::new(1.1));
+    }
+
+    #[test]
+    fn angle_normalizes_into_full_turn() {
+        let mut rng = Pcg64Mcg::seed_from_u64(1);
+        for _ in 0..1000 {
+            let radians = rng.gen_range(-100.0, 100.0);
+            let angle = Angle::from_radians(radians);
+            assert!(0.0 <= angle.radians() && angle.radians() < 2.0 * PI);
+            assert_abs_diff_eq!(angle.cos(), radians.cos(), epsilon = 1e-9);
+            assert_abs_diff_eq!(angle.sin(), radians.sin(), epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn deflection_normalizes_into_half_turns() {
+        let mut rng = Pcg64Mcg::seed_from_u64(2);
+        for _ in 0..1000 {
+            let radians = rng.gen_range(-100.0, 100.0);
+            let deflection = Deflection::from_radians(radians);
+            assert!(-PI < deflection.radians() && deflection.radians() <= PI);
+            assert_abs_diff_eq!(deflection.radians().cos(), radians.cos(), epsilon = 1e-9);
+            assert_abs_diff_eq!(deflection.radians().sin(), radians.sin(), epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn angle_difference_is_shortest_turn_that_undoes_it() {
+        let mut rng = Pcg64Mcg::seed_from_u64(3);
+        for _ in 0..1000 {
+            let angle1 = Angle::from_radians(rng.gen_range(0.0, 2.0 * PI));
+            let angle2 = Angle::from_radians(rng.gen_range(0.0, 2.0 * PI));
+            let turn = angle2 - angle1;
+            assert!(turn.radians().abs() <= PI);
+            assert_abs_diff_eq!((angle1 + turn).cos(), angle2.cos(), epsilon = 1e-9);
+            assert_abs_diff_eq!((angle1 + turn).sin(), angle2.sin(), epsilon = 1e-9);
+            assert_abs_diff_eq!((angle2 - turn).cos(), angle1.cos(), epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn half_turn_is_positive() {
+        assert_eq!(Deflection::from_radians(-PI).radians(), PI);
+        assert_eq!(Angle::from_radians(PI).signed_radians(), PI);
+        assert_eq!(Angle::from_radians(1.5 * PI).signed_radians(), -0.5 * PI);
+    }
+
+    #[test]
+    fn tiny_negative_angle_is_below_full_turn() {
+        assert!(Angle::from_radians(-1e-18).radians() < 2.0 * PI);
+    }
+
+    #[test]
+    fn non_finite_angle_stays_non_finite() {
+        assert!(Angle::from_radians(f64::INFINITY).radians().is_nan());
+    }
+
+    #[test]
+    fn clamped_deflection_is_limited_either_way() {
+        assert_eq!(Deflection::from_radians_clamped(1.0, 0.5).radians(), 0.5);
+        assert_eq!(Deflection::from_radians_clamped(-1.0, 0.5).radians(), -0.5);
+        // 3π/2 is a quarter turn clockwise
+        assert_eq!(
+            Deflection::from_radians_clamped(1.5 * PI, 0.25).radians(),
+            -0.25
+        );
+    }
+
+    #[test]
+    fn deflection_arithmetic() {
+        let turn = Deflection::from_radians(0.5);
+        assert_eq!(-turn, Deflection::from_radians(-0.5));
+        assert_eq!(turn * 2.0, Deflection::from_radians(1.0));
+        assert_eq!(turn / 2.0, Deflection::from_radians(0.25));
+        assert_eq!(turn - turn, Deflection::ZERO);
+        assert_eq!(Angle::from_radians(1.0) - turn, Angle::from_radians(0.5));
+        assert_abs_diff_eq!(Deflection::from_degrees(90.0).radians(), PI / 2.0);
+    }
+
+    #[test]
+    fn displacement_angle() {
+        assert_abs_diff_eq!(
+            Displacement::new(0.0, -1.0).angle(),
+            Angle::from_radians(1.5 * PI)
+        );
+    }
 
     #[test]
     fn normalize_negative_angle() {
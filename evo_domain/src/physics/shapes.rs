@@ -6,8 +6,15 @@ pub trait Circle {
 
     fn center(&self) -> Position;
 
+    // From the center to one end of the axis, for a capsule: the shape the circle sweeps as its
+    // center moves along the axis from end to end. A plain circle's axis is just its center.
+    fn half_axis(&self) -> Displacement {
+        Displacement::ZERO
+    }
+
     fn area(&self) -> Area {
         PI * self.radius().sqr()
+            + Area::new(4.0 * self.radius().value()) * self.half_axis().length().value()
     }
 
     fn to_bounding_box(&self) -> Rectangle {
@@ -18,20 +25,75 @@ pub trait Circle {
     }
 
     fn min_x(&self) -> f64 {
-        self.center().x() - self.radius().value()
+        self.center().x() - self.half_axis().x().abs() - self.radius().value()
     }
 
     fn max_x(&self) -> f64 {
-        self.center().x() + self.radius().value()
+        self.center().x() + self.half_axis().x().abs() + self.radius().value()
     }
 
     fn min_y(&self) -> f64 {
-        self.center().y() - self.radius().value()
+        self.center().y() - self.half_axis().y().abs() - self.radius().value()
     }
 
     fn max_y(&self) -> f64 {
-        self.center().y() + self.radius().value()
+        self.center().y() + self.half_axis().y().abs() + self.radius().value()
+    }
+}
+
+// The nearest points on the two circles' axes, which for plain circles are their centers.
+// Two capsules overlap if and only if these points are closer than the sum of their radii.
+pub fn closest_axis_points<C1: Circle + ?Sized, C2: Circle + ?Sized>(
+    circle1: &C1,
+    circle2: &C2,
+) -> (Position, Position) {
+    let half_axis1 = circle1.half_axis();
+    let half_axis2 = circle2.half_axis();
+    if half_axis1 == Displacement::ZERO && half_axis2 == Displacement::ZERO {
+        return (circle1.center(), circle2.center());
     }
+
+    // The axes as start + fraction * direction, for fractions from 0 to 1, after Ericson,
+    // "Real-Time Collision Detection", 5.1.9
+    let start1 = circle1.center() + -half_axis1;
+    let start2 = circle2.center() + -half_axis2;
+    let (d1x, d1y) = (2.0 * half_axis1.x(), 2.0 * half_axis1.y());
+    let (d2x, d2y) = (2.0 * half_axis2.x(), 2.0 * half_axis2.y());
+    let (rx, ry) = (start1.x() - start2.x(), start1.y() - start2.y());
+    let length1_sqr = d1x * d1x + d1y * d1y;
+    let length2_sqr = d2x * d2x + d2y * d2y;
+    let f = d2x * rx + d2y * ry;
+
+    let (fraction1, fraction2) = if length1_sqr == 0.0 {
+        (0.0, (f / length2_sqr).clamp(0.0, 1.0))
+    } else {
+        let c = d1x * rx + d1y * ry;
+        if length2_sqr == 0.0 {
+            ((-c / length1_sqr).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1x * d2x + d1y * d2y;
+            let denominator = length1_sqr * length2_sqr - b * b;
+            // parallel axes have many closest pairs; any one will do
+            let fraction1 = if denominator != 0.0 {
+                ((b * f - c * length2_sqr) / denominator).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let fraction2 = (b * fraction1 + f) / length2_sqr;
+            if fraction2 < 0.0 {
+                ((-c / length1_sqr).clamp(0.0, 1.0), 0.0)
+            } else if fraction2 > 1.0 {
+                (((b - c) / length1_sqr).clamp(0.0, 1.0), 1.0)
+            } else {
+                (fraction1, fraction2)
+            }
+        }
+    };
+
+    (
+        start1 + Displacement::new(fraction1 * d1x, fraction1 * d1y),
+        start2 + Displacement::new(fraction2 * d2x, fraction2 * d2y),
+    )
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -89,11 +151,24 @@ impl FloatRange {
 pub struct SimpleCircle {
     pub center: Position,
     pub radius: Length,
+    pub half_axis: Displacement,
 }
 
 impl SimpleCircle {
     pub fn new(center: Position, radius: Length) -> SimpleCircle {
-        SimpleCircle { center, radius }
+        SimpleCircle {
+            center,
+            radius,
+            half_axis: Displacement::ZERO,
+        }
+    }
+
+    pub fn capsule(center: Position, radius: Length, half_axis: Displacement) -> SimpleCircle {
+        SimpleCircle {
+            center,
+            radius,
+            half_axis,
+        }
     }
 }
 
@@ -105,6 +180,10 @@ impl Circle for SimpleCircle {
     fn center(&self) -> Position {
         self.center
     }
+
+    fn half_axis(&self) -> Displacement {
+        self.half_axis
+    }
 }
 
 #[cfg(test)]
@@ -127,6 +206,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn capsule_area_and_bounding_box_include_axis() {
+        let subject = SimpleCircle::capsule(
+            Position::new(0.0, 0.0),
+            Length::new(1.0),
+            Displacement::new(3.0, -4.0),
+        );
+        assert_eq!(subject.area(), Area::new(PI + 20.0));
+        assert_eq!(
+            subject.to_bounding_box(),
+            Rectangle::new(Position::new(-4.0, -5.0), Position::new(4.0, 5.0))
+        );
+    }
+
+    #[test]
+    fn closest_axis_points_of_circles_are_centers() {
+        let circle1 = SimpleCircle::new(Position::new(1.0, 2.0), Length::new(1.0));
+        let circle2 = SimpleCircle::new(Position::new(3.0, 4.0), Length::new(1.0));
+        assert_eq!(
+            closest_axis_points(&circle1, &circle2),
+            (Position::new(1.0, 2.0), Position::new(3.0, 4.0))
+        );
+    }
+
+    #[test]
+    fn closest_axis_points_of_crossing_capsule_and_circle() {
+        let capsule = SimpleCircle::capsule(
+            Position::new(0.0, 0.0),
+            Length::new(1.0),
+            Displacement::new(5.0, 0.0),
+        );
+        let circle = SimpleCircle::new(Position::new(3.0, 2.0), Length::new(1.0));
+        assert_eq!(
+            closest_axis_points(&capsule, &circle),
+            (Position::new(3.0, 0.0), Position::new(3.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn closest_axis_points_of_capsules_end_to_end() {
+        let capsule1 = SimpleCircle::capsule(
+            Position::new(0.0, 0.0),
+            Length::new(1.0),
+            Displacement::new(2.0, 0.0),
+        );
+        let capsule2 = SimpleCircle::capsule(
+            Position::new(5.0, 1.0),
+            Length::new(1.0),
+            Displacement::new(0.0, 1.0),
+        );
+        assert_eq!(
+            closest_axis_points(&capsule1, &capsule2),
+            (Position::new(2.0, 0.0), Position::new(5.0, 0.0))
+        );
+    }
+
     #[test]
     fn float_range_overlap() {
         assert!(!FloatRange::new(0.0, 0.9).overlaps(FloatRange::new(1.0, 2.0)));
@@ -23,6 +23,7 @@ pub struct SimpleCircleNode {
     graph_node_data: GraphNodeData,
     center: Position,
     radius: Length,
+    half_axis: Displacement,
 }
 
 impl SimpleCircleNode {
@@ -31,9 +32,15 @@ impl SimpleCircleNode {
             graph_node_data: GraphNodeData::new(),
             center,
             radius,
+            half_axis: Displacement::ZERO,
         }
     }
 
+    pub fn with_half_axis(mut self, half_axis: Displacement) -> Self {
+        self.half_axis = half_axis;
+        self
+    }
+
     pub fn set_center(&mut self, pos: Position) {
         self.center = pos;
     }
@@ -47,6 +54,10 @@ impl Circle for SimpleCircleNode {
     fn center(&self) -> Position {
         self.center
     }
+
+    fn half_axis(&self) -> Displacement {
+        self.half_axis
+    }
 }
 
 #[derive(Debug, GraphEdge, PartialEq)]
@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 use std::convert::TryInto;
 use std::fmt;
 use std::fmt::{Error, Formatter};
-use std::u32;
+use std::mem;
 
 pub const MAX_NODE_EDGES: usize = 8;
 
@@ -13,6 +13,23 @@ pub struct SortableGraph<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> {
     node_handles: Vec<NodeHandle>,
     edges: Vec<E>,
     meta_edges: Vec<ME>,
+    churn: GraphChurn,
+}
+
+// How many nodes and edges a graph has added and removed since its churn was last taken.
+// Edges removed along with their nodes count as removed edges.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct GraphChurn {
+    pub nodes_added: usize,
+    pub nodes_removed: usize,
+    pub edges_added: usize,
+    pub edges_removed: usize,
+}
+
+impl GraphChurn {
+    pub fn total(&self) -> usize {
+        self.nodes_added + self.nodes_removed + self.edges_added + self.edges_removed
+    }
 }
 
 impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
@@ -23,6 +40,7 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
             node_handles: vec![],
             edges: vec![],
             meta_edges: vec![],
+            churn: GraphChurn::default(),
         }
     }
 
@@ -31,6 +49,7 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
         node.graph_node_data_mut().handle = handle;
         self.nodes.push(node);
         self.node_handles.push(handle);
+        self.churn.nodes_added += 1;
         handle
     }
 
@@ -49,6 +68,7 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
         self.add_edge_to_node(edge.node1_handle(), handle, edge_index_on_node1);
         self.add_edge_to_node(edge.node2_handle(), handle, edge_index_on_node2);
         self.edges.push(edge);
+        self.churn.edges_added += 1;
         handle
     }
 
@@ -81,10 +101,10 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
     /// Warning: this function has two big gotchas:
     ///
     /// 1) `handles` should be in ascending order of `index`. If not, the function will
-    /// panic on index out-of-bounds if we're removing nodes at the end of self.nodes.
+    ///    panic on index out-of-bounds if we're removing nodes at the end of self.nodes.
     ///
     /// 2) Worse, this function changes the nodes referenced by some of the remaining handles.
-    /// Never retain handles across a call to this function.
+    ///    Never retain handles across a call to this function.
     pub fn remove_nodes(&mut self, handles: &[NodeHandle]) {
         for handle in handles.iter().rev() {
             self.remove_node(*handle);
@@ -96,6 +116,7 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
     fn remove_node(&mut self, handle: NodeHandle) {
         self.remove_node_edges(&self.node(handle).graph_node_data().edge_handles.clone());
         self.nodes.swap_remove(handle.index());
+        self.churn.nodes_removed += 1;
         self.fix_swapped_node_if_needed(handle);
     }
 
@@ -114,12 +135,11 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
             .edge_handles
             .clone()
             .iter()
+            .flatten()
         {
-            if let Some(edge_handle) = edge_handle {
-                self.edge_mut(*edge_handle)
-                    .graph_edge_data_mut()
-                    .replace_node_handle(old_handle, new_handle);
-            }
+            self.edge_mut(*edge_handle)
+                .graph_edge_data_mut()
+                .replace_node_handle(old_handle, new_handle);
         }
     }
 
@@ -146,8 +166,11 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
     fn remove_edge(&mut self, handle: EdgeHandle) {
         self.remove_edge_from_node(self.edge(handle).node1_handle(), handle);
         self.remove_edge_from_node(self.edge(handle).node2_handle(), handle);
-        // TODO remove obsolete meta-edges
+        self.meta_edges.retain(|meta_edge| {
+            meta_edge.edge1_handle() != handle && meta_edge.edge2_handle() != handle
+        });
         self.edges.swap_remove(handle.index());
+        self.churn.edges_removed += 1;
         self.fix_swapped_edge_if_needed(handle);
     }
 
@@ -169,7 +192,11 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
         let edge_data = self.edge(new_handle).graph_edge_data().clone();
         self.replace_edge_handle(edge_data.node1_handle, old_handle, new_handle);
         self.replace_edge_handle(edge_data.node2_handle, old_handle, new_handle);
-        // TODO update handles to swapped edges in remaining meta-edges
+        for meta_edge in &mut self.meta_edges {
+            meta_edge
+                .graph_meta_edge_data_mut()
+                .replace_edge_handle(old_handle, new_handle);
+        }
     }
 
     fn replace_edge_handle(
@@ -258,6 +285,10 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
         &self.edges
     }
 
+    pub fn edges_mut(&mut self) -> &mut [E] {
+        &mut self.edges
+    }
+
     pub fn edge(&self, handle: EdgeHandle) -> &E {
         &self.edges[handle.index()]
     }
@@ -266,6 +297,29 @@ impl<N: GraphNode, E: GraphEdge, ME: GraphMetaEdge> SortableGraph<N, E, ME> {
         &mut self.edges[handle.index()]
     }
 
+    // The number of nodes the graph has room for without reallocating.
+    pub fn node_capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    // Gives back the memory left over from removed nodes and edges. Nodes and edges are kept
+    // packed as they are removed, so no handles change.
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.node_handles.shrink_to_fit();
+        self.edges.shrink_to_fit();
+        self.meta_edges.shrink_to_fit();
+    }
+
+    pub fn churn(&self) -> GraphChurn {
+        self.churn
+    }
+
+    // Returns the churn so far and starts counting again from zero.
+    pub fn take_churn(&mut self) -> GraphChurn {
+        mem::take(&mut self.churn)
+    }
+
     pub fn meta_edges(&self) -> &[ME] {
         &self.meta_edges
     }
@@ -327,7 +381,7 @@ impl NodeHandle {
         &mut nodes[self.index()]
     }
 
-    fn index(self) -> usize {
+    pub fn index(self) -> usize {
         self.index.try_into().unwrap()
     }
 }
@@ -358,7 +412,7 @@ impl GraphNodeData {
     }
 
     pub fn has_edge_handle(&self, node_edge_index: usize) -> bool {
-        self.edge_handles[node_edge_index] != None
+        self.edge_handles[node_edge_index].is_some()
     }
 
     pub fn edge_handle(&self, edge_index: usize) -> EdgeHandle {
@@ -432,7 +486,7 @@ impl EdgeHandle {
         &mut edges[self.index()]
     }
 
-    fn index(self) -> usize {
+    pub fn index(self) -> usize {
         self.index.try_into().unwrap()
     }
 }
@@ -519,6 +573,15 @@ impl GraphMetaEdgeData {
     pub fn edge2_handle(&self) -> EdgeHandle {
         self.edge2_handle
     }
+
+    fn replace_edge_handle(&mut self, old_handle: EdgeHandle, new_handle: EdgeHandle) {
+        if self.edge1_handle == old_handle {
+            self.edge1_handle = new_handle;
+        }
+        if self.edge2_handle == old_handle {
+            self.edge2_handle = new_handle;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -556,7 +619,7 @@ mod tests {
         graph.add_node(SimpleGraphNode::new(1));
         let node2_handle = graph.add_node(SimpleGraphNode::new(2));
 
-        graph.remove_nodes(&vec![node0_handle, node2_handle]);
+        graph.remove_nodes(&[node0_handle, node2_handle]);
 
         assert_eq!(graph.nodes.len(), 1);
         let node = &graph.nodes()[0];
@@ -605,7 +668,7 @@ mod tests {
             0,
         );
 
-        graph.remove_edges(&vec![edge01_handle]);
+        graph.remove_edges(&[edge01_handle]);
 
         assert_eq!(graph.edges().len(), 1);
         assert_eq!(
@@ -671,7 +734,7 @@ mod tests {
             0,
         );
 
-        graph.remove_nodes(&vec![node0_handle]);
+        graph.remove_nodes(&[node0_handle]);
 
         assert_eq!(graph.edges().len(), 1);
         assert_eq!(
@@ -698,9 +761,56 @@ mod tests {
             0,
         );
 
-        assert!(graph.have_edge(&graph.node(node0_handle), &graph.node(node1_handle)));
-        assert!(graph.have_edge(&graph.node(node1_handle), &graph.node(node0_handle)));
-        assert!(!graph.have_edge(&graph.node(node0_handle), &graph.node(node2_handle)));
+        assert!(graph.have_edge(graph.node(node0_handle), graph.node(node1_handle)));
+        assert!(graph.have_edge(graph.node(node1_handle), graph.node(node0_handle)));
+        assert!(!graph.have_edge(graph.node(node0_handle), graph.node(node2_handle)));
+    }
+
+    #[test]
+    fn counts_churn_until_taken() {
+        let mut graph: SortableGraph<SimpleGraphNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        let node0_handle = graph.add_node(SimpleGraphNode::new(0));
+        let node1_handle = graph.add_node(SimpleGraphNode::new(1));
+        graph.add_edge(
+            SimpleGraphEdge::new(graph.node(node0_handle), graph.node(node1_handle)),
+            1,
+            0,
+        );
+
+        graph.remove_nodes(&[node1_handle]);
+
+        assert_eq!(
+            graph.take_churn(),
+            GraphChurn {
+                nodes_added: 2,
+                nodes_removed: 1,
+                edges_added: 1,
+                edges_removed: 1,
+            }
+        );
+        assert_eq!(graph.churn().total(), 0);
+    }
+
+    #[test]
+    fn shrinking_keeps_handles() {
+        let mut graph: SortableGraph<SimpleGraphNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        let handles: Vec<NodeHandle> = (0..100)
+            .map(|id| graph.add_node(SimpleGraphNode::new(id)))
+            .collect();
+        graph.add_edge(
+            SimpleGraphEdge::new(graph.node(handles[0]), graph.node(handles[1])),
+            1,
+            0,
+        );
+
+        graph.remove_nodes(&handles[2..]);
+        graph.shrink_to_fit();
+
+        assert!(graph.node_capacity() < 100);
+        assert!(graph.have_edge(graph.node(handles[0]), graph.node(handles[1])));
+        assert_eq!(graph.node(handles[1]).id, 1);
     }
 
     #[test]
@@ -732,4 +842,45 @@ mod tests {
         assert_eq!(meta_edge.edge1_handle(), edge01_handle);
         assert_eq!(meta_edge.edge2_handle(), edge12_handle);
     }
+
+    #[test]
+    fn removing_edges_removes_their_meta_edges_and_fixes_swapped_handles() {
+        let mut graph: SortableGraph<SimpleGraphNode, SimpleGraphEdge, SimpleGraphMetaEdge> =
+            SortableGraph::new();
+        let node_handles: Vec<NodeHandle> = (0..4)
+            .map(|i| graph.add_node(SimpleGraphNode::new(i)))
+            .collect();
+        let edge_handles: Vec<EdgeHandle> = (0..3)
+            .map(|i| {
+                graph.add_edge(
+                    SimpleGraphEdge::new(
+                        graph.node(node_handles[i]),
+                        graph.node(node_handles[i + 1]),
+                    ),
+                    1,
+                    0,
+                )
+            })
+            .collect();
+        graph.add_meta_edge(SimpleGraphMetaEdge::new(
+            graph.edge(edge_handles[0]),
+            graph.edge(edge_handles[1]),
+        ));
+        graph.add_meta_edge(SimpleGraphMetaEdge::new(
+            graph.edge(edge_handles[1]),
+            graph.edge(edge_handles[2]),
+        ));
+
+        graph.remove_edges(&[edge_handles[0]]);
+
+        assert_eq!(graph.meta_edges().len(), 1);
+        let meta_edge = &graph.meta_edges()[0];
+        // the last edge was swapped into the removed edge's place
+        assert_eq!(meta_edge.edge1_handle(), edge_handles[1]);
+        assert_eq!(meta_edge.edge2_handle(), edge_handles[0]);
+        assert_eq!(
+            graph.edge(meta_edge.edge2_handle()).node1_handle(),
+            node_handles[2]
+        );
+    }
 }
@@ -0,0 +1,345 @@
+use crate::physics::overlap::cmp_by_min_x;
+use crate::physics::quantities::*;
+use crate::physics::shapes::*;
+use crate::physics::sortable_graph::*;
+
+// Queries that use the graph's node handles, sorted by min x, as a broad-phase index: the
+// same sort that find_pair_overlaps maintains, so it is usually cheap to bring up to date.
+
+// Returns handles of the circles whose centers are inside the rectangle, in min x order.
+pub fn find_circles_in_rect<C, E, ME>(
+    graph: &mut SortableGraph<C, E, ME>,
+    min_corner: Position,
+    max_corner: Position,
+) -> Vec<NodeHandle>
+where
+    C: Circle + GraphNode,
+    E: GraphEdge,
+    ME: GraphMetaEdge,
+{
+    graph.sort_already_mostly_sorted_node_handles(cmp_by_min_x);
+
+    let mut handles = vec![];
+    for handle in graph.node_handles() {
+        let center = graph.node(*handle).center();
+        // a center is never left of its circle's min x
+        if graph.node(*handle).min_x() > max_corner.x() {
+            break;
+        }
+        if min_corner.x() <= center.x()
+            && center.x() <= max_corner.x()
+            && min_corner.y() <= center.y()
+            && center.y() <= max_corner.y()
+        {
+            handles.push(*handle);
+        }
+    }
+    handles
+}
+
+// Returns handles of the circles whose shapes, including any capsule's axis, contain `pos`, in
+// min x order.
+pub fn find_circles_containing<C, E, ME>(
+    graph: &mut SortableGraph<C, E, ME>,
+    pos: Position,
+) -> Vec<NodeHandle>
+where
+    C: Circle + GraphNode,
+    E: GraphEdge,
+    ME: GraphMetaEdge,
+{
+    graph.sort_already_mostly_sorted_node_handles(cmp_by_min_x);
+
+    let point = SimpleCircle::new(pos, Length::ZERO);
+    let mut handles = vec![];
+    for handle in graph.node_handles() {
+        let node = graph.node(*handle);
+        if node.min_x() > pos.x() {
+            break;
+        }
+        if pos.x() <= node.max_x() && node.min_y() <= pos.y() && pos.y() <= node.max_y() {
+            let (axis_point, _) = closest_axis_points(node, &point);
+            if distance(axis_point, pos) <= node.radius().value() {
+                handles.push(*handle);
+            }
+        }
+    }
+    handles
+}
+
+// Returns handles of the (up to) `k` circles whose centers are nearest to `pos`, nearest first.
+pub fn find_nearest_circles<C, E, ME>(
+    graph: &mut SortableGraph<C, E, ME>,
+    pos: Position,
+    k: usize,
+) -> Vec<NodeHandle>
+where
+    C: Circle + GraphNode,
+    E: GraphEdge,
+    ME: GraphMetaEdge,
+{
+    if k == 0 {
+        return vec![];
+    }
+
+    graph.sort_already_mostly_sorted_node_handles(cmp_by_min_x);
+
+    let max_center_inset = graph
+        .nodes()
+        .iter()
+        .map(|node| node.center().x() - node.min_x())
+        .fold(0.0, f64::max);
+    let handles = graph.node_handles();
+    let start = handles.partition_point(|handle| graph.node(*handle).min_x() < pos.x());
+    let mut nearest = NearestList::new(k);
+
+    // Rightward, every center is at least as far right as its circle's min x.
+    for handle in &handles[start..] {
+        let node = graph.node(*handle);
+        if nearest.is_full() && node.min_x() - pos.x() > nearest.max_distance() {
+            break;
+        }
+        nearest.offer(*handle, distance(node.center(), pos));
+    }
+    // Leftward, no center is further right than its circle's min x plus the largest distance
+    // from any circle's min x to its center: its radius, plus any capsule's axis.
+    for handle in handles[..start].iter().rev() {
+        let node = graph.node(*handle);
+        if nearest.is_full() && pos.x() - (node.min_x() + max_center_inset) > nearest.max_distance()
+        {
+            break;
+        }
+        nearest.offer(*handle, distance(node.center(), pos));
+    }
+
+    nearest.into_handles()
+}
+
+// Returns the overlapping pairs, capsules included, among the circles `is_member` picks, each
+// as (lower handle, higher handle), sorted by handle.
+pub fn find_overlapping_pairs<C, E, ME, F>(
+    graph: &mut SortableGraph<C, E, ME>,
+    is_member: F,
+) -> Vec<(NodeHandle, NodeHandle)>
+where
+    C: Circle + GraphNode,
+    E: GraphEdge,
+    ME: GraphMetaEdge,
+    F: Fn(NodeHandle) -> bool,
+{
+    graph.sort_already_mostly_sorted_node_handles(cmp_by_min_x);
+
+    let members: Vec<NodeHandle> = graph
+        .node_handles()
+        .iter()
+        .copied()
+        .filter(|handle| is_member(*handle))
+        .collect();
+    let mut pairs = vec![];
+    for (i, handle1) in members.iter().enumerate() {
+        let circle1 = graph.node(*handle1);
+        for handle2 in &members[(i + 1)..] {
+            let circle2 = graph.node(*handle2);
+            if circle2.min_x() >= circle1.max_x() {
+                break;
+            }
+            let (point1, point2) = closest_axis_points(circle1, circle2);
+            if distance(point1, point2) < circle1.radius().value() + circle2.radius().value() {
+                pairs.push(((*handle1).min(*handle2), (*handle1).max(*handle2)));
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+fn distance(pos1: Position, pos2: Position) -> f64 {
+    (pos1 - pos2).length().value()
+}
+
+// The k nearest handles found so far, sorted by distance.
+struct NearestList {
+    k: usize,
+    entries: Vec<(f64, NodeHandle)>,
+}
+
+impl NearestList {
+    fn new(k: usize) -> Self {
+        NearestList {
+            k,
+            entries: Vec::with_capacity(k + 1),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.entries.len() == self.k
+    }
+
+    fn max_distance(&self) -> f64 {
+        self.entries.last().map_or(f64::INFINITY, |entry| entry.0)
+    }
+
+    fn offer(&mut self, handle: NodeHandle, distance: f64) {
+        if self.is_full() && distance >= self.max_distance() {
+            return;
+        }
+        let index = self
+            .entries
+            .partition_point(|(entry_distance, _)| *entry_distance <= distance);
+        self.entries.insert(index, (distance, handle));
+        self.entries.truncate(self.k);
+    }
+
+    fn into_handles(self) -> Vec<NodeHandle> {
+        self.entries.into_iter().map(|(_, handle)| handle).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::simple_graph_elements::*;
+
+    #[test]
+    fn finds_circles_with_centers_in_rect() {
+        let mut graph = graph_with_circles(&[(0.0, 0.0, 1.0), (5.0, 5.0, 1.0), (5.0, 20.0, 1.0)]);
+
+        let handles = find_circles_in_rect(
+            &mut graph,
+            Position::new(-1.0, -1.0),
+            Position::new(10.0, 10.0),
+        );
+
+        assert_eq!(centers(&graph, &handles), vec![(0.0, 0.0), (5.0, 5.0)]);
+    }
+
+    #[test]
+    fn circle_overlapping_rect_but_with_center_outside_is_not_in_rect() {
+        let mut graph = graph_with_circles(&[(11.0, 0.0, 2.0)]);
+
+        let handles = find_circles_in_rect(
+            &mut graph,
+            Position::new(0.0, 0.0),
+            Position::new(10.0, 10.0),
+        );
+
+        assert!(handles.is_empty());
+    }
+
+    #[test]
+    fn finds_circles_and_capsules_containing_point() {
+        let mut graph = graph_with_circles(&[(0.0, 0.0, 1.5), (1.7, 0.0, 1.25), (5.0, 5.0, 1.0)]);
+        graph.add_node(
+            SimpleCircleNode::new(Position::new(1.0, 4.0), Length::new(0.5))
+                .with_half_axis(Displacement::new(0.0, 3.0)),
+        );
+
+        let handles = find_circles_containing(&mut graph, Position::new(0.8, 0.7));
+
+        assert_eq!(
+            centers(&graph, &handles),
+            vec![(0.0, 0.0), (1.7, 0.0), (1.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn finds_nearest_circles_nearest_first() {
+        let mut graph = graph_with_circles(&[
+            (-10.0, 0.0, 1.0),
+            (3.0, 0.0, 1.0),
+            (0.0, -2.0, 5.0),
+            (100.0, 0.0, 1.0),
+        ]);
+
+        let handles = find_nearest_circles(&mut graph, Position::ORIGIN, 2);
+
+        assert_eq!(centers(&graph, &handles), vec![(0.0, -2.0), (3.0, 0.0)]);
+    }
+
+    #[test]
+    fn nearest_circles_returns_all_when_k_exceeds_count() {
+        let mut graph = graph_with_circles(&[(1.0, 0.0, 1.0), (-2.0, 0.0, 1.0)]);
+
+        let handles = find_nearest_circles(&mut graph, Position::ORIGIN, 5);
+
+        assert_eq!(centers(&graph, &handles), vec![(1.0, 0.0), (-2.0, 0.0)]);
+    }
+
+    #[test]
+    fn nearest_circles_matches_brute_force() {
+        let circles: Vec<(f64, f64, f64)> = (0..50)
+            .map(|i| {
+                let i = i as f64;
+                ((i * 37.0) % 23.0, (i * 11.0) % 17.0, 0.5 + (i % 4.0))
+            })
+            .collect();
+        let mut graph = graph_with_circles(&circles);
+        let pos = Position::new(7.0, 5.0);
+
+        let handles = find_nearest_circles(&mut graph, pos, 5);
+
+        let mut brute_force: Vec<f64> = graph
+            .nodes()
+            .iter()
+            .map(|node| distance(node.center(), pos))
+            .collect();
+        brute_force.sort_by(|d1, d2| d1.partial_cmp(d2).unwrap());
+        let found: Vec<f64> = handles
+            .iter()
+            .map(|handle| distance(graph.node(*handle).center(), pos))
+            .collect();
+        assert_eq!(found, brute_force[..5].to_vec());
+    }
+
+    #[test]
+    fn finds_overlapping_pairs_among_members() {
+        let mut graph = graph_with_circles(&[
+            (5.0, 0.0, 1.0),
+            (0.0, 0.0, 1.0),
+            (1.5, 0.0, 1.0),
+            (6.5, 0.0, 1.0),
+            (3.0, 0.0, 1.0),
+        ]);
+        graph.add_node(
+            SimpleCircleNode::new(Position::new(0.0, 3.0), Length::new(0.5))
+                .with_half_axis(Displacement::new(0.0, 2.0)),
+        );
+        let handles = graph.node_handles().to_vec();
+
+        let pairs = find_overlapping_pairs(&mut graph, |handle| handle != handles[3]);
+
+        let pair_centers: Vec<(f64, f64)> = pairs
+            .iter()
+            .map(|(handle1, handle2)| {
+                (
+                    graph.node(*handle1).center().x(),
+                    graph.node(*handle2).center().x(),
+                )
+            })
+            .collect();
+        assert_eq!(pair_centers, vec![(0.0, 1.5), (0.0, 0.0), (1.5, 3.0)]);
+    }
+
+    type SimpleGraph = SortableGraph<SimpleCircleNode, SimpleGraphEdge, SimpleGraphMetaEdge>;
+
+    fn graph_with_circles(circles: &[(f64, f64, f64)]) -> SimpleGraph {
+        let mut graph = SortableGraph::new();
+        for (x, y, radius) in circles {
+            graph.add_node(SimpleCircleNode::new(
+                Position::new(*x, *y),
+                Length::new(*radius),
+            ));
+        }
+        graph
+    }
+
+    fn centers(graph: &SimpleGraph, handles: &[NodeHandle]) -> Vec<(f64, f64)> {
+        handles
+            .iter()
+            .map(|handle| {
+                let center = graph.node(*handle).center();
+                (center.x(), center.y())
+            })
+            .collect()
+    }
+}
@@ -3,22 +3,3 @@ use std::f64;
 pub fn sqr(x: f64) -> f64 {
     x * x
 }
-
-// Stolen from https://users.rust-lang.org/t/assert-eq-for-float-numbers/7034/4
-#[allow(clippy::float_cmp)]
-pub fn nearly_equal(a: f64, b: f64) -> bool {
-    let abs_a = a.abs();
-    let abs_b = b.abs();
-    let diff = (a - b).abs();
-
-    if a == b {
-        // Handle infinities.
-        true
-    } else if a == 0.0 || b == 0.0 || diff < f64::MIN_POSITIVE {
-        // One of a or b is zero (or both are extremely close to it,) use absolute error.
-        diff < (f64::EPSILON * f64::MIN_POSITIVE)
-    } else {
-        // Use relative error.
-        (diff / f64::min(abs_a + abs_b, f64::MAX)) < f64::EPSILON
-    }
-}
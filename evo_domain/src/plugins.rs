@@ -0,0 +1,109 @@
+use crate::biology::cell::Cell;
+use crate::world::World;
+use std::collections::BTreeMap;
+
+// Experiment-specific logic, e.g. custom stats or interventions, that the world runs at fixed
+// points in every tick, so that it can live outside this crate. Every hook does nothing by
+// default. The world runs its plugins in the order they were added.
+pub trait WorldPlugin {
+    // At the start of the tick, before anything else happens.
+    fn before_tick(&mut self, _world: &mut World) {}
+
+    // After the influences have added their forces, light and overlaps to the cells. Must not
+    // add or remove cells.
+    fn after_influences(&mut self, _world: &mut World) {}
+
+    // After the cells' controls have run and the world has added their children and removed
+    // the dead, but before the cells move.
+    fn after_controls(&mut self, _world: &mut World) {}
+
+    // When a cell's control has budded a new cell, which already has its ID.
+    fn on_cell_born(&mut self, _world: &World, _cell: &Cell) {}
+
+    // When a cell has died, just before the world removes it.
+    fn on_cell_died(&mut self, _world: &World, _cell: &Cell) {}
+}
+
+pub type PluginFactory = fn() -> Box<dyn WorldPlugin>;
+
+// Plugin factories by name, so that a program can add the plugins named on its command line.
+// Crates outside this one, such as a lab's own extensions, make their plugins available by
+// registering factories for them.
+#[derive(Default)]
+pub struct PluginRegistry {
+    factories: BTreeMap<String, PluginFactory>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_plugin(mut self, name: &str, factory: PluginFactory) -> Self {
+        self.register(name, factory);
+        self
+    }
+
+    pub fn register(&mut self, name: &str, factory: PluginFactory) {
+        let previous = self.factories.insert(name.to_string(), factory);
+        assert!(previous.is_none(), "Duplicate plugin name: {}", name);
+    }
+
+    // In alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn WorldPlugin>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::quantities::*;
+
+    #[test]
+    fn registry_creates_plugins_by_name() {
+        let registry = PluginRegistry::new()
+            .with_plugin("darken", || Box::new(DarkenPlugin))
+            .with_plugin("noop", || Box::new(NoopPlugin));
+
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["darken", "noop"]);
+        assert!(registry.create("noop").is_some());
+        assert!(registry.create("other").is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn registry_rejects_duplicate_names() {
+        PluginRegistry::new()
+            .with_plugin("noop", || Box::new(NoopPlugin))
+            .with_plugin("noop", || Box::new(DarkenPlugin));
+    }
+
+    #[test]
+    fn created_plugin_runs_in_world() {
+        let registry = PluginRegistry::new().with_plugin("darken", || Box::new(DarkenPlugin));
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_sunlight(0.0, 1.0)
+            .with_plugin(registry.create("darken").unwrap());
+
+        world.tick();
+
+        assert!(!world.is_influence_enabled("sunlight"));
+    }
+
+    struct NoopPlugin;
+
+    impl WorldPlugin for NoopPlugin {}
+
+    struct DarkenPlugin;
+
+    impl WorldPlugin for DarkenPlugin {
+        fn before_tick(&mut self, world: &mut World) {
+            world.set_influence_enabled("sunlight", false);
+        }
+    }
+}
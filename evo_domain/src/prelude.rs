@@ -0,0 +1,52 @@
+// The types a program embedding the simulator needs to build a world, give its cells controls,
+// and run it: `use evo_domain::prelude::*;`. Everything here follows the crate's version: a
+// release that removes or changes any of it gets a new major version (or, before 1.0, a new
+// minor version). Items reached through the crate's modules instead can change in any release.
+
+pub use crate::biology::cell::{Cell, CellId, CellTag};
+pub use crate::biology::control::{
+    CellControl, CellLayerStateSnapshot, CellStateSnapshot, ContinuousRequestsControl,
+    ContinuousResizeControl, NullControl,
+};
+pub use crate::biology::control_requests::ControlRequest;
+pub use crate::biology::layers::{
+    BondingCellLayerSpecialty, CellLayer, CellLayerSpecialty, CiliaCellLayerSpecialty, Color,
+    ElongatorCellLayerSpecialty, LayerHealthParameters, LayerResizeParameters,
+    NullCellLayerSpecialty, ParasiteCellLayerSpecialty, PhotoCellLayerSpecialty,
+    PigmentCellLayerSpecialty, SensorCellLayerSpecialty, ThrusterCellLayerSpecialty,
+};
+pub use crate::document::WorldDocument;
+pub use crate::environment::influences::{Influence, TunableParameter};
+pub use crate::physics::newtonian::NewtonianBody;
+pub use crate::physics::quantities::*;
+pub use crate::physics::shapes::Circle;
+pub use crate::plugins::WorldPlugin;
+pub use crate::world::{TickReport, World};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prelude_builds_and_runs_world() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(
+                Cell::new(
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                    vec![CellLayer::new(
+                        Area::new(1.0),
+                        Density::new(1.0),
+                        Color::Green,
+                        Box::new(NullCellLayerSpecialty::new()),
+                    )],
+                )
+                .with_control(Box::new(NullControl::new())),
+            );
+
+        let report: TickReport = world.tick();
+
+        assert_eq!(report.births, 0);
+        assert_eq!(world.cells()[0].center(), Position::ORIGIN);
+    }
+}
@@ -0,0 +1,103 @@
+// A 64-bit FNV-1a hasher. Unlike the standard library's hasher, its hashes are the same on
+// every platform and Rust version, so they can be kept in golden files and compared between
+// runs.
+#[derive(Clone, Copy, Debug)]
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl StateHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        StateHasher {
+            hash: Self::OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    // Hashes the exact bits, so values that differ only in their last bit hash differently.
+    pub fn write_f64(&mut self, value: f64) {
+        self.write_u64(value.to_bits());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// Hashes of the parts of a world's state, kept separate so a difference can be traced to
+// the part it came from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StateHash {
+    // Cell IDs, positions, velocities, and headings
+    pub motion: u64,
+    pub energies: u64,
+    // Cell layers, with their parameters and specialties, adhesion tags, and control states
+    pub genomes: u64,
+    // Which cells are bonded, in which slots, and the energy each bond holds
+    pub bonds: u64,
+}
+
+impl StateHash {
+    pub fn combined(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(self.motion);
+        hasher.write_u64(self.energies);
+        hasher.write_u64(self.genomes);
+        hasher.write_u64(self.bonds);
+        hasher.finish()
+    }
+
+    // The names of the parts whose hashes differ from the other's.
+    pub fn differing_parts(&self, other: &StateHash) -> Vec<&'static str> {
+        let mut parts = vec![];
+        if self.motion != other.motion {
+            parts.push("motion");
+        }
+        if self.energies != other.energies {
+            parts.push("energies");
+        }
+        if self.genomes != other.genomes {
+            parts.push("genomes");
+        }
+        if self.bonds != other.bonds {
+            parts.push("bonds");
+        }
+        parts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hasher_matches_known_fnv_values() {
+        assert_eq!(StateHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut hasher = StateHasher::new();
+        hasher.write_bytes(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn hasher_distinguishes_nearby_floats() {
+        let mut hasher1 = StateHasher::new();
+        hasher1.write_f64(1.0);
+        let mut hasher2 = StateHasher::new();
+        hasher2.write_f64(1.0 + f64::EPSILON);
+        assert_ne!(hasher1.finish(), hasher2.finish());
+    }
+}
@@ -0,0 +1,228 @@
+// Stand-ins for influences, controls and layer specialties, for tests of how the world drives
+// them rather than of what real physics or biology does. They record their calls in a shared
+// CallLog that the test keeps after moving them into a world, and return canned outputs. With
+// the default outputs they add and remove no energy.
+
+use crate::biology::cell::Cell;
+use crate::biology::control::*;
+use crate::biology::control_requests::*;
+use crate::biology::layers::*;
+use crate::document::{InfluenceDocument, SpecialtyDocument};
+use crate::environment::influences::*;
+use crate::environment::local_environment::LocalEnvironment;
+use crate::physics::bond::*;
+use crate::physics::newtonian::NewtonianBody;
+use crate::physics::quantities::*;
+use crate::physics::sortable_graph::SortableGraph;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+
+// The calls made on test doubles, in order, shared by clones.
+#[derive(Clone, Debug, Default)]
+pub struct CallLog {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl CallLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    // The number of calls that start with the prefix, e.g. a method name.
+    pub fn count(&self, prefix: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|call| call.starts_with(prefix))
+            .count()
+    }
+}
+
+// Logs "before_tick {tick}" and "apply {cell count}", and adds its force, if any, to every cell.
+#[derive(Debug)]
+pub struct MockInfluence {
+    log: CallLog,
+    force: Force,
+    flux: EnergyFlux,
+    light_intensity: f64,
+}
+
+impl MockInfluence {
+    pub fn new(log: &CallLog) -> Self {
+        MockInfluence {
+            log: log.clone(),
+            force: Force::ZERO,
+            flux: EnergyFlux::ZERO,
+            light_intensity: 0.0,
+        }
+    }
+
+    pub fn with_force(mut self, force: Force) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_energy_flux(mut self, flux: EnergyFlux) -> Self {
+        self.flux = flux;
+        self
+    }
+
+    pub fn with_light_intensity(mut self, intensity: f64) -> Self {
+        self.light_intensity = intensity;
+        self
+    }
+}
+
+impl Influence for MockInfluence {
+    fn apply(&self, cell_graph: &mut SortableGraph<Cell, Bond, AngleGusset>) {
+        self.log
+            .record(format!("apply {}", cell_graph.nodes().len()));
+        if self.force != Force::ZERO {
+            for cell in cell_graph.nodes_mut() {
+                cell.forces_mut().add_force(self.force);
+            }
+        }
+    }
+
+    // A mock's only effect on a saved world would be its force.
+    fn to_document(&self) -> InfluenceDocument {
+        InfluenceDocument::ConstantForce {
+            force: [self.force.x(), self.force.y()],
+        }
+    }
+
+    fn before_tick(&mut self, tick: u64) {
+        self.log.record(format!("before_tick {}", tick));
+    }
+
+    fn light_intensity_at(&self, _position: Position) -> f64 {
+        self.light_intensity
+    }
+
+    fn energy_flux(&self, _cell_graph: &SortableGraph<Cell, Bond, AngleGusset>) -> EnergyFlux {
+        self.flux
+    }
+}
+
+// Logs "run {cell energy}" and "spawn", and returns its scripted requests, one batch per run,
+// then no requests once the script runs out. Spawned controls share the log but have no script.
+#[derive(Debug)]
+pub struct MockControl {
+    log: CallLog,
+    script: VecDeque<Vec<ControlRequest>>,
+}
+
+impl MockControl {
+    pub fn new(log: &CallLog) -> Self {
+        MockControl {
+            log: log.clone(),
+            script: VecDeque::new(),
+        }
+    }
+
+    pub fn with_requests(mut self, requests: Vec<ControlRequest>) -> Self {
+        self.script.push_back(requests);
+        self
+    }
+}
+
+impl CellControl for MockControl {
+    fn run(&mut self, cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+        self.log
+            .record(format!("run {}", cell_state.energy.value()));
+        self.script.pop_front().unwrap_or_default()
+    }
+
+    fn spawn(&mut self) -> Box<dyn CellControl> {
+        self.log.record("spawn".to_string());
+        Box::new(MockControl::new(&self.log))
+    }
+}
+
+// Accepts requests on every specialty channel at no cost, logging
+// "execute {channel} {allowed value}" for each, and returns its canned energy and force after
+// influences.
+#[derive(Debug)]
+pub struct MockSpecialty {
+    log: CallLog,
+    energy: BioEnergy,
+    force: Force,
+}
+
+impl MockSpecialty {
+    pub fn new(log: &CallLog) -> Self {
+        MockSpecialty {
+            log: log.clone(),
+            energy: BioEnergy::ZERO,
+            force: Force::ZERO,
+        }
+    }
+
+    pub fn with_after_influences(mut self, energy: BioEnergy, force: Force) -> Self {
+        self.energy = energy;
+        self.force = force;
+        self
+    }
+}
+
+impl CellLayerSpecialty for MockSpecialty {
+    fn box_spawn(&self) -> Box<dyn CellLayerSpecialty> {
+        Box::new(MockSpecialty::new(&self.log).with_after_influences(self.energy, self.force))
+    }
+
+    fn to_document(&self) -> SpecialtyDocument {
+        SpecialtyDocument::Null
+    }
+
+    fn after_influences(
+        &mut self,
+        _body: &CellLayerBody,
+        _env: &LocalEnvironment,
+    ) -> (BioEnergy, Force) {
+        self.log.record("after_influences".to_string());
+        (self.energy, self.force)
+    }
+
+    fn cost_control_request(&self, request: ControlRequest) -> CostedControlRequest {
+        CostedControlRequest::free(request)
+    }
+
+    fn execute_control_request(
+        &mut self,
+        _body: &CellLayerBody,
+        request: BudgetedControlRequest,
+        _bond_requests: &mut BondRequests,
+    ) {
+        self.log.record(format!(
+            "execute {} {}",
+            request.channel_index(),
+            request.allowed_value()
+        ));
+    }
+}
+
+// A ball of radius 1 and mass 1 with a mock control and a single mock layer, both logging to
+// the log.
+pub fn mock_cell(log: &CallLog, control: MockControl) -> Cell {
+    Cell::new(
+        Position::ORIGIN,
+        Velocity::ZERO,
+        vec![CellLayer::new(
+            Area::new(PI),
+            Density::new(1.0 / PI),
+            Color::Green,
+            Box::new(MockSpecialty::new(log)),
+        )],
+    )
+    .with_control(Box::new(control))
+}
@@ -0,0 +1,100 @@
+// Shortcuts for tests that build and run small worlds.
+
+use crate::biology::cell::Cell;
+use crate::environment::influences::*;
+use crate::physics::newtonian::NewtonianBody;
+use crate::physics::quantities::*;
+use crate::world::World;
+
+// A world builder with test defaults: a 200x200 world centered on the origin, with no
+// influences unless they are added.
+pub struct TestWorld {
+    world: World,
+}
+
+impl TestWorld {
+    pub fn new() -> Self {
+        Self::with_corners(Position::new(-100.0, -100.0), Position::new(100.0, 100.0))
+    }
+
+    pub fn with_corners(min_corner: Position, max_corner: Position) -> Self {
+        TestWorld {
+            world: World::new(min_corner, max_corner),
+        }
+    }
+
+    // A ball of radius 1 and mass 1.
+    pub fn with_ball(self, position: Position, velocity: Velocity) -> Self {
+        self.with_cell(Cell::ball(
+            Length::new(1.0),
+            Mass::new(1.0),
+            position,
+            velocity,
+        ))
+    }
+
+    pub fn with_cell(mut self, cell: Cell) -> Self {
+        self.world = self.world.with_cell(cell);
+        self
+    }
+
+    pub fn with_influence(mut self, influence: Box<dyn Influence>) -> Self {
+        self.world = self.world.with_influence(influence);
+        self
+    }
+
+    pub fn with_constant_force(self, force: Force) -> Self {
+        self.with_influence(Box::new(SimpleForceInfluence::new(Box::new(
+            ConstantForce::new(force),
+        ))))
+    }
+
+    pub fn with_pair_collisions(mut self) -> Self {
+        self.world = self.world.with_pair_collisions();
+        self
+    }
+
+    pub fn build(self) -> World {
+        self.world
+    }
+}
+
+pub fn tick_n(world: &mut World, num_ticks: usize) {
+    for _ in 0..num_ticks {
+        world.tick();
+    }
+}
+
+pub fn assert_cell_count(world: &World, expected: usize) {
+    assert_eq!(
+        world.cells().len(),
+        expected,
+        "cell count after {} ticks",
+        world.current_tick()
+    );
+}
+
+// Runs the world and checks that the cells' total kinetic energy stays within the relative
+// tolerance of its starting value, e.g. for worlds without drag, thrust or growth.
+pub fn assert_energy_conserved(world: &mut World, num_ticks: usize, relative_tolerance: f64) {
+    let start_energy = total_kinetic_energy(world);
+    for _ in 0..num_ticks {
+        world.tick();
+        let energy = total_kinetic_energy(world);
+        assert!(
+            (energy - start_energy).abs() <= relative_tolerance * start_energy,
+            "kinetic energy went from {} to {} at tick {}",
+            start_energy,
+            energy,
+            world.current_tick()
+        );
+    }
+}
+
+fn total_kinetic_energy(world: &World) -> f64 {
+    world
+        .cells()
+        .iter()
+        .map(|cell| 0.5 * cell.mass().value() * cell.velocity().value().dot_sqr())
+        .sum()
+}
@@ -0,0 +1,330 @@
+use crate::biology::control::CellControl;
+use crate::document::OrganismDocument;
+use crate::environment::influences::*;
+use crate::physics::quantities::*;
+use crate::world::World;
+
+// The arena that tournament entrants compete in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TournamentParameters {
+    pub min_corner: Position,
+    pub max_corner: Position,
+    pub min_light_intensity: f64,
+    pub max_light_intensity: f64,
+    pub gravity: f64,
+    pub fluid_density: f64,
+    pub viscosity: f64,
+    // If set, replaces the energy each entrant cell was exported with
+    pub initial_cell_energy: Option<f64>,
+    pub num_ticks: u64,
+}
+
+impl TournamentParameters {
+    pub const DEFAULT: TournamentParameters = TournamentParameters {
+        min_corner: Position::new(0.0, -400.0),
+        max_corner: Position::new(400.0, 0.0),
+        min_light_intensity: 0.0,
+        max_light_intensity: 1.0,
+        gravity: -0.05,
+        fluid_density: 0.001,
+        viscosity: 0.005,
+        initial_cell_energy: None,
+        num_ticks: 1000,
+    };
+}
+
+type ControlFactory = Box<dyn FnMut(usize) -> Box<dyn CellControl>>;
+
+// Runs exported organisms against each other in a fresh arena and reports how each fared.
+// Entrants are spaced evenly across the middle of the arena, in the order they were added.
+// Each entrant's cells, and their descendants, are told apart by lineage.
+pub struct Tournament {
+    parameters: TournamentParameters,
+    entrants: Vec<Entrant>,
+}
+
+struct Entrant {
+    name: String,
+    organism: OrganismDocument,
+    create_control: Option<ControlFactory>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentOutcome {
+    pub num_ticks: u64,
+    pub results: Vec<EntrantResult>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntrantResult {
+    pub name: String,
+    pub initial_cells: usize,
+    pub initial_energy: f64,
+    pub final_cells: usize,
+    pub final_energy: f64,
+    // The first tick after which the entrant had no living cells
+    pub extinct_at_tick: Option<u64>,
+}
+
+impl Tournament {
+    pub fn new(parameters: TournamentParameters) -> Self {
+        Tournament {
+            parameters,
+            entrants: vec![],
+        }
+    }
+
+    // The entrant's cells get NullControls, since organism documents don't include controls.
+    pub fn with_entrant(mut self, name: &str, organism: OrganismDocument) -> Self {
+        self.entrants.push(Entrant {
+            name: name.to_string(),
+            organism,
+            create_control: None,
+        });
+        self
+    }
+
+    // `create_control` gets the index of each of the entrant's cells in its document.
+    pub fn with_entrant_controls<F>(
+        mut self,
+        name: &str,
+        organism: OrganismDocument,
+        create_control: F,
+    ) -> Self
+    where
+        F: FnMut(usize) -> Box<dyn CellControl> + 'static,
+    {
+        self.entrants.push(Entrant {
+            name: name.to_string(),
+            organism,
+            create_control: Some(Box::new(create_control)),
+        });
+        self
+    }
+
+    pub fn run(mut self) -> TournamentOutcome {
+        assert!(!self.entrants.is_empty());
+        let mut world = self.create_arena();
+        self.add_entrants(&mut world);
+
+        let mut results: Vec<EntrantResult> = self
+            .entrants
+            .iter()
+            .enumerate()
+            .map(|(index, entrant)| {
+                let (cells, energy) = Self::census(&world, Self::lineage(index));
+                EntrantResult {
+                    name: entrant.name.clone(),
+                    initial_cells: cells,
+                    initial_energy: energy,
+                    final_cells: cells,
+                    final_energy: energy,
+                    extinct_at_tick: None,
+                }
+            })
+            .collect();
+
+        for tick in 1..=self.parameters.num_ticks {
+            world.tick();
+            for (index, result) in results.iter_mut().enumerate() {
+                let (cells, energy) = Self::census(&world, Self::lineage(index));
+                result.final_cells = cells;
+                result.final_energy = energy;
+                if cells == 0 && result.extinct_at_tick.is_none() {
+                    result.extinct_at_tick = Some(tick);
+                }
+            }
+        }
+
+        TournamentOutcome {
+            num_ticks: self.parameters.num_ticks,
+            results,
+        }
+    }
+
+    fn create_arena(&self) -> World {
+        let params = &self.parameters;
+        World::new(params.min_corner, params.max_corner)
+            .with_standard_influences()
+            .with_sunlight(params.min_light_intensity, params.max_light_intensity)
+            .with_influences(vec![
+                Box::new(SimpleForceInfluence::new(Box::new(WeightForce::new(
+                    params.gravity,
+                )))),
+                Box::new(SimpleForceInfluence::new(Box::new(BuoyancyForce::new(
+                    params.gravity,
+                    params.fluid_density,
+                )))),
+                Box::new(SimpleForceInfluence::new(Box::new(DragForce::new(
+                    params.viscosity,
+                )))),
+            ])
+    }
+
+    fn add_entrants(&mut self, world: &mut World) {
+        let params = self.parameters;
+        let spacing =
+            (params.max_corner.x() - params.min_corner.x()) / (self.entrants.len() + 1) as f64;
+        let y = (params.min_corner.y() + params.max_corner.y()) / 2.0;
+        for (index, entrant) in self.entrants.iter_mut().enumerate() {
+            let mut organism = entrant.organism.clone();
+            for cell in &mut organism.cells {
+                cell.lineage = Self::lineage(index);
+                if let Some(energy) = params.initial_cell_energy {
+                    cell.energy = energy;
+                }
+            }
+            let position = Position::new(params.min_corner.x() + (index + 1) as f64 * spacing, y);
+            match &mut entrant.create_control {
+                Some(create_control) => {
+                    world.graft_with_controls(&organism, position, Angle::ZERO, create_control);
+                }
+                None => {
+                    world.graft(&organism, position, Angle::ZERO);
+                }
+            }
+        }
+    }
+
+    // Lineage zero means none, so entrants start at one.
+    fn lineage(entrant_index: usize) -> u32 {
+        entrant_index as u32 + 1
+    }
+
+    fn census(world: &World, lineage: u32) -> (usize, f64) {
+        world
+            .cells()
+            .iter()
+            .filter(|cell| cell.lineage() == lineage)
+            .fold((0, 0.0), |(cells, energy), cell| {
+                (cells + 1, energy + cell.energy().value())
+            })
+    }
+}
+
+impl TournamentOutcome {
+    // The entrant with the most cells at the end, with ties going to the most energy. A NaN
+    // energy, as from a blown-up cell, ranks below every real energy.
+    pub fn winner(&self) -> &EntrantResult {
+        let energy = |result: &EntrantResult| {
+            if result.final_energy.is_nan() {
+                f64::NEG_INFINITY
+            } else {
+                result.final_energy
+            }
+        };
+        self.results
+            .iter()
+            .max_by(|result1, result2| {
+                result1
+                    .final_cells
+                    .cmp(&result2.final_cells)
+                    .then(energy(result1).total_cmp(&energy(result2)))
+            })
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::cell::Cell;
+    use crate::biology::layers::*;
+    use crate::physics::shapes::Circle;
+    use crate::physics::sortable_graph::GraphNode;
+
+    #[test]
+    fn tournament_reports_each_entrant() {
+        let outcome = Tournament::new(TournamentParameters {
+            num_ticks: 10,
+            ..TournamentParameters::DEFAULT
+        })
+        .with_entrant("big", organism(5.0, 20.0))
+        .with_entrant("small", organism(1.0, 20.0))
+        .run();
+
+        assert_eq!(outcome.num_ticks, 10);
+        assert_eq!(outcome.results.len(), 2);
+        assert_eq!(outcome.results[0].name, "big");
+        assert_eq!(outcome.results[0].initial_cells, 1);
+        assert_eq!(outcome.results[0].initial_energy, 20.0);
+        assert_eq!(outcome.results[1].final_cells, 1);
+    }
+
+    #[test]
+    fn bigger_photosynthesizer_wins() {
+        let outcome = Tournament::new(TournamentParameters {
+            num_ticks: 10,
+            ..TournamentParameters::DEFAULT
+        })
+        .with_entrant("small", organism(1.0, 0.0))
+        .with_entrant("big", organism(5.0, 0.0))
+        .run();
+
+        assert_eq!(outcome.winner().name, "big");
+    }
+
+    #[test]
+    fn nan_energy_loses_ties() {
+        let result = |name: &str, final_energy| EntrantResult {
+            name: name.to_string(),
+            initial_cells: 1,
+            initial_energy: 1.0,
+            final_cells: 2,
+            final_energy,
+            extinct_at_tick: None,
+        };
+        let outcome = TournamentOutcome {
+            num_ticks: 10,
+            results: vec![result("blown_up", f64::NAN), result("healthy", 1.0)],
+        };
+
+        assert_eq!(outcome.winner().name, "healthy");
+    }
+
+    #[test]
+    fn initial_cell_energy_overrides_exported_energy() {
+        let outcome = Tournament::new(TournamentParameters {
+            num_ticks: 0,
+            initial_cell_energy: Some(7.0),
+            ..TournamentParameters::DEFAULT
+        })
+        .with_entrant("only", organism(1.0, 20.0))
+        .run();
+
+        assert_eq!(outcome.results[0].initial_energy, 7.0);
+    }
+
+    #[test]
+    fn dead_entrant_goes_extinct() {
+        let mut document = organism(1.0, 0.0);
+        document.cells[0].layers[0].health = 0.0;
+
+        let outcome = Tournament::new(TournamentParameters {
+            num_ticks: 3,
+            ..TournamentParameters::DEFAULT
+        })
+        .with_entrant("dead", document)
+        .run();
+
+        assert_eq!(outcome.results[0].final_cells, 0);
+        assert_eq!(outcome.results[0].extinct_at_tick, Some(1));
+    }
+
+    fn organism(radius: f64, energy: f64) -> OrganismDocument {
+        let cell = Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![CellLayer::new(
+                Area::new(std::f64::consts::PI * radius * radius),
+                Density::new(0.001),
+                Color::Green,
+                Box::new(PhotoCellLayerSpecialty::new(0.1)),
+            )],
+        )
+        .with_initial_energy(BioEnergy::new(energy));
+        assert_eq!(cell.radius(), Length::new(radius));
+        let world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(cell);
+        world.export_organism(world.cells()[0].node_handle())
+    }
+}
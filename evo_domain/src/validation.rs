@@ -0,0 +1,739 @@
+use crate::biology::adhesion::AdhesionRule;
+use crate::biology::tissue_multipliers::TissueMultipliers;
+use crate::document::*;
+use crate::environment::influences::Schedule;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+// A problem found in a world file, with where it was found: the path of the field, e.g.
+// "cells[2].layers[0].density", and the line the field starts on, when known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigProblem {
+    pub line: Option<usize>,
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        if !self.field.is_empty() {
+            write!(f, "{}: ", self.field)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+// Parses a world file, checking it for the problems that would otherwise panic in the middle
+// of building the world, e.g. unknown influence types, out-of-range parameters, and cells with
+// no area. Reports every problem found rather than stopping at the first.
+pub fn validate_world_json(json: &str) -> Result<WorldDocument, Vec<ConfigProblem>> {
+    let value: Value = serde_json::from_str(json).map_err(|err| {
+        vec![ConfigProblem {
+            line: None,
+            field: String::new(),
+            message: err.to_string(),
+        }]
+    })?;
+    let mut checker = Checker::new(json);
+    checker.check_world(&value);
+    if !checker.problems.is_empty() {
+        return Err(checker.problems);
+    }
+    serde_json::from_value(value).map_err(|err| {
+        vec![ConfigProblem {
+            line: None,
+            field: String::new(),
+            message: err.to_string(),
+        }]
+    })
+}
+
+struct Checker {
+    problems: Vec<ConfigProblem>,
+    lines: HashMap<String, usize>,
+}
+
+impl Checker {
+    fn new(json: &str) -> Self {
+        Checker {
+            problems: vec![],
+            lines: LineIndexer::index(json),
+        }
+    }
+
+    fn report(&mut self, field: String, message: String) {
+        let line = self.line_of(&field);
+        self.problems.push(ConfigProblem {
+            line,
+            field,
+            message,
+        });
+    }
+
+    // The line of the field, or of the nearest enclosing field that has one, e.g. for a missing
+    // field.
+    fn line_of(&self, field: &str) -> Option<usize> {
+        let mut path = field;
+        loop {
+            if let Some(&line) = self.lines.get(path) {
+                return Some(line);
+            }
+            match path.rfind(['.', '[']) {
+                Some(index) => path = &path[..index],
+                None => return None,
+            }
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(&mut self, field: String, value: Option<&Value>) -> Option<T> {
+        let value = match value {
+            Some(value) => value,
+            None => {
+                self.report(field, "missing".to_string());
+                return None;
+            }
+        };
+        match serde_json::from_value(value.clone()) {
+            Ok(parsed) => Some(parsed),
+            Err(err) => {
+                let message = err.to_string();
+                let field = if message.starts_with("unknown variant") {
+                    format!("{}.type", field)
+                } else {
+                    field
+                };
+                self.report(field, message);
+                None
+            }
+        }
+    }
+
+    fn check_world(&mut self, value: &Value) {
+        let world = match value.as_object() {
+            Some(world) => world,
+            None => {
+                self.report(String::new(), "expected a JSON object".to_string());
+                return;
+            }
+        };
+
+        if world.get("version").and_then(Value::as_u64) != Some(WorldDocument::VERSION as u64) {
+            self.report(
+                "version".to_string(),
+                format!("expected {}", WorldDocument::VERSION),
+            );
+        }
+        let min_corner: Option<[f64; 2]> =
+            self.parse("min_corner".to_string(), world.get("min_corner"));
+        let max_corner: Option<[f64; 2]> =
+            self.parse("max_corner".to_string(), world.get("max_corner"));
+        if let (Some(min_corner), Some(max_corner)) = (min_corner, max_corner) {
+            self.check_corners("max_corner".to_string(), min_corner, max_corner);
+        }
+
+        for (index, influence) in self.elements(world.get("influences"), "influences") {
+            let field = format!("influences[{}]", index);
+            if let Some(influence) = self.parse(field.clone(), Some(&influence)) {
+                self.check_influence(&field, &influence);
+            }
+        }
+
+        let cells = self.elements(world.get("cells"), "cells");
+        for (index, cell) in &cells {
+            let field = format!("cells[{}]", index);
+            if let Some(cell) = self.parse(field.clone(), Some(cell)) {
+                self.check_cell(&field, &cell);
+            }
+        }
+
+        let bonds = self.elements(world.get("bonds"), "bonds");
+        for (index, bond) in &bonds {
+            let field = format!("bonds[{}]", index);
+            if let Some(bond) = self.parse::<BondDocument>(field.clone(), Some(bond)) {
+                self.check_index(format!("{}.cell1", field), bond.cell1, cells.len(), "cell");
+                self.check_index(format!("{}.cell2", field), bond.cell2, cells.len(), "cell");
+                if bond.cell1 == bond.cell2 {
+                    self.report(field, "bonds a cell to itself".to_string());
+                }
+            }
+        }
+
+        for (index, gusset) in self.elements(world.get("angle_gussets"), "angle_gussets") {
+            let field = format!("angle_gussets[{}]", index);
+            if let Some(gusset) = self.parse::<AngleGussetDocument>(field.clone(), Some(&gusset)) {
+                self.check_index(
+                    format!("{}.bond1", field),
+                    gusset.bond1,
+                    bonds.len(),
+                    "bond",
+                );
+                self.check_index(
+                    format!("{}.bond2", field),
+                    gusset.bond2,
+                    bonds.len(),
+                    "bond",
+                );
+            }
+        }
+
+        if let Some(multipliers) = world.get("tissue_multipliers") {
+            let field = "tissue_multipliers".to_string();
+            if let Some(multipliers) =
+                self.parse::<TissueMultipliers>(field.clone(), Some(multipliers))
+            {
+                for (color, multiplier) in multipliers.iter() {
+                    self.non_negative(
+                        format!("{}.{:?}.energy_gain", field, color),
+                        multiplier.energy_gain,
+                    );
+                    self.non_negative(format!("{}.{:?}.cost", field, color), multiplier.cost);
+                }
+            }
+        }
+
+        if let Some(rule) = world.get("adhesion_rule").filter(|rule| !rule.is_null()) {
+            let field = "adhesion_rule".to_string();
+            if let Some(rule) = self.parse::<AdhesionRule>(field.clone(), Some(rule)) {
+                self.fraction(format!("{}.bit_flip_rate", field), rule.bit_flip_rate);
+            }
+        }
+    }
+
+    fn elements(&mut self, value: Option<&Value>, field: &str) -> Vec<(usize, Value)> {
+        match value.map(Value::as_array) {
+            Some(Some(elements)) => elements.iter().cloned().enumerate().collect(),
+            Some(None) => {
+                self.report(field.to_string(), "expected an array".to_string());
+                vec![]
+            }
+            None => {
+                self.report(field.to_string(), "missing".to_string());
+                vec![]
+            }
+        }
+    }
+
+    fn check_influence(&mut self, field: &str, influence: &InfluenceDocument) {
+        match influence {
+            InfluenceDocument::WallCollisions {
+                min_corner,
+                max_corner,
+                static_friction,
+                kinetic_friction,
+            } => {
+                self.check_corners(format!("{}.max_corner", field), *min_corner, *max_corner);
+                self.non_negative(format!("{}.static_friction", field), *static_friction);
+                self.non_negative(format!("{}.kinetic_friction", field), *kinetic_friction);
+            }
+            InfluenceDocument::ElasticBondForces {
+                spring_constant,
+                loss_factor,
+            } => {
+                self.positive(format!("{}.spring_constant", field), *spring_constant);
+                self.fraction(format!("{}.loss_factor", field), *loss_factor);
+            }
+            InfluenceDocument::DragForce { viscosity, .. } => {
+                self.non_negative(format!("{}.viscosity", field), *viscosity);
+            }
+            InfluenceDocument::ThermalNoise { magnitude, .. } => {
+                self.non_negative(format!("{}.magnitude", field), *magnitude);
+            }
+            InfluenceDocument::EnergyTax { rate } if !(0.0..1.0).contains(rate) => {
+                self.report(
+                    format!("{}.rate", field),
+                    format!("must be at least 0 and less than 1, not {}", rate),
+                );
+            }
+            InfluenceDocument::Sunlight { shading, .. } => {
+                self.fraction(format!("{}.shading", field), *shading);
+            }
+            InfluenceDocument::LightPatch {
+                min_corner,
+                max_corner,
+                ..
+            } => {
+                self.check_corners(format!("{}.max_corner", field), *min_corner, *max_corner);
+            }
+            InfluenceDocument::Scheduled {
+                influence,
+                parameter,
+                schedule,
+                ..
+            } => {
+                let num_problems = self.problems.len();
+                self.check_influence(&format!("{}.influence", field), influence);
+                // the inner influence can only be built to ask it if it has no problems
+                if self.problems.len() == num_problems
+                    && influence
+                        .to_influence()
+                        .tunable_parameter(*parameter)
+                        .is_none()
+                {
+                    self.report(
+                        format!("{}.parameter", field),
+                        format!("{:?} is not a parameter of the inner influence", parameter),
+                    );
+                }
+                self.check_schedule(&format!("{}.schedule", field), schedule);
+            }
+            _ => {}
+        }
+    }
+
+    fn check_schedule(&mut self, field: &str, schedule: &Schedule) {
+        match *schedule {
+            Schedule::Sine { period_ticks, .. } => {
+                self.positive(format!("{}.period_ticks", field), period_ticks);
+            }
+            Schedule::Pulses {
+                period_ticks: 0, ..
+            } => {
+                self.report(
+                    format!("{}.period_ticks", field),
+                    "must be more than 0".to_string(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn check_cell(&mut self, field: &str, cell: &CellDocument) {
+        self.non_negative(format!("{}.energy", field), cell.energy);
+        if cell.layers.is_empty() {
+            self.report(format!("{}.layers", field), "has no layers".to_string());
+        } else if cell.layers.iter().map(|layer| layer.area).sum::<f64>() <= 0.0 {
+            self.report(
+                format!("{}.layers", field),
+                "layers have no total area".to_string(),
+            );
+        }
+        if let Some(index) = cell.neural_layer_index {
+            self.check_index(
+                format!("{}.neural_layer_index", field),
+                index,
+                cell.layers.len(),
+                "layer",
+            );
+        }
+        for (index, layer) in cell.layers.iter().enumerate() {
+            self.check_layer(&format!("{}.layers[{}]", field, index), layer);
+        }
+    }
+
+    fn check_layer(&mut self, field: &str, layer: &LayerDocument) {
+        self.non_negative(format!("{}.area", field), layer.area);
+        self.positive(format!("{}.density", field), layer.density);
+        self.fraction(format!("{}.health", field), layer.health);
+
+        let health = &layer.health_parameters;
+        let health_field = format!("{}.health_parameters", field);
+        self.non_positive(
+            format!("{}.healing_energy_delta", health_field),
+            health.healing_energy_delta,
+        );
+        self.non_positive(
+            format!("{}.entropic_damage_health_delta", health_field),
+            health.entropic_damage_health_delta,
+        );
+        self.non_positive(
+            format!("{}.overlap_damage_health_delta", health_field),
+            health.overlap_damage_health_delta,
+        );
+
+        let resize = &layer.resize_parameters;
+        let resize_field = format!("{}.resize_parameters", field);
+        self.non_positive(
+            format!("{}.growth_energy_delta", resize_field),
+            resize.growth_energy_delta,
+        );
+        if let Some(max_growth_rate) = resize.max_growth_rate {
+            self.non_negative(format!("{}.max_growth_rate", resize_field), max_growth_rate);
+        }
+        self.non_negative(
+            format!("{}.max_shrinkage_rate", resize_field),
+            resize.max_shrinkage_rate,
+        );
+
+        let specialty_field = format!("{}.specialty", field);
+        match layer.specialty {
+            SpecialtyDocument::Thruster {
+                energy_per_thrust_sqr,
+            } => {
+                self.non_negative(
+                    format!("{}.energy_per_thrust_sqr", specialty_field),
+                    energy_per_thrust_sqr,
+                );
+            }
+            SpecialtyDocument::Cilia {
+                max_thrust_per_area,
+                energy_per_thrust_sqr,
+            } => {
+                self.non_negative(
+                    format!("{}.max_thrust_per_area", specialty_field),
+                    max_thrust_per_area,
+                );
+                self.non_negative(
+                    format!("{}.energy_per_thrust_sqr", specialty_field),
+                    energy_per_thrust_sqr,
+                );
+            }
+            SpecialtyDocument::Parasite {
+                siphon_rate,
+                shell_resistance,
+            } => {
+                self.non_negative(format!("{}.siphon_rate", specialty_field), siphon_rate);
+                self.non_negative(
+                    format!("{}.shell_resistance", specialty_field),
+                    shell_resistance,
+                );
+            }
+            SpecialtyDocument::Sensor { range, .. } => {
+                self.positive(format!("{}.range", specialty_field), range);
+            }
+            SpecialtyDocument::Elongator {
+                max_length,
+                energy_per_length,
+                length,
+            } => {
+                self.non_negative(format!("{}.max_length", specialty_field), max_length);
+                self.non_negative(
+                    format!("{}.energy_per_length", specialty_field),
+                    energy_per_length,
+                );
+                if !(0.0..=max_length).contains(&length) {
+                    self.report(
+                        format!("{}.length", specialty_field),
+                        format!("must be from 0 to max_length, not {}", length),
+                    );
+                }
+            }
+            SpecialtyDocument::Pigment {
+                efficiency,
+                energy_per_change,
+                ..
+            } => {
+                self.non_negative(format!("{}.efficiency", specialty_field), efficiency);
+                self.non_negative(
+                    format!("{}.energy_per_change", specialty_field),
+                    energy_per_change,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn check_corners(&mut self, field: String, min_corner: [f64; 2], max_corner: [f64; 2]) {
+        if min_corner[0] > max_corner[0] || min_corner[1] > max_corner[1] {
+            self.report(field, "is below or left of min_corner".to_string());
+        }
+    }
+
+    fn check_index(&mut self, field: String, index: usize, count: usize, kind: &str) {
+        if index >= count {
+            self.report(
+                field,
+                format!("no {} {}: there are only {}", kind, index, count),
+            );
+        }
+    }
+
+    fn positive(&mut self, field: String, value: f64) {
+        if value.is_nan() || value <= 0.0 {
+            self.report(field, format!("must be more than 0, not {}", value));
+        }
+    }
+
+    fn non_negative(&mut self, field: String, value: f64) {
+        if value.is_nan() || value < 0.0 {
+            self.report(field, format!("must not be negative, not {}", value));
+        }
+    }
+
+    fn non_positive(&mut self, field: String, value: f64) {
+        if value.is_nan() || value > 0.0 {
+            self.report(field, format!("must not be positive, not {}", value));
+        }
+    }
+
+    fn fraction(&mut self, field: String, value: f64) {
+        if !(0.0..=1.0).contains(&value) {
+            self.report(field, format!("must be from 0 to 1, not {}", value));
+        }
+    }
+}
+
+// Finds the line each value in a JSON text starts on, keyed by the value's path, e.g.
+// "cells[0].layers[1].area". The text must already be known to be valid JSON.
+struct LineIndexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+    lines: HashMap<String, usize>,
+}
+
+impl<'a> LineIndexer<'a> {
+    fn index(json: &'a str) -> HashMap<String, usize> {
+        let mut indexer = LineIndexer {
+            bytes: json.as_bytes(),
+            pos: 0,
+            line: 1,
+            lines: HashMap::new(),
+        };
+        indexer.scan_value(String::new());
+        indexer.lines
+    }
+
+    fn scan_value(&mut self, path: String) {
+        self.skip_whitespace();
+        self.lines.insert(path.clone(), self.line);
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                while self.next_element(b'}') {
+                    let key = self.scan_string();
+                    self.skip_whitespace();
+                    self.pos += 1; // the colon
+                    let child = if path.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    self.scan_value(child);
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut index = 0;
+                while self.next_element(b']') {
+                    self.scan_value(format!("{}[{}]", path, index));
+                    index += 1;
+                }
+            }
+            Some(b'"') => {
+                self.scan_string();
+            }
+            Some(_) => {
+                while let Some(byte) = self.peek() {
+                    if b",]} \t\r\n".contains(&byte) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            }
+            None => {}
+        }
+    }
+
+    // Skips to the next element of an object or array, returning false, after skipping the
+    // closing bracket, if there are no more.
+    fn next_element(&mut self, close: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+        match self.peek() {
+            Some(byte) if byte == close => {
+                self.pos += 1;
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn scan_string(&mut self) -> String {
+        self.pos += 1; // the opening quote
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        let string = String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned();
+        self.pos += 1; // the closing quote
+        string
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'\n' => self.line += 1,
+                b' ' | b'\t' | b'\r' => {}
+                _ => return,
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::cell::Cell;
+    use crate::environment::influences::*;
+    use crate::physics::quantities::*;
+    use crate::world::World;
+
+    #[test]
+    fn valid_world_passes() {
+        let world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_standard_influences()
+            .with_influence(Box::new(EnergyTax::new(0.1)))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ));
+        let document = world.to_document();
+
+        assert_eq!(validate_world_json(&document.to_json()), Ok(document));
+    }
+
+    #[test]
+    fn reports_all_problems_with_lines() {
+        let json = r#"{
+  "version": 1,
+  "min_corner": [-10.0, -10.0],
+  "max_corner": [10.0, 10.0],
+  "influences": [
+    {"type": "PairCollisions"},
+    {"type": "Gravity", "gravity": 9.8},
+    {"type": "EnergyTax", "rate": 1.5}
+  ],
+  "cells": [
+    {
+      "position": [0.0, 0.0],
+      "velocity": [0.0, 0.0],
+      "energy": 0.0,
+      "layers": []
+    }
+  ],
+  "bonds": [],
+  "angle_gussets": []
+}"#;
+
+        let problems = validate_world_json(json).unwrap_err();
+
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| (problem.line, problem.field.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(7), "influences[1].type"),
+                (Some(8), "influences[2].rate"),
+                (Some(15), "cells[0].layers"),
+            ]
+        );
+        assert!(problems[0].message.contains("Gravity"));
+    }
+
+    #[test]
+    fn reports_unsupported_scheduled_parameters_and_zero_pulse_periods() {
+        let mut document =
+            World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0)).to_document();
+        document.influences = vec![InfluenceDocument::Scheduled {
+            influence: Box::new(InfluenceDocument::PairCollisions),
+            parameter: TunableParameter::Gravity,
+            base_value: 1.0,
+            schedule: Schedule::Pulses {
+                first_tick: 0,
+                period_ticks: 0,
+                duration_ticks: 1,
+                pulse_factor: 1.0,
+                rest_factor: 0.0,
+            },
+        }];
+
+        let problems = validate_world_json(&document.to_json()).unwrap_err();
+
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| problem.field.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "influences[0].parameter",
+                "influences[0].schedule.period_ticks"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_out_of_range_layer_parameters() {
+        let mut document = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ))
+            .to_document();
+        document.cells[0].layers[0].density = 0.0;
+        document.cells[0].layers[0].health = 2.0;
+
+        let problems = validate_world_json(&document.to_json()).unwrap_err();
+
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| problem.field.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cells[0].layers[0].density", "cells[0].layers[0].health"]
+        );
+    }
+
+    #[test]
+    fn reports_negative_tissue_multipliers() {
+        let json = r#"{
+  "version": 1,
+  "min_corner": [-10.0, -10.0],
+  "max_corner": [10.0, 10.0],
+  "influences": [],
+  "cells": [],
+  "bonds": [],
+  "angle_gussets": [],
+  "tissue_multipliers": {
+    "Green": {"energy_gain": -1.0, "cost": 1.0}
+  }
+}"#;
+
+        let problems = validate_world_json(json).unwrap_err();
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].field, "tissue_multipliers.Green.energy_gain");
+        assert_eq!(problems[0].line, Some(10));
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        let problems = validate_world_json("{\n  \"version\": 1,\n}").unwrap_err();
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("line 3"));
+    }
+
+    #[test]
+    fn reports_missing_fields_at_enclosing_line() {
+        let json = "{\n  \"version\": 1,\n  \"min_corner\": [0.0, 0.0],\n  \"max_corner\": [1.0, 1.0],\n  \"influences\": [],\n  \"cells\": [\n    {\"position\": [0.0, 0.0]}\n  ],\n  \"bonds\": [],\n  \"angle_gussets\": []\n}";
+
+        let problems = validate_world_json(json).unwrap_err();
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, Some(7));
+        assert_eq!(problems[0].field, "cells[0]");
+    }
+}
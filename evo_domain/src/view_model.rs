@@ -0,0 +1,167 @@
+use crate::biology::layers::Color;
+use crate::document::from_position;
+use crate::physics::newtonian::NewtonianBody;
+use crate::physics::shapes::Circle;
+use crate::physics::sortable_graph::GraphEdge;
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+
+// What a view shows of a world at one tick, independent of any graphics library, for
+// frontends such as a browser or a terminal that read frames as JSON lines. Cell indexes are
+// positions in `cells`. Frames only describe the world; they cannot change it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViewFrame {
+    pub version: u32,
+    pub tick: u64,
+    pub min_corner: [f64; 2],
+    pub max_corner: [f64; 2],
+    // Rectangles that cells are kept inside, as [min x, min y, max x, max y]
+    pub walls: Vec<[f64; 4]>,
+    pub cells: Vec<CellView>,
+    // Pairs of bonded cell indexes
+    pub bonds: Vec<[usize; 2]>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellView {
+    pub id: u64,
+    pub center: [f64; 2],
+    // From the center to one end of a capsule's axis; zero for a round cell
+    #[serde(default)]
+    pub half_axis: [f64; 2],
+    pub velocity: [f64; 2],
+    pub energy: f64,
+    pub lineage: u32,
+    pub selected: bool,
+    // Innermost first
+    pub layers: Vec<LayerView>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayerView {
+    pub outer_radius: f64,
+    pub health: f64,
+    pub color: Color,
+}
+
+impl ViewFrame {
+    // Frontends should reject frames with a different version.
+    pub const VERSION: u32 = 1;
+
+    pub fn from_world(world: &World) -> Self {
+        ViewFrame {
+            version: Self::VERSION,
+            tick: world.current_tick(),
+            min_corner: from_position(world.min_corner()),
+            max_corner: from_position(world.max_corner()),
+            walls: world
+                .walls()
+                .iter()
+                .map(|walls| {
+                    [
+                        walls.min_corner().x(),
+                        walls.min_corner().y(),
+                        walls.max_corner().x(),
+                        walls.max_corner().y(),
+                    ]
+                })
+                .collect(),
+            cells: world
+                .cells()
+                .iter()
+                .map(|cell| CellView {
+                    id: cell.id().value(),
+                    center: from_position(cell.center()),
+                    half_axis: [cell.half_axis().x(), cell.half_axis().y()],
+                    velocity: [cell.velocity().x(), cell.velocity().y()],
+                    energy: cell.energy().value(),
+                    lineage: cell.lineage(),
+                    selected: cell.is_selected(),
+                    layers: cell
+                        .layers()
+                        .iter()
+                        .map(|layer| LayerView {
+                            outer_radius: layer.outer_radius().value(),
+                            health: layer.health(),
+                            color: layer.visible_color(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            bonds: world
+                .bonds()
+                .iter()
+                .map(|bond| [bond.node1_handle().index(), bond.node2_handle().index()])
+                .collect(),
+        }
+    }
+
+    // One line of JSON, so that a stream of frames can be split on newlines.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+impl CellView {
+    pub fn radius(&self) -> f64 {
+        self.layers.last().map_or(0.0, |layer| layer.outer_radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::cell::Cell;
+    use crate::physics::quantities::*;
+
+    #[test]
+    fn frame_describes_cells_bonds_and_walls() {
+        let world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_perimeter_walls()
+            .with_cells(vec![
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::new(-1.0, 0.0),
+                    Velocity::ZERO,
+                ),
+                Cell::ball(
+                    Length::new(2.0),
+                    Mass::new(1.0),
+                    Position::new(2.0, 0.0),
+                    Velocity::ZERO,
+                ),
+            ])
+            .with_bonds(vec![(0, 1)]);
+
+        let frame = ViewFrame::from_world(&world);
+
+        assert_eq!(frame.walls, vec![[-10.0, -10.0, 10.0, 10.0]]);
+        assert_eq!(frame.cells.len(), 2);
+        assert_eq!(frame.cells[1].center, [2.0, 0.0]);
+        assert!((frame.cells[1].radius() - 2.0).abs() < 1e-9);
+        assert_eq!(frame.bonds, vec![[0, 1]]);
+    }
+
+    #[test]
+    fn frame_round_trips_through_json_line() {
+        let world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0)).with_cell(
+            Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::new(1.0, 0.0),
+            ),
+        );
+        let frame = ViewFrame::from_world(&world);
+
+        let json = frame.to_json_line();
+
+        assert!(!json.contains('\n'));
+        assert_eq!(ViewFrame::from_json(&json).unwrap(), frame);
+    }
+}
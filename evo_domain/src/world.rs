@@ -1,29 +1,166 @@
-use crate::biology::cell::Cell;
+use crate::biology::adhesion::AdhesionRule;
+use crate::biology::cell::{Cell, CellId, ChildPlacementStrategy, ControlLod, Spore};
 use crate::biology::changes::*;
+use crate::biology::control::*;
+use crate::biology::control_requests::EnergyBudget;
+use crate::biology::genealogy::Genealogy;
+use crate::biology::invasion::Invasion;
 use crate::biology::layers::*;
+use crate::biology::selection::*;
+use crate::biology::tissue_multipliers::TissueMultipliers;
+use crate::document::*;
 use crate::environment::influences::*;
 use crate::environment::local_environment::*;
+use crate::environment::probes::*;
+use crate::environment::trajectories::TrajectoryRecorder;
+use crate::events::*;
+use crate::parallelism::Parallelism;
 use crate::physics::bond::*;
 use crate::physics::newtonian::NewtonianBody;
+use crate::physics::overlap::Walls;
 use crate::physics::quantities::*;
+use crate::physics::shapes::{closest_axis_points, Circle};
 use crate::physics::sortable_graph::*;
-use std::collections::HashSet;
-use std::iter::FromIterator;
+use crate::physics::spatial_queries::*;
+use crate::plugins::WorldPlugin;
+use crate::state_hash::*;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::time::Instant;
 
 pub struct World {
     min_corner: Position,
     max_corner: Position,
     cell_graph: SortableGraph<Cell, Bond, AngleGusset>,
-    influences: Vec<Box<dyn Influence>>,
+    influences: Vec<WorldInfluence>,
+    current_tick: u64,
+    scheduled_culls: Vec<ScheduledCull>,
+    scheduled_invasions: Vec<ScheduledInvasion>,
+    event_log: EventLog,
+    probes: Vec<Probe>,
+    trajectory_recorder: Option<TrajectoryRecorder>,
+    genealogy: Option<Genealogy>,
+    next_cell_id: u64,
+    // The energy the influences added and removed during the last tick
+    energy_flux: EnergyFlux,
+    parallelism: Parallelism,
+    fast_math: bool,
+    // The mutation rate last set on the cells' controls, if any, which cells added later get too
+    mutation_rate: Option<f64>,
+    finite_checks: bool,
+    // The state hashes after each phase of the last tick, if they are being recorded
+    phase_hashes: Option<Vec<(&'static str, StateHash)>>,
+    parasite_attachments: Vec<ParasiteAttachment>,
+    population_monitor: Option<PopulationMonitor>,
+    control_lod: Option<ControlLod>,
+    child_placement: ChildPlacementStrategy,
+    // The fraction of the energy donated through a bond that reaches the other cell
+    donation_efficiency: f64,
+    // The fraction of the energy waiting in bonds that is lost every tick
+    bond_energy_decay: f64,
+    tissue_multipliers: TissueMultipliers,
+    adhesion_rule: Option<AdhesionRule>,
+    resource_limits: ResourceLimits,
+    churn_cap: Option<ChurnCap>,
+    // Compact when fewer than this fraction of the cell graph's capacity is in use
+    auto_compaction: Option<f64>,
+    blowup_bounds: Option<BlowupBounds>,
+    // The last tick's blowup, if it had one
+    blowup: Option<Blowup>,
+    // The lineages of the cells born and died during the last tick, or since it if they were
+    // killed between ticks
+    births: Vec<u32>,
+    deaths: Vec<u32>,
+    breakpoints: Vec<Breakpoint>,
+    // The breakpoint conditions that held at the end of the last tick, so that each one is hit
+    // only when it starts holding
+    held_breakpoints: Vec<BreakpointHit>,
+    selected_cell_deaths: Vec<CellId>,
+    // Counts for the last tick's report
+    bonds_formed: usize,
+    bonds_broken: usize,
+    wall_collisions: usize,
+    // The bond changes beyond the churn cap, made or dropped
+    churn_overflow: usize,
+    plugins: Vec<Box<dyn WorldPlugin>>,
+    // Scratch buffers reused every tick. The work lists handed to the parallelism, the bond
+    // strains, and the sensors' and parasites' neighbor queries are still allocated each tick.
+    changes: WorldChanges,
+    bond_requests: Vec<BondRequests>,
+    graph_changes: GraphChangeBuffers,
+}
+
+struct WorldInfluence {
+    id: String,
+    enabled: bool,
+    influence: Box<dyn Influence>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ScheduledCull {
+    tick: u64,
+    num_survivors: usize,
+    selection: CullSelection,
+}
+
+#[derive(Debug)]
+struct ScheduledInvasion {
+    tick: u64,
+    invasion: Invasion,
 }
 
 impl World {
+    // How many of the nearest cells, including itself, an unattached parasite checks for a host
+    const PARASITE_HOST_CANDIDATES: usize = 4;
+    // Below this, auto-compaction would save too little to be worth it
+    const MIN_COMPACTED_CAPACITY: usize = 64;
+
     pub fn new(min_corner: Position, max_corner: Position) -> Self {
         World {
             min_corner,
             max_corner,
             cell_graph: SortableGraph::new(),
             influences: vec![],
+            current_tick: 0,
+            scheduled_culls: vec![],
+            scheduled_invasions: vec![],
+            event_log: EventLog::new(),
+            probes: vec![],
+            trajectory_recorder: None,
+            genealogy: None,
+            next_cell_id: 1,
+            energy_flux: EnergyFlux::ZERO,
+            parallelism: Parallelism::Serial,
+            fast_math: false,
+            mutation_rate: None,
+            finite_checks: false,
+            phase_hashes: None,
+            parasite_attachments: vec![],
+            population_monitor: None,
+            control_lod: None,
+            child_placement: ChildPlacementStrategy::BuddingAngle,
+            donation_efficiency: 1.0,
+            bond_energy_decay: 0.0,
+            tissue_multipliers: TissueMultipliers::new(),
+            adhesion_rule: None,
+            resource_limits: ResourceLimits::NONE,
+            churn_cap: None,
+            auto_compaction: None,
+            blowup_bounds: None,
+            blowup: None,
+            births: vec![],
+            deaths: vec![],
+            breakpoints: vec![],
+            held_breakpoints: vec![],
+            selected_cell_deaths: vec![],
+            bonds_formed: 0,
+            bonds_broken: 0,
+            wall_collisions: 0,
+            churn_overflow: 0,
+            plugins: vec![],
+            changes: WorldChanges::new(0, 0),
+            bond_requests: vec![],
+            graph_changes: GraphChangeBuffers::default(),
         }
     }
 
@@ -60,16 +197,489 @@ impl World {
         )))
     }
 
-    pub fn with_influence(mut self, influence: Box<dyn Influence>) -> Self {
-        self.influences.push(influence);
+    pub fn with_energy_tax(self, rate: f64) -> Self {
+        self.with_influence(Box::new(EnergyTax::new(rate)))
+    }
+
+    // The influence's ID is its kind, e.g. "sunlight"; see InfluenceDocument::default_id.
+    pub fn with_influence(self, influence: Box<dyn Influence>) -> Self {
+        let id = influence.to_document().default_id();
+        self.with_named_influence(id, influence)
+    }
+
+    pub fn with_named_influence(mut self, id: &str, influence: Box<dyn Influence>) -> Self {
+        self.influences.push(WorldInfluence {
+            id: id.to_string(),
+            enabled: true,
+            influence,
+        });
+        self
+    }
+
+    pub fn with_parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    // Has the cells' controls use fast approximate math, including cells added later.
+    pub fn with_fast_math(mut self) -> Self {
+        self.fast_math = true;
+        for cell in self.cell_graph.nodes_mut() {
+            cell.use_fast_math();
+        }
+        self
+    }
+
+    pub fn with_plugin(mut self, plugin: Box<dyn WorldPlugin>) -> Self {
+        self.add_plugin(plugin);
+        self
+    }
+
+    pub fn add_plugin(&mut self, plugin: Box<dyn WorldPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    // Checks the cells for NaN and infinite values after each tick phase, and panics with the
+    // offending cell and phase, instead of letting the values spread. Slows down ticks.
+    pub fn with_finite_checks(mut self) -> Self {
+        self.finite_checks = true;
+        self
+    }
+
+    // Records the state hashes after each tick phase, e.g. to find the phase in which two runs
+    // that should match first differ. Slows down ticks.
+    pub fn with_phase_hashes(mut self) -> Self {
+        self.phase_hashes = Some(vec![]);
+        self
+    }
+
+    // The phases of the last tick and the state hashes after each, if they are being recorded.
+    pub fn phase_hashes(&self) -> &[(&'static str, StateHash)] {
+        self.phase_hashes.as_deref().unwrap_or(&[])
+    }
+
+    pub fn with_influences(mut self, influences: Vec<Box<dyn Influence>>) -> Self {
+        for influence in influences {
+            self = self.with_influence(influence);
+        }
+        self
+    }
+
+    pub fn influence_ids(&self) -> Vec<&str> {
+        self.influences
+            .iter()
+            .map(|world_influence| world_influence.id.as_str())
+            .collect()
+    }
+
+    pub fn is_influence_enabled(&self, id: &str) -> bool {
+        self.influences
+            .iter()
+            .any(|world_influence| world_influence.id == id && world_influence.enabled)
+    }
+
+    // Enables or disables every influence with the given ID. A disabled influence has no
+    // effect on the world until it is enabled again.
+    pub fn set_influence_enabled(&mut self, id: &str, enabled: bool) {
+        let mut found = false;
+        for world_influence in &mut self.influences {
+            if world_influence.id == id {
+                world_influence.enabled = enabled;
+                found = true;
+            }
+        }
+        assert!(found, "No influence with ID {:?}", id);
+        self.event_log.record(
+            self.current_tick,
+            WorldEventKind::InfluenceEnabled {
+                id: id.to_string(),
+                enabled,
+            },
+        );
+    }
+
+    // Removes every influence with the given ID.
+    pub fn remove_influence(&mut self, id: &str) {
+        let num_influences = self.influences.len();
+        self.influences
+            .retain(|world_influence| world_influence.id != id);
+        assert!(
+            self.influences.len() < num_influences,
+            "No influence with ID {:?}",
+            id
+        );
+    }
+
+    // Returns an error, leaving the world unchanged, if the patch removes an influence the
+    // world does not have or adds one that cannot be built.
+    pub fn apply_patch(&mut self, patch: &WorldPatch) -> Result<(), DocumentError> {
+        check_influences(&patch.add_influences)?;
+        if let Some(id) = patch.remove_influences.iter().find(|id| {
+            !self
+                .influences
+                .iter()
+                .any(|world_influence| world_influence.id == **id)
+        }) {
+            return Err(DocumentError::UnknownInfluence(id.clone()));
+        }
+        self.influences
+            .retain(|world_influence| !patch.remove_influences.contains(&world_influence.id));
+        for influence_document in &patch.add_influences {
+            let id = influence_document.default_id();
+            self.influences.push(WorldInfluence {
+                id: id.to_string(),
+                enabled: true,
+                influence: influence_document.to_influence(),
+            });
+        }
+        for setting in &patch.set_parameters {
+            self.set_tunable_parameter(setting.parameter, setting.value);
+        }
+        Ok(())
+    }
+
+    // Sets the world's tunable parameters to those of the document's influences and tissue
+    // multipliers where they differ, e.g. after the world's file has been edited, logging each
+    // change. Each document influence is matched to a world influence by ID, the first with an
+    // ID to the first, and so on, and sets only that influence's parameters, so that e.g. a
+    // light patch's intensity can be changed apart from the sunlight's. Everything else in the
+    // document is ignored, since changing it would disturb the running world. Returns the
+    // changes.
+    pub fn reload_parameters(&mut self, document: &WorldDocument) -> Vec<ParameterSetting> {
+        let mut changes = vec![];
+        for &parameter in &TunableParameter::ALL {
+            if let (Some(old_value), Some(new_value)) = (
+                self.tissue_multipliers.tunable_parameter(parameter),
+                document.tissue_multipliers.tunable_parameter(parameter),
+            ) {
+                if old_value != new_value {
+                    self.tissue_multipliers
+                        .set_tunable_parameter(parameter, new_value);
+                    changes.push(self.record_parameter_change(parameter, old_value, new_value));
+                }
+            }
+        }
+
+        let mut document_influences: Vec<(&str, Box<dyn Influence>)> = document
+            .influences
+            .iter()
+            .map(|influence| (influence.default_id(), influence.to_influence()))
+            .collect();
+        for index in 0..self.influences.len() {
+            let position = match document_influences
+                .iter()
+                .position(|(id, _)| *id == self.influences[index].id)
+            {
+                Some(position) => position,
+                None => continue,
+            };
+            let (_, new_influence) = document_influences.remove(position);
+            for &parameter in &TunableParameter::ALL {
+                let influence = &mut self.influences[index].influence;
+                if let (Some(old_value), Some(new_value)) = (
+                    influence.tunable_parameter(parameter),
+                    new_influence.tunable_parameter(parameter),
+                ) {
+                    if old_value != new_value {
+                        influence.set_tunable_parameter(parameter, new_value);
+                        changes.push(self.record_parameter_change(parameter, old_value, new_value));
+                    }
+                }
+            }
+        }
+        changes
+    }
+
+    fn record_parameter_change(
+        &mut self,
+        parameter: TunableParameter,
+        old_value: f64,
+        new_value: f64,
+    ) -> ParameterSetting {
+        self.event_log.record(
+            self.current_tick,
+            WorldEventKind::ParameterChanged {
+                parameter,
+                old_value,
+                new_value,
+            },
+        );
+        ParameterSetting {
+            parameter,
+            value: new_value,
+        }
+    }
+
+    // The mutation rate is that of the first cell whose control has one, unless it has been set.
+    pub fn tunable_parameter(&self, parameter: TunableParameter) -> Option<f64> {
+        if parameter == TunableParameter::MutationRate {
+            return self
+                .mutation_rate
+                .or_else(|| self.cells().iter().find_map(|cell| cell.mutation_rate()));
+        }
+        self.tissue_multipliers
+            .tunable_parameter(parameter)
+            .or_else(|| {
+                self.influences.iter().find_map(|world_influence| {
+                    world_influence.influence.tunable_parameter(parameter)
+                })
+            })
+    }
+
+    pub fn set_tunable_parameter(&mut self, parameter: TunableParameter, value: f64) {
+        if parameter == TunableParameter::MutationRate {
+            self.mutation_rate = Some(value);
+            for cell in self.cell_graph.nodes_mut() {
+                cell.set_mutation_rate(value);
+            }
+            return;
+        }
+        self.tissue_multipliers
+            .set_tunable_parameter(parameter, value);
+        for world_influence in &mut self.influences {
+            world_influence
+                .influence
+                .set_tunable_parameter(parameter, value);
+        }
+    }
+
+    // Culls the population down to `num_survivors` cells just before running tick `tick`.
+    pub fn with_scheduled_cull(
+        mut self,
+        tick: u64,
+        num_survivors: usize,
+        selection: CullSelection,
+    ) -> Self {
+        self.scheduled_culls.push(ScheduledCull {
+            tick,
+            num_survivors,
+            selection,
+        });
+        self
+    }
+
+    pub fn cull_to(&mut self, num_survivors: usize, selection: CullSelection) {
+        let num_cells_before = self.cells().len();
+        let survivors = select_survivors(self.cells(), num_survivors, selection);
+        let mut survivor_iter = survivors.iter().peekable();
+        let mut culled_handles = vec![];
+        for (index, cell) in self.cells().iter().enumerate() {
+            if survivor_iter.peek() == Some(&&index) {
+                survivor_iter.next();
+            } else {
+                culled_handles.push(cell.node_handle());
+            }
+        }
+        self.remove_cells(&culled_handles);
+        self.event_log.record(
+            self.current_tick,
+            WorldEventKind::Cull {
+                selection,
+                num_cells_before,
+                num_survivors: survivors.len(),
+            },
+        );
+    }
+
+    // Adds the invasion's cells just before running tick `tick`.
+    pub fn with_scheduled_invasion(mut self, tick: u64, invasion: Invasion) -> Self {
+        self.scheduled_invasions
+            .push(ScheduledInvasion { tick, invasion });
+        self
+    }
+
+    // Returns the IDs of the invading cells.
+    pub fn invade(&mut self, mut invasion: Invasion) -> Vec<CellId> {
+        let invader_ids: Vec<CellId> = invasion
+            .create_invaders()
+            .into_iter()
+            .map(|invader| {
+                let handle = self.add_cell(invader);
+                self.cell(handle).id()
+            })
+            .collect();
+        self.event_log.record(
+            self.current_tick,
+            WorldEventKind::Invasion {
+                tag: invasion.tag().to_string(),
+                num_cells: invader_ids.len(),
+            },
+        );
+        invader_ids
+    }
+
+    // Records extinctions, population crashes and radiations in the event log as they happen.
+    pub fn with_population_monitor(mut self, parameters: PopulationMonitorParameters) -> Self {
+        self.population_monitor = Some(PopulationMonitor::new(parameters));
+        self
+    }
+
+    pub fn with_control_lod(mut self, lod: ControlLod) -> Self {
+        assert!(lod.interval > 0);
+        self.control_lod = Some(lod);
+        self
+    }
+
+    // For cells that don't have their own child placement.
+    pub fn with_child_placement(mut self, placement: ChildPlacementStrategy) -> Self {
+        self.child_placement = placement;
+        self
+    }
+
+    // Energy donated through a bond loses the rest as heat on the way, so that relaying energy
+    // along a chain of cells costs something at every link.
+    pub fn with_donation_efficiency(mut self, efficiency: f64) -> Self {
+        assert!(0.0 < efficiency && efficiency <= 1.0);
+        self.donation_efficiency = efficiency;
+        self
+    }
+
+    pub fn donation_efficiency(&self) -> f64 {
+        self.donation_efficiency
+    }
+
+    // Energy left waiting in a bond because its cell did not claim it, e.g. while the control
+    // LOD skips the cell, loses this fraction of itself as heat every tick, so that bonds are not
+    // free energy storage. Energy claimed on the first tick it can be is not taxed.
+    pub fn with_bond_energy_decay(mut self, fraction: f64) -> Self {
+        assert!((0.0..=1.0).contains(&fraction));
+        self.bond_energy_decay = fraction;
+        self
+    }
+
+    pub fn bond_energy_decay(&self) -> f64 {
+        self.bond_energy_decay
+    }
+
+    pub fn with_tissue_multipliers(mut self, multipliers: TissueMultipliers) -> Self {
+        self.tissue_multipliers = multipliers;
+        self
+    }
+
+    pub fn tissue_multipliers(&self) -> &TissueMultipliers {
+        &self.tissue_multipliers
+    }
+
+    // Without an adhesion rule, cells adhere whatever their adhesion tags.
+    pub fn with_adhesion_rule(mut self, rule: AdhesionRule) -> Self {
+        self.adhesion_rule = Some(rule);
+        self
+    }
+
+    pub fn adhesion_rule(&self) -> Option<AdhesionRule> {
+        self.adhesion_rule
+    }
+
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = limits;
+        self
+    }
+
+    pub fn with_churn_cap(mut self, cap: ChurnCap) -> Self {
+        self.churn_cap = Some(cap);
+        self
+    }
+
+    // Compacts at the end of any tick that leaves fewer cells than the fraction of the cell
+    // graph's capacity, e.g. after a die-off.
+    pub fn with_auto_compaction(mut self, min_occupancy: f64) -> Self {
+        assert!(0.0 < min_occupancy && min_occupancy <= 1.0);
+        self.auto_compaction = Some(min_occupancy);
+        self
+    }
+
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.add_breakpoint(breakpoint);
+        self
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    // Checks the cells against the bounds after they move, and records any that left them in
+    // blowup() and the event log, so that a run can stop and report instead of carrying on
+    // with garbage.
+    pub fn with_blowup_detection(mut self, bounds: BlowupBounds) -> Self {
+        self.blowup_bounds = Some(bounds);
+        self
+    }
+
+    pub fn blowup(&self) -> Option<&Blowup> {
+        self.blowup.as_ref()
+    }
+
+    pub fn resource_limits(&self) -> &ResourceLimits {
+        &self.resource_limits
+    }
+
+    pub fn churn_cap(&self) -> Option<ChurnCap> {
+        self.churn_cap
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.held_breakpoints.clear();
+    }
+
+    pub fn with_probe(mut self, probe: Probe) -> Self {
+        self.probes.push(probe);
+        self
+    }
+
+    pub fn probes(&self) -> &[Probe] {
+        &self.probes
+    }
+
+    pub fn with_trajectory_recorder(mut self, recorder: TrajectoryRecorder) -> Self {
+        self.trajectory_recorder = Some(recorder);
         self
     }
 
-    pub fn with_influences(mut self, mut influences: Vec<Box<dyn Influence>>) -> Self {
-        self.influences.append(&mut influences);
+    pub fn trajectory_recorder(&self) -> Option<&TrajectoryRecorder> {
+        self.trajectory_recorder.as_ref()
+    }
+
+    // Records every birth from now on.
+    pub fn with_genealogy(mut self) -> Self {
+        self.genealogy = Some(Genealogy::new());
         self
     }
 
+    pub fn genealogy(&self) -> Option<&Genealogy> {
+        self.genealogy.as_ref()
+    }
+
+    // Total light from all enabled influences at the position.
+    pub fn light_intensity_at(&self, position: Position) -> f64 {
+        self.influences
+            .iter()
+            .filter(|world_influence| world_influence.enabled)
+            .map(|world_influence| world_influence.influence.light_intensity_at(position))
+            .sum()
+    }
+
+    // The walls of all enabled influences.
+    pub fn walls(&self) -> Vec<&Walls> {
+        self.influences
+            .iter()
+            .filter(|world_influence| world_influence.enabled)
+            .filter_map(|world_influence| world_influence.influence.walls())
+            .collect()
+    }
+
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick
+    }
+
+    pub fn event_log(&self) -> &EventLog {
+        &self.event_log
+    }
+
     pub fn min_corner(&self) -> Position {
         self.min_corner
     }
@@ -90,16 +700,40 @@ impl World {
         self
     }
 
-    pub fn add_cell(&mut self, cell: Cell) -> NodeHandle {
+    pub fn add_cell(&mut self, mut cell: Cell) -> NodeHandle {
+        cell.set_id(CellId::new(self.next_cell_id));
+        self.next_cell_id += 1;
+        if self.fast_math {
+            cell.use_fast_math();
+        }
+        if let Some(rate) = self.mutation_rate {
+            cell.set_mutation_rate(rate);
+        }
         self.cell_graph.add_node(cell)
     }
 
     pub fn cells(&self) -> &[Cell] {
-        &self.cell_graph.nodes()
+        self.cell_graph.nodes()
     }
 
     pub fn cell(&self, handle: NodeHandle) -> &Cell {
-        &self.cell_graph.node(handle)
+        self.cell_graph.node(handle)
+    }
+
+    // Handles of the cells whose centers are inside the rectangle. Like all node handles,
+    // these are invalidated when cells are removed, e.g. by a tick.
+    pub fn cells_in_rect(&mut self, min_corner: Position, max_corner: Position) -> Vec<NodeHandle> {
+        find_circles_in_rect(&mut self.cell_graph, min_corner, max_corner)
+    }
+
+    // Handles of the cells whose shapes contain `pos`.
+    pub fn cells_containing(&mut self, pos: Position) -> Vec<NodeHandle> {
+        find_circles_containing(&mut self.cell_graph, pos)
+    }
+
+    // Handles of the (up to) `k` cells whose centers are nearest to `pos`, nearest first.
+    pub fn nearest_cells(&mut self, pos: Position, k: usize) -> Vec<NodeHandle> {
+        find_nearest_circles(&mut self.cell_graph, pos, k)
     }
 
     pub fn with_bonds(mut self, index_pairs: Vec<(usize, usize)>) -> Self {
@@ -116,11 +750,11 @@ impl World {
     }
 
     pub fn bonds(&self) -> &[Bond] {
-        &self.cell_graph.edges()
+        self.cell_graph.edges()
     }
 
     pub fn bond(&self, handle: EdgeHandle) -> &Bond {
-        &self.cell_graph.edge(handle)
+        self.cell_graph.edge(handle)
     }
 
     pub fn with_angle_gussets(mut self, index_pairs_with_angles: Vec<(usize, usize, f64)>) -> Self {
@@ -135,516 +769,4050 @@ impl World {
         self
     }
 
-    pub fn add_angle_gusset(&mut self, gusset: AngleGusset) {
-        self.cell_graph.add_meta_edge(gusset);
+    pub fn angle_gussets(&self) -> &[AngleGusset] {
+        self.cell_graph.meta_edges()
     }
 
-    pub fn debug_print_cells(&self) {
-        println!("{:#?}", self.cell_graph);
+    pub fn add_angle_gusset(&mut self, gusset: AngleGusset) {
+        self.cell_graph.add_meta_edge(gusset);
     }
 
-    pub fn toggle_select_cell_at(&mut self, pos: Position) {
-        for cell in self.cell_graph.nodes_mut() {
-            if cell.overlaps(pos) {
-                cell.set_selected(!cell.is_selected());
-                return;
-            }
+    pub fn from_document(document: &WorldDocument) -> Result<Self, DocumentError> {
+        document.check()?;
+        let mut world = World::new(
+            to_position(document.min_corner),
+            to_position(document.max_corner),
+        )
+        .with_influences(
+            document
+                .influences
+                .iter()
+                .map(InfluenceDocument::to_influence)
+                .collect(),
+        )
+        .with_tissue_multipliers(document.tissue_multipliers.clone())
+        .with_cells(document.cells.iter().map(Cell::from_document).collect());
+        world.current_tick = document.tick;
+        world.adhesion_rule = document.adhesion_rule;
+        for bond_document in &document.bonds {
+            world.add_bond_from_document(bond_document, 0);
+        }
+        for gusset_document in &document.angle_gussets {
+            world.add_angle_gusset_from_document(gusset_document, 0);
         }
+        Ok(world)
     }
 
-    pub fn tick(&mut self) {
-        let mut changes = self.new_world_changes();
-        self.apply_influences(&mut changes);
-        self.process_cell_bond_energy();
-        self.run_cell_controls(&mut changes);
-        self.tick_cells();
-        //self._apply_changes(&changes);
+    // Indexes in the documents are offset by the indexes of the first cell and first bond.
+    fn add_bond_from_document(&mut self, bond_document: &BondDocument, first_cell_index: usize) {
+        let mut bond = Bond::new(
+            &self.cells()[first_cell_index + bond_document.cell1],
+            &self.cells()[first_cell_index + bond_document.cell2],
+        );
+        bond.set_energy_from_cell(
+            bond.node2_handle(),
+            BioEnergy::new(bond_document.energy_for_cell1),
+        );
+        bond.set_energy_from_cell(
+            bond.node1_handle(),
+            BioEnergy::new(bond_document.energy_for_cell2),
+        );
+        self.add_bond(
+            bond,
+            bond_document.bond_index_on_cell1,
+            bond_document.bond_index_on_cell2,
+        );
     }
 
-    fn new_world_changes(&self) -> WorldChanges {
-        if self.cell_graph.nodes().is_empty() {
-            return WorldChanges::new(0, 0);
-        }
+    fn add_angle_gusset_from_document(
+        &mut self,
+        gusset_document: &AngleGussetDocument,
+        first_bond_index: usize,
+    ) {
+        let gusset = AngleGusset::new(
+            &self.bonds()[first_bond_index + gusset_document.bond1],
+            &self.bonds()[first_bond_index + gusset_document.bond2],
+            Angle::from_radians(gusset_document.angle),
+        );
+        self.add_angle_gusset(gusset);
+    }
 
-        WorldChanges::new(
-            self.cell_graph.nodes().len(),
-            self.cell_graph.nodes()[0].layers().len(),
-        )
+    // Exports the organism that includes the cell: it and every cell connected to it by
+    // bonds. Cell positions are relative to the organism's mean cell position.
+    pub fn export_organism(&self, handle: NodeHandle) -> OrganismDocument {
+        self.export_cells(&self.connected_cells(handle))
     }
 
-    fn apply_influences(&mut self, changes: &mut WorldChanges) {
-        for influence in &self.influences {
-            influence.apply(&mut self.cell_graph);
+    // Like export_organism, but for any group of cells. Only bonds between cells in the group
+    // are included.
+    pub fn export_cells(&self, cell_handles: &[NodeHandle]) -> OrganismDocument {
+        assert!(!cell_handles.is_empty());
+        let mut local_indexes = HashMap::new();
+        for (local_index, cell_handle) in cell_handles.iter().enumerate() {
+            local_indexes.insert(*cell_handle, local_index);
         }
-        for (index, cell) in self.cell_graph.nodes_mut().iter_mut().enumerate() {
-            cell.after_influences(&mut changes.cells[index]);
+
+        let mut bond_handles: Vec<EdgeHandle> = cell_handles
+            .iter()
+            .flat_map(|cell_handle| self.cell(*cell_handle).edge_handles().iter().flatten())
+            .cloned()
+            .filter(|bond_handle| {
+                let bond = self.bond(*bond_handle);
+                local_indexes.contains_key(&bond.node1_handle())
+                    && local_indexes.contains_key(&bond.node2_handle())
+            })
+            .collect();
+        bond_handles.sort_unstable();
+        bond_handles.dedup();
+        let mut local_bond_indexes = HashMap::new();
+        for (local_index, bond_handle) in bond_handles.iter().enumerate() {
+            local_bond_indexes.insert(*bond_handle, local_index);
         }
-    }
 
-    fn process_cell_bond_energy(&mut self) {
-        self.cell_graph.for_each_node(|_index, cell, edge_source| {
-            Self::claim_bond_energy(cell, edge_source);
-        });
-    }
+        let num_cells = cell_handles.len() as f64;
+        let center = Position::new(
+            cell_handles
+                .iter()
+                .map(|handle| self.cell(*handle).position().x())
+                .sum::<f64>()
+                / num_cells,
+            cell_handles
+                .iter()
+                .map(|handle| self.cell(*handle).position().y())
+                .sum::<f64>()
+                / num_cells,
+        );
 
-    fn claim_bond_energy(cell: &mut Cell, edge_source: &mut EdgeSource<Bond>) {
-        let mut energy = BioEnergy::ZERO;
-        for edge_handle in cell.edge_handles() {
-            if let Some(edge_handle) = edge_handle {
-                let bond = edge_source.edge(*edge_handle);
-                energy += bond.claim_energy_for_cell(cell.node_handle());
-            }
+        OrganismDocument {
+            version: OrganismDocument::VERSION,
+            cells: cell_handles
+                .iter()
+                .map(|handle| {
+                    let mut cell_document = self.cell(*handle).to_document();
+                    let offset = self.cell(*handle).position() - center;
+                    cell_document.position = [offset.x(), offset.y()];
+                    cell_document
+                })
+                .collect(),
+            bonds: bond_handles
+                .iter()
+                .map(|handle| {
+                    let bond = self.bond(*handle);
+                    self.bond_to_document(
+                        bond,
+                        local_indexes[&bond.node1_handle()],
+                        local_indexes[&bond.node2_handle()],
+                    )
+                })
+                .collect(),
+            angle_gussets: self
+                .cell_graph
+                .meta_edges()
+                .iter()
+                .filter_map(|gusset| {
+                    Some(AngleGussetDocument {
+                        bond1: *local_bond_indexes.get(&gusset.edge1_handle())?,
+                        bond2: *local_bond_indexes.get(&gusset.edge2_handle())?,
+                        angle: gusset.angle().radians(),
+                    })
+                })
+                .collect(),
         }
-        cell.add_energy(energy);
-    }
-
-    fn run_cell_controls(&mut self, changes: &mut WorldChanges) {
-        // TODO test: inner layer grows while outer layer buds at correct distance
-        let mut new_children = vec![];
-        let mut broken_bond_handles = HashSet::new();
-        let mut dead_cell_handles = vec![];
-        self.cell_graph.for_each_node(|index, cell, edge_source| {
-            let mut bond_requests = NONE_BOND_REQUESTS;
-            cell.run_control(&mut bond_requests, &mut changes.cells[index]);
-            Self::execute_bond_requests(
-                cell,
-                edge_source,
-                &bond_requests,
-                &mut new_children,
-                &mut broken_bond_handles,
-            );
-            if !cell.is_alive() {
-                dead_cell_handles.push(cell.node_handle());
-            }
-        });
-        self.update_cell_graph(new_children, broken_bond_handles, dead_cell_handles);
     }
 
-    fn execute_bond_requests(
-        cell: &mut Cell,
-        edge_source: &mut EdgeSource<Bond>,
-        bond_requests: &BondRequests,
-        new_children: &mut Vec<NewChildData>,
-        broken_bond_handles: &mut HashSet<EdgeHandle>,
-    ) {
-        for (index, bond_request) in bond_requests.iter().enumerate() {
-            if bond_request.retain_bond {
-                if bond_request.donation_energy != BioEnergy::ZERO {
-                    if cell.has_edge(index) {
-                        let bond = edge_source.edge(cell.edge_handle(index));
-                        bond.set_energy_from_cell(cell.node_handle(), bond_request.donation_energy);
-                    } else {
-                        let child = cell.create_and_place_child_cell(
-                            bond_request.budding_angle,
-                            BioEnergy::ZERO,
-                        );
-                        new_children.push(NewChildData {
-                            parent: cell.node_handle(),
-                            bond_index: index,
-                            child,
-                            donated_energy: bond_request.donation_energy,
-                        });
-                    }
+    fn connected_cells(&self, handle: NodeHandle) -> Vec<NodeHandle> {
+        let mut visited = HashSet::new();
+        visited.insert(handle);
+        let mut cell_handles = vec![handle];
+        let mut next = 0;
+        while next < cell_handles.len() {
+            let cell_handle = cell_handles[next];
+            next += 1;
+            for edge_handle in self.cell(cell_handle).edge_handles().iter().flatten() {
+                let bond = self.bond(*edge_handle);
+                let other_handle = if bond.node1_handle() == cell_handle {
+                    bond.node2_handle()
+                } else {
+                    bond.node1_handle()
+                };
+                if visited.insert(other_handle) {
+                    cell_handles.push(other_handle);
                 }
-            } else if cell.has_edge(index) {
-                broken_bond_handles.insert(cell.edge_handle(index));
             }
         }
+        cell_handles
     }
 
-    fn update_cell_graph(
+    // Adds an exported organism to the world, rotated about its center and then centered at
+    // the position. The organism's cells get NullControls, and dropout seeds from their IDs, so
+    // that organisms grafted from the same document do not drop the same requests. Returns the
+    // new cells' handles, in the order of the document's cells.
+    pub fn graft(
         &mut self,
-        new_children: Vec<NewChildData>,
-        broken_bond_handles: HashSet<EdgeHandle>,
-        dead_cell_handles: Vec<NodeHandle>,
-    ) {
-        self.add_children(new_children);
-        self.remove_bonds(&broken_bond_handles);
-        self.cell_graph.remove_nodes(&dead_cell_handles);
+        organism: &OrganismDocument,
+        position: Position,
+        rotation: Angle,
+    ) -> Vec<NodeHandle> {
+        self.graft_with_controls(organism, position, rotation, |_| {
+            Box::new(NullControl::new())
+        })
     }
 
-    fn add_children(&mut self, new_children: Vec<NewChildData>) {
-        for new_child_data in new_children {
-            let child_handle = self.add_cell(new_child_data.child);
-            let child = self.cell(child_handle);
-            let mut bond = Bond::new(self.cell(new_child_data.parent), child);
-            bond.set_energy_from_cell(new_child_data.parent, new_child_data.donated_energy);
-            self.add_bond(bond, new_child_data.bond_index, 0);
+    // Like graft, but gives each new cell the control that `create_control` returns for the
+    // index of its document.
+    pub fn graft_with_controls<F>(
+        &mut self,
+        organism: &OrganismDocument,
+        position: Position,
+        rotation: Angle,
+        mut create_control: F,
+    ) -> Vec<NodeHandle>
+    where
+        F: FnMut(usize) -> Box<dyn CellControl>,
+    {
+        assert_eq!(organism.version, OrganismDocument::VERSION);
+        let first_cell_index = self.cells().len();
+        let first_bond_index = self.bonds().len();
+        let mut handles = Vec::with_capacity(organism.cells.len());
+        for (index, cell_document) in organism.cells.iter().enumerate() {
+            let offset =
+                Displacement::from(to_position(cell_document.position).value().rotate(rotation));
+            let velocity = Velocity::from(
+                Velocity::new(cell_document.velocity[0], cell_document.velocity[1])
+                    .value()
+                    .rotate(rotation),
+            );
+            let cell_document = CellDocument {
+                dropout_seed: None,
+                ..cell_document.clone()
+            };
+            let mut cell = Cell::from_document(&cell_document).with_control(create_control(index));
+            cell.set_initial_position(position + offset);
+            cell.set_initial_velocity(velocity);
+            handles.push(self.add_cell(cell));
         }
+        for bond_document in &organism.bonds {
+            self.add_bond_from_document(bond_document, first_cell_index);
+        }
+        for gusset_document in &organism.angle_gussets {
+            self.add_angle_gusset_from_document(gusset_document, first_bond_index);
+        }
+        handles
+    }
+
+    pub fn to_document(&self) -> WorldDocument {
+        WorldDocument {
+            version: WorldDocument::VERSION,
+            tick: self.current_tick,
+            min_corner: from_position(self.min_corner),
+            max_corner: from_position(self.max_corner),
+            influences: self
+                .influences
+                .iter()
+                .map(|world_influence| world_influence.influence.to_document())
+                .collect(),
+            cells: self.cells().iter().map(Cell::to_document).collect(),
+            bonds: self
+                .bonds()
+                .iter()
+                .map(|bond| {
+                    self.bond_to_document(
+                        bond,
+                        bond.node1_handle().index(),
+                        bond.node2_handle().index(),
+                    )
+                })
+                .collect(),
+            angle_gussets: self
+                .cell_graph
+                .meta_edges()
+                .iter()
+                .map(|gusset| AngleGussetDocument {
+                    bond1: gusset.edge1_handle().index(),
+                    bond2: gusset.edge2_handle().index(),
+                    angle: gusset.angle().radians(),
+                })
+                .collect(),
+            tissue_multipliers: self.tissue_multipliers.clone(),
+            adhesion_rule: self.adhesion_rule,
+        }
+    }
+
+    fn bond_to_document(&self, bond: &Bond, cell1: usize, cell2: usize) -> BondDocument {
+        BondDocument {
+            cell1,
+            cell2,
+            bond_index_on_cell1: Self::bond_index_on_cell(self.cell(bond.node1_handle()), bond),
+            bond_index_on_cell2: Self::bond_index_on_cell(self.cell(bond.node2_handle()), bond),
+            energy_for_cell1: bond.energy_for_cell1().value(),
+            energy_for_cell2: bond.energy_for_cell2().value(),
+        }
+    }
+
+    fn bond_index_on_cell(cell: &Cell, bond: &Bond) -> usize {
+        cell.edge_handles()
+            .iter()
+            .position(|edge_handle| *edge_handle == Some(bond.edge_handle()))
+            .unwrap()
+    }
+
+    // A hash of the cells' motion, energies, genomes, and bonds that is the same for identical
+    // states on any platform, for comparing runs that should match.
+    pub fn state_hash(&self) -> u64 {
+        self.state_hashes().combined()
+    }
+
+    pub fn state_hashes(&self) -> StateHash {
+        let mut motion = StateHasher::new();
+        let mut energies = StateHasher::new();
+        let mut genomes = StateHasher::new();
+        let mut bonds = StateHasher::new();
+        for cell in self.cells() {
+            motion.write_u64(cell.id().value());
+            motion.write_f64(cell.position().x());
+            motion.write_f64(cell.position().y());
+            motion.write_f64(cell.velocity().x());
+            motion.write_f64(cell.velocity().y());
+            motion.write_f64(cell.heading().radians());
+            energies.write_f64(cell.energy().value());
+            cell.hash_genome(&mut genomes);
+        }
+        for bond in self.bonds() {
+            let cell1 = self.cell(bond.node1_handle());
+            let cell2 = self.cell(bond.node2_handle());
+            bonds.write_u64(cell1.id().value());
+            bonds.write_u64(cell2.id().value());
+            bonds.write_u64(Self::bond_index_on_cell(cell1, bond) as u64);
+            bonds.write_u64(Self::bond_index_on_cell(cell2, bond) as u64);
+            bonds.write_f64(bond.energy_for_cell1().value());
+            bonds.write_f64(bond.energy_for_cell2().value());
+        }
+        StateHash {
+            motion: motion.finish(),
+            energies: energies.finish(),
+            genomes: genomes.finish(),
+            bonds: bonds.finish(),
+        }
+    }
+
+    pub fn debug_print_cells(&self) {
+        println!("{:#?}", self.cell_graph);
+    }
+
+    pub fn cell_with_id(&self, id: CellId) -> Option<&Cell> {
+        self.cells().iter().find(|cell| cell.id() == id)
+    }
+
+    // Adds a copy of the cell with the ID, at rest at the position. The copy is made from the
+    // cell's document, so like a loaded cell it gets a NullControl, but it gets its own dropout
+    // seed from its ID. Returns the copy's ID, or None if there is no such cell.
+    pub fn add_copy_of_cell(&mut self, id: CellId, position: Position) -> Option<CellId> {
+        let mut document = self.cell_with_id(id)?.to_document();
+        document.position = from_position(position);
+        document.velocity = [0.0, 0.0];
+        document.dropout_seed = None;
+        let handle = self.add_cell(Cell::from_document(&document));
+        Some(self.cell(handle).id())
+    }
+
+    // Puts the cell with the ID at the position, at rest. Its bonds stretch to follow it.
+    pub fn move_cell(&mut self, id: CellId, position: Position) {
+        if let Some(cell) = self
+            .cell_graph
+            .nodes_mut()
+            .iter_mut()
+            .find(|cell| cell.id() == id)
+        {
+            cell.set_initial_position(position);
+            cell.set_initial_velocity(Velocity::ZERO);
+        }
+    }
+
+    // Bonds the cells with the IDs, using the first free bond slot on each. Returns false,
+    // leaving the world unchanged, if either cell is missing or has no free slot, or if they
+    // are already bonded.
+    pub fn bond_cells(&mut self, id1: CellId, id2: CellId) -> bool {
+        let (cell1, cell2) = match (self.cell_with_id(id1), self.cell_with_id(id2)) {
+            (Some(cell1), Some(cell2)) if id1 != id2 => (cell1, cell2),
+            _ => return false,
+        };
+        if self.cell_graph.have_edge(cell1, cell2) {
+            return false;
+        }
+        let free_slot = |cell: &Cell| cell.edge_handles().iter().position(Option::is_none);
+        match (free_slot(cell1), free_slot(cell2)) {
+            (Some(slot1), Some(slot2)) => {
+                let bond = Bond::new(cell1, cell2);
+                self.add_bond(bond, slot1, slot2);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn toggle_select_cell(&mut self, id: CellId) {
+        if let Some(cell) = self
+            .cell_graph
+            .nodes_mut()
+            .iter_mut()
+            .find(|cell| cell.id() == id)
+        {
+            cell.set_selected(!cell.is_selected());
+        }
+    }
+
+    // Replaces the selection with the cells whose centers are inside the rectangle.
+    pub fn select_cells_in_rect(&mut self, min_corner: Position, max_corner: Position) {
+        self.clear_selection();
+        for handle in self.cells_in_rect(min_corner, max_corner) {
+            self.cell_graph.node_mut(handle).set_selected(true);
+        }
+    }
+
+    pub fn select_only_cell(&mut self, id: CellId) {
+        self.clear_selection();
+        self.toggle_select_cell(id);
+    }
+
+    pub fn clear_selection(&mut self) {
+        for cell in self.cell_graph.nodes_mut() {
+            cell.set_selected(false);
+        }
+    }
+
+    // In ascending order.
+    pub fn selected_cell_handles(&self) -> Vec<NodeHandle> {
+        self.cells()
+            .iter()
+            .filter(|cell| cell.is_selected())
+            .map(|cell| cell.node_handle())
+            .collect()
+    }
+
+    pub fn export_selected_cells(&self) -> Option<OrganismDocument> {
+        let handles = self.selected_cell_handles();
+        if handles.is_empty() {
+            None
+        } else {
+            Some(self.export_cells(&handles))
+        }
+    }
+
+    // Tags the selected cells with an inherited tag.
+    pub fn tag_selected_cells(&mut self, name: &str) {
+        for cell in self.cell_graph.nodes_mut() {
+            if cell.is_selected() {
+                cell.add_tag(name);
+            }
+        }
+    }
+
+    // Sorted and without duplicates.
+    pub fn tag_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .cells()
+            .iter()
+            .flat_map(|cell| cell.tags().iter().map(|tag| tag.name.clone()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn energy_flux(&self) -> EnergyFlux {
+        self.energy_flux
+    }
+
+    // The sum of the cells' energy budgets for the last tick.
+    pub fn total_energy_budget(&self) -> EnergyBudget {
+        let mut total = EnergyBudget::ZERO;
+        for cell in self.cells() {
+            total.add(cell.energy_budget());
+        }
+        total
+    }
+
+    // Population, biomass and mean energy per lineage, with the lineage's births and deaths
+    // during the last tick, in lineage order. Lineages stand in for species until there is
+    // species clustering. Lineages whose last cells died during the last tick are included.
+    pub fn lineage_stats(&self) -> Vec<LineageStats> {
+        let mut stats_by_lineage: HashMap<u32, LineageStats> = HashMap::new();
+        let mut total_energies: HashMap<u32, f64> = HashMap::new();
+        for cell in self.cells() {
+            let stats = stats_by_lineage
+                .entry(cell.lineage())
+                .or_insert_with(|| LineageStats::new(cell.lineage()));
+            stats.cells += 1;
+            stats.biomass = stats.biomass + cell.mass();
+            *total_energies.entry(cell.lineage()).or_insert(0.0) += cell.energy().value();
+        }
+        for &lineage in &self.births {
+            stats_by_lineage
+                .entry(lineage)
+                .or_insert_with(|| LineageStats::new(lineage))
+                .births += 1;
+        }
+        for &lineage in &self.deaths {
+            stats_by_lineage
+                .entry(lineage)
+                .or_insert_with(|| LineageStats::new(lineage))
+                .deaths += 1;
+        }
+        let mut all_stats: Vec<LineageStats> = stats_by_lineage.into_values().collect();
+        for stats in &mut all_stats {
+            if stats.cells > 0 {
+                stats.mean_energy =
+                    BioEnergy::new(total_energies[&stats.lineage] / stats.cells as f64);
+            }
+        }
+        all_stats.sort_by_key(|stats| stats.lineage);
+        all_stats
+    }
+
+    pub fn count_cells_with_tag(&self, name: &str) -> usize {
+        self.cells()
+            .iter()
+            .filter(|cell| cell.has_tag(name))
+            .count()
+    }
+
+    // Removes the selected cells, and their bonds, from the world.
+    pub fn kill_selected_cells(&mut self) {
+        let handles = self.selected_cell_handles();
+        self.remove_cells(&handles);
+    }
+
+    // Removes the cells, and their bonds, after telling the plugins and counting the deaths.
+    // Every way a cell leaves the world goes through here.
+    fn remove_cells(&mut self, handles: &[NodeHandle]) {
+        self.notify_plugins_of_cells(handles, |plugin, world, cell| {
+            plugin.on_cell_died(world, cell)
+        });
+        for &handle in handles {
+            let cell = self.cell_graph.node(handle);
+            self.deaths.push(cell.lineage());
+            if cell.is_selected() {
+                self.selected_cell_deaths.push(cell.id());
+            }
+            if let Some(genealogy) = &mut self.genealogy {
+                genealogy.record_death(cell.id());
+            }
+        }
+        self.cell_graph.remove_nodes(handles);
+    }
+
+    // Of the cells that contain the position, toggles the one whose center is nearest to it.
+    pub fn toggle_select_cell_at(&mut self, pos: Position) {
+        let distance_to = |cell: &Cell| (cell.center() - pos).length().value();
+        let handle = self
+            .cells_containing(pos)
+            .into_iter()
+            .min_by(|&handle1, &handle2| {
+                distance_to(self.cell(handle1)).total_cmp(&distance_to(self.cell(handle2)))
+            });
+        if let Some(handle) = handle {
+            let cell = self.cell_graph.node_mut(handle);
+            cell.set_selected(!cell.is_selected());
+        }
+    }
+
+    pub fn tick(&mut self) -> TickReport {
+        let tick_start = Instant::now();
+        self.births.clear();
+        self.deaths.clear();
+        self.selected_cell_deaths.clear();
+        self.blowup = None;
+        self.bonds_formed = 0;
+        self.bonds_broken = 0;
+        self.wall_collisions = 0;
+        self.churn_overflow = 0;
+        self.cell_graph.take_churn();
+        if let Some(phase_hashes) = &mut self.phase_hashes {
+            phase_hashes.clear();
+        }
+        self.run_plugins(|plugin, world| plugin.before_tick(world));
+        self.run_scheduled_culls();
+        self.run_scheduled_invasions();
+        let mut changes = self.take_world_changes();
+        self.apply_influences(&mut changes);
+        self.end_phase("influences");
+        let num_cells = self.cells().len();
+        self.run_plugins(|plugin, world| plugin.after_influences(world));
+        assert_eq!(
+            self.cells().len(),
+            num_cells,
+            "Plugins must not add or remove cells after influences"
+        );
+        self.process_cell_bond_energy();
+        self.end_phase("bond energy");
+        self.run_parasitism();
+        self.end_phase("parasitism");
+        self.run_sensors();
+        self.run_cell_controls(&mut changes);
+        self.end_phase("controls");
+        self.run_plugins(|plugin, world| plugin.after_controls(world));
+        self.tick_cells();
+        self.end_phase("movement");
+        //self._apply_changes(&changes);
+        self.changes = changes;
+        self.current_tick += 1;
+        self.take_probe_readings();
+        if let Some(recorder) = &mut self.trajectory_recorder {
+            recorder.record(self.current_tick, self.cell_graph.nodes());
+        }
+        self.monitor_population();
+        let breakpoint_hit = self.check_breakpoints();
+        let limit_exceeded = self.enforce_resource_limits(tick_start);
+        self.compact_if_sparse();
+        TickReport {
+            births: self.births.len(),
+            deaths: self.deaths.len(),
+            bonds_formed: self.bonds_formed,
+            bonds_broken: self.bonds_broken,
+            energy_injected: self.energy_flux.injected,
+            energy_dissipated: self.energy_flux.dissipated,
+            wall_collisions: self.wall_collisions,
+            churn: self.cell_graph.churn(),
+            churn_overflow: self.churn_overflow,
+            breakpoint_hit,
+            limit_exceeded,
+            blown_up_cells: self.blowup.as_ref().map_or(0, |blowup| blowup.cells.len()),
+        }
+    }
+
+    // Gives back the memory the cell graph and the per-tick scratch buffers kept for cells and
+    // bonds that are gone. The buffers grow back as needed on the next tick.
+    pub fn compact(&mut self) {
+        self.cell_graph.shrink_to_fit();
+        self.changes = WorldChanges::new(0, 0);
+        self.bond_requests = vec![];
+        self.graph_changes = GraphChangeBuffers::default();
+        self.parasite_attachments.shrink_to_fit();
+    }
+
+    fn compact_if_sparse(&mut self) {
+        if let Some(min_occupancy) = self.auto_compaction {
+            let capacity = self.cell_graph.node_capacity();
+            if capacity > Self::MIN_COMPACTED_CAPACITY
+                && (self.cells().len() as f64) < min_occupancy * capacity as f64
+            {
+                self.compact();
+            }
+        }
+    }
+
+    fn enforce_resource_limits(&mut self, tick_start: Instant) -> Option<LimitExceeded> {
+        if self.resource_limits == ResourceLimits::NONE {
+            return None;
+        }
+        let exceeded = self.resource_limits.check(
+            self.cells().len(),
+            self.bonds().len(),
+            tick_start.elapsed(),
+        )?;
+        self.event_log
+            .record(self.current_tick, WorldEventKind::LimitExceeded(exceeded));
+        if self.resource_limits.action == LimitAction::Cull {
+            let num_survivors = self
+                .resource_limits
+                .num_survivors(exceeded, self.cells().len());
+            self.cull_to(num_survivors, CullSelection::Fittest(FitnessMetric::Energy));
+        }
+        Some(exceeded)
+    }
+
+    // Returns the first breakpoint condition that started holding for a selected cell.
+    fn check_breakpoints(&mut self) -> Option<BreakpointHit> {
+        if self.breakpoints.is_empty() {
+            return None;
+        }
+        let mut holding = vec![];
+        for &breakpoint in &self.breakpoints {
+            match breakpoint {
+                Breakpoint::EnergyBelow(energy) => {
+                    for cell in self.cells().iter().filter(|cell| cell.is_selected()) {
+                        if cell.energy().value() < energy {
+                            holding.push(BreakpointHit::new(cell.id(), breakpoint));
+                        }
+                    }
+                }
+                Breakpoint::HealthBelow(health) => {
+                    for cell in self.cells().iter().filter(|cell| cell.is_selected()) {
+                        if cell.layers().iter().any(|layer| layer.health() < health) {
+                            holding.push(BreakpointHit::new(cell.id(), breakpoint));
+                        }
+                    }
+                }
+                Breakpoint::Death => {
+                    for &cell_id in &self.selected_cell_deaths {
+                        holding.push(BreakpointHit::new(cell_id, breakpoint));
+                    }
+                }
+            }
+        }
+        let hit = holding
+            .iter()
+            .find(|hit| !self.held_breakpoints.contains(hit))
+            .copied();
+        self.held_breakpoints = holding;
+        hit
+    }
+
+    // Plugins that a hook adds run after the existing ones, starting with the next hook.
+    fn run_plugins<F>(&mut self, mut hook: F)
+    where
+        F: FnMut(&mut dyn WorldPlugin, &mut World),
+    {
+        if self.plugins.is_empty() {
+            return;
+        }
+        let mut plugins = mem::take(&mut self.plugins);
+        for plugin in &mut plugins {
+            hook(plugin.as_mut(), self);
+        }
+        plugins.append(&mut self.plugins);
+        self.plugins = plugins;
+    }
+
+    fn notify_plugins_of_cells<F>(&mut self, handles: &[NodeHandle], hook: F)
+    where
+        F: Fn(&mut dyn WorldPlugin, &World, &Cell),
+    {
+        if self.plugins.is_empty() {
+            return;
+        }
+        let mut plugins = mem::take(&mut self.plugins);
+        for plugin in &mut plugins {
+            for &handle in handles {
+                hook(plugin.as_mut(), self, self.cell(handle));
+            }
+        }
+        self.plugins = plugins;
+    }
+
+    fn monitor_population(&mut self) {
+        if self.population_monitor.is_none() {
+            return;
+        }
+        let lineage_stats = self.lineage_stats();
+        let monitor = self.population_monitor.as_mut().unwrap();
+        for kind in monitor.observe(self.current_tick, &lineage_stats) {
+            self.event_log.record(self.current_tick, kind);
+        }
+    }
+
+    fn end_phase(&mut self, phase: &'static str) {
+        self.check_finite(phase);
+        if let Some(mut phase_hashes) = self.phase_hashes.take() {
+            phase_hashes.push((phase, self.state_hashes()));
+            self.phase_hashes = Some(phase_hashes);
+        }
+    }
+
+    fn check_finite(&self, phase: &str) {
+        if !self.finite_checks {
+            return;
+        }
+        for cell in self.cells() {
+            if let Some(state) = cell.non_finite_state() {
+                panic!(
+                    "Tick {}, after {}: cell {} has non-finite {}",
+                    self.current_tick,
+                    phase,
+                    cell.id().value(),
+                    state
+                );
+            }
+        }
+    }
+
+    fn take_probe_readings(&mut self) {
+        let mut probes = std::mem::take(&mut self.probes);
+        for probe in &mut probes {
+            let light_intensity = self.light_intensity_at(probe.region().center());
+            probe.take_reading(self.current_tick, self.cells(), light_intensity);
+        }
+        self.probes = probes;
+    }
+
+    fn run_scheduled_culls(&mut self) {
+        let current_tick = self.current_tick;
+        let due_culls: Vec<ScheduledCull> = self
+            .scheduled_culls
+            .iter()
+            .filter(|cull| cull.tick == current_tick)
+            .cloned()
+            .collect();
+        for cull in due_culls {
+            self.cull_to(cull.num_survivors, cull.selection);
+        }
+    }
+
+    fn run_scheduled_invasions(&mut self) {
+        let current_tick = self.current_tick;
+        if !self
+            .scheduled_invasions
+            .iter()
+            .any(|scheduled| scheduled.tick == current_tick)
+        {
+            return;
+        }
+        let (due_invasions, pending_invasions): (Vec<_>, Vec<_>) =
+            mem::take(&mut self.scheduled_invasions)
+                .into_iter()
+                .partition(|scheduled| scheduled.tick == current_tick);
+        self.scheduled_invasions = pending_invasions;
+        for scheduled in due_invasions {
+            self.invade(scheduled.invasion);
+        }
+    }
+
+    fn take_world_changes(&mut self) -> WorldChanges {
+        let mut changes = mem::replace(&mut self.changes, WorldChanges::new(0, 0));
+        match self.cell_graph.nodes().first() {
+            Some(cell) => changes.reset(self.cell_graph.nodes().len(), cell.layers().len()),
+            None => changes.reset(0, 0),
+        }
+        changes
+    }
+
+    fn apply_influences(&mut self, changes: &mut WorldChanges) {
+        for world_influence in &mut self.influences {
+            world_influence.influence.before_tick(self.current_tick);
+        }
+        self.energy_flux = EnergyFlux::ZERO;
+        for world_influence in &self.influences {
+            if world_influence.enabled {
+                world_influence.influence.apply(&mut self.cell_graph);
+                self.energy_flux += world_influence.influence.energy_flux(&self.cell_graph);
+                if let Some(walls) = world_influence.influence.walls() {
+                    self.wall_collisions += walls.count_overlaps(&self.cell_graph);
+                }
+            }
+        }
+        let mut cells_and_changes: Vec<(&mut Cell, &mut CellChanges)> = self
+            .cell_graph
+            .nodes_mut()
+            .iter_mut()
+            .zip(changes.cells.iter_mut())
+            .collect();
+        let tissue_multipliers = &self.tissue_multipliers;
+        self.parallelism
+            .for_each(&mut cells_and_changes, |(cell, cell_changes)| {
+                cell.after_influences_with_multipliers(cell_changes, tissue_multipliers)
+            });
+    }
+
+    fn process_cell_bond_energy(&mut self) {
+        let efficiency = self.donation_efficiency;
+        let control_lod = self.control_lod;
+        let mut lost_energy = 0.0;
+        self.cell_graph.for_each_node(|_index, cell, edge_source| {
+            if cell.claims_bond_energy(control_lod.as_ref()) {
+                lost_energy += Self::claim_bond_energy(cell, edge_source, efficiency);
+            }
+        });
+        self.energy_flux += EnergyFlux::dissipated(lost_energy);
+        // Only the energy left unclaimed decays
+        if self.bond_energy_decay > 0.0 {
+            let mut decayed_energy = BioEnergy::ZERO;
+            for bond in self.cell_graph.edges_mut() {
+                decayed_energy += bond.decay_energy(self.bond_energy_decay);
+            }
+            self.energy_flux += EnergyFlux::dissipated(decayed_energy.value());
+        }
+    }
+
+    // Returns the energy lost on the way.
+    fn claim_bond_energy(
+        cell: &mut Cell,
+        edge_source: &mut EdgeSource<Bond>,
+        efficiency: f64,
+    ) -> f64 {
+        let mut energy = BioEnergy::ZERO;
+        for edge_handle in cell.edge_handles().iter().flatten() {
+            let bond = edge_source.edge(*edge_handle);
+            energy += bond.claim_energy_for_cell(cell.node_handle());
+        }
+        cell.add_energy(energy * efficiency);
+        energy.value() * (1.0 - efficiency)
+    }
+
+    pub fn parasite_attachments(&self) -> &[ParasiteAttachment] {
+        &self.parasite_attachments
+    }
+
+    // Parasites stay attached to their hosts while they overlap, and otherwise attach to the
+    // nearest other cell they overlap. Each siphons energy from its host.
+    fn run_parasitism(&mut self) {
+        if self.parasite_attachments.is_empty()
+            && !self.cells().iter().any(|cell| cell.siphon().is_some())
+        {
+            return;
+        }
+        let handles_by_id: HashMap<CellId, NodeHandle> = self
+            .cells()
+            .iter()
+            .map(|cell| (cell.id(), cell.node_handle()))
+            .collect();
+        let mut attachments = mem::take(&mut self.parasite_attachments);
+        attachments.retain(|attachment| {
+            match (
+                handles_by_id.get(&attachment.parasite),
+                handles_by_id.get(&attachment.host),
+            ) {
+                (Some(&parasite), Some(&host)) => {
+                    self.cell(parasite).siphon().is_some() && self.cells_overlap(parasite, host)
+                }
+                _ => false,
+            }
+        });
+
+        let unattached_parasites: Vec<NodeHandle> = self
+            .cells()
+            .iter()
+            .filter(|cell| cell.siphon().is_some())
+            .filter(|cell| !attachments.iter().any(|a| a.parasite == cell.id()))
+            .map(|cell| cell.node_handle())
+            .collect();
+        for parasite in unattached_parasites {
+            let center = self.cell(parasite).center();
+            if let Some(host) = self
+                .nearest_cells(center, Self::PARASITE_HOST_CANDIDATES)
+                .into_iter()
+                .find(|&host| host != parasite && self.cells_overlap(parasite, host))
+            {
+                attachments.push(ParasiteAttachment {
+                    parasite: self.cell(parasite).id(),
+                    host: self.cell(host).id(),
+                });
+            }
+        }
+
+        for attachment in &attachments {
+            let parasite = handles_by_id[&attachment.parasite];
+            let host = handles_by_id[&attachment.host];
+            let siphon = self.cell(parasite).siphon().unwrap();
+            let max_energy = siphon.energy_from_host(self.cell(host).shell_strength());
+            let energy = self.cell_graph.node_mut(host).take_energy(max_energy);
+            self.cell_graph.node_mut(parasite).add_energy(energy);
+        }
+        self.parasite_attachments = attachments;
+    }
+
+    // Each live sensor layer reads the nearest other cell it senses within its range.
+    fn run_sensors(&mut self) {
+        let sensors: Vec<(NodeHandle, usize, Sensor)> = self
+            .cells()
+            .iter()
+            .flat_map(|cell| {
+                cell.layers()
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(index, layer)| {
+                        layer
+                            .sensor()
+                            .map(|sensor| (cell.node_handle(), index, sensor))
+                    })
+            })
+            .collect();
+        for (handle, layer_index, sensor) in sensors {
+            let center = self.cell(handle).center();
+            let range = sensor.range.value();
+            let nearest = self
+                .cells_in_rect(
+                    center + Displacement::new(-range, -range),
+                    center + Displacement::new(range, range),
+                )
+                .into_iter()
+                .filter(|&other| other != handle && sensor.senses(self.cell(other)))
+                .map(|other| self.cell(other).center() - center)
+                .filter(|offset| offset.length() <= sensor.range)
+                .min_by(|offset1, offset2| {
+                    offset1
+                        .length()
+                        .value()
+                        .total_cmp(&offset2.length().value())
+                });
+            let heading = self.cell(handle).heading();
+            let reading = nearest.map(|offset| SensorReading {
+                distance: offset.length(),
+                bearing: Angle::from_radians(offset.angle().radians() - heading.radians()),
+            });
+            self.cell_graph
+                .node_mut(handle)
+                .set_sensor_reading(layer_index, reading);
+        }
+    }
+
+    fn cells_overlap(&self, handle1: NodeHandle, handle2: NodeHandle) -> bool {
+        let cell1 = self.cell(handle1);
+        let cell2 = self.cell(handle2);
+        let (point1, point2) = closest_axis_points(cell1, cell2);
+        (point1 - point2).length() < cell1.radius() + cell2.radius()
+    }
+
+    fn run_cell_controls(&mut self, changes: &mut WorldChanges) {
+        // TODO test: inner layer grows while outer layer buds at correct distance
+        let mut graph_changes = mem::take(&mut self.graph_changes);
+        graph_changes.clear();
+        // Controls only touch their own cells, so they can run in parallel. Their bond requests
+        // are then executed one cell at a time, in order.
+        let control_lod = self.control_lod;
+        let child_placement = self.child_placement;
+        let tissue_multipliers = &self.tissue_multipliers;
+        let mut all_bond_requests = mem::take(&mut self.bond_requests);
+        all_bond_requests.clear();
+        all_bond_requests.resize(self.cell_graph.nodes().len(), NONE_BOND_REQUESTS);
+        let mut control_work: Vec<(&mut Cell, &mut CellChanges, &mut BondRequests)> = self
+            .cell_graph
+            .nodes_mut()
+            .iter_mut()
+            .zip(changes.cells.iter_mut())
+            .zip(all_bond_requests.iter_mut())
+            .map(|((cell, cell_changes), bond_requests)| (cell, cell_changes, bond_requests))
+            .collect();
+        self.parallelism
+            .for_each(&mut control_work, |(cell, cell_changes, bond_requests)| {
+                cell.run_control_with_lod(
+                    bond_requests,
+                    cell_changes,
+                    control_lod.as_ref(),
+                    tissue_multipliers,
+                )
+            });
+        self.cell_graph.for_each_node(|index, cell, edge_source| {
+            let bond_requests = &all_bond_requests[index];
+            Self::execute_bond_requests(
+                cell,
+                edge_source,
+                bond_requests,
+                child_placement,
+                &mut graph_changes.new_children,
+                &mut graph_changes.broken_bond_handles,
+            );
+            if cell.is_alive() {
+                Self::collect_adhesion_offers(
+                    cell,
+                    bond_requests,
+                    &mut graph_changes.adhesion_offers,
+                );
+            } else {
+                graph_changes.dead_cell_handles.push(cell.node_handle());
+            }
+        });
+        self.bond_requests = all_bond_requests;
+        self.update_cell_graph(&mut graph_changes);
+        self.graph_changes = graph_changes;
+    }
+
+    fn collect_adhesion_offers(
+        cell: &Cell,
+        bond_requests: &BondRequests,
+        adhesion_offers: &mut Vec<AdhesionOffer>,
+    ) {
+        for (index, bond_request) in bond_requests.iter().enumerate() {
+            let is_budding =
+                bond_request.retain_bond && bond_request.donation_energy != BioEnergy::ZERO;
+            if bond_request.adhere && !is_budding && !cell.has_edge(index) {
+                adhesion_offers.push(AdhesionOffer {
+                    cell_handle: cell.node_handle(),
+                    bond_index: index,
+                    taken: false,
+                });
+            }
+        }
+    }
+
+    fn execute_bond_requests(
+        cell: &mut Cell,
+        edge_source: &mut EdgeSource<Bond>,
+        bond_requests: &BondRequests,
+        child_placement: ChildPlacementStrategy,
+        new_children: &mut Vec<NewChildData>,
+        broken_bond_handles: &mut Vec<EdgeHandle>,
+    ) {
+        for (index, bond_request) in bond_requests.iter().enumerate() {
+            let mut is_budding = false;
+            if bond_request.retain_bond {
+                if bond_request.donation_energy != BioEnergy::ZERO {
+                    if cell.has_edge(index) {
+                        let bond = edge_source.edge(cell.edge_handle(index));
+                        bond.set_energy_from_cell(cell.node_handle(), bond_request.donation_energy);
+                    } else if bond_request.child_bond_index < BondRequest::MAX_BONDS {
+                        is_budding = true;
+                        let mut child = cell.create_and_place_child_cell(
+                            bond_request.budding_angle,
+                            bond_request.child_layer_area,
+                            bond_request.child_energy,
+                            child_placement,
+                        );
+                        child.set_heading(cell.heading() + bond_request.child_heading_offset);
+                        child.set_spore(Spore::from_hatch_conditions(
+                            bond_request.child_hatch_light_intensity,
+                            bond_request.child_hatch_ticks,
+                        ));
+                        new_children.push(NewChildData {
+                            parent: cell.node_handle(),
+                            bond_index: index,
+                            child_bond_index: bond_request.child_bond_index,
+                            child,
+                            donated_energy: bond_request.donation_energy,
+                        });
+                    } else {
+                        // no child to donate to
+                        cell.add_energy(bond_request.donation_energy);
+                    }
+                }
+            } else if cell.has_edge(index) {
+                broken_bond_handles.push(cell.edge_handle(index));
+            }
+            if !is_budding {
+                // return the unused endowment
+                cell.add_energy(bond_request.child_energy);
+            }
+        }
+    }
+
+    fn update_cell_graph(&mut self, graph_changes: &mut GraphChangeBuffers) {
+        let num_bonds_before = self.bonds().len();
+        // Both cells of a bond may ask to break it
+        graph_changes.broken_bond_handles.sort_unstable();
+        graph_changes.broken_bond_handles.dedup();
+        let num_requested_changes =
+            graph_changes.new_children.len() + graph_changes.broken_bond_handles.len();
+        let mut allowance = match self.churn_cap {
+            Some(cap) if cap.overflow == ChurnOverflow::Drop => Some(cap.max_bond_changes),
+            _ => None,
+        };
+        let num_allowed_children = allowed_len(graph_changes.new_children.len(), &mut allowance);
+        self.refund_dropped_children(graph_changes.new_children.drain(num_allowed_children..));
+        self.add_children(
+            graph_changes.new_children.drain(..),
+            &mut graph_changes.child_handles,
+        );
+        self.notify_plugins_of_cells(&graph_changes.child_handles, |plugin, world, cell| {
+            plugin.on_cell_born(world, cell)
+        });
+        let num_allowed_breaks =
+            allowed_len(graph_changes.broken_bond_handles.len(), &mut allowance);
+        self.cell_graph
+            .remove_edges(&graph_changes.broken_bond_handles[..num_allowed_breaks]);
+        let (num_adhesion_bonds, num_dropped_adhesions) =
+            self.add_adhesion_bonds(&mut graph_changes.adhesion_offers, &mut allowance);
+        self.check_churn_cap(num_requested_changes + num_adhesion_bonds + num_dropped_adhesions);
+        self.remove_cells(&graph_changes.dead_cell_handles);
+        // Each child is bonded to its parent
+        let num_bonds_formed = graph_changes.child_handles.len() + num_adhesion_bonds;
+        self.bonds_formed += num_bonds_formed;
+        self.bonds_broken += num_bonds_before + num_bonds_formed - self.bonds().len();
+    }
+
+    // Puts the dropped buds' energy, including what they would have been donated, back into
+    // their parents.
+    fn refund_dropped_children(&mut self, dropped_children: impl Iterator<Item = NewChildData>) {
+        for new_child_data in dropped_children {
+            let refund = new_child_data.child.energy() + new_child_data.donated_energy;
+            self.cell_graph
+                .node_mut(new_child_data.parent)
+                .add_energy(refund);
+        }
+    }
+
+    fn check_churn_cap(&mut self, num_bond_changes: usize) {
+        let max_bond_changes = match self.churn_cap {
+            Some(cap) => cap.max_bond_changes,
+            None => return,
+        };
+        self.churn_overflow = num_bond_changes.saturating_sub(max_bond_changes);
+        if self.churn_overflow > 0 {
+            self.event_log.record(
+                self.current_tick,
+                WorldEventKind::ChurnCapExceeded {
+                    bond_changes: num_bond_changes,
+                },
+            );
+        }
+    }
+
+    // Bonds pairs of overlapping, unbonded cells that both offered free bond slots. Each
+    // offered slot is used at most once, first come first served in cell order. Pairs beyond
+    // the allowance, if any, are not bonded. Returns the numbers of bonds formed and dropped.
+    fn add_adhesion_bonds(
+        &mut self,
+        adhesion_offers: &mut [AdhesionOffer],
+        allowance: &mut Option<usize>,
+    ) -> (usize, usize) {
+        if adhesion_offers.is_empty() {
+            return (0, 0);
+        }
+        // The offers are in cell order, so each cell's offers are together, and an offer can
+        // only be paired with a later cell's offer.
+        let overlapping_pairs = find_overlapping_pairs(&mut self.cell_graph, |handle| {
+            adhesion_offers
+                .binary_search_by_key(&handle, |offer| offer.cell_handle)
+                .is_ok()
+        });
+        let mut num_bonds = 0;
+        let mut num_dropped = 0;
+        for i in 0..adhesion_offers.len() {
+            let offer1 = adhesion_offers[i];
+            if offer1.taken {
+                continue;
+            }
+            let start = overlapping_pairs.partition_point(|(cell1, _)| *cell1 < offer1.cell_handle);
+            let partner_offer = overlapping_pairs[start..]
+                .iter()
+                .take_while(|(cell1, _)| *cell1 == offer1.cell_handle)
+                .filter_map(|(_, cell2)| Self::first_untaken_offer(adhesion_offers, *cell2))
+                .find(|&j| self.can_adhere(offer1.cell_handle, adhesion_offers[j].cell_handle));
+            let j = match partner_offer {
+                Some(j) => j,
+                None => continue,
+            };
+            let offer2 = adhesion_offers[j];
+            adhesion_offers[i].taken = true;
+            adhesion_offers[j].taken = true;
+            if *allowance == Some(0) {
+                num_dropped += 1;
+                continue;
+            }
+            if let Some(remaining) = allowance {
+                *remaining -= 1;
+            }
+            let bond = Bond::new(self.cell(offer1.cell_handle), self.cell(offer2.cell_handle));
+            self.add_bond(bond, offer1.bond_index, offer2.bond_index);
+            num_bonds += 1;
+        }
+        (num_bonds, num_dropped)
+    }
+
+    fn first_untaken_offer(adhesion_offers: &[AdhesionOffer], handle: NodeHandle) -> Option<usize> {
+        let start = adhesion_offers.partition_point(|offer| offer.cell_handle < handle);
+        adhesion_offers[start..]
+            .iter()
+            .take_while(|offer| offer.cell_handle == handle)
+            .position(|offer| !offer.taken)
+            .map(|index| start + index)
+    }
+
+    fn can_adhere(&self, handle1: NodeHandle, handle2: NodeHandle) -> bool {
+        if handle1 == handle2 {
+            return false;
+        }
+        self.cells_overlap(handle1, handle2)
+            && self.adhesion_rule.is_none_or(|rule| {
+                rule.allows(
+                    self.cell(handle1).adhesion_tag(),
+                    self.cell(handle2).adhesion_tag(),
+                )
+            })
+            && !self
+                .cell_graph
+                .have_edge(self.cell(handle1), self.cell(handle2))
+    }
+
+    fn add_children(
+        &mut self,
+        new_children: impl Iterator<Item = NewChildData>,
+        child_handles: &mut Vec<NodeHandle>,
+    ) {
+        for new_child_data in new_children {
+            self.births.push(new_child_data.child.lineage());
+            let child_handle = self.add_cell(new_child_data.child);
+            child_handles.push(child_handle);
+            if let Some(rule) = self.adhesion_rule {
+                let parent_tag = self.cell(new_child_data.parent).adhesion_tag();
+                let child = self.cell_graph.node_mut(child_handle);
+                let child_tag = rule.child_tag(parent_tag, child.id().value());
+                child.set_adhesion_tag(child_tag);
+            }
+            if let Some(genealogy) = &mut self.genealogy {
+                genealogy.record_birth(
+                    self.cell_graph.node(new_child_data.parent).id(),
+                    self.cell_graph.node(child_handle).id(),
+                );
+            }
+            let child = self.cell(child_handle);
+            let mut bond = Bond::new(self.cell(new_child_data.parent), child);
+            bond.set_energy_from_cell(new_child_data.parent, new_child_data.donated_energy);
+            self.add_bond(
+                bond,
+                new_child_data.bond_index,
+                new_child_data.child_bond_index,
+            );
+        }
+    }
+
+    fn tick_cells(&mut self) {
+        for cell in self.cell_graph.nodes_mut() {
+            Self::print_selected_cell_state(cell, "start");
+            Self::integrate_cell_motion(cell);
+        }
+        self.detect_blowup();
+        for cell in self.cell_graph.nodes_mut() {
+            Self::clear_cell_environment(cell);
+            Self::print_selected_cell_state(cell, "end");
+        }
+    }
+
+    // Before the cells' forces are cleared, so that the report can include them. The blowup's
+    // tick is the one the world will be at after this tick, as in checkpoints.
+    fn detect_blowup(&mut self) {
+        let bounds = match self.blowup_bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let cells: Vec<BlownUpCell> = self
+            .cells()
+            .iter()
+            .filter(|cell| !bounds.contain(cell.position(), cell.velocity()))
+            .map(|cell| BlownUpCell {
+                id: cell.id(),
+                position: cell.position(),
+                velocity: cell.velocity(),
+                net_force: cell.forces().net_force(),
+            })
+            .collect();
+        if cells.is_empty() {
+            return;
+        }
+        let tick = self.current_tick + 1;
+        self.event_log.record(
+            tick,
+            WorldEventKind::Blowup {
+                num_cells: cells.len(),
+            },
+        );
+        self.blowup = Some(Blowup { tick, cells });
+    }
+
+    fn integrate_cell_motion(cell: &mut Cell) {
+        cell.exert_forces_for_one_tick();
+        cell.move_for_one_tick();
+    }
+
+    fn clear_cell_environment(cell: &mut Cell) {
+        cell.environment_mut().clear();
+        cell.forces_mut().clear();
+    }
+
+    fn print_selected_cell_state(cell: &Cell, start_end_str: &str) {
+        if cell.is_selected() {
+            println!(
+                "Cell {} {} position: {}, velocity: {}, force: {}",
+                cell.node_handle(),
+                start_end_str,
+                cell.position(),
+                cell.velocity(),
+                cell.forces().net_force()
+            );
+            println!(
+                "Cell {} {} energy budget: {}",
+                cell.node_handle(),
+                start_end_str,
+                cell.energy_budget()
+            );
+        }
+    }
+
+    // TODO
+    fn _apply_changes(&mut self, changes: &WorldChanges) {
+        for (index, cell) in self.cell_graph.nodes_mut().iter_mut().enumerate() {
+            cell.apply_changes(&changes.cells[index]);
+        }
+    }
+}
+
+// What happened during one tick. Deaths and broken bonds do not include culls. Energy is as in
+// EnergyFlux, and wall collisions count the cells touching walls.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TickReport {
+    pub births: usize,
+    pub deaths: usize,
+    pub bonds_formed: usize,
+    pub bonds_broken: usize,
+    pub energy_injected: f64,
+    pub energy_dissipated: f64,
+    pub wall_collisions: usize,
+    pub churn: GraphChurn,
+    pub churn_overflow: usize,
+    pub breakpoint_hit: Option<BreakpointHit>,
+    pub limit_exceeded: Option<LimitExceeded>,
+    pub blown_up_cells: usize,
+}
+
+// A condition on the selected cells that should pause an interactive run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Breakpoint {
+    EnergyBelow(f64),
+    // Any of the cell's layers
+    HealthBelow(f64),
+    Death,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BreakpointHit {
+    pub cell_id: CellId,
+    pub breakpoint: Breakpoint,
+}
+
+impl BreakpointHit {
+    fn new(cell_id: CellId, breakpoint: Breakpoint) -> Self {
+        BreakpointHit {
+            cell_id,
+            breakpoint,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineageStats {
+    pub lineage: u32,
+    pub cells: usize,
+    pub biomass: Mass,
+    pub mean_energy: BioEnergy,
+    pub births: usize,
+    pub deaths: usize,
+}
+
+impl LineageStats {
+    fn new(lineage: u32) -> Self {
+        LineageStats {
+            lineage,
+            cells: 0,
+            biomass: Mass::ZERO,
+            mean_energy: BioEnergy::ZERO,
+            births: 0,
+            deaths: 0,
+        }
+    }
+}
+
+// A parasite cell attached to the host cell it siphons energy from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParasiteAttachment {
+    pub parasite: CellId,
+    pub host: CellId,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct AdhesionOffer {
+    cell_handle: NodeHandle,
+    bond_index: usize,
+    taken: bool,
+}
+
+// The graph changes the cells request in a tick, kept between ticks to reuse their buffers
+#[derive(Default)]
+struct GraphChangeBuffers {
+    new_children: Vec<NewChildData>,
+    broken_bond_handles: Vec<EdgeHandle>,
+    adhesion_offers: Vec<AdhesionOffer>,
+    dead_cell_handles: Vec<NodeHandle>,
+    child_handles: Vec<NodeHandle>,
+}
+
+impl GraphChangeBuffers {
+    fn clear(&mut self) {
+        self.new_children.clear();
+        self.broken_bond_handles.clear();
+        self.adhesion_offers.clear();
+        self.dead_cell_handles.clear();
+        self.child_handles.clear();
+    }
+}
+
+struct NewChildData {
+    parent: NodeHandle,
+    bond_index: usize,
+    child_bond_index: usize,
+    child: Cell,
+    donated_energy: BioEnergy,
+}
+
+// Returns how many of the items fit in the allowance, if there is one, and uses it up.
+fn allowed_len(num_items: usize, allowance: &mut Option<usize>) -> usize {
+    match allowance {
+        Some(remaining) => {
+            let allowed_len = num_items.min(*remaining);
+            *remaining -= allowed_len;
+            allowed_len
+        }
+        None => num_items,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::adhesion::AdhesionTag;
+    use crate::biology::control_requests::ControlRequest;
+    use crate::biology::tissue_multipliers::TissueMultiplier;
+    use crate::physics::overlap::Overlap;
+    use crate::test_doubles::*;
+    use crate::test_support::*;
+    use approx::assert_abs_diff_eq;
+    use std::cell::RefCell;
+    use std::f64::consts::PI;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn tick_moves_ball() {
+        let mut world = TestWorld::new()
+            .with_ball(Position::ORIGIN, Velocity::new(1.0, 1.0))
+            .build();
+
+        world.tick();
+
+        let ball = &world.cells()[0];
+        assert!(ball.position().x() > 0.0);
+        assert!(ball.position().y() > 0.0);
+    }
+
+    #[test]
+    fn tick_with_force_accelerates_ball() {
+        let mut world = TestWorld::new()
+            .with_constant_force(Force::new(1.0, 1.0))
+            .with_ball(Position::ORIGIN, Velocity::ZERO)
+            .build();
+
+        world.tick();
+
+        let ball = &world.cells()[0];
+        assert!(ball.velocity().x() > 0.0);
+        assert!(ball.velocity().y() > 0.0);
+    }
+
+    #[test]
+    fn colliding_balls_bounce_apart_without_gaining_energy() {
+        let mut world = TestWorld::new()
+            .with_pair_collisions()
+            .with_ball(Position::new(-2.0, 0.0), Velocity::new(0.5, 0.0))
+            .with_ball(Position::new(2.0, 0.0), Velocity::new(-0.5, 0.0))
+            .build();
+
+        assert_energy_conserved(&mut world, 10, 1e-6);
+
+        assert_cell_count(&world, 2);
+        assert!(world.cells()[0].velocity().x() < 0.0);
+        assert!(world.cells()[1].velocity().x() > 0.0);
+    }
+
+    #[test]
+    fn parasite_siphons_energy_from_overlapping_host_through_its_shell() {
+        let mut world = TestWorld::new()
+            .with_cell(
+                simple_layered_cell(vec![CellLayer::new(
+                    Area::new(PI),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(ParasiteCellLayerSpecialty::new(2.0, 1.0)),
+                )])
+                .with_initial_position(Position::new(1.0, 0.0)),
+            )
+            .with_cell(
+                simple_layered_cell(vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::White,
+                    Box::new(NullCellLayerSpecialty::new()),
+                )])
+                .with_initial_energy(BioEnergy::new(10.0)),
+            )
+            .build();
+
+        world.tick();
+
+        assert_eq!(
+            world.parasite_attachments(),
+            &[ParasiteAttachment {
+                parasite: world.cells()[0].id(),
+                host: world.cells()[1].id(),
+            }]
+        );
+        // the host's shell strength is 1, halving the siphon rate
+        assert_eq!(world.cells()[0].energy(), BioEnergy::new(1.0));
+        assert_eq!(world.cells()[1].energy(), BioEnergy::new(9.0));
+    }
+
+    #[test]
+    fn sensor_reads_nearest_cell_of_target_color_relative_to_heading() {
+        let mut world = TestWorld::new()
+            .with_cell(
+                simple_layered_cell(vec![CellLayer::new(
+                    Area::new(PI),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(SensorCellLayerSpecialty::new(
+                        Length::new(5.0),
+                        Some(Color::White),
+                    )),
+                )])
+                .with_heading(Angle::from_radians(PI / 2.0)),
+            )
+            .with_ball(Position::new(0.0, 2.5), Velocity::ZERO)
+            .with_cell(white_cell(Position::new(4.0, 0.0)))
+            .with_cell(white_cell(Position::new(-9.0, 0.0)))
+            .build();
+
+        world.tick();
+
+        let reading = world.cells()[0].layers()[0].sensor_reading().unwrap();
+        assert_eq!(reading.distance, Length::new(4.0));
+        assert_abs_diff_eq!(
+            reading.bearing,
+            Angle::from_radians(1.5 * PI),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn sensor_reads_nothing_out_of_range() {
+        let mut world = TestWorld::new()
+            .with_cell(simple_layered_cell(vec![CellLayer::new(
+                Area::new(PI),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(SensorCellLayerSpecialty::new(Length::new(5.0), None)),
+            )]))
+            .with_ball(Position::new(6.0, 0.0), Velocity::ZERO)
+            .build();
+
+        world.tick();
+
+        assert_eq!(world.cells()[0].layers()[0].sensor_reading(), None);
+    }
+
+    fn white_cell(position: Position) -> Cell {
+        simple_layered_cell(vec![CellLayer::new(
+            Area::new(PI),
+            Density::new(1.0),
+            Color::White,
+            Box::new(NullCellLayerSpecialty::new()),
+        )])
+        .with_initial_position(position)
+    }
+
+    #[test]
+    fn parasite_detaches_from_host_it_no_longer_overlaps() {
+        let mut world = TestWorld::new()
+            .with_cell(Cell::new(
+                Position::ORIGIN,
+                Velocity::new(5.0, 0.0),
+                vec![CellLayer::new(
+                    Area::new(PI),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(ParasiteCellLayerSpecialty::new(1.0, 0.0)),
+                )],
+            ))
+            .with_ball(Position::new(1.0, 0.0), Velocity::ZERO)
+            .build();
+
+        world.tick();
+        assert_eq!(world.parasite_attachments().len(), 1);
+
+        world.tick();
+        assert!(world.parasite_attachments().is_empty());
+    }
+
+    #[test]
+    fn overlaps_do_not_persist() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_influence(Box::new(UniversalOverlap::new(Overlap::new(
+                Displacement::new(1.0, 1.0),
+                1.0,
+            ))))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ));
+
+        world.tick();
+
+        let ball = &world.cells()[0];
+        assert!(ball.environment().overlaps().is_empty());
+    }
+
+    #[test]
+    fn forces_do_not_persist() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
+                ConstantForce::new(Force::new(1.0, 1.0)),
+            ))))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ));
+
+        world.tick();
+
+        let ball = &world.cells()[0];
+        assert_eq!(ball.forces().net_force(), Force::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn cannot_bounce_off_drag_force() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cell(Cell::ball(
+                Length::new(10.0),
+                Mass::new(0.01),
+                Position::ORIGIN,
+                Velocity::new(10.0, 10.0),
+            ))
+            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
+                DragForce::new(0.01),
+            ))));
+
+        world.tick();
+
+        let ball = &world.cells()[0];
+        assert!(ball.velocity().x() >= 0.0);
+        assert!(ball.velocity().y() >= 0.0);
+    }
+
+    #[test]
+    fn tick_runs_photo_layer() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_influence(Box::new(Sunlight::new(-10.0, 10.0, 0.0, 10.0)))
+            .with_cell(simple_layered_cell(vec![CellLayer::new(
+                Area::new(10.0),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(PhotoCellLayerSpecialty::new(1.0)),
+            )]));
+
+        world.tick();
+
+        let cell = &world.cells()[0];
+        assert_eq!(cell.energy().value().round(), 50.0);
+    }
+
+    #[test]
+    fn tissue_energy_gain_multiplier_scales_photo_layer() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_influence(Box::new(Sunlight::new(-10.0, 10.0, 0.0, 10.0)))
+            .with_tissue_multipliers(TissueMultipliers::new().with_multiplier(
+                Color::Green,
+                TissueMultiplier {
+                    energy_gain: 0.5,
+                    cost: 1.0,
+                },
+            ))
+            .with_cell(simple_layered_cell(vec![CellLayer::new(
+                Area::new(10.0),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(PhotoCellLayerSpecialty::new(1.0)),
+            )]));
+
+        world.tick();
+
+        let cell = &world.cells()[0];
+        assert_eq!(cell.energy().value().round(), 25.0);
+    }
+
+    #[test]
+    fn tissue_cost_multiplier_scales_request_costs() {
+        const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
+            growth_energy_delta: BioEnergyDelta::new(-1.0),
+            ..LayerResizeParameters::UNLIMITED
+        };
+
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_tissue_multipliers(TissueMultipliers::new().with_multiplier(
+                Color::Yellow,
+                TissueMultiplier {
+                    energy_gain: 1.0,
+                    cost: 3.0,
+                },
+            ))
+            .with_cell(
+                simple_layered_cell(vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Yellow,
+                    Box::new(NullCellLayerSpecialty::new()),
+                )
+                .with_resize_parameters(LAYER_RESIZE_PARAMS)])
+                .with_control(Box::new(ContinuousResizeControl::new(
+                    0,
+                    AreaDelta::new(2.0),
+                )))
+                .with_initial_energy(BioEnergy::new(10.0)),
+            );
+
+        world.tick();
+
+        let cell = &world.cells()[0];
+        assert_eq!(cell.area(), Area::new(3.0));
+        assert_eq!(cell.energy(), BioEnergy::new(4.0));
+    }
+
+    #[test]
+    fn tick_runs_cell_growth() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+            simple_layered_cell(vec![CellLayer::new(
+                Area::new(1.0),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(NullCellLayerSpecialty::new()),
+            )])
+            .with_control(Box::new(ContinuousResizeControl::new(
+                0,
+                AreaDelta::new(2.0),
+            ))),
+        );
+
+        world.tick();
+
+        let cell = &world.cells()[0];
+        assert_eq!(cell.area(), Area::new(3.0));
+    }
+
+    #[test]
+    fn tick_runs_cell_thruster() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(
+                simple_layered_cell(vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(ThrusterCellLayerSpecialty::new(0.0)),
+                )])
+                .with_heading(Angle::from_radians(-PI / 4.0))
+                .with_control(Box::new(SimpleThrusterControl::new(
+                    0,
+                    1.0,
+                    Deflection::ZERO,
+                ))),
+            );
+
+        world.tick();
+        world.tick();
+
+        let cell = &world.cells()[0];
+        assert!(cell.velocity().x() > 0.0);
+        assert!(cell.velocity().y() < 0.0);
+    }
+
+    #[test]
+    fn growth_is_limited_by_energy() {
+        const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
+            growth_energy_delta: BioEnergyDelta::new(-10.0),
+            ..LayerResizeParameters::UNLIMITED
+        };
+
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(Sunlight::new(-10.0, 10.0, 0.0, 10.0)))
+            .with_cell(
+                simple_layered_cell(vec![CellLayer::new(
+                    Area::new(10.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(PhotoCellLayerSpecialty::new(1.0)),
+                )
+                .with_resize_parameters(LAYER_RESIZE_PARAMS)])
+                .with_control(Box::new(ContinuousResizeControl::new(
+                    0,
+                    AreaDelta::new(100.0),
+                ))),
+            );
+
+        world.tick();
+
+        let cell = &world.cells()[0];
+        assert_eq!(cell.area().value().round(), 15.0);
+    }
+
+    #[test]
+    fn child_bond_goes_in_requested_child_slot() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+            Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )],
+            )
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+                BondingCellLayerSpecialty::child_bond_index_request(0, 1, 3),
+                BondingCellLayerSpecialty::retain_bond_request(0, 2, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 2, BioEnergy::new(1.0)),
+                BondingCellLayerSpecialty::child_bond_index_request(0, 2, 99),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0)),
+        );
+
+        world.tick();
+
+        assert_eq!(world.cells().len(), 2);
+        assert!(!world.cells()[0].has_edge(2));
+        assert_eq!(world.cells()[0].energy(), BioEnergy::new(9.0));
+        let child = &world.cells()[1];
+        assert!(child.has_edge(3));
+        assert!(!child.has_edge(0));
+    }
+
+    #[test]
+    fn child_heading_is_parent_heading_plus_requested_offset() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+            Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )],
+            )
+            .with_heading(Angle::from_radians(1.0))
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+                BondingCellLayerSpecialty::child_heading_offset_request(
+                    0,
+                    1,
+                    Deflection::from_radians(0.5),
+                ),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0)),
+        );
+
+        world.tick();
+
+        assert_eq!(world.cells()[1].heading(), Angle::from_radians(1.5));
+    }
+
+    #[test]
+    fn donated_energy_loses_inefficiency_as_heat() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+            ])
+            .with_donation_efficiency(0.75);
+        let mut bond = Bond::new(&world.cells()[0], &world.cells()[1]);
+        bond.set_energy_from_cell(world.cells()[0].node_handle(), BioEnergy::new(2.0));
+        world.add_bond(bond, 0, 0);
+
+        world.tick();
+
+        assert_eq!(world.cells()[1].energy(), BioEnergy::new(1.5));
+        assert_eq!(world.energy_flux().dissipated, 0.5);
+    }
+
+    #[test]
+    fn promptly_claimed_bond_energy_does_not_decay() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+            ])
+            .with_bond_energy_decay(0.5);
+        let mut bond = Bond::new(&world.cells()[0], &world.cells()[1]);
+        bond.set_energy_from_cell(world.cells()[0].node_handle(), BioEnergy::new(2.0));
+        world.add_bond(bond, 0, 0);
+
+        world.tick();
+
+        assert_eq!(world.cells()[1].energy(), BioEnergy::new(2.0));
+        assert_eq!(world.energy_flux().dissipated, 0.0);
+    }
+
+    #[test]
+    fn unclaimed_bond_energy_decays_every_tick_it_waits() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                ),
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::new(2.0, 0.0),
+                    Velocity::ZERO,
+                ),
+            ])
+            .with_control_lod(ControlLod {
+                interval: 5,
+                max_radius: Length::new(10.0),
+                max_energy: BioEnergy::ZERO,
+            })
+            .with_bond_energy_decay(0.5);
+        let mut bond = Bond::new(&world.cells()[0], &world.cells()[1]);
+        bond.set_energy_from_cell(world.cells()[0].node_handle(), BioEnergy::new(2.0));
+        world.add_bond(bond, 0, 0);
+
+        // The second cell's ID is 2, so it first runs its control, and claims, at age 3
+        world.tick();
+        assert_eq!(world.bonds()[0].energy_for_cell2(), BioEnergy::new(1.0));
+        assert_eq!(world.energy_flux().dissipated, 1.0);
+        world.tick();
+        assert_eq!(world.bonds()[0].energy_for_cell2(), BioEnergy::new(0.5));
+        assert_eq!(world.cells()[1].energy(), BioEnergy::ZERO);
+        world.tick();
+
+        assert_eq!(world.bonds()[0].energy_for_cell2(), BioEnergy::ZERO);
+        assert_eq!(world.cells()[1].energy(), BioEnergy::new(0.5));
+        assert_eq!(world.energy_flux().dissipated, 0.0);
+    }
+
+    #[test]
+    fn new_cell_is_added_to_world_with_bond_to_parent() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+            Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )],
+            )
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0)),
+        );
+
+        world.tick();
+
+        assert_eq!(world.cells().len(), 2);
+        assert_eq!(world.bonds().len(), 1);
+        let parent = &world.cells()[0];
+        assert!(parent.has_edge(1));
+        let child = &world.cells()[1];
+        assert!(child.has_edge(0));
+        assert_eq!(parent.energy(), BioEnergy::new(9.0));
+        assert_eq!(child.energy(), BioEnergy::ZERO);
+        let bond = &world.bonds()[0];
+        assert_eq!(bond.energy_for_cell2(), BioEnergy::new(1.0));
+    }
+
+    #[test]
+    fn parent_endows_new_cell_with_layer_area_and_energy() {
+        const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
+            growth_energy_delta: BioEnergyDelta::new(-0.5),
+            ..LayerResizeParameters::UNLIMITED
+        };
+
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+            Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )
+                .with_resize_parameters(LAYER_RESIZE_PARAMS)],
+            )
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+                BondingCellLayerSpecialty::child_layer_area_request(0, 1, Area::new(2.0)),
+                BondingCellLayerSpecialty::child_energy_request(0, 1, BioEnergy::new(3.0)),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0)),
+        );
+
+        world.tick();
+
+        let parent = &world.cells()[0];
+        assert_eq!(parent.energy(), BioEnergy::new(6.0));
+        let child = &world.cells()[1];
+        assert_eq!(child.layers()[0].area(), Area::new(2.0));
+        assert_eq!(child.energy(), BioEnergy::new(2.0));
+        assert_eq!(child.parent_id(), Some(parent.id()));
+    }
+
+    #[test]
+    fn genealogy_records_budding_and_forgets_dead_leaves() {
+        const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
+            growth_energy_delta: BioEnergyDelta::new(-0.5),
+            ..LayerResizeParameters::UNLIMITED
+        };
+
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_genealogy()
+            .with_cell(
+                Cell::new(
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                    vec![CellLayer::new(
+                        Area::new(1.0),
+                        Density::new(1.0),
+                        Color::Green,
+                        Box::new(BondingCellLayerSpecialty::new()),
+                    )
+                    .with_resize_parameters(LAYER_RESIZE_PARAMS)],
+                )
+                .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                    BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                    BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+                    BondingCellLayerSpecialty::child_layer_area_request(0, 1, Area::new(2.0)),
+                    BondingCellLayerSpecialty::child_energy_request(0, 1, BioEnergy::new(3.0)),
+                ])))
+                .with_initial_energy(BioEnergy::new(10.0)),
+            );
+
+        world.tick();
+
+        let parent_id = world.cells()[0].id();
+        let child_id = world.cells()[1].id();
+        let genealogy = world.genealogy().unwrap();
+        assert_eq!(genealogy.parent(child_id), Some(parent_id));
+        assert_eq!(genealogy.children(parent_id), &[child_id]);
+
+        world.select_only_cell(child_id);
+        world.kill_selected_cells();
+
+        assert!(world.genealogy().unwrap().is_empty());
+    }
+
+    #[test]
+    fn deterministic_parallel_ticks_match_serial_ticks() {
+        fn budding_world(parallelism: Parallelism) -> World {
+            let cells = (0..5)
+                .map(|i| {
+                    Cell::new(
+                        Position::new(i as f64 * 20.0 - 40.0, 0.0),
+                        Velocity::new(0.5, 0.25),
+                        vec![
+                            CellLayer::new(
+                                Area::new(5.0),
+                                Density::new(1.0),
+                                Color::Green,
+                                Box::new(PhotoCellLayerSpecialty::new(1.0)),
+                            ),
+                            CellLayer::new(
+                                Area::new(1.0),
+                                Density::new(1.0),
+                                Color::Yellow,
+                                Box::new(BondingCellLayerSpecialty::new()),
+                            ),
+                        ],
+                    )
+                    .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                        BondingCellLayerSpecialty::retain_bond_request(1, 1, true),
+                        BondingCellLayerSpecialty::donation_energy_request(
+                            1,
+                            1,
+                            BioEnergy::new(1.0),
+                        ),
+                    ])))
+                    .with_initial_energy(BioEnergy::new(5.0))
+                })
+                .collect();
+            World::new(Position::new(-50.0, -50.0), Position::new(50.0, 50.0))
+                .with_parallelism(parallelism)
+                .with_standard_influences()
+                .with_sunlight(0.0, 10.0)
+                .with_cells(cells)
+        }
+
+        let mut serial_world = budding_world(Parallelism::Serial);
+        let mut parallel_world = budding_world(Parallelism::Deterministic { num_threads: 3 });
+        tick_n(&mut serial_world, 5);
+        tick_n(&mut parallel_world, 5);
+
+        assert!(serial_world.cells().len() > 5);
+        assert_eq!(
+            parallel_world.to_document().to_json(),
+            serial_world.to_document().to_json()
+        );
+    }
+
+    #[test]
+    fn budded_spore_lets_go_of_its_parent() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+            Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )],
+            )
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+                BondingCellLayerSpecialty::child_hatch_ticks_request(0, 1, 10),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0)),
+        );
+
+        world.tick();
+        assert!(world.cells()[1].is_spore());
+        assert_eq!(world.bonds().len(), 1);
+
+        world.tick();
+        assert_eq!(world.cells()[1].energy(), BioEnergy::new(1.0));
+        assert!(!world.cells()[1].has_edge(0));
+    }
+
+    #[test]
+    fn unused_child_energy_is_returned_to_parent() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+            Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )],
+            )
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::child_energy_request(0, 1, BioEnergy::new(3.0)),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0)),
+        );
+
+        world.tick();
+
+        assert_eq!(world.cells().len(), 1);
+        assert_eq!(world.cells()[0].energy(), BioEnergy::new(10.0));
+    }
+
+    #[test]
+    fn overlapping_cells_that_both_offer_slots_adhere() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            adhering_cell(Position::new(0.0, 0.0), true),
+            adhering_cell(Position::new(1.5, 0.0), true),
+        ]);
+
+        world.tick();
+
+        assert_eq!(world.bonds().len(), 1);
+        assert!(world.cells()[0].has_edge(2));
+        assert!(world.cells()[1].has_edge(2));
+    }
+
+    #[test]
+    fn adhesion_offers_are_paired_in_cell_order() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            adhering_cell(Position::new(1.5, 0.0), true),
+            adhering_cell(Position::new(0.0, 0.0), true),
+            adhering_cell(Position::new(0.75, 1.2), true),
+        ]);
+
+        world.tick();
+
+        assert_eq!(world.bonds().len(), 1);
+        assert!(world.cells()[0].has_edge(2));
+        assert!(world.cells()[1].has_edge(2));
+        assert!(!world.cells()[2].has_edge(2));
+    }
+
+    #[test]
+    fn cell_that_does_not_offer_a_slot_is_not_bonded() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            adhering_cell(Position::new(0.0, 0.0), true),
+            adhering_cell(Position::new(1.5, 0.0), false),
+        ]);
+
+        world.tick();
+
+        assert!(world.bonds().is_empty());
+    }
+
+    #[test]
+    fn cells_that_do_not_overlap_do_not_adhere() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            adhering_cell(Position::new(0.0, 0.0), true),
+            adhering_cell(Position::new(5.0, 0.0), true),
+        ]);
+
+        world.tick();
+
+        assert!(world.bonds().is_empty());
+    }
+
+    #[test]
+    fn cells_with_incompatible_adhesion_tags_do_not_adhere() {
+        let rule = AdhesionRule {
+            max_distance: 1,
+            bit_flip_rate: 0.0,
+            seed: 0,
+        };
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_adhesion_rule(rule)
+            .with_cells(vec![
+                adhering_cell(Position::new(0.0, 0.0), true).with_adhesion_tag(AdhesionTag::new(0)),
+                adhering_cell(Position::new(1.5, 0.0), true)
+                    .with_adhesion_tag(AdhesionTag::new(0b11)),
+                adhering_cell(Position::new(0.0, 1.5), true)
+                    .with_adhesion_tag(AdhesionTag::new(0b10)),
+            ]);
+
+        world.tick();
+
+        // the first cell skips the second and bonds with the third
+        assert_eq!(world.bonds().len(), 1);
+        assert!(!world.cells()[1].has_edge(2));
+        assert!(world.cells()[2].has_edge(2));
+    }
+
+    #[test]
+    fn budded_cells_get_mutated_adhesion_tags() {
+        let rule = AdhesionRule {
+            max_distance: 0,
+            bit_flip_rate: 0.5,
+            seed: 1,
+        };
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_adhesion_rule(rule)
+            .with_cells(vec![Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )],
+            )
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0))
+            .with_adhesion_tag(AdhesionTag::new(0xf0f0))]);
+
+        world.tick();
+
+        let child = &world.cells()[1];
+        assert_eq!(
+            child.adhesion_tag(),
+            rule.child_tag(AdhesionTag::new(0xf0f0), child.id().value())
+        );
+        assert_ne!(child.adhesion_tag(), AdhesionTag::new(0xf0f0));
+    }
+
+    fn adhering_cell(position: Position, adhere: bool) -> Cell {
+        Cell::new(
+            position,
+            Velocity::ZERO,
+            vec![CellLayer::new(
+                Area::new(PI),
+                Density::new(1.0),
+                Color::Yellow,
+                Box::new(BondingCellLayerSpecialty::new()),
+            )],
+        )
+        .with_control(Box::new(ContinuousRequestsControl::new(vec![
+            BondingCellLayerSpecialty::retain_bond_request(0, 2, true),
+            BondingCellLayerSpecialty::adhere_request(0, 2, adhere),
+        ])))
+    }
+
+    #[test]
+    fn cells_can_pass_energy_through_bond() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                Cell::new(
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                    vec![CellLayer::new(
+                        Area::new(1.0),
+                        Density::new(1.0),
+                        Color::Green,
+                        Box::new(BondingCellLayerSpecialty::new()),
+                    )],
+                )
+                .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                    BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                    BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(2.0)),
+                ])))
+                .with_initial_energy(BioEnergy::new(10.0)),
+                Cell::new(
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                    vec![CellLayer::new(
+                        Area::new(1.0),
+                        Density::new(1.0),
+                        Color::Green,
+                        Box::new(BondingCellLayerSpecialty::new()),
+                    )],
+                )
+                .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                    BondingCellLayerSpecialty::retain_bond_request(0, 0, true),
+                    BondingCellLayerSpecialty::donation_energy_request(0, 0, BioEnergy::new(3.0)),
+                ])))
+                .with_initial_energy(BioEnergy::new(10.0)),
+            ])
+            .with_bonds(vec![(0, 1)]);
+
+        world.tick();
+
+        assert_eq!(world.cells().len(), 2);
+        assert_eq!(world.bonds().len(), 1);
+        let cell1 = &world.cells()[0];
+        assert_eq!(cell1.energy(), BioEnergy::new(8.0));
+        let cell2 = &world.cells()[1];
+        assert_eq!(cell2.energy(), BioEnergy::new(7.0));
+        let bond = &world.bonds()[0];
+        assert_eq!(bond.energy_for_cell1(), BioEnergy::new(3.0));
+        assert_eq!(bond.energy_for_cell2(), BioEnergy::new(2.0));
+
+        world.tick();
+
+        let cell1 = &world.cells()[0];
+        assert_eq!(cell1.energy(), BioEnergy::new(9.0)); // 8 + 3 - 2
+        let cell2 = &world.cells()[1];
+        assert_eq!(cell2.energy(), BioEnergy::new(6.0)); // 7 + 2 - 3
+        let bond = &world.bonds()[0];
+        assert_eq!(bond.energy_for_cell1(), BioEnergy::new(3.0));
+        assert_eq!(bond.energy_for_cell2(), BioEnergy::new(2.0));
+    }
+
+    #[test]
+    fn world_breaks_bond_when_requested() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                simple_layered_cell(vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )])
+                .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                    BondingCellLayerSpecialty::retain_bond_request(0, 1, false),
+                ]))),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+            ])
+            .with_bonds(vec![(0, 1)]);
+
+        world.tick();
+
+        assert_eq!(world.bonds().len(), 0);
+    }
+
+    #[test]
+    fn dead_cells_get_removed_from_world() {
+        let mut world =
+            World::new(Position::ORIGIN, Position::ORIGIN).with_cell(simple_layered_cell(vec![
+                simple_cell_layer(Area::new(1.0), Density::new(1.0)).dead(),
+            ]));
+
+        world.tick();
+
+        assert_eq!(world.cells().len(), 0);
+    }
+
+    #[test]
+    fn lineage_stats_count_cells_births_and_deaths() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            Cell::new(
+                Position::ORIGIN,
+                Velocity::ZERO,
+                vec![CellLayer::new(
+                    Area::new(1.0),
+                    Density::new(1.0),
+                    Color::Green,
+                    Box::new(BondingCellLayerSpecialty::new()),
+                )],
+            )
+            .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+            ])))
+            .with_initial_energy(BioEnergy::new(10.0))
+            .with_lineage(1),
+            simple_layered_cell(vec![
+                simple_cell_layer(Area::new(1.0), Density::new(1.0)).dead()
+            ])
+            .with_lineage(2),
+        ]);
+
+        world.tick();
+
+        let stats = world.lineage_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].lineage, 1);
+        assert_eq!(stats[0].cells, 2);
+        assert_eq!(stats[0].births, 1);
+        assert_eq!(stats[0].deaths, 0);
+        assert!(stats[0].biomass > Mass::ZERO);
+        assert_eq!(stats[1].lineage, 2);
+        assert_eq!(stats[1].cells, 0);
+        assert_eq!(stats[1].deaths, 1);
+    }
+
+    #[test]
+    fn tick_drives_influences_then_layers_then_controls() {
+        let log = CallLog::new();
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(MockInfluence::new(&log)))
+            .with_cell(mock_cell(
+                &log,
+                MockControl::new(&log).with_requests(vec![ControlRequest::new(0, 2, 0, 0.5)]),
+            ));
+
+        world.tick();
+        world.tick();
+
+        assert_eq!(
+            log.calls(),
+            vec![
+                "before_tick 0",
+                "apply 1",
+                "after_influences",
+                "run 0",
+                "execute 2 0.5",
+                "before_tick 1",
+                "apply 1",
+                "after_influences",
+                "run 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled_influences_are_not_applied() {
+        let log = CallLog::new();
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(MockInfluence::new(&log)))
+            .with_cell(mock_cell(&log, MockControl::new(&log)));
+        world.set_influence_enabled("constant_force", false);
+
+        world.tick();
+
+        assert_eq!(log.count("before_tick"), 1);
+        assert_eq!(log.count("apply"), 0);
+    }
+
+    #[test]
+    fn influence_forces_move_cells() {
+        let log = CallLog::new();
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(
+                MockInfluence::new(&log).with_force(Force::new(1.0, 0.0)),
+            ))
+            .with_cell(mock_cell(&log, MockControl::new(&log)));
+
+        world.tick();
+
+        assert!(world.cells()[0].velocity().x() > 0.0);
+        assert_eq!(world.cells()[0].velocity().y(), 0.0);
+    }
+
+    #[test]
+    fn light_intensity_sums_influences() {
+        let log = CallLog::new();
+        let world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(
+                MockInfluence::new(&log).with_light_intensity(0.25),
+            ))
+            .with_influence(Box::new(MockInfluence::new(&log).with_light_intensity(0.5)));
+
+        assert_eq!(world.light_intensity_at(Position::ORIGIN), 0.75);
+    }
+
+    #[test]
+    fn world_totals_influence_energy_flux() {
+        let log = CallLog::new();
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(
+                MockInfluence::new(&log).with_energy_flux(EnergyFlux::injected(2.0)),
+            ))
+            .with_influence(Box::new(
+                MockInfluence::new(&log).with_energy_flux(EnergyFlux::dissipated(0.5)),
+            ))
+            .with_cell(mock_cell(&log, MockControl::new(&log)));
+
+        world.tick();
+
+        assert_eq!(
+            world.energy_flux(),
+            EnergyFlux {
+                injected: 2.0,
+                dissipated: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn control_lod_runs_small_cells_controls_less_often() {
+        #[derive(Debug)]
+        struct CountingControl {
+            runs: Arc<AtomicUsize>,
+        }
+
+        impl CellControl for CountingControl {
+            fn run(&mut self, _cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+                self.runs.fetch_add(1, Ordering::Relaxed);
+                vec![]
+            }
+
+            fn spawn(&mut self) -> Box<dyn CellControl> {
+                Box::new(NullControl::new())
+            }
+        }
+
+        let small_runs = Arc::new(AtomicUsize::new(0));
+        let large_runs = Arc::new(AtomicUsize::new(0));
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_control_lod(ControlLod {
+                interval: 2,
+                max_radius: Length::new(2.0),
+                max_energy: BioEnergy::ZERO,
+            })
+            .with_cells(vec![
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::new(-5.0, 0.0),
+                    Velocity::ZERO,
+                )
+                .with_control(Box::new(CountingControl {
+                    runs: Arc::clone(&small_runs),
+                })),
+                Cell::ball(
+                    Length::new(3.0),
+                    Mass::new(1.0),
+                    Position::new(5.0, 0.0),
+                    Velocity::ZERO,
+                )
+                .with_control(Box::new(CountingControl {
+                    runs: Arc::clone(&large_runs),
+                })),
+            ]);
+
+        tick_n(&mut world, 4);
+
+        assert_eq!(small_runs.load(Ordering::Relaxed), 2);
+        assert_eq!(large_runs.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn plugins_see_every_hook_birth_and_death() {
+        #[derive(Default)]
+        struct HookCounts {
+            ticks: usize,
+            after_influences: usize,
+            after_controls: usize,
+            born_lineages: Vec<u32>,
+            died_lineages: Vec<u32>,
+        }
+
+        struct CountingPlugin {
+            counts: Rc<RefCell<HookCounts>>,
+        }
+
+        impl WorldPlugin for CountingPlugin {
+            fn before_tick(&mut self, _world: &mut World) {
+                self.counts.borrow_mut().ticks += 1;
+            }
+
+            fn after_influences(&mut self, _world: &mut World) {
+                self.counts.borrow_mut().after_influences += 1;
+            }
+
+            fn after_controls(&mut self, _world: &mut World) {
+                self.counts.borrow_mut().after_controls += 1;
+            }
+
+            fn on_cell_born(&mut self, _world: &World, cell: &Cell) {
+                self.counts.borrow_mut().born_lineages.push(cell.lineage());
+            }
+
+            fn on_cell_died(&mut self, _world: &World, cell: &Cell) {
+                self.counts.borrow_mut().died_lineages.push(cell.lineage());
+            }
+        }
+
+        let counts = Rc::new(RefCell::new(HookCounts::default()));
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_plugin(Box::new(CountingPlugin {
+                counts: Rc::clone(&counts),
+            }))
+            .with_cells(vec![
+                Cell::new(
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                    vec![CellLayer::new(
+                        Area::new(1.0),
+                        Density::new(1.0),
+                        Color::Green,
+                        Box::new(BondingCellLayerSpecialty::new()),
+                    )],
+                )
+                .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                    BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                    BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+                ])))
+                .with_initial_energy(BioEnergy::new(10.0))
+                .with_lineage(1),
+                simple_layered_cell(vec![
+                    simple_cell_layer(Area::new(1.0), Density::new(1.0)).dead()
+                ])
+                .with_lineage(2),
+            ]);
+
+        world.tick();
+
+        let counts = counts.borrow();
+        assert_eq!(counts.ticks, 1);
+        assert_eq!(counts.after_influences, 1);
+        assert_eq!(counts.after_controls, 1);
+        assert_eq!(counts.born_lineages, vec![1]);
+        assert_eq!(counts.died_lineages, vec![2]);
+    }
+
+    #[test]
+    fn tick_reports_births_deaths_bonds_and_wall_collisions() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_perimeter_walls()
+            .with_cells(vec![
+                Cell::new(
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                    vec![CellLayer::new(
+                        Area::new(1.0),
+                        Density::new(1.0),
+                        Color::Green,
+                        Box::new(BondingCellLayerSpecialty::new()),
+                    )],
+                )
+                .with_control(Box::new(ContinuousRequestsControl::new(vec![
+                    BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+                    BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+                ])))
+                .with_initial_energy(BioEnergy::new(10.0)),
+                simple_layered_cell(vec![
+                    simple_cell_layer(Area::new(1.0), Density::new(1.0)).dead()
+                ])
+                .with_initial_position(Position::new(9.8, 0.0)),
+            ]);
+
+        let report = world.tick();
+
+        assert_eq!(
+            report,
+            TickReport {
+                births: 1,
+                deaths: 1,
+                bonds_formed: 1,
+                bonds_broken: 0,
+                energy_injected: 0.0,
+                energy_dissipated: 0.0,
+                wall_collisions: 1,
+                churn: GraphChurn {
+                    nodes_added: 1,
+                    nodes_removed: 1,
+                    edges_added: 1,
+                    edges_removed: 0,
+                },
+                churn_overflow: 0,
+                breakpoint_hit: None,
+                limit_exceeded: None,
+                blown_up_cells: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn dropped_buds_are_refunded_to_their_parents() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_churn_cap(ChurnCap {
+                max_bond_changes: 1,
+                overflow: ChurnOverflow::Drop,
+            })
+            .with_cells(vec![budding_cell(), budding_cell()]);
+        let mut uncapped_world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![budding_cell(), budding_cell()]);
+
+        let report = world.tick();
+        uncapped_world.tick();
+
+        assert_eq!(report.births, 1);
+        assert_eq!(report.churn.nodes_added, 1);
+        assert_eq!(report.churn_overflow, 1);
+        assert_eq!(world.cells().len(), 3);
+        assert_eq!(
+            world.event_log().events()[0].kind,
+            WorldEventKind::ChurnCapExceeded { bond_changes: 2 }
+        );
+        assert!((total_energy(&world) - total_energy(&uncapped_world)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reported_churn_overflow_makes_every_change() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_churn_cap(ChurnCap {
+                max_bond_changes: 1,
+                overflow: ChurnOverflow::Report,
+            })
+            .with_cells(vec![budding_cell(), budding_cell()]);
+
+        let report = world.tick();
+
+        assert_eq!(report.births, 2);
+        assert_eq!(report.churn_overflow, 1);
+        assert_eq!(world.event_log().events().len(), 1);
+    }
+
+    fn budding_cell() -> Cell {
+        Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![CellLayer::new(
+                Area::new(1.0),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(BondingCellLayerSpecialty::new()),
+            )],
+        )
+        .with_control(Box::new(ContinuousRequestsControl::new(vec![
+            BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
+            BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
+        ])))
+        .with_initial_energy(BioEnergy::new(10.0))
+    }
+
+    // The cells' energy and the energy waiting in their bonds
+    fn total_energy(world: &World) -> f64 {
+        world
+            .cells()
+            .iter()
+            .map(|cell| cell.energy().value())
+            .chain(
+                world
+                    .bonds()
+                    .iter()
+                    .map(|bond| bond.energy_for_cell1().value() + bond.energy_for_cell2().value()),
+            )
+            .sum()
+    }
+
+    #[test]
+    fn breakpoint_is_hit_when_its_condition_starts_holding() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_breakpoint(Breakpoint::EnergyBelow(5.0))
+            .with_cells(vec![
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                    .with_initial_energy(BioEnergy::new(2.0)),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                    .with_initial_energy(BioEnergy::new(2.0)),
+            ]);
+        let cell_id = world.cells()[1].id();
+        world.toggle_select_cell(cell_id);
+
+        assert_eq!(
+            world.tick().breakpoint_hit,
+            Some(BreakpointHit::new(cell_id, Breakpoint::EnergyBelow(5.0)))
+        );
+        assert_eq!(world.tick().breakpoint_hit, None);
+    }
+
+    #[test]
+    fn death_breakpoint_is_hit_when_selected_cell_dies() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_breakpoint(Breakpoint::Death)
+            .with_cell(simple_layered_cell(vec![simple_cell_layer(
+                Area::new(1.0),
+                Density::new(1.0),
+            )
+            .dead()]));
+        let cell_id = world.cells()[0].id();
+        world.toggle_select_cell(cell_id);
+
+        assert_eq!(
+            world.tick().breakpoint_hit,
+            Some(BreakpointHit::new(cell_id, Breakpoint::Death))
+        );
+    }
+
+    #[test]
+    fn population_monitor_records_extinction_events() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_population_monitor(PopulationMonitorParameters::DEFAULT)
+            .with_cells(vec![
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                    .with_lineage(1),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                    .with_lineage(2),
+            ]);
+        world.tick();
+
+        world.toggle_select_cell(world.cells()[1].id());
+        world.kill_selected_cells();
+        world.tick();
+
+        assert_eq!(
+            world.event_log().events(),
+            &[
+                WorldEvent {
+                    tick: 2,
+                    kind: WorldEventKind::Extinction { lineage: 2 },
+                },
+                WorldEvent {
+                    tick: 2,
+                    kind: WorldEventKind::PopulationCrash {
+                        peak_cells: 2,
+                        num_cells: 1
+                    },
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn cell_ids_survive_removal_of_other_cells() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            simple_layered_cell(vec![
+                simple_cell_layer(Area::new(1.0), Density::new(1.0)).dead()
+            ]),
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                .with_initial_energy(BioEnergy::new(5.0)),
+        ]);
+        let survivor_id = world.cells()[1].id();
+        assert_ne!(world.cells()[0].id(), survivor_id);
+
+        world.tick();
+        world.toggle_select_cell(survivor_id);
+
+        let survivor = world.cell_with_id(survivor_id).unwrap();
+        assert_eq!(survivor.energy(), BioEnergy::new(5.0));
+        assert!(survivor.is_selected());
+    }
+
+    #[test]
+    fn cull_keeps_fittest_cells_and_their_bonds() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                    .with_initial_energy(BioEnergy::new(1.0)),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                    .with_initial_energy(BioEnergy::new(3.0)),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))])
+                    .with_initial_energy(BioEnergy::new(2.0)),
+            ])
+            .with_bonds(vec![(1, 2)]);
+
+        world.cull_to(2, CullSelection::Fittest(FitnessMetric::Energy));
+
+        assert_eq!(world.cells().len(), 2);
+        let mut energies: Vec<f64> = world
+            .cells()
+            .iter()
+            .map(|cell| cell.energy().value())
+            .collect();
+        energies.sort_by(f64::total_cmp);
+        assert_eq!(energies, vec![2.0, 3.0]);
+        assert_eq!(world.bonds().len(), 1);
+    }
+
+    #[test]
+    fn blowup_detection_reports_cells_that_leave_bounds() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cells(vec![
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                ),
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::ORIGIN,
+                    Velocity::new(1000.0, 0.0),
+                ),
+            ])
+            .with_blowup_detection(BlowupBounds::for_world(
+                Position::new(-10.0, -10.0),
+                Position::new(10.0, 10.0),
+            ));
+
+        let report = world.tick();
+
+        assert_eq!(report.blown_up_cells, 1);
+        let blowup = world.blowup().unwrap();
+        assert_eq!(blowup.tick, 1);
+        assert_eq!(blowup.cells[0].id, world.cells()[1].id());
+        assert_eq!(
+            world.event_log().events()[0].kind,
+            WorldEventKind::Blowup { num_cells: 1 }
+        );
+    }
+
+    #[test]
+    fn cell_limit_culls_lowest_energy_cells() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(
+                (1..=4)
+                    .map(|energy| {
+                        simple_layered_cell(vec![simple_cell_layer(
+                            Area::new(1.0),
+                            Density::new(1.0),
+                        )])
+                        .with_initial_energy(BioEnergy::new(energy as f64))
+                    })
+                    .collect(),
+            )
+            .with_resource_limits(ResourceLimits {
+                max_cells: Some(3),
+                action: LimitAction::Cull,
+                ..ResourceLimits::NONE
+            });
+
+        let report = world.tick();
+
+        assert_eq!(report.limit_exceeded, Some(LimitExceeded::Cells(4)));
+        assert_eq!(world.cells().len(), 3);
+        assert!(world.cells().iter().all(|cell| cell.energy().value() > 1.5));
+    }
+
+    #[test]
+    fn auto_compaction_shrinks_cell_graph_after_die_off() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_auto_compaction(0.25)
+            .with_cells(
+                (1..=100)
+                    .map(|energy| {
+                        simple_layered_cell(vec![simple_cell_layer(
+                            Area::new(1.0),
+                            Density::new(1.0),
+                        )])
+                        .with_initial_energy(BioEnergy::new(energy as f64))
+                    })
+                    .collect(),
+            )
+            .with_resource_limits(ResourceLimits {
+                max_cells: Some(10),
+                action: LimitAction::Cull,
+                ..ResourceLimits::NONE
+            });
+
+        world.tick();
+
+        assert_eq!(world.cells().len(), 10);
+        assert!(world.cell_graph.node_capacity() < 100);
+        world.tick();
+        assert_eq!(world.cells().len(), 10);
+    }
+
+    #[test]
+    fn scheduled_cull_happens_at_its_tick_and_is_logged() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(
+                (0..5)
+                    .map(|_| {
+                        Cell::ball(
+                            Length::new(1.0),
+                            Mass::new(1.0),
+                            Position::ORIGIN,
+                            Velocity::ZERO,
+                        )
+                    })
+                    .collect(),
+            )
+            .with_scheduled_cull(1, 2, CullSelection::Random { seed: 0 });
+
+        world.tick();
+        assert_eq!(world.cells().len(), 5);
+        world.tick();
+        assert_eq!(world.cells().len(), 2);
+
+        assert_eq!(
+            world.event_log().events(),
+            &[WorldEvent {
+                tick: 1,
+                kind: WorldEventKind::Cull {
+                    selection: CullSelection::Random { seed: 0 },
+                    num_cells_before: 5,
+                    num_survivors: 2,
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn culled_and_killed_cells_are_reported_as_deaths() {
+        struct DeathPlugin {
+            died: Rc<RefCell<Vec<CellId>>>,
+        }
+
+        impl WorldPlugin for DeathPlugin {
+            fn on_cell_died(&mut self, _world: &World, cell: &Cell) {
+                self.died.borrow_mut().push(cell.id());
+            }
+        }
+
+        let died = Rc::new(RefCell::new(vec![]));
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_plugin(Box::new(DeathPlugin {
+                died: Rc::clone(&died),
+            }))
+            .with_breakpoint(Breakpoint::Death)
+            .with_cells(
+                (1..=3)
+                    .map(|lineage| {
+                        simple_layered_cell(vec![simple_cell_layer(
+                            Area::new(1.0),
+                            Density::new(1.0),
+                        )])
+                        .with_initial_energy(BioEnergy::new(lineage as f64))
+                        .with_lineage(lineage)
+                    })
+                    .collect(),
+            )
+            .with_scheduled_cull(0, 2, CullSelection::Fittest(FitnessMetric::Energy));
+        let culled_id = world.cells()[0].id();
+        let killed_id = world.cells()[1].id();
+        world.toggle_select_cell(culled_id);
+
+        assert_eq!(
+            world.tick().breakpoint_hit,
+            Some(BreakpointHit::new(culled_id, Breakpoint::Death))
+        );
+        assert_eq!(*died.borrow(), vec![culled_id]);
+        assert_eq!(world.lineage_stats()[0].deaths, 1);
+
+        world.toggle_select_cell(killed_id);
+        world.kill_selected_cells();
+
+        assert_eq!(*died.borrow(), vec![culled_id, killed_id]);
+        let stats = world.lineage_stats();
+        assert_eq!(stats[1].lineage, 2);
+        assert_eq!(stats[1].deaths, 1);
+    }
+
+    #[test]
+    fn invaders_join_world_with_new_ids() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ));
+
+        let invader_ids = world.invade(
+            Invasion::new(
+                Cell::ball(
+                    Length::new(0.5),
+                    Mass::new(1.0),
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                ),
+                3,
+                Position::new(5.0, 5.0),
+                Position::new(10.0, 10.0),
+            )
+            .with_tag("mutant"),
+        );
+
+        assert_eq!(world.cells().len(), 4);
+        assert_eq!(invader_ids.len(), 3);
+        for cell in &world.cells()[1..] {
+            assert!(invader_ids.contains(&cell.id()));
+            assert!(cell.has_tag("mutant"));
+        }
+        assert!(!world.cells()[0].has_tag("mutant"));
+    }
+
+    #[test]
+    fn scheduled_invasion_happens_at_its_tick_and_is_logged() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_scheduled_invasion(
+                1,
+                Invasion::new(
+                    Cell::ball(
+                        Length::new(1.0),
+                        Mass::new(1.0),
+                        Position::ORIGIN,
+                        Velocity::ZERO,
+                    ),
+                    2,
+                    Position::new(-5.0, -5.0),
+                    Position::new(5.0, 5.0),
+                ),
+            );
+
+        world.tick();
+        assert_eq!(world.cells().len(), 0);
+        world.tick();
+        assert_eq!(world.cells().len(), 2);
+        world.tick();
+        assert_eq!(world.cells().len(), 2);
+
+        assert_eq!(
+            world.event_log().events(),
+            &[WorldEvent {
+                tick: 1,
+                kind: WorldEventKind::Invasion {
+                    tag: Invasion::DEFAULT_TAG.to_string(),
+                    num_cells: 2,
+                },
+            }]
+        );
     }
 
-    fn remove_bonds(&mut self, bond_handles: &HashSet<EdgeHandle>) {
-        let mut sorted_bond_handles = Vec::from_iter(bond_handles.iter().cloned());
-        sorted_bond_handles.sort_unstable();
-        self.cell_graph.remove_edges(&sorted_bond_handles);
+    #[test]
+    fn probes_take_a_reading_every_tick() {
+        let mut world = World::new(Position::new(0.0, -10.0), Position::new(10.0, 0.0))
+            .with_sunlight(0.0, 1.0)
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::new(5.0, -5.0),
+                Velocity::ZERO,
+            ))
+            .with_probe(Probe::new(
+                "middle",
+                ProbeRegion::Point(Position::new(5.0, -5.0)),
+            ));
+
+        world.tick();
+        world.tick();
+
+        let readings = world.probes()[0].readings();
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[1].tick, 2);
+        assert_eq!(readings[1].light_intensity, 0.5);
+        assert_eq!(readings[1].cell_count, 1);
     }
 
-    fn tick_cells(&mut self) {
-        for cell in self.cell_graph.nodes_mut() {
-            Self::print_selected_cell_state(cell, "start");
-            Self::move_cell(cell);
-            Self::clear_cell_environment(cell);
-            Self::print_selected_cell_state(cell, "end");
+    #[test]
+    fn trajectory_recorder_follows_tagged_cell() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::ORIGIN,
+                    Velocity::new(1.0, 0.0),
+                )
+                .with_tag("tracked"),
+            )
+            .with_trajectory_recorder(TrajectoryRecorder::new("tracked", 10));
+
+        world.tick();
+        world.tick();
+
+        let cell_id = world.cells()[0].id();
+        let trajectory = world
+            .trajectory_recorder()
+            .unwrap()
+            .trajectory(cell_id)
+            .unwrap();
+        assert_eq!(trajectory.len(), 2);
+        assert_eq!(trajectory[1].tick, 2);
+        assert_eq!(trajectory[1].position, world.cells()[0].position());
+    }
+
+    #[test]
+    fn tuning_a_parameter_changes_all_influences_that_use_it() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_influences(vec![
+            Box::new(SimpleForceInfluence::new(Box::new(WeightForce::new(-1.0)))),
+            Box::new(SimpleForceInfluence::new(Box::new(BuoyancyForce::new(
+                -1.0, 0.5,
+            )))),
+        ]);
+
+        world.set_tunable_parameter(TunableParameter::Gravity, -2.0);
+
+        assert_eq!(
+            world.tunable_parameter(TunableParameter::Gravity),
+            Some(-2.0)
+        );
+        assert_eq!(
+            world.influences[1].influence.to_document(),
+            InfluenceDocument::BuoyancyForce {
+                gravity: -2.0,
+                fluid_density: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn tuning_the_mutation_rate_sets_it_on_current_and_later_cells() {
+        #[derive(Debug)]
+        struct MutatingControl {
+            rate: f64,
+        }
+
+        impl CellControl for MutatingControl {
+            fn run(&mut self, _cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+                vec![]
+            }
+
+            fn spawn(&mut self) -> Box<dyn CellControl> {
+                Box::new(MutatingControl { rate: self.rate })
+            }
+
+            fn mutation_rate(&self) -> Option<f64> {
+                Some(self.rate)
+            }
+
+            fn set_mutation_rate(&mut self, rate: f64) {
+                self.rate = rate;
+            }
+        }
+
+        fn mutating_cell() -> Cell {
+            Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            )
+            .with_control(Box::new(MutatingControl { rate: 0.5 }))
         }
+
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(mutating_cell());
+        assert_eq!(
+            world.tunable_parameter(TunableParameter::MutationRate),
+            Some(0.5)
+        );
+
+        world.set_tunable_parameter(TunableParameter::MutationRate, 0.1);
+        world.add_cell(mutating_cell());
+
+        assert_eq!(world.cells()[0].mutation_rate(), Some(0.1));
+        assert_eq!(world.cells()[1].mutation_rate(), Some(0.1));
     }
 
-    fn move_cell(cell: &mut Cell) {
-        cell.exert_forces_for_one_tick();
-        cell.move_for_one_tick();
+    #[test]
+    fn patch_replaces_influences_and_sets_parameters() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_influences(vec![
+            Box::new(PairCollisions::new()),
+            Box::new(SimpleForceInfluence::new(Box::new(WeightForce::new(-1.0)))),
+        ]);
+
+        world
+            .apply_patch(&WorldPatch {
+                remove_influences: vec!["pair_collisions".to_string()],
+                add_influences: vec![InfluenceDocument::DragForce {
+                    viscosity: 0.1,
+                    speed_law: DragSpeedLaw::default(),
+                    size_measure: DragSizeMeasure::default(),
+                }],
+                set_parameters: vec![ParameterSetting {
+                    parameter: TunableParameter::Gravity,
+                    value: -2.0,
+                }],
+            })
+            .unwrap();
+
+        assert_eq!(world.influence_ids(), vec!["weight", "drag"]);
+        assert_eq!(
+            world.tunable_parameter(TunableParameter::Gravity),
+            Some(-2.0)
+        );
     }
 
-    fn clear_cell_environment(cell: &mut Cell) {
-        cell.environment_mut().clear();
-        cell.forces_mut().clear();
+    #[test]
+    fn edited_cells_can_be_copied_moved_and_bonded() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(2.0),
+                    Position::ORIGIN,
+                    Velocity::new(1.0, 0.0),
+                )
+                .with_lineage(3),
+            );
+        let original_id = world.cells()[0].id();
+
+        let copy_id = world
+            .add_copy_of_cell(original_id, Position::new(5.0, 0.0))
+            .unwrap();
+        world.move_cell(original_id, Position::new(3.0, 0.0));
+
+        let copy = world.cell_with_id(copy_id).unwrap();
+        assert_eq!(copy.position(), Position::new(5.0, 0.0));
+        assert_eq!(copy.velocity(), Velocity::ZERO);
+        assert_eq!(copy.mass(), Mass::new(2.0));
+        assert_eq!(copy.lineage(), 3);
+        let original = world.cell_with_id(original_id).unwrap();
+        assert_eq!(original.position(), Position::new(3.0, 0.0));
+        assert_eq!(original.velocity(), Velocity::ZERO);
+
+        assert!(world.bond_cells(original_id, copy_id));
+        assert!(!world.bond_cells(copy_id, original_id));
+        assert_eq!(world.bonds().len(), 1);
     }
 
-    fn print_selected_cell_state(cell: &Cell, start_end_str: &str) {
-        if cell.is_selected() {
-            println!(
-                "Cell {} {} position: {}, velocity: {}, force: {}",
-                cell.node_handle(),
-                start_end_str,
-                cell.position(),
-                cell.velocity(),
-                cell.forces().net_force()
+    #[test]
+    fn copying_missing_cell_adds_nothing() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
+
+        assert_eq!(
+            world.add_copy_of_cell(CellId::new(7), Position::ORIGIN),
+            None
+        );
+        assert!(world.cells().is_empty());
+    }
+
+    #[test]
+    fn reload_sets_changed_parameters_and_logs_them() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
+                WeightForce::new(-9.8),
+            ))))
+            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
+                DragForce::new(0.1),
+            ))));
+        let mut document = world.to_document();
+        document.influences = vec![
+            InfluenceDocument::WeightForce { gravity: -9.8 },
+            InfluenceDocument::DragForce {
+                viscosity: 0.5,
+                speed_law: DragSpeedLaw::default(),
+                size_measure: DragSizeMeasure::default(),
+            },
+            InfluenceDocument::EnergyTax { rate: 0.1 },
+        ];
+
+        let changes = world.reload_parameters(&document);
+
+        assert_eq!(
+            changes,
+            vec![ParameterSetting {
+                parameter: TunableParameter::DragViscosity,
+                value: 0.5
+            }]
+        );
+        assert_eq!(
+            world.tunable_parameter(TunableParameter::DragViscosity),
+            Some(0.5)
+        );
+        assert_eq!(world.influences.len(), 2);
+        assert_eq!(
+            world.event_log().events(),
+            &[WorldEvent {
+                tick: 0,
+                kind: WorldEventKind::ParameterChanged {
+                    parameter: TunableParameter::DragViscosity,
+                    old_value: 0.1,
+                    new_value: 0.5,
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn reload_sets_parameters_only_on_matching_influences() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_influence(Box::new(Sunlight::new(-10.0, 10.0, 0.0, 10.0)))
+            .with_influence(Box::new(LightPatch::new(
+                Position::new(-1.0, -1.0),
+                Position::new(1.0, 1.0),
+                5.0,
+            )));
+        let mut document = world.to_document();
+        document.influences[1] =
+            LightPatch::new(Position::new(-1.0, -1.0), Position::new(1.0, 1.0), 7.0).to_document();
+
+        world.reload_parameters(&document);
+
+        assert_eq!(
+            world.light_intensity_at(Position::new(0.0, 10.0)),
+            10.0 + 0.0
+        );
+        assert_eq!(world.light_intensity_at(Position::new(0.0, 0.0)), 5.0 + 7.0);
+
+        document.influences[0] = Sunlight::new(-10.0, 10.0, 0.0, 20.0).to_document();
+
+        world.reload_parameters(&document);
+
+        assert_eq!(world.light_intensity_at(Position::new(0.0, 10.0)), 20.0);
+        assert_eq!(
+            world.light_intensity_at(Position::new(0.0, 0.0)),
+            10.0 + 7.0
+        );
+    }
+
+    #[test]
+    fn tissue_multipliers_are_tunable_and_reloadable() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
+        let mut document = world.to_document();
+        document.tissue_multipliers = TissueMultipliers::new().with_multiplier(
+            Color::Green,
+            TissueMultiplier {
+                energy_gain: 2.0,
+                cost: 1.0,
+            },
+        );
+
+        world.set_tunable_parameter(TunableParameter::TissueCost(Color::Yellow), 0.5);
+        let changes = world.reload_parameters(&document);
+
+        assert_eq!(
+            changes,
+            vec![
+                ParameterSetting {
+                    parameter: TunableParameter::TissueEnergyGain(Color::Green),
+                    value: 2.0
+                },
+                ParameterSetting {
+                    parameter: TunableParameter::TissueCost(Color::Yellow),
+                    value: 1.0
+                },
+            ]
+        );
+        assert_eq!(world.tissue_multipliers(), &document.tissue_multipliers);
+    }
+
+    #[test]
+    fn patch_removing_unknown_influence_fails_without_changes() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_influence(Box::new(PairCollisions::new()));
+
+        let result = world.apply_patch(&WorldPatch {
+            remove_influences: vec!["pair_collisions".to_string(), "sunlight".to_string()],
+            ..WorldPatch::default()
+        });
+
+        assert_eq!(
+            result,
+            Err(DocumentError::UnknownInfluence("sunlight".to_string()))
+        );
+        assert_eq!(world.influence_ids(), vec!["pair_collisions"]);
+    }
+
+    #[test]
+    fn patch_adding_unbuildable_influence_fails_without_changes() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_influence(Box::new(PairCollisions::new()));
+
+        let result = world.apply_patch(&WorldPatch {
+            remove_influences: vec!["pair_collisions".to_string()],
+            add_influences: vec![InfluenceDocument::Scheduled {
+                influence: Box::new(InfluenceDocument::PairCollisions),
+                parameter: TunableParameter::LightIntensity,
+                base_value: 1.0,
+                schedule: Schedule::Step {
+                    tick: 10,
+                    before_factor: 1.0,
+                    after_factor: 2.0,
+                },
+            }],
+            ..WorldPatch::default()
+        });
+
+        assert_eq!(
+            result,
+            Err(DocumentError::UnsupportedScheduledParameter {
+                influence: 0,
+                parameter: TunableParameter::LightIntensity
+            })
+        );
+        assert_eq!(world.influence_ids(), vec!["pair_collisions"]);
+    }
+
+    #[test]
+    fn world_without_influence_has_no_tunable_parameter() {
+        let world = World::new(Position::ORIGIN, Position::ORIGIN);
+        assert_eq!(
+            world.tunable_parameter(TunableParameter::DragViscosity),
+            None
+        );
+    }
+
+    #[test]
+    fn influences_get_default_ids_unless_named() {
+        let world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_sunlight(0.0, 1.0)
+            .with_named_influence(
+                "wind",
+                Box::new(SimpleForceInfluence::new(Box::new(ConstantForce::new(
+                    Force::new(1.0, 0.0),
+                )))),
             );
+
+        assert_eq!(world.influence_ids(), vec!["sunlight", "wind"]);
+    }
+
+    #[test]
+    fn walls_come_from_enabled_influences() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_standard_influences();
+        assert_eq!(world.walls().len(), 1);
+        assert_eq!(world.walls()[0].max_corner(), Position::new(10.0, 10.0));
+
+        world.set_influence_enabled("wall_collisions", false);
+        assert!(world.walls().is_empty());
+    }
+
+    #[test]
+    fn disabled_influence_has_no_effect_and_change_is_logged() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
+                ConstantForce::new(Force::new(1.0, 1.0)),
+            ))))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::ZERO,
+            ));
+
+        world.set_influence_enabled("constant_force", false);
+        world.tick();
+
+        assert!(!world.is_influence_enabled("constant_force"));
+        assert_eq!(world.cells()[0].velocity(), Velocity::ZERO);
+        assert_eq!(
+            world.event_log().events(),
+            &[WorldEvent {
+                tick: 0,
+                kind: WorldEventKind::InfluenceEnabled {
+                    id: "constant_force".to_string(),
+                    enabled: false,
+                },
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn enabling_unknown_influence_panics() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN);
+        world.set_influence_enabled("sunlight", true);
+    }
+
+    #[test]
+    #[should_panic(expected = "after influences: cell 1 has non-finite velocity")]
+    fn finite_checks_report_cell_and_phase() {
+        let mut world = TestWorld::new()
+            .with_ball(Position::ORIGIN, Velocity::new(f64::NAN, 0.0))
+            .build()
+            .with_finite_checks();
+
+        world.tick();
+    }
+
+    #[test]
+    fn world_round_trips_through_document() {
+        let world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_standard_influences()
+            .with_sunlight(0.0, 1.0)
+            .with_cells(vec![
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::ORIGIN,
+                    Velocity::ZERO,
+                ),
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::new(2.0, 0.0),
+                    Velocity::new(1.0, -1.0),
+                ),
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::new(4.0, 0.0),
+                    Velocity::ZERO,
+                )
+                .with_initial_energy(BioEnergy::new(5.0)),
+            ])
+            .with_bonds(vec![(0, 1), (1, 2)])
+            .with_angle_gussets(vec![(0, 1, PI)]);
+
+        let document = world.to_document();
+        let loaded_world =
+            World::from_document(&WorldDocument::from_json(&document.to_json()).unwrap()).unwrap();
+
+        assert_eq!(loaded_world.cells().len(), 3);
+        assert_eq!(loaded_world.bonds().len(), 2);
+        assert_eq!(loaded_world.cell_graph.meta_edges().len(), 1);
+        assert_eq!(loaded_world.influences.len(), 5);
+        // The balls' controls are not saved
+        let mut expected = document;
+        for cell in &mut expected.cells {
+            assert!(cell.has_control);
+            cell.has_control = false;
         }
+        assert_eq!(loaded_world.to_document(), expected);
     }
 
-    // TODO
-    fn _apply_changes(&mut self, changes: &WorldChanges) {
-        for (index, cell) in self.cell_graph.nodes_mut().iter_mut().enumerate() {
-            cell.apply_changes(&changes.cells[index]);
+    #[test]
+    fn loaded_world_continues_from_saved_tick() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN);
+        for _ in 0..3 {
+            world.tick();
         }
+
+        let document = WorldDocument::from_json(&world.to_document().to_json()).unwrap();
+        let mut loaded_world = World::from_document(&document).unwrap();
+
+        assert_eq!(loaded_world.current_tick(), 3);
+        loaded_world.tick();
+        assert_eq!(loaded_world.current_tick(), 4);
     }
-}
 
-struct NewChildData {
-    parent: NodeHandle,
-    bond_index: usize,
-    child: Cell,
-    donated_energy: BioEnergy,
-}
+    #[test]
+    fn document_preserves_bond_indexes_and_energy() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+            simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+        ]);
+        let mut bond = Bond::new(&world.cells()[0], &world.cells()[1]);
+        bond.set_energy_from_cell(world.cells()[0].node_handle(), BioEnergy::new(2.0));
+        world.add_bond(bond, 3, 5);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::biology::control::*;
-    use crate::physics::overlap::Overlap;
-    use crate::physics::shapes::*;
+        let loaded_world = World::from_document(&world.to_document()).unwrap();
+
+        assert!(loaded_world.cells()[0].has_edge(3));
+        assert!(loaded_world.cells()[1].has_edge(5));
+        assert_eq!(
+            loaded_world.bonds()[0].energy_for_cell2(),
+            BioEnergy::new(2.0)
+        );
+    }
 
     #[test]
-    fn tick_moves_ball() {
-        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(Cell::ball(
-            Length::new(1.0),
-            Mass::new(1.0),
-            Position::ORIGIN,
-            Velocity::new(1.0, 1.0),
-        ));
+    fn loading_document_with_unsupported_version_fails() {
+        let mut document = World::new(Position::ORIGIN, Position::ORIGIN).to_document();
+        document.version += 1;
+
+        assert_eq!(
+            World::from_document(&document).err(),
+            Some(DocumentError::UnsupportedVersion(
+                WorldDocument::VERSION + 1
+            ))
+        );
+    }
+
+    #[test]
+    fn loading_document_with_bad_bond_references_fails() {
+        let world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
+            ])
+            .with_bonds(vec![(0, 1)]);
+        let document = world.to_document();
+
+        let mut missing_cell = document.clone();
+        missing_cell.bonds[0].cell2 = 2;
+        assert_eq!(
+            World::from_document(&missing_cell).err(),
+            Some(DocumentError::InvalidCellIndex { bond: 0, cell: 2 })
+        );
+
+        let mut bad_slot = document.clone();
+        bad_slot.bonds[0].bond_index_on_cell1 = MAX_NODE_EDGES;
+        assert_eq!(
+            World::from_document(&bad_slot).err(),
+            Some(DocumentError::InvalidBondSlot {
+                bond: 0,
+                slot: MAX_NODE_EDGES
+            })
+        );
+
+        let mut reused_slot = document.clone();
+        reused_slot.bonds.push(reused_slot.bonds[0]);
+        assert_eq!(
+            World::from_document(&reused_slot).err(),
+            Some(DocumentError::BondSlotInUse {
+                bond: 1,
+                cell: 0,
+                slot: document.bonds[0].bond_index_on_cell1
+            })
+        );
+
+        let mut missing_bond = document;
+        missing_bond.angle_gussets.push(AngleGussetDocument {
+            bond1: 0,
+            bond2: 1,
+            angle: PI,
+        });
+        assert_eq!(
+            World::from_document(&missing_bond).err(),
+            Some(DocumentError::InvalidBondIndex { gusset: 0, bond: 1 })
+        );
+    }
+
+    #[test]
+    fn world_from_document_rejects_schedules_it_cannot_build() {
+        let scheduled = |influence, period_ticks| InfluenceDocument::Scheduled {
+            influence: Box::new(influence),
+            parameter: TunableParameter::Gravity,
+            base_value: 1.0,
+            schedule: Schedule::Pulses {
+                first_tick: 0,
+                period_ticks,
+                duration_ticks: 1,
+                pulse_factor: 1.0,
+                rest_factor: 0.0,
+            },
+        };
+        let mut document = World::new(Position::ORIGIN, Position::ORIGIN).to_document();
+
+        document.influences = vec![
+            InfluenceDocument::PairCollisions,
+            scheduled(InfluenceDocument::PairCollisions, 10),
+        ];
+        assert_eq!(
+            World::from_document(&document).err(),
+            Some(DocumentError::UnsupportedScheduledParameter {
+                influence: 1,
+                parameter: TunableParameter::Gravity
+            })
+        );
+
+        document.influences = vec![scheduled(
+            InfluenceDocument::WeightForce { gravity: -1.0 },
+            0,
+        )];
+        assert_eq!(
+            World::from_document(&document).err(),
+            Some(DocumentError::ZeroPulsePeriod { influence: 0 })
+        );
+    }
+
+    #[test]
+    fn export_organism_includes_only_connected_cells() {
+        let world = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                ball_at(Position::new(0.0, 0.0)),
+                ball_at(Position::new(2.0, 0.0)),
+                ball_at(Position::new(4.0, 0.0)),
+                ball_at(Position::new(20.0, 20.0)),
+            ])
+            .with_bonds(vec![(0, 1), (1, 2)])
+            .with_angle_gussets(vec![(0, 1, PI)]);
 
-        world.tick();
+        let organism = world.export_organism(world.cells()[1].node_handle());
 
-        let ball = &world.cells()[0];
-        assert!(ball.position().x() > 0.0);
-        assert!(ball.position().y() > 0.0);
+        assert_eq!(organism.cells.len(), 3);
+        assert_eq!(organism.bonds.len(), 2);
+        assert_eq!(organism.angle_gussets.len(), 1);
+        let mut xs: Vec<f64> = organism.cells.iter().map(|cell| cell.position[0]).collect();
+        xs.sort_by(|x1, x2| x1.partial_cmp(x2).unwrap());
+        assert_eq!(xs, vec![-2.0, 0.0, 2.0]);
     }
 
     #[test]
-    fn tick_with_force_accelerates_ball() {
+    fn graft_rotates_and_places_organism_after_existing_cells() {
+        let donor = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cells(vec![
+                ball_at(Position::new(0.0, 0.0)),
+                ball_at(Position::new(2.0, 0.0)),
+            ])
+            .with_bonds(vec![(0, 1)]);
+        let organism = OrganismDocument::from_json(
+            &donor
+                .export_organism(donor.cells()[0].node_handle())
+                .to_json(),
+        )
+        .unwrap();
         let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
-            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
-                ConstantForce::new(Force::new(1.0, 1.0)),
-            ))))
-            .with_cell(Cell::ball(
-                Length::new(1.0),
-                Mass::new(1.0),
-                Position::ORIGIN,
-                Velocity::ZERO,
-            ));
+            .with_cells(vec![ball_at(Position::new(-5.0, -5.0))]);
 
-        world.tick();
+        let handles = world.graft(
+            &organism,
+            Position::new(10.0, 10.0),
+            Angle::from_radians(PI / 2.0),
+        );
 
-        let ball = &world.cells()[0];
-        assert!(ball.velocity().x() > 0.0);
-        assert!(ball.velocity().y() > 0.0);
+        assert_eq!(world.cells().len(), 3);
+        assert_eq!(world.bonds().len(), 1);
+        let position1 = world.cell(handles[0]).position();
+        let position2 = world.cell(handles[1]).position();
+        assert!((position1 - Position::new(10.0, 9.0)).length().value() < 1e-9);
+        assert!((position2 - Position::new(10.0, 11.0)).length().value() < 1e-9);
+        let bond = &world.bonds()[0];
+        assert_eq!(bond.node1_handle(), handles[0]);
+        assert_eq!(bond.node2_handle(), handles[1]);
     }
 
     #[test]
-    fn overlaps_do_not_persist() {
-        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
-            .with_influence(Box::new(UniversalOverlap::new(Overlap::new(
-                Displacement::new(1.0, 1.0),
-                1.0,
-            ))))
-            .with_cell(Cell::ball(
-                Length::new(1.0),
-                Mass::new(1.0),
-                Position::ORIGIN,
-                Velocity::ZERO,
-            ));
+    fn rect_selection_replaces_previous_selection() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            ball_at(Position::new(0.0, 0.0)),
+            ball_at(Position::new(5.0, 0.0)),
+            ball_at(Position::new(10.0, 0.0)),
+        ]);
+        world.toggle_select_cell(world.cells()[0].id());
 
-        world.tick();
+        world.select_cells_in_rect(Position::new(4.0, -1.0), Position::new(11.0, 1.0));
 
-        let ball = &world.cells()[0];
-        assert!(ball.environment().overlaps().is_empty());
+        let selected: Vec<bool> = world.cells().iter().map(Cell::is_selected).collect();
+        assert_eq!(selected, vec![false, true, true]);
     }
 
     #[test]
-    fn forces_do_not_persist() {
+    fn exported_selection_includes_only_bonds_within_it() {
         let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
-            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
-                ConstantForce::new(Force::new(1.0, 1.0)),
-            ))))
-            .with_cell(Cell::ball(
-                Length::new(1.0),
-                Mass::new(1.0),
-                Position::ORIGIN,
-                Velocity::ZERO,
-            ));
+            .with_cells(vec![
+                ball_at(Position::new(0.0, 0.0)),
+                ball_at(Position::new(2.0, 0.0)),
+                ball_at(Position::new(4.0, 0.0)),
+            ])
+            .with_bonds(vec![(0, 1), (1, 2)]);
 
-        world.tick();
+        world.select_cells_in_rect(Position::new(-1.0, -1.0), Position::new(3.0, 1.0));
+        let document = world.export_selected_cells().unwrap();
 
-        let ball = &world.cells()[0];
-        assert_eq!(ball.forces().net_force(), Force::new(0.0, 0.0));
+        assert_eq!(document.cells.len(), 2);
+        assert_eq!(document.bonds.len(), 1);
     }
 
     #[test]
-    fn cannot_bounce_off_drag_force() {
+    fn killing_selection_removes_cells_and_their_bonds() {
         let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
-            .with_cell(Cell::ball(
-                Length::new(10.0),
-                Mass::new(0.01),
-                Position::ORIGIN,
-                Velocity::new(10.0, 10.0),
-            ))
-            .with_influence(Box::new(SimpleForceInfluence::new(Box::new(
-                DragForce::new(0.01),
-            ))));
+            .with_cells(vec![
+                ball_at(Position::new(0.0, 0.0)),
+                ball_at(Position::new(2.0, 0.0)),
+                ball_at(Position::new(10.0, 0.0)),
+            ])
+            .with_bonds(vec![(0, 1)]);
+        let survivor_id = world.cells()[2].id();
 
-        world.tick();
+        world.select_cells_in_rect(Position::new(-1.0, -1.0), Position::new(3.0, 1.0));
+        world.kill_selected_cells();
 
-        let ball = &world.cells()[0];
-        assert!(ball.velocity().x() >= 0.0);
-        assert!(ball.velocity().y() >= 0.0);
+        assert_eq!(world.cells().len(), 1);
+        assert_eq!(world.cells()[0].id(), survivor_id);
+        assert!(world.bonds().is_empty());
+        assert_eq!(world.export_selected_cells(), None);
     }
 
     #[test]
-    fn tick_runs_photo_layer() {
-        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
-            .with_influence(Box::new(Sunlight::new(-10.0, 10.0, 0.0, 10.0)))
-            .with_cell(simple_layered_cell(vec![CellLayer::new(
-                Area::new(10.0),
-                Density::new(1.0),
-                Color::Green,
-                Box::new(PhotoCellLayerSpecialty::new(1.0)),
-            )]));
+    fn tagging_selection_tags_only_selected_cells() {
+        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+            ball_at(Position::new(0.0, 0.0)),
+            ball_at(Position::new(10.0, 0.0)),
+            ball_at(Position::new(20.0, 0.0)).with_tag("founderB"),
+        ]);
 
-        world.tick();
+        world.select_cells_in_rect(Position::new(-1.0, -1.0), Position::new(11.0, 1.0));
+        world.tag_selected_cells("founderA");
 
-        let cell = &world.cells()[0];
-        assert_eq!(cell.energy().value().round(), 50.0);
+        assert_eq!(world.tag_names(), vec!["founderA", "founderB"]);
+        assert_eq!(world.count_cells_with_tag("founderA"), 2);
+        assert_eq!(world.count_cells_with_tag("founderB"), 1);
     }
 
     #[test]
-    fn tick_runs_cell_growth() {
-        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
-            simple_layered_cell(vec![CellLayer::new(
-                Area::new(1.0),
-                Density::new(1.0),
-                Color::Green,
-                Box::new(NullCellLayerSpecialty::new()),
-            )])
-            .with_control(Box::new(ContinuousResizeControl::new(
-                0,
-                AreaDelta::new(2.0),
-            ))),
-        );
+    fn identical_worlds_have_same_state_hash() {
+        let mut world1 = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cells(vec![
+                ball_at(Position::new(0.0, 0.0)),
+                ball_at(Position::new(1.5, 0.0)),
+            ]);
+        let mut world2 = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cells(vec![
+                ball_at(Position::new(0.0, 0.0)),
+                ball_at(Position::new(1.5, 0.0)),
+            ]);
 
-        world.tick();
+        world1.tick();
+        world2.tick();
 
-        let cell = &world.cells()[0];
-        assert_eq!(cell.area(), Area::new(3.0));
+        assert_eq!(world1.state_hash(), world2.state_hash());
     }
 
     #[test]
-    fn tick_runs_cell_thruster() {
-        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
-            .with_cell(
-                simple_layered_cell(vec![CellLayer::new(
-                    Area::new(1.0),
-                    Density::new(1.0),
-                    Color::Green,
-                    Box::new(ThrusterCellLayerSpecialty::new()),
-                )])
-                .with_control(Box::new(SimpleThrusterControl::new(
-                    0,
-                    Force::new(1.0, -1.0),
-                ))),
-            );
+    fn state_hashes_separate_energy_from_motion() {
+        let world1 = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cell(ball_at(Position::ORIGIN).with_initial_energy(BioEnergy::new(1.0)));
+        let world2 = World::new(Position::ORIGIN, Position::ORIGIN)
+            .with_cell(ball_at(Position::ORIGIN).with_initial_energy(BioEnergy::new(2.0)));
 
-        world.tick();
-        world.tick();
+        let hashes1 = world1.state_hashes();
+        let hashes2 = world2.state_hashes();
 
-        let cell = &world.cells()[0];
-        assert!(cell.velocity().x() > 0.0);
-        assert!(cell.velocity().y() < 0.0);
+        assert_ne!(world1.state_hash(), world2.state_hash());
+        assert_eq!(hashes1.motion, hashes2.motion);
+        assert_ne!(hashes1.energies, hashes2.energies);
+        assert_eq!(hashes1.genomes, hashes2.genomes);
     }
 
     #[test]
-    fn growth_is_limited_by_energy() {
-        const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
-            growth_energy_delta: BioEnergyDelta::new(-10.0),
-            ..LayerResizeParameters::UNLIMITED
+    fn state_hashes_cover_bond_energies() {
+        let world_with_bond_energy = |energy| {
+            let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cells(vec![
+                ball_at(Position::new(0.0, 0.0)),
+                ball_at(Position::new(1.5, 0.0)),
+            ]);
+            let mut bond = Bond::new(&world.cells()[0], &world.cells()[1]);
+            bond.set_energy_from_cell(world.cells()[0].node_handle(), BioEnergy::new(energy));
+            world.add_bond(bond, 0, 0);
+            world
         };
 
-        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
-            .with_influence(Box::new(Sunlight::new(-10.0, 10.0, 0.0, 10.0)))
-            .with_cell(
-                simple_layered_cell(vec![CellLayer::new(
-                    Area::new(10.0),
-                    Density::new(1.0),
-                    Color::Green,
-                    Box::new(PhotoCellLayerSpecialty::new(1.0)),
-                )
-                .with_resize_parameters(&LAYER_RESIZE_PARAMS)])
-                .with_control(Box::new(ContinuousResizeControl::new(
-                    0,
-                    AreaDelta::new(100.0),
-                ))),
-            );
-
-        world.tick();
+        let hashes1 = world_with_bond_energy(1.0).state_hashes();
+        let hashes2 = world_with_bond_energy(2.0).state_hashes();
 
-        let cell = &world.cells()[0];
-        assert_eq!(cell.area().value().round(), 15.0);
+        assert_eq!(hashes1.energies, hashes2.energies);
+        assert_ne!(hashes1.bonds, hashes2.bonds);
     }
 
     #[test]
-    fn new_cell_is_added_to_world_with_bond_to_parent() {
-        let mut world = World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
-            Cell::new(
-                Position::ORIGIN,
-                Velocity::ZERO,
-                vec![CellLayer::new(
-                    Area::new(1.0),
-                    Density::new(1.0),
-                    Color::Green,
-                    Box::new(BondingCellLayerSpecialty::new()),
-                )],
+    fn state_hashes_cover_adhesion_tags_and_controls() {
+        let world = |tag, root| {
+            World::new(Position::ORIGIN, Position::ORIGIN).with_cell(
+                ball_at(Position::ORIGIN)
+                    .with_adhesion_tag(AdhesionTag::new(tag))
+                    .with_control(Box::new(BehaviorTreeControl::new(root))),
             )
-            .with_control(Box::new(ContinuousRequestsControl::new(vec![
-                BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
-                BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(1.0)),
-            ])))
-            .with_initial_energy(BioEnergy::new(10.0)),
-        );
+        };
+        let sequence = || BehaviorNode::Sequence { children: vec![] };
+        let selector = || BehaviorNode::Selector { children: vec![] };
 
-        world.tick();
+        let hashes = world(0, sequence()).state_hashes();
 
-        assert_eq!(world.cells().len(), 2);
-        assert_eq!(world.bonds().len(), 1);
-        let parent = &world.cells()[0];
-        assert!(parent.has_edge(1));
-        let child = &world.cells()[1];
-        assert!(child.has_edge(0));
-        assert_eq!(parent.energy(), BioEnergy::new(9.0));
-        assert_eq!(child.energy(), BioEnergy::ZERO);
-        let bond = &world.bonds()[0];
-        assert_eq!(bond.energy_for_cell2(), BioEnergy::new(1.0));
+        assert_ne!(hashes.genomes, world(1, sequence()).state_hashes().genomes);
+        assert_ne!(hashes.genomes, world(0, selector()).state_hashes().genomes);
     }
 
     #[test]
-    fn cells_can_pass_energy_through_bond() {
-        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
+    fn clicking_selects_cell_containing_click_over_nearer_center() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
             .with_cells(vec![
-                Cell::new(
-                    Position::ORIGIN,
-                    Velocity::ZERO,
-                    vec![CellLayer::new(
-                        Area::new(1.0),
-                        Density::new(1.0),
-                        Color::Green,
-                        Box::new(BondingCellLayerSpecialty::new()),
-                    )],
-                )
-                .with_control(Box::new(ContinuousRequestsControl::new(vec![
-                    BondingCellLayerSpecialty::retain_bond_request(0, 1, true),
-                    BondingCellLayerSpecialty::donation_energy_request(0, 1, BioEnergy::new(2.0)),
-                ])))
-                .with_initial_energy(BioEnergy::new(10.0)),
-                Cell::new(
+                Cell::ball(
+                    Length::new(2.5),
+                    Mass::new(1.0),
                     Position::ORIGIN,
                     Velocity::ZERO,
-                    vec![CellLayer::new(
-                        Area::new(1.0),
-                        Density::new(1.0),
-                        Color::Green,
-                        Box::new(BondingCellLayerSpecialty::new()),
-                    )],
-                )
-                .with_control(Box::new(ContinuousRequestsControl::new(vec![
-                    BondingCellLayerSpecialty::retain_bond_request(0, 0, true),
-                    BondingCellLayerSpecialty::donation_energy_request(0, 0, BioEnergy::new(3.0)),
-                ])))
-                .with_initial_energy(BioEnergy::new(10.0)),
-            ])
-            .with_bonds(vec![(0, 1)]);
-
-        world.tick();
-
-        assert_eq!(world.cells().len(), 2);
-        assert_eq!(world.bonds().len(), 1);
-        let cell1 = &world.cells()[0];
-        assert_eq!(cell1.energy(), BioEnergy::new(8.0));
-        let cell2 = &world.cells()[1];
-        assert_eq!(cell2.energy(), BioEnergy::new(7.0));
-        let bond = &world.bonds()[0];
-        assert_eq!(bond.energy_for_cell1(), BioEnergy::new(3.0));
-        assert_eq!(bond.energy_for_cell2(), BioEnergy::new(2.0));
-
-        world.tick();
-
-        let cell1 = &world.cells()[0];
-        assert_eq!(cell1.energy(), BioEnergy::new(9.0)); // 8 + 3 - 2
-        let cell2 = &world.cells()[1];
-        assert_eq!(cell2.energy(), BioEnergy::new(6.0)); // 7 + 2 - 3
-        let bond = &world.bonds()[0];
-        assert_eq!(bond.energy_for_cell1(), BioEnergy::new(3.0));
-        assert_eq!(bond.energy_for_cell2(), BioEnergy::new(2.0));
-    }
-
-    #[test]
-    fn world_breaks_bond_when_requested() {
-        let mut world = World::new(Position::ORIGIN, Position::ORIGIN)
-            .with_cells(vec![
-                simple_layered_cell(vec![CellLayer::new(
-                    Area::new(1.0),
-                    Density::new(1.0),
-                    Color::Green,
-                    Box::new(BondingCellLayerSpecialty::new()),
-                )])
-                .with_control(Box::new(ContinuousRequestsControl::new(vec![
-                    BondingCellLayerSpecialty::retain_bond_request(0, 1, false),
-                ]))),
-                simple_layered_cell(vec![simple_cell_layer(Area::new(1.0), Density::new(1.0))]),
-            ])
-            .with_bonds(vec![(0, 1)]);
+                ),
+                ball_at(Position::new(4.0, 0.0)),
+            ]);
 
-        world.tick();
+        world.toggle_select_cell_at(Position::new(2.4, 0.0));
 
-        assert_eq!(world.bonds().len(), 0);
+        assert!(world.cells()[0].is_selected());
+        assert!(!world.cells()[1].is_selected());
     }
 
-    #[test]
-    fn dead_cells_get_removed_from_world() {
-        let mut world =
-            World::new(Position::ORIGIN, Position::ORIGIN).with_cell(simple_layered_cell(vec![
-                simple_cell_layer(Area::new(1.0), Density::new(1.0)).dead(),
-            ]));
-
-        world.tick();
-
-        assert_eq!(world.cells().len(), 0);
+    fn ball_at(position: Position) -> Cell {
+        Cell::ball(Length::new(1.0), Mass::new(1.0), position, Velocity::ZERO)
     }
 
     fn simple_layered_cell(layers: Vec<CellLayer>) -> Cell {
@@ -0,0 +1,210 @@
+use crate::biology::cell::Cell;
+use crate::environment::influences::*;
+use crate::physics::quantities::*;
+use crate::physics::shapes::Circle;
+use crate::world::World;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+
+// Parameters for generating a random world. Ranges are (min, max) pairs that random values
+// are drawn from uniformly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldGenParameters {
+    pub min_corner: Position,
+    pub max_corner: Position,
+    pub min_light_intensity: (f64, f64),
+    pub max_light_intensity: (f64, f64),
+    pub gravity: (f64, f64),
+    pub fluid_density: f64,
+    pub viscosity: (f64, f64),
+    pub num_cells: usize,
+    pub cell_energy: (f64, f64),
+    // How many random positions to try before giving up on finding one that doesn't overlap
+    // a cell already placed
+    pub max_placement_attempts: u32,
+}
+
+impl WorldGenParameters {
+    pub const DEFAULT: WorldGenParameters = WorldGenParameters {
+        min_corner: Position::new(0.0, -400.0),
+        max_corner: Position::new(400.0, 0.0),
+        min_light_intensity: (0.0, 0.2),
+        max_light_intensity: (0.5, 1.5),
+        gravity: (-0.1, -0.02),
+        fluid_density: 0.001,
+        viscosity: (0.001, 0.01),
+        num_cells: 10,
+        cell_energy: (20.0, 80.0),
+        max_placement_attempts: 100,
+    };
+}
+
+// Generates worlds from a seed: the same seed and parameters always give the same world.
+// The caller supplies the cells' structure and controls through `create_cell`, which gets a
+// seed of its own for each cell, e.g. to randomize that cell's genome.
+#[derive(Clone, Debug)]
+pub struct WorldGenerator {
+    rng: Pcg64Mcg,
+    parameters: WorldGenParameters,
+}
+
+impl WorldGenerator {
+    pub fn new(seed: u64, parameters: WorldGenParameters) -> Self {
+        assert!(parameters.min_corner.x() < parameters.max_corner.x());
+        assert!(parameters.min_corner.y() < parameters.max_corner.y());
+        WorldGenerator {
+            rng: Pcg64Mcg::seed_from_u64(seed),
+            parameters,
+        }
+    }
+
+    pub fn generate<F>(&mut self, mut create_cell: F) -> World
+    where
+        F: FnMut(u64) -> Cell,
+    {
+        let mut world = World::new(self.parameters.min_corner, self.parameters.max_corner)
+            .with_standard_influences()
+            .with_influences(self.random_influences());
+        let mut placed: Vec<(Position, Length)> = Vec::with_capacity(self.parameters.num_cells);
+        for _ in 0..self.parameters.num_cells {
+            let cell_seed = self.rng.gen();
+            let mut cell = create_cell(cell_seed);
+            let position = self.random_position(cell.radius(), &placed);
+            cell.set_initial_position(position);
+            cell.set_initial_energy(BioEnergy::new(
+                self.random_in_range(self.parameters.cell_energy),
+            ));
+            placed.push((position, cell.radius()));
+            world.add_cell(cell);
+        }
+        world
+    }
+
+    fn random_influences(&mut self) -> Vec<Box<dyn Influence>> {
+        let gravity = self.random_in_range(self.parameters.gravity);
+        let min_intensity = self.random_in_range(self.parameters.min_light_intensity);
+        let max_intensity = self
+            .random_in_range(self.parameters.max_light_intensity)
+            .max(min_intensity);
+        vec![
+            Box::new(Sunlight::new(
+                self.parameters.min_corner.y(),
+                self.parameters.max_corner.y(),
+                min_intensity,
+                max_intensity,
+            )),
+            Box::new(SimpleForceInfluence::new(Box::new(WeightForce::new(
+                gravity,
+            )))),
+            Box::new(SimpleForceInfluence::new(Box::new(BuoyancyForce::new(
+                gravity,
+                self.parameters.fluid_density,
+            )))),
+            Box::new(SimpleForceInfluence::new(Box::new(DragForce::new(
+                self.random_in_range(self.parameters.viscosity),
+            )))),
+        ]
+    }
+
+    // Falls back to the last position tried if every attempt overlaps a placed cell.
+    fn random_position(&mut self, radius: Length, placed: &[(Position, Length)]) -> Position {
+        let mut position = self.random_position_inside_walls(radius);
+        for _ in 1..self.parameters.max_placement_attempts {
+            if !Self::overlaps_any(position, radius, placed) {
+                break;
+            }
+            position = self.random_position_inside_walls(radius);
+        }
+        position
+    }
+
+    fn random_position_inside_walls(&mut self, radius: Length) -> Position {
+        let min_corner = self.parameters.min_corner;
+        let max_corner = self.parameters.max_corner;
+        let margin_x = radius.value().min((max_corner.x() - min_corner.x()) / 2.0);
+        let margin_y = radius.value().min((max_corner.y() - min_corner.y()) / 2.0);
+        Position::new(
+            self.random_in_range((min_corner.x() + margin_x, max_corner.x() - margin_x)),
+            self.random_in_range((min_corner.y() + margin_y, max_corner.y() - margin_y)),
+        )
+    }
+
+    fn overlaps_any(position: Position, radius: Length, placed: &[(Position, Length)]) -> bool {
+        placed.iter().any(|(other_position, other_radius)| {
+            (position - *other_position).length() < radius + *other_radius
+        })
+    }
+
+    fn random_in_range(&mut self, (min, max): (f64, f64)) -> f64 {
+        assert!(min <= max);
+        if min == max {
+            min
+        } else {
+            self.rng.gen_range(min, max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::layers::*;
+
+    #[test]
+    fn same_seed_generates_same_world() {
+        let world1 = WorldGenerator::new(42, WorldGenParameters::DEFAULT).generate(simple_cell);
+        let world2 = WorldGenerator::new(42, WorldGenParameters::DEFAULT).generate(simple_cell);
+        assert_eq!(world1.to_document(), world2.to_document());
+    }
+
+    #[test]
+    fn different_seeds_generate_different_worlds() {
+        let world1 = WorldGenerator::new(1, WorldGenParameters::DEFAULT).generate(simple_cell);
+        let world2 = WorldGenerator::new(2, WorldGenParameters::DEFAULT).generate(simple_cell);
+        assert_ne!(world1.to_document(), world2.to_document());
+    }
+
+    #[test]
+    fn generated_cells_are_inside_walls_and_do_not_overlap() {
+        let params = WorldGenParameters::DEFAULT;
+        let world = WorldGenerator::new(7, params).generate(simple_cell);
+
+        assert_eq!(world.cells().len(), params.num_cells);
+        for (index, cell) in world.cells().iter().enumerate() {
+            let position = cell.center();
+            let radius = cell.radius().value();
+            assert!(params.min_corner.x() + radius <= position.x());
+            assert!(position.x() <= params.max_corner.x() - radius);
+            assert!(params.min_corner.y() + radius <= position.y());
+            assert!(position.y() <= params.max_corner.y() - radius);
+            for other in &world.cells()[index + 1..] {
+                assert!((position - other.center()).length() >= cell.radius() + other.radius());
+            }
+        }
+    }
+
+    #[test]
+    fn create_cell_gets_a_different_seed_for_each_cell() {
+        let mut seeds = vec![];
+        WorldGenerator::new(0, WorldGenParameters::DEFAULT).generate(|seed| {
+            seeds.push(seed);
+            simple_cell(seed)
+        });
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), WorldGenParameters::DEFAULT.num_cells);
+    }
+
+    fn simple_cell(_seed: u64) -> Cell {
+        Cell::new(
+            Position::ORIGIN,
+            Velocity::ZERO,
+            vec![CellLayer::new(
+                Area::new(10.0),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(PhotoCellLayerSpecialty::new(0.5)),
+            )],
+        )
+    }
+}
@@ -42,7 +42,7 @@ impl BackgroundDrawing {
         frame
             .draw(
                 vertex_buffer,
-                &self.indices,
+                self.indices,
                 &self.shader_program,
                 &uniforms,
                 &Default::default(),
@@ -1,133 +1,269 @@
-use glium::{implement_vertex, uniform, Surface};
-
-#[derive(Clone, Copy)]
-pub struct BondSprite {
-    pub end1: [f32; 2],
-    pub end2: [f32; 2],
-    pub radius1: f32,
-    pub radius2: f32,
-    pub color: [f32; 3],
+use crate::trail_drawing::TrailVertex;
+use crate::Point;
+use evo_domain::biology::cell::Cell;
+use evo_domain::physics::quantities::Angle;
+use evo_domain::physics::shapes::Circle;
+use evo_domain::physics::sortable_graph::{GraphEdge, GraphMetaEdge};
+use evo_domain::world::World;
+
+// Structure overlays: lines between the centers of bonded cells, and, for each angle gusset,
+// an arc inside the cell the gusset's two bonds share, sweeping counterclockwise from the first
+// bond to the second. Both are line segments, drawn over the cells with the trail drawing.
+// Overlays outside the viewport, or too small on screen to see, are left out, so that worlds
+// with tens of thousands of bonds stay cheap to draw.
+pub struct BondLines {
+    show_bonds: bool,
+    show_gussets: bool,
 }
 
-implement_vertex!(BondSprite, end1, end2, radius1, radius2, color);
+impl BondLines {
+    const BOND_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 0.8];
+    const GUSSET_COLOR: [f32; 4] = [1.0, 0.5, 0.0, 0.9];
+    // Arc radius as a fraction of the shared cell's radius
+    const GUSSET_ARC_RADIUS: f32 = 0.6;
+    const GUSSET_ARC_SEGMENTS: usize = 8;
+    // Overlays smaller than this on screen are left out
+    const MIN_BOND_PIXELS: f32 = 1.0;
+    const MIN_GUSSET_ARC_PIXELS: f32 = 2.0;
 
-pub struct BondDrawing {
-    pub shader_program: glium::Program,
-    pub indices: glium::index::NoIndices,
-}
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        BondLines {
+            show_bonds: false,
+            show_gussets: false,
+        }
+    }
+
+    pub fn toggle_bonds(&mut self) {
+        self.show_bonds = !self.show_bonds;
+    }
 
-impl BondDrawing {
-    pub fn new(display: &glium::Display) -> Self {
-        BondDrawing {
-            shader_program: glium::Program::from_source(
-                display,
-                Self::VERTEX_SHADER_SRC,
-                Self::FRAGMENT_SHADER_SRC,
-                Some(Self::GEOMETRY_SHADER_SRC),
-            )
-            .unwrap(),
-            indices: glium::index::NoIndices(glium::index::PrimitiveType::Points),
+    pub fn toggle_gussets(&mut self) {
+        self.show_gussets = !self.show_gussets;
+    }
+
+    // The overlay line segments, as pairs of vertices.
+    pub fn vertices(&self, world: &World, viewport: &Viewport) -> Vec<TrailVertex> {
+        let mut vertices = vec![];
+        if self.show_bonds {
+            Self::add_bond_vertices(world, viewport, &mut vertices);
+        }
+        if self.show_gussets {
+            Self::add_gusset_vertices(world, viewport, &mut vertices);
         }
+        vertices
     }
 
-    pub fn draw<T>(
-        &self,
-        frame: &mut glium::Frame,
-        vertex_buffer: &glium::VertexBuffer<T>,
-        screen_transform: [[f32; 4]; 4],
-    ) where
-        T: Copy,
-    {
-        let uniforms = uniform! {
-            screen_transform: screen_transform,
-        };
-        frame
-            .draw(
-                vertex_buffer,
-                &self.indices,
-                &self.shader_program,
-                &uniforms,
-                &Default::default(),
-            )
-            .unwrap();
-    }
-
-    const VERTEX_SHADER_SRC: &'static str = r#"
-        #version 330 core
-
-        in vec2 end1;
-        in vec2 end2;
-        in float radius1;
-        in float radius2;
-        in vec3 color;
-
-        out BondSprite {
-            vec2 end1;
-            vec2 end2;
-            float radius1;
-            float radius2;
-            vec3 color;
-        } bond_out;
-
-        void main() {
-            bond_out.end1 = end1;
-            bond_out.end2 = end2;
-            bond_out.radius1 = radius1;
-            bond_out.radius2 = radius2;
-            bond_out.color = color;
+    fn add_bond_vertices(world: &World, viewport: &Viewport, vertices: &mut Vec<TrailVertex>) {
+        let min_length = Self::MIN_BOND_PIXELS / viewport.pixels_per_unit;
+        for bond in world.bonds() {
+            let start = Self::center(world.cell(bond.node1_handle()));
+            let end = Self::center(world.cell(bond.node2_handle()));
+            if (end[0] - start[0]).hypot(end[1] - start[1]) < min_length
+                || !viewport.overlaps_box(
+                    [start[0].min(end[0]), start[1].min(end[1])],
+                    [start[0].max(end[0]), start[1].max(end[1])],
+                )
+            {
+                continue;
+            }
+            Self::add_segment(start, end, Self::BOND_COLOR, vertices);
         }
-    "#;
+    }
 
-    const GEOMETRY_SHADER_SRC: &'static str = r#"
-        #version 330 core
+    fn add_gusset_vertices(world: &World, viewport: &Viewport, vertices: &mut Vec<TrailVertex>) {
+        let min_radius = Self::MIN_GUSSET_ARC_PIXELS / viewport.pixels_per_unit;
+        for gusset in world.angle_gussets() {
+            let bond1 = world.bond(gusset.edge1_handle());
+            let bond2 = world.bond(gusset.edge2_handle());
+            let shared_cell = world.cell(bond1.node2_handle());
+            let center = Self::center(shared_cell);
+            let radius = Self::GUSSET_ARC_RADIUS * shared_cell.radius().value() as f32;
+            if radius < min_radius
+                || !viewport.overlaps_box(
+                    [center[0] - radius, center[1] - radius],
+                    [center[0] + radius, center[1] + radius],
+                )
+            {
+                continue;
+            }
+            let start_angle =
+                Self::direction(center, Self::center(world.cell(bond1.node1_handle())));
+            let end_angle = Self::direction(center, Self::center(world.cell(bond2.node2_handle())));
+            let sweep = Angle::from_radians((end_angle - start_angle) as f64).radians() as f32;
+            let arc_point = |i: usize| {
+                let angle = start_angle + sweep * i as f32 / Self::GUSSET_ARC_SEGMENTS as f32;
+                [
+                    center[0] + radius * angle.cos(),
+                    center[1] + radius * angle.sin(),
+                ]
+            };
+            for i in 0..Self::GUSSET_ARC_SEGMENTS {
+                Self::add_segment(arc_point(i), arc_point(i + 1), Self::GUSSET_COLOR, vertices);
+            }
+        }
+    }
 
-        uniform mat4 screen_transform;
+    fn add_segment(start: Point, end: Point, color: [f32; 4], vertices: &mut Vec<TrailVertex>) {
+        vertices.push(TrailVertex {
+            position: start,
+            color,
+        });
+        vertices.push(TrailVertex {
+            position: end,
+            color,
+        });
+    }
 
-        layout (points) in;
-        layout (triangle_strip, max_vertices = 4) out;
+    fn center(cell: &Cell) -> Point {
+        [cell.center().x() as f32, cell.center().y() as f32]
+    }
 
-        in BondSprite {
-            vec2 end1;
-            vec2 end2;
-            float radius1;
-            float radius2;
-            vec3 color;
-        } bond_in[];
+    fn direction(from: Point, to: Point) -> f32 {
+        (to[1] - from[1]).atan2(to[0] - from[0])
+    }
+}
 
-        out BondPoint {
-            vec3 color;
-        } bond_point_out;
+// The part of the world the window shows, and how many pixels one unit of world length takes
+// up on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub min_corner: Point,
+    pub max_corner: Point,
+    pub pixels_per_unit: f32,
+}
 
-        void emit_corner(in vec2 corner, in vec3 color) {
-            bond_point_out.color = color;
-            gl_Position = screen_transform * vec4(corner[0], corner[1], 0.0, 1.0);
-            EmitVertex();
+impl Viewport {
+    // From a transform that scales and translates world positions to normalized device
+    // coordinates, for a window of the given size in pixels.
+    pub fn from_screen_transform(transform: [[f32; 4]; 4], window_dim: [f32; 2]) -> Self {
+        let scale = [transform[0][0], transform[1][1]];
+        let translate = [transform[3][0], transform[3][1]];
+        Viewport {
+            min_corner: [
+                (-1.0 - translate[0]) / scale[0],
+                (-1.0 - translate[1]) / scale[1],
+            ],
+            max_corner: [
+                (1.0 - translate[0]) / scale[0],
+                (1.0 - translate[1]) / scale[1],
+            ],
+            pixels_per_unit: scale[0] * window_dim[0] / 2.0,
         }
+    }
 
-        void emit_quad(in vec2 end1, in vec2 end2, in float radius1, in float radius2, in vec3 color) {
-            emit_corner(corners, 0, 1, color);
-            emit_corner(corners, 0, 3, color);
-            emit_corner(corners, 2, 1, color);
-            emit_corner(corners, 2, 3, color);
-            EndPrimitive();
-        }
+    fn overlaps_box(&self, min_corner: Point, max_corner: Point) -> bool {
+        min_corner[0] <= self.max_corner[0]
+            && max_corner[0] >= self.min_corner[0]
+            && min_corner[1] <= self.max_corner[1]
+            && max_corner[1] >= self.min_corner[1]
+    }
+}
 
-        void main() {
-            emit_quad(bond_in[0].end1, bond_in[0].end2, bond_in[0].radius1, bond_in[0].radius2, bond_in[0].color);
-        }
-    "#;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GliumView;
+    use evo_domain::physics::quantities::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    const WHOLE_WORLD: Viewport = Viewport {
+        min_corner: [-10.0, -10.0],
+        max_corner: [10.0, 10.0],
+        pixels_per_unit: 10.0,
+    };
 
-    const FRAGMENT_SHADER_SRC: &'static str = r#"
-        #version 330 core
+    #[test]
+    fn hidden_overlays_have_no_vertices() {
+        assert!(BondLines::new()
+            .vertices(&right_angle_world(), &WHOLE_WORLD)
+            .is_empty());
+    }
 
-        in BondPoint {
-            vec3 color;
-        } bond_point_in;
+    #[test]
+    fn bonds_are_lines_between_cell_centers() {
+        let mut bond_lines = BondLines::new();
+        bond_lines.toggle_bonds();
 
-        out vec4 color_out;
+        let vertices = bond_lines.vertices(&right_angle_world(), &WHOLE_WORLD);
 
-        void main() {
-            color_out = vec4(bond_point_in.color, 1.0);
-        }
-    "#;
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(vertices[0].position, [5.0, 0.0]);
+        assert_eq!(vertices[1].position, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn bonds_outside_viewport_are_left_out() {
+        let mut bond_lines = BondLines::new();
+        bond_lines.toggle_bonds();
+        // only the bond along the x axis crosses it
+        let viewport = Viewport {
+            min_corner: [2.0, -1.0],
+            max_corner: [10.0, 1.0],
+            ..WHOLE_WORLD
+        };
+
+        let vertices = bond_lines.vertices(&right_angle_world(), &viewport);
+
+        assert_eq!(vertices.len(), 2);
+        assert_eq!(vertices[0].position, [5.0, 0.0]);
+    }
+
+    #[test]
+    fn overlays_too_small_to_see_are_left_out() {
+        let mut bond_lines = BondLines::new();
+        bond_lines.toggle_bonds();
+        bond_lines.toggle_gussets();
+        // bonds are 5 units long, so half a pixel, and the arc is smaller still
+        let viewport = Viewport {
+            pixels_per_unit: 0.1,
+            ..WHOLE_WORLD
+        };
+
+        assert!(bond_lines
+            .vertices(&right_angle_world(), &viewport)
+            .is_empty());
+    }
+
+    #[test]
+    fn viewport_inverts_screen_transform() {
+        let transform =
+            GliumView::calc_screen_transform([-10.0, -5.0], [10.0, 5.0], [400.0, 100.0]);
+
+        let viewport = Viewport::from_screen_transform(transform, [400.0, 100.0]);
+
+        assert_eq!(viewport.min_corner, [-20.0, -5.0]);
+        assert_eq!(viewport.max_corner, [20.0, 5.0]);
+        assert_eq!(viewport.pixels_per_unit, 10.0);
+    }
+
+    #[test]
+    fn gusset_arc_sweeps_from_first_bond_to_second() {
+        let mut bond_lines = BondLines::new();
+        bond_lines.toggle_gussets();
+
+        let vertices = bond_lines.vertices(&right_angle_world(), &WHOLE_WORLD);
+
+        assert_eq!(vertices.len(), 2 * BondLines::GUSSET_ARC_SEGMENTS);
+        let first = vertices[0].position;
+        let last = vertices[vertices.len() - 1].position;
+        assert!((first[0] - 0.6).abs() < 1e-6 && first[1].abs() < 1e-6);
+        assert!(last[0].abs() < 1e-6 && (last[1] - 0.6).abs() < 1e-6);
+    }
+
+    // Cell 1 at the origin, bonded to cell 0 to its right and cell 2 above it.
+    fn right_angle_world() -> World {
+        World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cells(vec![
+                ball(Position::new(5.0, 0.0)),
+                ball(Position::ORIGIN),
+                ball(Position::new(0.0, 5.0)),
+            ])
+            .with_bonds(vec![(0, 1), (1, 2)])
+            .with_angle_gussets(vec![(0, 1, FRAC_PI_2)])
+    }
+
+    fn ball(position: Position) -> Cell {
+        Cell::ball(Length::new(1.0), Mass::new(1.0), position, Velocity::ZERO)
+    }
 }
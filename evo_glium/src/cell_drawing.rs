@@ -1,16 +1,107 @@
+use crate::Point;
+use evo_domain::biology::cell::CellId;
 use glium::{implement_vertex, uniform, Surface};
 
 #[derive(Clone, Copy)]
 pub struct CellSprite {
+    // Not a vertex attribute; lets clicks on the sprite be traced back to its cell.
+    pub cell_id: CellId,
     pub center: [f32; 2],
+    // From the center to one end of a capsule's axis; zero for a round cell
+    pub half_axis: [f32; 2],
     pub num_layers: u32,
     pub radii_0_3: [f32; 4],
     pub radii_4_7: [f32; 4],
     pub health_0_3: [f32; 4],
     pub health_4_7: [f32; 4],
+    // Indexes into the palette, so that each cell's layers can have their own colors
+    pub colors_0_3: [u32; 4],
+    pub colors_4_7: [u32; 4],
 }
 
-implement_vertex!(CellSprite, center, num_layers, radii_0_3, radii_4_7, health_0_3, health_4_7);
+implement_vertex!(
+    CellSprite, center, half_axis, num_layers, radii_0_3, radii_4_7, health_0_3, health_4_7,
+    colors_0_3, colors_4_7
+);
+
+impl CellSprite {
+    // The palette entry halos are drawn in, after the layer colors
+    pub const HALO_COLOR_INDEX: u32 = 3;
+
+    pub fn outer_radius(&self) -> f32 {
+        self.radii_0_3
+            .iter()
+            .chain(self.radii_4_7.iter())
+            .take(self.num_layers as usize)
+            .fold(0.0, |max, radius| max.max(*radius))
+    }
+
+    // Adds a ring one unit wide around the sprite, drawn in the halo color.
+    pub fn add_halo(&mut self, health: f32) {
+        let radius = self.outer_radius() + 1.0;
+        let index = self.num_layers as usize;
+        assert!(index < 8);
+        if index < 4 {
+            self.radii_0_3[index] = radius;
+            self.health_0_3[index] = health;
+            self.colors_0_3[index] = Self::HALO_COLOR_INDEX;
+        } else {
+            self.radii_4_7[index - 4] = radius;
+            self.health_4_7[index - 4] = health;
+            self.colors_4_7[index - 4] = Self::HALO_COLOR_INDEX;
+        }
+        self.num_layers += 1;
+    }
+
+    // The distance from the point to the sprite's axis, which is just its center if it is round.
+    fn distance_to_axis(&self, point: Point) -> f32 {
+        let dx = point[0] - self.center[0];
+        let dy = point[1] - self.center[1];
+        let [ax, ay] = self.half_axis;
+        let half_axis_sqr = ax * ax + ay * ay;
+        let fraction = if half_axis_sqr == 0.0 {
+            0.0
+        } else {
+            ((dx * ax + dy * ay) / half_axis_sqr).clamp(-1.0, 1.0)
+        };
+        let (ox, oy) = (dx - fraction * ax, dy - fraction * ay);
+        (ox * ox + oy * oy).sqrt()
+    }
+
+    // The index of the innermost ring whose outer radius reaches the point.
+    pub fn layer_at(&self, point: Point) -> usize {
+        let distance = self.distance_to_axis(point);
+        self.radii_0_3
+            .iter()
+            .chain(self.radii_4_7.iter())
+            .take(self.num_layers as usize)
+            .position(|radius| distance <= *radius)
+            .unwrap_or(self.num_layers as usize - 1)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.distance_to_axis(point) <= self.outer_radius()
+    }
+}
+
+// Returns the ID of the cell whose sprite is drawn on top at the point, if any, and the index
+// of its ring that contains the point.
+pub fn find_cell_layer_at(sprites: &[CellSprite], point: Point) -> Option<(CellId, usize)> {
+    sprites
+        .iter()
+        .rev()
+        .find(|sprite| sprite.contains(point))
+        .map(|sprite| (sprite.cell_id, sprite.layer_at(point)))
+}
+
+// Returns the ID of the cell whose sprite is drawn on top at the point, if any.
+pub fn find_cell_at(sprites: &[CellSprite], point: Point) -> Option<CellId> {
+    sprites
+        .iter()
+        .rev()
+        .find(|sprite| sprite.contains(point))
+        .map(|sprite| sprite.cell_id)
+}
 
 pub struct CellDrawing {
     pub shader_program: glium::Program,
@@ -36,19 +127,18 @@ impl CellDrawing {
         frame: &mut glium::Frame,
         vertex_buffer: &glium::VertexBuffer<T>,
         screen_transform: [[f32; 4]; 4],
-        layer_colors: [[f32; 4]; 8],
+        palette: [[f32; 4]; 4],
     ) where
         T: Copy,
     {
         let uniforms = uniform! {
             screen_transform: screen_transform,
-            layer_colors_0_3: [layer_colors[0], layer_colors[1], layer_colors[2], layer_colors[3]],
-            layer_colors_4_7: [layer_colors[4], layer_colors[5], layer_colors[6], layer_colors[7]],
+            palette: palette,
         };
         frame
             .draw(
                 vertex_buffer,
-                &self.indices,
+                self.indices,
                 &self.shader_program,
                 &uniforms,
                 &Default::default(),
@@ -60,26 +150,34 @@ impl CellDrawing {
         #version 330 core
 
         in vec2 center;
+        in vec2 half_axis;
         in uint num_layers;
         in vec4 radii_0_3;
         in vec4 radii_4_7;
         in vec4 health_0_3;
         in vec4 health_4_7;
+        in uvec4 colors_0_3;
+        in uvec4 colors_4_7;
 
         out CellSprite {
             vec2 center;
+            vec2 half_axis;
             uint num_layers;
             float radii[8];
             float health[8];
+            uint colors[8];
         } cell_out;
 
         void main() {
             cell_out.center = center;
+            cell_out.half_axis = half_axis;
             cell_out.num_layers = num_layers;
             cell_out.radii = float[](radii_0_3[0], radii_0_3[1], radii_0_3[2], radii_0_3[3],
                                      radii_4_7[0], radii_4_7[1], radii_4_7[2], radii_4_7[3]);
             cell_out.health = float[](health_0_3[0], health_0_3[1], health_0_3[2], health_0_3[3],
                                       health_4_7[0], health_4_7[1], health_4_7[2], health_4_7[3]);
+            cell_out.colors = uint[](colors_0_3[0], colors_0_3[1], colors_0_3[2], colors_0_3[3],
+                                     colors_4_7[0], colors_4_7[1], colors_4_7[2], colors_4_7[3]);
         }
     "#;
 
@@ -93,54 +191,67 @@ impl CellDrawing {
 
         in CellSprite {
             vec2 center;
+            vec2 half_axis;
             uint num_layers;
             float radii[8];
             float health[8];
+            uint colors[8];
         } cell_in[];
 
         out CellPoint {
             vec2 offset;
+            flat vec2 half_axis;
             flat uint num_layers;
             flat float radii[8];
             flat float health[8];
+            flat uint colors[8];
         } cell_point_out;
 
-        void emit_circle_bounding_box_corner(in vec2 center, in float radius, in vec2 corner) {
-            vec2 offset = vec2(radius, radius) * corner;
+        void emit_capsule_bounding_box_corner(in vec2 center, in vec2 along, in vec2 across,
+                                              in vec2 corner) {
+            vec2 offset = along * corner.x + across * corner.y;
             cell_point_out.offset = offset;
+            cell_point_out.half_axis = cell_in[0].half_axis;
             cell_point_out.num_layers = cell_in[0].num_layers;
             cell_point_out.radii = cell_in[0].radii;
             cell_point_out.health = cell_in[0].health;
+            cell_point_out.colors = cell_in[0].colors;
             gl_Position = screen_transform * vec4(center + offset, 0.0, 1.0);
             EmitVertex();
         }
 
-        void emit_circle_bounding_box(in vec2 center, in float radius) {
-            emit_circle_bounding_box_corner(center, radius, vec2(-1.0, -1.0));
-            emit_circle_bounding_box_corner(center, radius, vec2(-1.0, 1.0));
-            emit_circle_bounding_box_corner(center, radius, vec2(1.0, -1.0));
-            emit_circle_bounding_box_corner(center, radius, vec2(1.0, 1.0));
+        // A box around the capsule, lined up with its axis. A round cell's box is a square.
+        void emit_capsule_bounding_box(in vec2 center, in vec2 half_axis, in float radius) {
+            float half_length = length(half_axis);
+            vec2 direction = (half_length > 0.0) ? half_axis / half_length : vec2(1.0, 0.0);
+            vec2 along = direction * (half_length + radius);
+            vec2 across = vec2(-direction.y, direction.x) * radius;
+            emit_capsule_bounding_box_corner(center, along, across, vec2(-1.0, -1.0));
+            emit_capsule_bounding_box_corner(center, along, across, vec2(-1.0, 1.0));
+            emit_capsule_bounding_box_corner(center, along, across, vec2(1.0, -1.0));
+            emit_capsule_bounding_box_corner(center, along, across, vec2(1.0, 1.0));
             EndPrimitive();
         }
 
         void main() {
             uint num_layers = cell_in[0].num_layers;
             float radius = cell_in[0].radii[num_layers - 1u];
-            emit_circle_bounding_box(cell_in[0].center, radius);
+            emit_capsule_bounding_box(cell_in[0].center, cell_in[0].half_axis, radius);
         }
     "#;
 
     const FRAGMENT_SHADER_SRC: &'static str = r#"
         #version 330 core
 
-        uniform mat4 layer_colors_0_3;
-        uniform mat4 layer_colors_4_7;
+        uniform mat4 palette;
 
         in CellPoint {
             vec2 offset;
+            flat vec2 half_axis;
             flat uint num_layers;
             flat float radii[8];
             flat float health[8];
+            flat uint colors[8];
         } cell_point_in;
 
         out vec4 color_out;
@@ -170,18 +281,21 @@ impl CellDrawing {
             return vec4(hsv2rgb(vec3(hsv[0], adjusted_s, adjusted_v)), color.a);
         }
 
-        void emit_color(in uint layer_index, in float health) {
-            vec4 pure_color = (layer_index < 4u)
-                ? layer_colors_0_3[layer_index]
-                : layer_colors_4_7[layer_index - 4u];
-            color_out = adjust_color_per_health(pure_color, health);
+        void emit_color(in uint color_index, in float health) {
+            color_out = adjust_color_per_health(palette[color_index], health);
         }
 
         void main() {
-            float radial_offset = sqrt(dot(cell_point_in.offset, cell_point_in.offset));
+            // the distance to the capsule's axis, which for a round cell is just its center
+            vec2 half_axis = cell_point_in.half_axis;
+            float half_axis_sqr = dot(half_axis, half_axis);
+            float fraction = (half_axis_sqr > 0.0)
+                ? clamp(dot(cell_point_in.offset, half_axis) / half_axis_sqr, -1.0, 1.0)
+                : 0.0;
+            float radial_offset = length(cell_point_in.offset - fraction * half_axis);
             for (uint i = 0u; i < min(8u, cell_point_in.num_layers); ++i) {
                 if (radial_offset <= cell_point_in.radii[i]) {
-                    emit_color(i, cell_point_in.health[i]);
+                    emit_color(cell_point_in.colors[i], cell_point_in.health[i]);
                     return;
                 }
             }
@@ -189,3 +303,74 @@ impl CellDrawing {
         }
     "#;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_topmost_cell_at_point() {
+        let sprites = vec![
+            sprite(1, [0.0, 0.0], 5.0),
+            sprite(2, [3.0, 0.0], 1.0),
+            sprite(3, [20.0, 0.0], 1.0),
+        ];
+
+        assert_eq!(find_cell_at(&sprites, [3.5, 0.0]), Some(CellId::new(2)));
+        assert_eq!(find_cell_at(&sprites, [-4.0, 0.0]), Some(CellId::new(1)));
+        assert_eq!(find_cell_at(&sprites, [10.0, 0.0]), None);
+    }
+
+    #[test]
+    fn finds_ring_at_point() {
+        let mut sprite = sprite(1, [0.0, 0.0], 2.0);
+        sprite.radii_0_3[1] = 5.0;
+        sprite.num_layers = 2;
+
+        assert_eq!(
+            find_cell_layer_at(&[sprite], [1.0, 1.0]),
+            Some((CellId::new(1), 0))
+        );
+        assert_eq!(
+            find_cell_layer_at(&[sprite], [0.0, -4.0]),
+            Some((CellId::new(1), 1))
+        );
+    }
+
+    #[test]
+    fn capsule_sprite_contains_points_along_its_axis() {
+        let mut sprite = sprite(1, [0.0, 0.0], 1.0);
+        sprite.half_axis = [3.0, 0.0];
+
+        assert!(sprite.contains([3.5, 0.5]));
+        assert!(!sprite.contains([4.5, 0.0]));
+        assert!(!sprite.contains([0.0, 1.5]));
+    }
+
+    #[test]
+    fn halo_surrounds_sprite() {
+        let mut sprite = sprite(1, [0.0, 0.0], 5.0);
+
+        sprite.add_halo(0.5);
+
+        assert_eq!(sprite.num_layers, 2);
+        assert_eq!(sprite.outer_radius(), 6.0);
+        assert_eq!(sprite.health_0_3[1], 0.5);
+        assert_eq!(sprite.colors_0_3[1], CellSprite::HALO_COLOR_INDEX);
+    }
+
+    fn sprite(id: u64, center: [f32; 2], radius: f32) -> CellSprite {
+        CellSprite {
+            cell_id: CellId::new(id),
+            center,
+            half_axis: [0.0, 0.0],
+            num_layers: 1,
+            radii_0_3: [radius, 0.0, 0.0, 0.0],
+            radii_4_7: [0.0; 4],
+            health_0_3: [1.0, 0.0, 0.0, 0.0],
+            health_4_7: [0.0; 4],
+            colors_0_3: [0; 4],
+            colors_4_7: [0; 4],
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::background_drawing::World as Rect;
+use crate::tooltip::Tooltip;
+use evo_domain::biology::cell::CellId;
+use evo_domain::biology::genealogy::Genealogy;
+use evo_domain::UserAction;
+use std::collections::HashSet;
+
+// The ancestors and living descendants of the first selected cell, drawn as text over the
+// bottom left of the window. Descendants form a tree, indented by generation, in which only
+// branches with living cells appear. Click a branch's "-" or "+" to collapse or expand it, and
+// click any other part of a living relative's line to select that relative instead.
+pub struct GenealogyPanel {
+    visible: bool,
+    collapsed: HashSet<CellId>,
+    // The lines last drawn and how, for mapping clicks to relatives
+    lines: Vec<PanelLine>,
+    tooltip: Option<Tooltip>,
+    // The selected cell and genealogy revision the lines were made for
+    lines_key: Option<(CellId, u64)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PanelLine {
+    text: String,
+    cell_id: Option<CellId>,
+    alive: bool,
+    // Where the branch's "-" or "+" is, if it has living descendants
+    marker_column: Option<usize>,
+}
+
+impl GenealogyPanel {
+    // Beyond this, lines are left out and the last line says so
+    const MAX_LINES: usize = 30;
+    const INDENT: usize = 2;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        GenealogyPanel {
+            visible: false,
+            collapsed: HashSet::new(),
+            lines: vec![],
+            tooltip: None,
+            lines_key: None,
+        }
+    }
+
+    pub fn toggle_visible(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn toggle_branch(&mut self, cell_id: CellId) {
+        if !self.collapsed.remove(&cell_id) {
+            self.collapsed.insert(cell_id);
+        }
+        self.lines_key = None;
+    }
+
+    pub fn rects(&mut self, world: &evo_domain::world::World, tooltip: &Tooltip) -> Vec<Rect> {
+        self.tooltip = None;
+        if !self.visible {
+            self.lines.clear();
+            self.lines_key = None;
+            return vec![];
+        }
+
+        let selected_id = world
+            .cells()
+            .iter()
+            .find(|cell| cell.is_selected())
+            .map(|cell| cell.id());
+        match (world.genealogy(), selected_id) {
+            (None, _) => self.set_label("NO GENEALOGY RECORDED"),
+            (Some(_), None) => self.set_label("SELECT A CELL"),
+            (Some(genealogy), Some(cell_id)) => {
+                let key = Some((cell_id, genealogy.revision()));
+                if self.lines_key != key {
+                    self.lines = self.genealogy_lines(genealogy, cell_id);
+                    self.lines_key = key;
+                }
+            }
+        }
+        self.tooltip = Some(*tooltip);
+        tooltip.bottom_left_rects(&self.texts())
+    }
+
+    // `point` is in normalized device coordinates.
+    pub fn click_to_user_action(&self, point: [f32; 2]) -> Option<UserAction> {
+        let tooltip = self.tooltip?;
+        let (line_index, char_index) = tooltip.bottom_left_char_at(&self.texts(), point)?;
+        let line = &self.lines[line_index];
+        let cell_id = line.cell_id?;
+        if line.marker_column == Some(char_index) {
+            Some(UserAction::ToggleGenealogyBranch { cell_id })
+        } else if line.alive {
+            Some(UserAction::SelectOnlyCell { cell_id })
+        } else {
+            None
+        }
+    }
+
+    fn set_label(&mut self, text: &str) {
+        self.lines = vec![PanelLine::label(text)];
+        self.lines_key = None;
+    }
+
+    fn texts(&self) -> Vec<String> {
+        self.lines.iter().map(|line| line.text.clone()).collect()
+    }
+
+    // Walks only as far as the lines that fit.
+    fn genealogy_lines(&self, genealogy: &Genealogy, cell_id: CellId) -> Vec<PanelLine> {
+        let mut lines = vec![
+            PanelLine::label(&format!("CELL {}", cell_id.value())),
+            PanelLine::label("ANCESTORS"),
+        ];
+        for ancestor_id in genealogy.ancestors(cell_id).take(Self::MAX_LINES) {
+            lines.push(PanelLine::relative(
+                ancestor_id,
+                !genealogy.is_dead(ancestor_id),
+                Self::INDENT,
+                None,
+            ));
+        }
+        lines.push(PanelLine::label("DESCENDANTS"));
+        let descendants = genealogy
+            .descendants(cell_id, |id| !self.collapsed.contains(&id))
+            .take((Self::MAX_LINES + 1).saturating_sub(lines.len()));
+        for (descendant_id, generation) in descendants {
+            let marker = if genealogy.children(descendant_id).is_empty() {
+                None
+            } else if self.collapsed.contains(&descendant_id) {
+                Some('+')
+            } else {
+                Some('-')
+            };
+            lines.push(PanelLine::relative(
+                descendant_id,
+                !genealogy.is_dead(descendant_id),
+                generation * Self::INDENT,
+                marker,
+            ));
+        }
+
+        if lines.len() > Self::MAX_LINES {
+            lines.truncate(Self::MAX_LINES - 1);
+            lines.push(PanelLine::label("..."));
+        }
+        lines
+    }
+}
+
+impl PanelLine {
+    fn label(text: &str) -> Self {
+        PanelLine {
+            text: text.to_string(),
+            cell_id: None,
+            alive: false,
+            marker_column: None,
+        }
+    }
+
+    // Lines up the relative's ID after a marker column, which is blank unless it has a marker.
+    fn relative(cell_id: CellId, alive: bool, indent: usize, marker: Option<char>) -> Self {
+        let text = format!(
+            "{}{} {}{}",
+            " ".repeat(indent - 1),
+            marker.unwrap_or(' '),
+            cell_id.value(),
+            if alive { "" } else { " DEAD" }
+        );
+        PanelLine {
+            text,
+            cell_id: Some(cell_id),
+            alive,
+            marker_column: marker.map(|_| indent - 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_ancestors_and_living_branches_of_descendants() {
+        let panel = GenealogyPanel::new();
+        let genealogy = family(&[1, 5, 6]);
+
+        let lines = panel.genealogy_lines(&genealogy, id(2));
+
+        assert_eq!(
+            texts(&lines),
+            vec![
+                "CELL 2",
+                "ANCESTORS",
+                "   1",
+                "DESCENDANTS",
+                " - 3 DEAD",
+                "     5",
+                "   6",
+            ]
+        );
+    }
+
+    #[test]
+    fn collapsed_branch_hides_its_descendants() {
+        let mut panel = GenealogyPanel::new();
+        let genealogy = family(&[5, 6]);
+        panel.toggle_branch(id(3));
+
+        let lines = panel.genealogy_lines(&genealogy, id(2));
+
+        assert_eq!(&texts(&lines)[4..], &[" + 3 DEAD", "   6"]);
+    }
+
+    #[test]
+    fn long_lineages_are_cut_at_max_lines() {
+        let panel = GenealogyPanel::new();
+        let mut genealogy = Genealogy::new();
+        for value in 1..1000 {
+            genealogy.record_birth(id(value), id(value + 1));
+        }
+
+        let lines = panel.genealogy_lines(&genealogy, id(500));
+
+        assert_eq!(lines.len(), GenealogyPanel::MAX_LINES);
+        assert_eq!(lines[GenealogyPanel::MAX_LINES - 1].text, "...");
+    }
+
+    #[test]
+    fn clicks_toggle_branches_and_select_living_relatives() {
+        let mut panel = GenealogyPanel::new();
+        let tooltip = Tooltip::new([0.01, 0.01]);
+        panel.lines = panel.genealogy_lines(&family(&[5, 6]), id(2));
+        panel.tooltip = Some(tooltip);
+        let corners = tooltip.bottom_left_rects(&panel.texts())[0].corners;
+        // line 4 is " - 3 DEAD" and line 5 is "     5"; lines are 7 font pixels apart after
+        // 2 of padding, and characters 4 apart
+        let line_4_y = corners[3] - 0.01 * (2.0 + 4.0 * 7.0 + 2.0);
+        let line_5_y = line_4_y - 0.07;
+
+        assert_eq!(
+            panel.click_to_user_action([corners[0] + 0.01 * (2.0 + 4.0 + 1.0), line_4_y]),
+            Some(UserAction::ToggleGenealogyBranch { cell_id: id(3) })
+        );
+        assert_eq!(
+            panel.click_to_user_action([corners[0] + 0.01 * (2.0 + 8.0 + 1.0), line_4_y]),
+            None
+        );
+        assert_eq!(
+            panel.click_to_user_action([corners[0] + 0.01 * (2.0 + 20.0 + 1.0), line_5_y]),
+            Some(UserAction::SelectOnlyCell { cell_id: id(5) })
+        );
+    }
+
+    #[test]
+    fn hidden_panel_ignores_clicks() {
+        let panel = GenealogyPanel::new();
+        assert_eq!(panel.click_to_user_action([-0.9, -0.9]), None);
+    }
+
+    // 1 budded 2, which budded 3 and 6, and 3 budded 4 and 5; all but the living have died
+    fn family(living: &[u64]) -> Genealogy {
+        let mut genealogy = Genealogy::new();
+        genealogy.record_birth(id(1), id(2));
+        genealogy.record_birth(id(2), id(3));
+        genealogy.record_birth(id(3), id(4));
+        genealogy.record_birth(id(3), id(5));
+        genealogy.record_birth(id(2), id(6));
+        for value in 1..=6 {
+            if !living.contains(&value) {
+                genealogy.record_death(id(value));
+            }
+        }
+        genealogy
+    }
+
+    fn texts(lines: &[PanelLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    fn id(value: u64) -> CellId {
+        CellId::new(value)
+    }
+}
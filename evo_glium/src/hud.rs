@@ -0,0 +1,138 @@
+use crate::background_drawing::World as Rect;
+use crate::tooltip::Tooltip;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// The current tick, the ticks and frames per second achieved over the last second of wall-clock
+// time, and the number of cells, drawn in the top right corner of the window.
+pub struct Hud {
+    visible: bool,
+    editing: bool,
+    // When each recent frame was rendered, and the world's tick at the time, oldest first
+    frames: VecDeque<(Instant, u64)>,
+}
+
+impl Hud {
+    const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Hud {
+            visible: true,
+            editing: false,
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub fn toggle_visible(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn set_editing(&mut self, editing: bool) {
+        self.editing = editing;
+    }
+
+    pub fn record_frame(&mut self, now: Instant, tick: u64) {
+        self.frames.push_back((now, tick));
+        while let Some(&(time, _)) = self.frames.front() {
+            if now.duration_since(time) <= Self::RATE_WINDOW {
+                break;
+            }
+            self.frames.pop_front();
+        }
+    }
+
+    // Ticks and frames per second, or zeros until there are two frames to measure between.
+    fn rates(&self) -> (f64, f64) {
+        match (self.frames.front(), self.frames.back()) {
+            (Some(&(first_time, first_tick)), Some(&(last_time, last_tick)))
+                if last_time > first_time =>
+            {
+                let seconds = last_time.duration_since(first_time).as_secs_f64();
+                (
+                    (last_tick - first_tick) as f64 / seconds,
+                    (self.frames.len() - 1) as f64 / seconds,
+                )
+            }
+            _ => (0.0, 0.0),
+        }
+    }
+
+    fn lines(&self, world: &evo_domain::world::World) -> Vec<String> {
+        let (ticks_per_second, frames_per_second) = self.rates();
+        let mut lines = vec![
+            format!("TICK {}", world.current_tick()),
+            format!("TPS {:.1}", ticks_per_second),
+            format!("FPS {:.1}", frames_per_second),
+            format!("CELLS {}", world.cells().len()),
+        ];
+        if self.editing {
+            lines.push("EDITING".to_string());
+        }
+        lines
+    }
+
+    pub fn rects(&self, world: &evo_domain::world::World, tooltip: &Tooltip) -> Vec<Rect> {
+        if !self.visible {
+            return vec![];
+        }
+        tooltip.top_right_rects(&self.lines(world))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evo_domain::physics::quantities::Position;
+
+    #[test]
+    fn rates_need_two_frames() {
+        let mut hud = Hud::new();
+        hud.record_frame(Instant::now(), 5);
+        assert_eq!(hud.rates(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn rates_are_measured_over_recent_frames() {
+        let mut hud = Hud::new();
+        let start = Instant::now();
+        hud.record_frame(start, 0);
+        hud.record_frame(start + Duration::from_secs(2), 10);
+        hud.record_frame(start + Duration::from_millis(2500), 40);
+        hud.record_frame(start + Duration::from_secs(3), 70);
+
+        assert_eq!(hud.rates(), (60.0, 2.0));
+    }
+
+    #[test]
+    fn lines_show_tick_and_population() {
+        let hud = Hud::new();
+        let world =
+            evo_domain::world::World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
+
+        let lines = hud.lines(&world);
+
+        assert_eq!(lines[0], "TICK 0");
+        assert_eq!(lines[3], "CELLS 0");
+    }
+
+    #[test]
+    fn lines_show_edit_mode() {
+        let mut hud = Hud::new();
+        hud.set_editing(true);
+        let world =
+            evo_domain::world::World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
+
+        assert_eq!(hud.lines(&world).last().unwrap(), "EDITING");
+    }
+
+    #[test]
+    fn hidden_hud_draws_nothing() {
+        let mut hud = Hud::new();
+        hud.toggle_visible();
+        let world =
+            evo_domain::world::World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
+
+        assert!(hud.rects(&world, &Tooltip::new([0.01, 0.01])).is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+use crate::cell_drawing::*;
+use crate::genealogy_panel::GenealogyPanel;
+use crate::parameter_panel::ParameterPanel;
+use crate::LogicalPositionToWorldPosition;
+use evo_domain::physics::quantities::Position;
+use evo_domain::UserAction;
+use glium::glutin;
+use glium::glutin::VirtualKeyCode;
+use std::collections::HashMap;
+use std::fs;
+
+// Maps keys to user actions. A keymap file overrides the default bindings one line at a time:
+//     # comment
+//     <key> <action>
+// where <key> is a name like "P", "5", "Escape", "Space" or "F1", and <action> is one of
+// the key actions below, or "None" to unbind the key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<VirtualKeyCode, UserAction>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        bindings.insert(VirtualKeyCode::D, UserAction::DebugPrint);
+        bindings.insert(VirtualKeyCode::E, UserAction::ExportSelectedCells);
+        bindings.insert(VirtualKeyCode::K, UserAction::KillSelectedCells);
+        bindings.insert(VirtualKeyCode::Delete, UserAction::KillSelectedCells);
+        bindings.insert(VirtualKeyCode::N, UserAction::BondSelectedCells);
+        bindings.insert(VirtualKeyCode::W, UserAction::ExportWorld);
+        bindings.insert(VirtualKeyCode::F2, UserAction::ToggleEditMode);
+        bindings.insert(VirtualKeyCode::Escape, UserAction::Exit);
+        bindings.insert(VirtualKeyCode::Q, UserAction::Exit);
+        bindings.insert(VirtualKeyCode::X, UserAction::Exit);
+        bindings.insert(VirtualKeyCode::P, UserAction::PlayToggle);
+        bindings.insert(VirtualKeyCode::S, UserAction::SingleTick);
+        bindings.insert(VirtualKeyCode::C, UserAction::Screenshot);
+        bindings.insert(VirtualKeyCode::G, UserAction::TagSelectedCells);
+        bindings.insert(VirtualKeyCode::B, UserAction::ToggleBonds);
+        bindings.insert(VirtualKeyCode::J, UserAction::ToggleGussets);
+        bindings.insert(VirtualKeyCode::H, UserAction::ToggleHud);
+        bindings.insert(VirtualKeyCode::M, UserAction::ToggleMotionTrails);
+        bindings.insert(VirtualKeyCode::L, UserAction::ToggleParentLines);
+        bindings.insert(VirtualKeyCode::T, UserAction::ToggleParameterPanel);
+        bindings.insert(VirtualKeyCode::A, UserAction::ToggleGenealogyPanel);
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    const KEY_ACTIONS: [(&'static str, UserAction); 18] = [
+        ("BondSelectedCells", UserAction::BondSelectedCells),
+        ("DebugPrint", UserAction::DebugPrint),
+        ("Exit", UserAction::Exit),
+        ("ExportSelectedCells", UserAction::ExportSelectedCells),
+        ("ExportWorld", UserAction::ExportWorld),
+        ("KillSelectedCells", UserAction::KillSelectedCells),
+        ("PlayToggle", UserAction::PlayToggle),
+        ("Screenshot", UserAction::Screenshot),
+        ("SingleTick", UserAction::SingleTick),
+        ("TagSelectedCells", UserAction::TagSelectedCells),
+        ("ToggleBonds", UserAction::ToggleBonds),
+        ("ToggleEditMode", UserAction::ToggleEditMode),
+        ("ToggleGenealogyPanel", UserAction::ToggleGenealogyPanel),
+        ("ToggleGussets", UserAction::ToggleGussets),
+        ("ToggleHud", UserAction::ToggleHud),
+        ("ToggleMotionTrails", UserAction::ToggleMotionTrails),
+        ("ToggleParameterPanel", UserAction::ToggleParameterPanel),
+        ("ToggleParentLines", UserAction::ToggleParentLines),
+    ];
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Cannot read keymap file {}: {}", path, err))?;
+        Self::default().with_overrides(&text)
+    }
+
+    pub fn with_overrides(mut self, text: &str) -> Result<Self, String> {
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 2 {
+                return Err(format!(
+                    "Line {}: expected <key> <action>: {}",
+                    line_index + 1,
+                    line
+                ));
+            }
+            let key_code = key_code_from_name(fields[0])
+                .ok_or_else(|| format!("Line {}: unknown key: {}", line_index + 1, fields[0]))?;
+            if fields[1] == "None" {
+                self.bindings.remove(&key_code);
+            } else {
+                let action = Self::key_action_from_name(fields[1]).ok_or_else(|| {
+                    format!("Line {}: unknown action: {}", line_index + 1, fields[1])
+                })?;
+                self.bindings.insert(key_code, action);
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn action_for_key(&self, key_code: VirtualKeyCode) -> Option<UserAction> {
+        self.bindings.get(&key_code).copied()
+    }
+
+    fn key_action_from_name(name: &str) -> Option<UserAction> {
+        Self::KEY_ACTIONS
+            .iter()
+            .find(|(action_name, _)| *action_name == name)
+            .map(|(_, action)| *action)
+    }
+}
+
+fn key_code_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+    const LETTERS: [VirtualKeyCode; 26] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+    const DIGITS: [VirtualKeyCode; 10] =
+        [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+    const FUNCTION_KEYS: [VirtualKeyCode; 12] = [F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12];
+    const NAMED_KEYS: [(&str, VirtualKeyCode); 12] = [
+        ("Escape", Escape),
+        ("Space", Space),
+        ("Return", Return),
+        ("Tab", Tab),
+        ("Back", Back),
+        ("Delete", Delete),
+        ("Left", Left),
+        ("Right", Right),
+        ("Up", Up),
+        ("Down", Down),
+        ("PageUp", PageUp),
+        ("PageDown", PageDown),
+    ];
+
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphabetic() {
+            return Some(LETTERS[(c.to_ascii_uppercase() as u8 - b'A') as usize]);
+        }
+        if let Some(digit) = c.to_digit(10) {
+            return Some(DIGITS[digit as usize]);
+        }
+    }
+    if let Some(number) = name.strip_prefix('F').and_then(|n| n.parse::<usize>().ok()) {
+        if (1..=FUNCTION_KEYS.len()).contains(&number) {
+            return Some(FUNCTION_KEYS[number - 1]);
+        }
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key_name, _)| *key_name == name)
+        .map(|(_, key_code)| *key_code)
+}
+
+// A left-button press and release closer together than this, in logical pixels, is a click
+// rather than a drag.
+const MAX_CLICK_DISTANCE: f64 = 4.0;
+
+pub struct MouseState {
+    position: glutin::dpi::LogicalPosition,
+    press_position: Option<glutin::dpi::LogicalPosition>,
+    in_window: bool,
+}
+
+impl MouseState {
+    pub fn new() -> Self {
+        MouseState {
+            position: glutin::dpi::LogicalPosition::new(0.0, 0.0),
+            press_position: None,
+            in_window: false,
+        }
+    }
+
+    // Where the mouse pointer is, unless it is outside the window.
+    pub fn hover_position(&self) -> Option<glutin::dpi::LogicalPosition> {
+        if self.in_window {
+            Some(self.position)
+        } else {
+            None
+        }
+    }
+}
+
+// What the view knows about its current state that's needed to interpret events.
+pub struct InputContext<'a> {
+    pub keymap: &'a Keymap,
+    pub logical_position_to_world_position: &'a LogicalPositionToWorldPosition,
+    pub parameter_panel: &'a ParameterPanel,
+    pub genealogy_panel: &'a GenealogyPanel,
+    pub cell_sprites: &'a [CellSprite],
+    // In edit mode, clicking empty space adds a cell and dragging a cell moves it
+    pub edit_mode: bool,
+}
+
+pub fn event_to_user_action(
+    event: &glutin::Event,
+    context: &InputContext,
+    mouse: &mut MouseState,
+) -> Option<UserAction> {
+    match event {
+        glutin::Event::WindowEvent { event, .. } => match event {
+            glutin::WindowEvent::CloseRequested => Some(UserAction::Exit),
+
+            glutin::WindowEvent::CursorMoved { position, .. } => {
+                mouse.position = *position;
+                mouse.in_window = true;
+                None
+            }
+
+            glutin::WindowEvent::CursorEntered { .. } => {
+                mouse.in_window = true;
+                None
+            }
+
+            glutin::WindowEvent::CursorLeft { .. } => {
+                mouse.in_window = false;
+                None
+            }
+
+            glutin::WindowEvent::KeyboardInput {
+                input:
+                    glutin::KeyboardInput {
+                        state: glutin::ElementState::Pressed,
+                        virtual_keycode: Some(key_code),
+                        ..
+                    },
+                ..
+            } => context.keymap.action_for_key(*key_code),
+
+            glutin::WindowEvent::MouseInput {
+                button: glutin::MouseButton::Left,
+                state: glutin::ElementState::Pressed,
+                ..
+            } => {
+                let converter = context.logical_position_to_world_position;
+                let device_position = converter.convert_to_device(mouse.position);
+                if let Some(user_action) = context
+                    .parameter_panel
+                    .click_to_user_action(device_position)
+                {
+                    return Some(user_action);
+                }
+                if let Some(user_action) = context
+                    .genealogy_panel
+                    .click_to_user_action(device_position)
+                {
+                    return Some(user_action);
+                }
+                mouse.press_position = Some(mouse.position);
+                None
+            }
+
+            glutin::WindowEvent::MouseInput {
+                button: glutin::MouseButton::Left,
+                state: glutin::ElementState::Released,
+                modifiers,
+                ..
+            } => {
+                let press_position = mouse.press_position.take()?;
+                let converter = context.logical_position_to_world_position;
+                let press_world_position = converter.convert(press_position);
+                let world_position = converter.convert(mouse.position);
+                let point = [world_position.0 as f32, world_position.1 as f32];
+                if (mouse.position.x - press_position.x).abs() <= MAX_CLICK_DISTANCE
+                    && (mouse.position.y - press_position.y).abs() <= MAX_CLICK_DISTANCE
+                {
+                    // shift-click inspects the clicked ring instead of selecting its cell
+                    if modifiers.shift {
+                        find_cell_layer_at(context.cell_sprites, point).map(
+                            |(cell_id, layer_index)| UserAction::InspectLayer {
+                                cell_id,
+                                layer_index,
+                            },
+                        )
+                    } else {
+                        match find_cell_at(context.cell_sprites, point) {
+                            Some(cell_id) => Some(UserAction::SelectCellToggle { cell_id }),
+                            None if context.edit_mode => Some(UserAction::AddCell {
+                                position: Position::new(world_position.0, world_position.1),
+                            }),
+                            None => None,
+                        }
+                    }
+                } else if let Some(cell_id) = find_cell_at(
+                    context.cell_sprites,
+                    [press_world_position.0 as f32, press_world_position.1 as f32],
+                )
+                .filter(|_| context.edit_mode)
+                {
+                    Some(UserAction::MoveCell {
+                        cell_id,
+                        position: Position::new(world_position.0, world_position.1),
+                    })
+                } else {
+                    Some(UserAction::SelectCellsInRect {
+                        min_corner: Position::new(
+                            press_world_position.0.min(world_position.0),
+                            press_world_position.1.min(world_position.1),
+                        ),
+                        max_corner: Position::new(
+                            press_world_position.0.max(world_position.0),
+                            press_world_position.1.max(world_position.1),
+                        ),
+                    })
+                }
+            }
+
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_keymap_binds_play_toggle() {
+        assert_eq!(
+            Keymap::default().action_for_key(VirtualKeyCode::P),
+            Some(UserAction::PlayToggle)
+        );
+    }
+
+    #[test]
+    fn overrides_rebind_and_unbind_keys() {
+        let keymap = Keymap::default()
+            .with_overrides(
+                "# play with the space bar\n\
+                 Space PlayToggle\n\
+                 p None\n\
+                 F5 SingleTick  # step\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            keymap.action_for_key(VirtualKeyCode::Space),
+            Some(UserAction::PlayToggle)
+        );
+        assert_eq!(keymap.action_for_key(VirtualKeyCode::P), None);
+        assert_eq!(
+            keymap.action_for_key(VirtualKeyCode::F5),
+            Some(UserAction::SingleTick)
+        );
+        assert_eq!(
+            keymap.action_for_key(VirtualKeyCode::Escape),
+            Some(UserAction::Exit)
+        );
+    }
+
+    #[test]
+    fn overrides_report_bad_lines() {
+        assert_eq!(
+            Keymap::default().with_overrides("Space\n"),
+            Err("Line 1: expected <key> <action>: Space".to_string())
+        );
+        assert_eq!(
+            Keymap::default().with_overrides("\nHyper Exit\n"),
+            Err("Line 2: unknown key: Hyper".to_string())
+        );
+        assert_eq!(
+            Keymap::default().with_overrides("3 Explode\n"),
+            Err("Line 1: unknown action: Explode".to_string())
+        );
+    }
+}
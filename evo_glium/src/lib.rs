@@ -1,16 +1,36 @@
 use glium::{glutin, Surface};
 
 mod background_drawing;
-//mod bond_drawing;
+mod bond_drawing;
 mod cell_drawing;
+mod genealogy_panel;
+mod hud;
+mod input;
+mod parameter_panel;
+mod screenshot;
+mod tooltip;
+mod trail_drawing;
+mod wall_drawing;
 
 use background_drawing::*;
-//use bond_drawing::*;
+use bond_drawing::*;
 use cell_drawing::*;
-use evo_domain::biology::cell::Cell;
+use evo_domain::biology::cell::{Cell, CellId};
 use evo_domain::biology::layers;
 use evo_domain::physics::shapes::Circle;
 use evo_domain::UserAction;
+use genealogy_panel::*;
+use hud::*;
+pub use input::Keymap;
+use input::*;
+use parameter_panel::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Instant;
+use tooltip::*;
+use trail_drawing::*;
+use wall_drawing::*;
 
 type Point = [f32; 2];
 
@@ -21,11 +41,33 @@ pub struct GliumView {
     world_max_corner: Point,
     background_drawing: BackgroundDrawing,
     cell_drawing: CellDrawing,
+    trail_drawing: TrailDrawing,
     world_vb: glium::VertexBuffer<World>,
-    mouse_position: glutin::dpi::LogicalPosition,
+    parameter_panel: ParameterPanel,
+    genealogy_panel: GenealogyPanel,
+    hud: Hud,
+    mouse: MouseState,
+    keymap: Keymap,
+    edit_mode: bool,
+    // The sprites last drawn, for mapping clicks to cells
+    cell_sprites: Vec<CellSprite>,
+    trails: Trails,
+    trail_vertices: Vec<TrailVertex>,
+    bond_lines: BondLines,
+    bond_vertices: Vec<TrailVertex>,
+    wall_rects: Vec<World>,
 }
 
 impl GliumView {
+    const TRAIL_LENGTH: usize = 60;
+    // How much dimmer the halo around the cell under the mouse pointer is than the selection
+    // halo, as a layer health
+    const HOVER_HALO_HEALTH: f32 = 0.3;
+    // Wall width as a fraction of the world's larger dimension
+    const WALL_THICKNESS: f32 = 0.01;
+    // Logical pixels per tooltip font pixel
+    const TOOLTIP_PIXEL_SIZE: f64 = 2.0;
+
     pub fn new(world_min_corner: Point, world_max_corner: Point) -> Self {
         let events_loop = glutin::EventsLoop::new();
         let window = glutin::WindowBuilder::new().with_dimensions(Self::calc_initial_window_size(
@@ -42,6 +84,7 @@ impl GliumView {
         let display = glium::Display::new(window, context, &events_loop).unwrap();
         let background_drawing = BackgroundDrawing::new(&display);
         let cell_drawing = CellDrawing::new(&display);
+        let trail_drawing = TrailDrawing::new(&display);
         let world = vec![World {
             corners: [
                 world_min_corner[0],
@@ -61,11 +104,28 @@ impl GliumView {
             world_max_corner,
             background_drawing,
             cell_drawing,
+            trail_drawing,
             world_vb,
-            mouse_position: glutin::dpi::LogicalPosition::new(0.0, 0.0),
+            parameter_panel: ParameterPanel::new(),
+            genealogy_panel: GenealogyPanel::new(),
+            hud: Hud::new(),
+            mouse: MouseState::new(),
+            keymap: Keymap::default(),
+            edit_mode: false,
+            cell_sprites: vec![],
+            trails: Trails::new(Self::TRAIL_LENGTH),
+            trail_vertices: vec![],
+            bond_lines: BondLines::new(),
+            bond_vertices: vec![],
+            wall_rects: vec![],
         }
     }
 
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
     fn get_screen_size(monitor: glutin::MonitorId) -> glutin::dpi::LogicalSize {
         monitor
             .get_dimensions()
@@ -94,10 +154,109 @@ impl GliumView {
     }
 
     pub fn render(&mut self, world: &evo_domain::world::World) {
-        self.draw_frame(
-            &Self::world_cells_to_cell_sprites(world),
-            Self::get_layer_colors(world),
+        self.cell_sprites = Self::world_cells_to_cell_sprites(world);
+        self.trail_vertices = self.trails.update(world);
+        let viewport = self.viewport();
+        self.bond_vertices = self.bond_lines.vertices(world, &viewport);
+        self.wall_rects = WallRects::new(Self::WALL_THICKNESS).rects(world);
+        self.hud.record_frame(Instant::now(), world.current_tick());
+        let tooltip = self.tooltip();
+        let mut overlay_rects = self.parameter_panel.rects(world);
+        overlay_rects.extend(self.genealogy_panel.rects(world, &tooltip));
+        overlay_rects.extend(self.hud.rects(world, &tooltip));
+        overlay_rects.extend(self.highlight_hovered_cell(world));
+        self.draw_frame(&overlay_rects);
+    }
+
+    // Puts a dim halo around the cell under the mouse pointer, unless it is selected, and
+    // returns the rects of a tooltip describing it.
+    fn highlight_hovered_cell(&mut self, world: &evo_domain::world::World) -> Vec<World> {
+        let hover_position = match self.mouse.hover_position() {
+            Some(position) => position,
+            None => return vec![],
+        };
+        let converter = LogicalPositionToWorldPosition::new(
+            self.window_size(),
+            self.world_min_corner,
+            self.world_max_corner,
         );
+        let world_position = converter.convert(hover_position);
+        let cell_id = match find_cell_at(
+            &self.cell_sprites,
+            [world_position.0 as f32, world_position.1 as f32],
+        ) {
+            Some(cell_id) => cell_id,
+            None => return vec![],
+        };
+        let cell_index = world
+            .cells()
+            .iter()
+            .position(|cell| cell.id() == cell_id)
+            .unwrap();
+        let cell = &world.cells()[cell_index];
+        if !cell.is_selected() {
+            self.cell_sprites[cell_index].add_halo(Self::HOVER_HALO_HEALTH);
+        }
+
+        self.tooltip().rects(
+            &Self::cell_tooltip_lines(cell),
+            converter.convert_to_device(hover_position),
+        )
+    }
+
+    fn tooltip(&self) -> Tooltip {
+        let window_size = self.window_size();
+        Tooltip::new([
+            (2.0 * Self::TOOLTIP_PIXEL_SIZE / window_size.width) as f32,
+            (2.0 * Self::TOOLTIP_PIXEL_SIZE / window_size.height) as f32,
+        ])
+    }
+
+    fn cell_tooltip_lines(cell: &Cell) -> Vec<String> {
+        vec![
+            format!("CELL {}", cell.id().value()),
+            format!("ENERGY {:.1}", cell.energy().value()),
+            format!("AGE {}", cell.age()),
+            format!("LINEAGE {}", cell.lineage()),
+        ]
+    }
+
+    pub fn toggle_parameter_panel(&mut self) {
+        self.parameter_panel.toggle_visible();
+    }
+
+    pub fn toggle_genealogy_panel(&mut self) {
+        self.genealogy_panel.toggle_visible();
+    }
+
+    pub fn toggle_genealogy_branch(&mut self, cell_id: CellId) {
+        self.genealogy_panel.toggle_branch(cell_id);
+    }
+
+    // In edit mode, clicks and drags add and move cells.
+    pub fn set_edit_mode(&mut self, edit_mode: bool) {
+        self.edit_mode = edit_mode;
+        self.hud.set_editing(edit_mode);
+    }
+
+    pub fn toggle_hud(&mut self) {
+        self.hud.toggle_visible();
+    }
+
+    pub fn toggle_motion_trails(&mut self) {
+        self.trails.toggle_motion_trails();
+    }
+
+    pub fn toggle_parent_lines(&mut self) {
+        self.trails.toggle_parent_lines();
+    }
+
+    pub fn toggle_bonds(&mut self) {
+        self.bond_lines.toggle_bonds();
+    }
+
+    pub fn toggle_gussets(&mut self) {
+        self.bond_lines.toggle_gussets();
     }
 
     fn world_cells_to_cell_sprites(world: &evo_domain::world::World) -> Vec<CellSprite> {
@@ -109,65 +268,101 @@ impl GliumView {
     }
 
     fn world_cell_to_cell_sprite(cell: &Cell) -> CellSprite {
-        let mut num_layers = cell.layers().len();
+        let num_layers = cell.layers().len();
         let mut radii: [f32; 8] = [0.0; 8];
         let mut health: [f32; 8] = [0.0; 8];
+        let mut colors: [u32; 8] = [0; 8];
         assert!(num_layers <= radii.len());
         for (i, layer) in cell.layers().iter().enumerate() {
             radii[i] = layer.outer_radius().value() as f32;
             health[i] = layer.health() as f32;
+            colors[i] = Self::palette_index(layer.visible_color());
         }
-        if cell.is_selected() {
-            num_layers += 1;
-            assert!(num_layers <= radii.len());
-            radii[num_layers - 1] = (cell.radius().value() + 1.0) as f32;
-            health[num_layers - 1] = 1.0;
-        }
-        CellSprite {
+        let mut sprite = CellSprite {
+            cell_id: cell.id(),
             center: [cell.center().x() as f32, cell.center().y() as f32],
+            half_axis: [cell.half_axis().x() as f32, cell.half_axis().y() as f32],
             num_layers: num_layers as u32,
             radii_0_3: [radii[0], radii[1], radii[2], radii[3]],
             radii_4_7: [radii[4], radii[5], radii[6], radii[7]],
             health_0_3: [health[0], health[1], health[2], health[3]],
             health_4_7: [health[4], health[5], health[6], health[7]],
+            colors_0_3: [colors[0], colors[1], colors[2], colors[3]],
+            colors_4_7: [colors[4], colors[5], colors[6], colors[7]],
+        };
+        if cell.is_selected() {
+            sprite.add_halo(1.0);
         }
+        sprite
     }
 
-    fn get_layer_colors(world: &evo_domain::world::World) -> [[f32; 4]; 8] {
-        const SELECTION_HALO_COLOR: [f32; 4] = [1.0, 0.0, 0.2, 1.0];
+    // The layer colors in the order of Color::ALL, then the selection halo color.
+    const PALETTE: [[f32; 4]; 4] = [
+        [0.1, 0.8, 0.1, 1.0],
+        [1.0, 1.0, 1.0, 1.0],
+        [0.7, 0.7, 0.0, 1.0],
+        [1.0, 0.0, 0.2, 1.0],
+    ];
 
-        let mut layer_colors: [[f32; 4]; 8] = [[0.0, 0.0, 0.0, 1.0]; 8];
-        if !world.cells().is_empty() {
-            let sample_cell = &world.cells()[0];
-            assert!(sample_cell.layers().len() < layer_colors.len());
-            for (i, layer) in sample_cell.layers().iter().enumerate() {
-                layer_colors[i] = Self::convert_to_rgb_color(layer.color());
-            }
-            layer_colors[sample_cell.layers().len()] = SELECTION_HALO_COLOR;
-        }
-        layer_colors
+    fn palette_index(color: layers::Color) -> u32 {
+        layers::Color::ALL
+            .iter()
+            .position(|&palette_color| palette_color == color)
+            .unwrap() as u32
     }
 
-    fn convert_to_rgb_color(color: layers::Color) -> [f32; 4] {
-        match color {
-            layers::Color::Green => [0.1, 0.8, 0.1, 1.0],
-            layers::Color::White => [1.0, 1.0, 1.0, 1.0],
-            layers::Color::Yellow => [0.7, 0.7, 0.0, 1.0],
-        }
-    }
+    fn draw_frame(&mut self, panel_rects: &[World]) {
+        const IDENTITY_TRANSFORM: [[f32; 4]; 4] = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
 
-    fn draw_frame(&mut self, cells: &[CellSprite], layer_colors: [[f32; 4]; 8]) {
-        let cells_vb = glium::VertexBuffer::new(&self.display, &cells).unwrap();
+        let cells_vb = glium::VertexBuffer::new(&self.display, &self.cell_sprites).unwrap();
         let screen_transform = self.current_screen_transform();
         let mut frame = self.display.draw();
         frame.clear_color(0.0, 0.0, 0.0, 1.0);
         self.background_drawing
             .draw(&mut frame, &self.world_vb, screen_transform);
+        if !self.wall_rects.is_empty() {
+            let walls_vb = glium::VertexBuffer::new(&self.display, &self.wall_rects).unwrap();
+            self.background_drawing
+                .draw(&mut frame, &walls_vb, screen_transform);
+        }
+        if !self.trail_vertices.is_empty() {
+            let trails_vb = glium::VertexBuffer::new(&self.display, &self.trail_vertices).unwrap();
+            self.trail_drawing
+                .draw(&mut frame, &trails_vb, screen_transform);
+        }
         self.cell_drawing
-            .draw(&mut frame, &cells_vb, screen_transform, layer_colors);
+            .draw(&mut frame, &cells_vb, screen_transform, Self::PALETTE);
+        if !self.bond_vertices.is_empty() {
+            let bonds_vb = glium::VertexBuffer::new(&self.display, &self.bond_vertices).unwrap();
+            self.trail_drawing
+                .draw(&mut frame, &bonds_vb, screen_transform);
+        }
+        if !panel_rects.is_empty() {
+            let panel_vb = glium::VertexBuffer::new(&self.display, panel_rects).unwrap();
+            self.background_drawing
+                .draw(&mut frame, &panel_vb, IDENTITY_TRANSFORM);
+        }
         frame.finish().unwrap();
     }
 
+    // Writes the last frame drawn to a PNG file.
+    pub fn save_screenshot(&self, path: &Path) -> Result<(), String> {
+        let image: glium::texture::RawImage2d<u8> = self
+            .display
+            .read_front_buffer()
+            .map_err(|err| format!("Cannot read frame: {:?}", err))?;
+        let pixels = screenshot::flip_rows(&image.data, image.width as usize * 4);
+        let file = File::create(path)
+            .map_err(|err| format!("Cannot create {}: {}", path.display(), err))?;
+        screenshot::write_png(BufWriter::new(file), image.width, image.height, &pixels)
+            .map_err(|err| format!("Cannot write {}: {}", path.display(), err))
+    }
+
     fn current_screen_transform(&mut self) -> [[f32; 4]; 4] {
         // TODO more efficient to do this only on glutin::WindowEvent::Resized
         let window_size = self.window_size();
@@ -175,6 +370,14 @@ impl GliumView {
         Self::calc_screen_transform(self.world_min_corner, self.world_max_corner, window_dim)
     }
 
+    fn viewport(&mut self) -> Viewport {
+        let window_size = self.window_size();
+        Viewport::from_screen_transform(
+            self.current_screen_transform(),
+            [window_size.width as f32, window_size.height as f32],
+        )
+    }
+
     fn window_size(&self) -> glutin::dpi::LogicalSize {
         self.display.gl_window().window().get_inner_size().unwrap()
     }
@@ -221,12 +424,19 @@ impl GliumView {
             self.world_min_corner,
             self.world_max_corner,
         );
-        let mouse_position = &mut self.mouse_position;
+        let context = InputContext {
+            keymap: &self.keymap,
+            logical_position_to_world_position: &logical_position_to_world_position,
+            parameter_panel: &self.parameter_panel,
+            genealogy_panel: &self.genealogy_panel,
+            cell_sprites: &self.cell_sprites,
+            edit_mode: self.edit_mode,
+        };
+        let mouse = &mut self.mouse;
         self.events_loop.poll_events(|event| {
             // drain the event queue, capturing the first user action
-            if result == None {
-                result =
-                    Self::handle_event(&event, &logical_position_to_world_position, mouse_position);
+            if result.is_none() {
+                result = event_to_user_action(&event, &context, mouse);
             }
         });
         result
@@ -239,12 +449,18 @@ impl GliumView {
             self.world_min_corner,
             self.world_max_corner,
         );
-        let mouse_position = &mut self.mouse_position;
+        let context = InputContext {
+            keymap: &self.keymap,
+            logical_position_to_world_position: &logical_position_to_world_position,
+            parameter_panel: &self.parameter_panel,
+            genealogy_panel: &self.genealogy_panel,
+            cell_sprites: &self.cell_sprites,
+            edit_mode: self.edit_mode,
+        };
+        let mouse = &mut self.mouse;
         self.events_loop
             .run_forever(|event| -> glutin::ControlFlow {
-                if let Some(user_action) =
-                    Self::handle_event(&event, &logical_position_to_world_position, mouse_position)
-                {
+                if let Some(user_action) = event_to_user_action(&event, &context, mouse) {
                     result = user_action;
                     glutin::ControlFlow::Break
                 } else {
@@ -253,62 +469,6 @@ impl GliumView {
             });
         result
     }
-
-    fn handle_event(
-        event: &glutin::Event,
-        logical_position_to_world_position: &LogicalPositionToWorldPosition,
-        mouse_position: &mut glutin::dpi::LogicalPosition,
-    ) -> Option<UserAction> {
-        match event {
-            glutin::Event::WindowEvent { event, .. } => match event {
-                glutin::WindowEvent::CloseRequested => Some(UserAction::Exit),
-
-                glutin::WindowEvent::CursorMoved { position, .. } => {
-                    *mouse_position = *position;
-                    None
-                }
-
-                glutin::WindowEvent::KeyboardInput {
-                    input:
-                        glutin::KeyboardInput {
-                            state: glutin::ElementState::Pressed,
-                            virtual_keycode: Some(key_code),
-                            ..
-                        },
-                    ..
-                } => Self::interpret_key_as_user_action(*key_code),
-
-                glutin::WindowEvent::MouseInput {
-                    button: glutin::MouseButton::Left,
-                    state: glutin::ElementState::Pressed,
-                    ..
-                } => {
-                    let world_position =
-                        logical_position_to_world_position.convert(*mouse_position);
-                    Some(UserAction::SelectCellToggle {
-                        x: world_position.0,
-                        y: world_position.1,
-                    })
-                }
-
-                _ => None,
-            },
-
-            _ => None,
-        }
-    }
-
-    fn interpret_key_as_user_action(key_code: glutin::VirtualKeyCode) -> Option<UserAction> {
-        match key_code {
-            glutin::VirtualKeyCode::D => Some(UserAction::DebugPrint),
-            glutin::VirtualKeyCode::Escape
-            | glutin::VirtualKeyCode::Q
-            | glutin::VirtualKeyCode::X => Some(UserAction::Exit),
-            glutin::VirtualKeyCode::P => Some(UserAction::PlayToggle),
-            glutin::VirtualKeyCode::S => Some(UserAction::SingleTick),
-            _ => None,
-        }
-    }
 }
 
 struct LogicalPositionToWorldPosition {
@@ -339,6 +499,14 @@ impl LogicalPositionToWorldPosition {
         )
     }
 
+    // converts to normalized device coordinates, which run from -1 to 1 across the window
+    fn convert_to_device(&self, logical_pos: glutin::dpi::LogicalPosition) -> [f32; 2] {
+        [
+            (2.0 * logical_pos.x / self.window_size.width - 1.0) as f32,
+            (1.0 - 2.0 * logical_pos.y / self.window_size.height) as f32,
+        ]
+    }
+
     fn world_size(&self) -> (f64, f64) {
         (
             (self.world_max_corner[0] - self.world_min_corner[0]) as f64,
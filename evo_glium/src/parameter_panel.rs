@@ -0,0 +1,179 @@
+use crate::background_drawing::World as Rect;
+use evo_domain::biology::layers::Color;
+use evo_domain::environment::influences::TunableParameter;
+use evo_domain::UserAction;
+
+// Sliders for live-tunable world parameters, drawn in normalized device coordinates
+// over the top left of the window. Click a slider to set its parameter.
+pub struct ParameterPanel {
+    visible: bool,
+    sliders: Vec<ParameterSlider>,
+}
+
+struct ParameterSlider {
+    parameter: TunableParameter,
+    min_value: f64,
+    max_value: f64,
+    color: [f32; 3],
+}
+
+impl ParameterPanel {
+    const LEFT: f32 = -0.95;
+    const WIDTH: f32 = 0.5;
+    const TOP: f32 = 0.95;
+    const HEIGHT: f32 = 0.05;
+    const SPACING: f32 = 0.08;
+    const TRACK_COLOR: [f32; 3] = [0.25, 0.25, 0.25];
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        ParameterPanel {
+            visible: false,
+            sliders: vec![
+                ParameterSlider {
+                    parameter: TunableParameter::Gravity,
+                    min_value: -0.2,
+                    max_value: 0.0,
+                    color: [0.8, 0.3, 0.3],
+                },
+                ParameterSlider {
+                    parameter: TunableParameter::LightIntensity,
+                    min_value: 0.0,
+                    max_value: 2.0,
+                    color: [0.8, 0.8, 0.2],
+                },
+                ParameterSlider {
+                    parameter: TunableParameter::DragViscosity,
+                    min_value: 0.0,
+                    max_value: 0.02,
+                    color: [0.3, 0.5, 0.9],
+                },
+                ParameterSlider {
+                    parameter: TunableParameter::MutationRate,
+                    min_value: 0.0,
+                    max_value: 1.0,
+                    color: [0.7, 0.3, 0.8],
+                },
+                ParameterSlider {
+                    parameter: TunableParameter::TissueEnergyGain(Color::Green),
+                    min_value: 0.0,
+                    max_value: 2.0,
+                    color: [0.2, 0.7, 0.2],
+                },
+                ParameterSlider {
+                    parameter: TunableParameter::TissueCost(Color::Yellow),
+                    min_value: 0.0,
+                    max_value: 2.0,
+                    color: [0.9, 0.7, 0.1],
+                },
+            ],
+        }
+    }
+
+    pub fn toggle_visible(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn rects(&self, world: &evo_domain::world::World) -> Vec<Rect> {
+        let mut rects = vec![];
+        if !self.visible {
+            return rects;
+        }
+
+        for (index, slider) in self.sliders.iter().enumerate() {
+            if let Some(value) = world.tunable_parameter(slider.parameter) {
+                let corners = Self::slider_corners(index);
+                rects.push(Rect {
+                    corners,
+                    top_color: Self::TRACK_COLOR,
+                    bottom_color: Self::TRACK_COLOR,
+                });
+                let fill_width = Self::WIDTH * slider.value_to_fraction(value) as f32;
+                rects.push(Rect {
+                    corners: [corners[0], corners[1], corners[0] + fill_width, corners[3]],
+                    top_color: slider.color,
+                    bottom_color: slider.color,
+                });
+            }
+        }
+        rects
+    }
+
+    // `point` is in normalized device coordinates.
+    pub fn click_to_user_action(&self, point: [f32; 2]) -> Option<UserAction> {
+        if !self.visible {
+            return None;
+        }
+
+        for (index, slider) in self.sliders.iter().enumerate() {
+            let corners = Self::slider_corners(index);
+            if corners[0] <= point[0]
+                && point[0] <= corners[2]
+                && corners[1] <= point[1]
+                && point[1] <= corners[3]
+            {
+                let fraction = ((point[0] - corners[0]) / Self::WIDTH) as f64;
+                return Some(UserAction::SetTunableParameter {
+                    parameter: slider.parameter,
+                    value: slider.fraction_to_value(fraction),
+                });
+            }
+        }
+        None
+    }
+
+    fn slider_corners(index: usize) -> [f32; 4] {
+        let top = Self::TOP - index as f32 * Self::SPACING;
+        [
+            Self::LEFT,
+            top - Self::HEIGHT,
+            Self::LEFT + Self::WIDTH,
+            top,
+        ]
+    }
+}
+
+impl ParameterSlider {
+    fn value_to_fraction(&self, value: f64) -> f64 {
+        ((value - self.min_value) / (self.max_value - self.min_value)).clamp(0.0, 1.0)
+    }
+
+    fn fraction_to_value(&self, fraction: f64) -> f64 {
+        self.min_value + fraction * (self.max_value - self.min_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_panel_ignores_clicks() {
+        let panel = ParameterPanel::new();
+        assert_eq!(panel.click_to_user_action([-0.7, 0.92]), None);
+    }
+
+    #[test]
+    fn click_on_slider_sets_parameter_by_fraction() {
+        let mut panel = ParameterPanel::new();
+        panel.toggle_visible();
+
+        let action =
+            panel.click_to_user_action([-0.95 + 0.25, 0.95 - ParameterPanel::SPACING - 0.02]);
+
+        match action {
+            Some(UserAction::SetTunableParameter { parameter, value }) => {
+                assert_eq!(parameter, TunableParameter::LightIntensity);
+                assert!((value - 1.0).abs() < 1e-6);
+            }
+            _ => panic!("Expected a parameter change, got {:?}", action),
+        }
+    }
+
+    #[test]
+    fn click_outside_sliders_is_not_a_parameter_change() {
+        let mut panel = ParameterPanel::new();
+        panel.toggle_visible();
+        assert_eq!(panel.click_to_user_action([0.5, 0.0]), None);
+    }
+}
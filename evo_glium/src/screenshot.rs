@@ -0,0 +1,48 @@
+use std::io::{self, Write};
+
+// Writes 8-bit RGBA pixels, top row first, as a PNG.
+pub fn write_png<W: Write>(out: W, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    assert_eq!(rgba.len(), width as usize * height as usize * 4);
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(())
+}
+
+// Reverses the order of the rows, e.g. to turn OpenGL's bottom-up pixels top-down.
+pub fn flip_rows(pixels: &[u8], row_len: usize) -> Vec<u8> {
+    pixels
+        .chunks(row_len.max(1))
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_round_trips_pixels() {
+        let rgba = [255, 0, 0, 255, 0, 255, 0, 128];
+        let mut png = vec![];
+
+        write_png(&mut png, 2, 1, &rgba).unwrap();
+
+        let mut reader = png::Decoder::new(&png[..]).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(&pixels[..info.buffer_size()], &rgba);
+    }
+
+    #[test]
+    fn flip_rows_reverses_rows() {
+        assert_eq!(flip_rows(&[1, 2, 3, 4, 5, 6], 2), vec![5, 6, 3, 4, 1, 2]);
+    }
+}
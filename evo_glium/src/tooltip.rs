@@ -0,0 +1,245 @@
+use crate::background_drawing::World as Rect;
+
+// A few lines of text in a box, drawn in normalized device coordinates with a tiny built-in
+// pixel font, since the view has no font rendering. Letters are drawn in upper case, and
+// characters the font lacks are drawn as spaces.
+#[derive(Clone, Copy, Debug)]
+pub struct Tooltip {
+    // Size of one font pixel, in normalized device coordinates
+    pixel_size: [f32; 2],
+}
+
+impl Tooltip {
+    const GLYPH_WIDTH: usize = 3;
+    const GLYPH_HEIGHT: usize = 5;
+    const CHAR_SPACING: usize = 1;
+    const LINE_SPACING: usize = 2;
+    const PADDING: usize = 2;
+    // Distance from the mouse pointer, in font pixels
+    const OFFSET: f32 = 6.0;
+    const BOX_COLOR: [f32; 3] = [0.15, 0.15, 0.15];
+    const TEXT_COLOR: [f32; 3] = [0.9, 0.9, 0.9];
+
+    pub fn new(pixel_size: [f32; 2]) -> Self {
+        Tooltip { pixel_size }
+    }
+
+    // The box goes below and to the right of `pointer`, or to the other side where it would
+    // run off the window.
+    pub fn rects(&self, lines: &[String], pointer: [f32; 2]) -> Vec<Rect> {
+        let (width, height) = Self::size_in_pixels(lines);
+        let box_width = width as f32 * self.pixel_size[0];
+        let box_height = height as f32 * self.pixel_size[1];
+        let mut left = pointer[0] + Self::OFFSET * self.pixel_size[0];
+        if left + box_width > 1.0 {
+            left = pointer[0] - Self::OFFSET * self.pixel_size[0] - box_width;
+        }
+        let mut top = pointer[1] - Self::OFFSET * self.pixel_size[1];
+        if top - box_height < -1.0 {
+            top = pointer[1] + Self::OFFSET * self.pixel_size[1] + box_height;
+        }
+        self.rects_at(lines, left, top)
+    }
+
+    // The box goes in the top right corner of the window.
+    pub fn top_right_rects(&self, lines: &[String]) -> Vec<Rect> {
+        let (width, _) = Self::size_in_pixels(lines);
+        let left = 1.0 - (width as f32 + Self::OFFSET) * self.pixel_size[0];
+        let top = 1.0 - Self::OFFSET * self.pixel_size[1];
+        self.rects_at(lines, left, top)
+    }
+
+    // The box goes in the bottom left corner of the window.
+    pub fn bottom_left_rects(&self, lines: &[String]) -> Vec<Rect> {
+        let (left, top) = self.bottom_left_corner(lines);
+        self.rects_at(lines, left, top)
+    }
+
+    // The line and character of the box from `bottom_left_rects` that the point is on, if any.
+    pub fn bottom_left_char_at(&self, lines: &[String], point: [f32; 2]) -> Option<(usize, usize)> {
+        let (left, top) = self.bottom_left_corner(lines);
+        let x = (point[0] - left) / self.pixel_size[0] - Self::PADDING as f32;
+        let y = (top - point[1]) / self.pixel_size[1] - Self::PADDING as f32;
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let line_index = y as usize / (Self::GLYPH_HEIGHT + Self::LINE_SPACING);
+        let char_index = x as usize / (Self::GLYPH_WIDTH + Self::CHAR_SPACING);
+        let line = lines.get(line_index)?;
+        if char_index >= line.chars().count() {
+            return None;
+        }
+        Some((line_index, char_index))
+    }
+
+    fn bottom_left_corner(&self, lines: &[String]) -> (f32, f32) {
+        let (_, height) = Self::size_in_pixels(lines);
+        let left = -1.0 + Self::OFFSET * self.pixel_size[0];
+        let top = -1.0 + (height as f32 + Self::OFFSET) * self.pixel_size[1];
+        (left, top)
+    }
+
+    fn rects_at(&self, lines: &[String], left: f32, top: f32) -> Vec<Rect> {
+        let (width, height) = Self::size_in_pixels(lines);
+        let box_width = width as f32 * self.pixel_size[0];
+        let box_height = height as f32 * self.pixel_size[1];
+        let mut rects = vec![Rect {
+            corners: [left, top - box_height, left + box_width, top],
+            top_color: Self::BOX_COLOR,
+            bottom_color: Self::BOX_COLOR,
+        }];
+        for (line_index, line) in lines.iter().enumerate() {
+            let line_top = Self::PADDING + line_index * (Self::GLYPH_HEIGHT + Self::LINE_SPACING);
+            for (char_index, c) in line.chars().enumerate() {
+                let char_left =
+                    Self::PADDING + char_index * (Self::GLYPH_WIDTH + Self::CHAR_SPACING);
+                for (row, bits) in glyph(c).iter().enumerate() {
+                    for column in 0..Self::GLYPH_WIDTH {
+                        if bits & (0b100 >> column) != 0 {
+                            rects.push(self.pixel_rect(
+                                left,
+                                top,
+                                char_left + column,
+                                line_top + row,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        rects
+    }
+
+    fn size_in_pixels(lines: &[String]) -> (usize, usize) {
+        let max_chars = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let text_width = (max_chars * (Self::GLYPH_WIDTH + Self::CHAR_SPACING))
+            .saturating_sub(Self::CHAR_SPACING);
+        let text_height = (lines.len() * (Self::GLYPH_HEIGHT + Self::LINE_SPACING))
+            .saturating_sub(Self::LINE_SPACING);
+        (
+            text_width + 2 * Self::PADDING,
+            text_height + 2 * Self::PADDING,
+        )
+    }
+
+    fn pixel_rect(&self, left: f32, top: f32, x: usize, y: usize) -> Rect {
+        let pixel_left = left + x as f32 * self.pixel_size[0];
+        let pixel_top = top - y as f32 * self.pixel_size[1];
+        Rect {
+            corners: [
+                pixel_left,
+                pixel_top - self.pixel_size[1],
+                pixel_left + self.pixel_size[0],
+                pixel_top,
+            ],
+            top_color: Self::TEXT_COLOR,
+            bottom_color: Self::TEXT_COLOR,
+        }
+    }
+}
+
+// Rows from top to bottom; in each row, 0b100 is the leftmost pixel.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        _ => [0; 5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_box_and_one_rect_per_lit_pixel() {
+        let tooltip = Tooltip::new([0.01, 0.01]);
+
+        let rects = tooltip.rects(&["1 -".to_string()], [0.0, 0.0]);
+
+        // "1" has 8 lit pixels, " " none and "-" 3
+        assert_eq!(rects.len(), 1 + 8 + 3);
+        // 15 by 9 font pixels: 3 glyphs, 2 gaps and the padding
+        let corners = rects[0].corners;
+        assert!((corners[2] - corners[0] - 0.15).abs() < 1e-6);
+        assert!((corners[3] - corners[1] - 0.09).abs() < 1e-6);
+    }
+
+    #[test]
+    fn top_right_box_stays_in_window() {
+        let tooltip = Tooltip::new([0.01, 0.01]);
+
+        let rects = tooltip.top_right_rects(&["TICK 100".to_string()]);
+
+        let corners = rects[0].corners;
+        assert!((corners[2] - 0.94).abs() < 1e-6);
+        assert!((corners[3] - 0.94).abs() < 1e-6);
+    }
+
+    #[test]
+    fn finds_char_under_point_in_bottom_left_box() {
+        let tooltip = Tooltip::new([0.01, 0.01]);
+        let lines = vec!["AB".to_string(), "C".to_string()];
+        let corners = tooltip.bottom_left_rects(&lines)[0].corners;
+
+        // second glyph of the first line, past the padding and the first glyph and its gap
+        let point = [corners[0] + 0.065, corners[3] - 0.03];
+        assert_eq!(tooltip.bottom_left_char_at(&lines, point), Some((0, 1)));
+        // past the end of the second line
+        let point = [corners[0] + 0.065, corners[3] - 0.10];
+        assert_eq!(tooltip.bottom_left_char_at(&lines, point), None);
+    }
+
+    #[test]
+    fn box_flips_to_stay_in_window() {
+        let tooltip = Tooltip::new([0.01, 0.01]);
+
+        let rects = tooltip.rects(&["A".to_string()], [0.95, -0.95]);
+
+        let corners = rects[0].corners;
+        assert!(corners[2] < 0.95);
+        assert!(corners[1] > -0.95);
+    }
+}
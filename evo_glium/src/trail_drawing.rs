@@ -0,0 +1,352 @@
+use crate::Point;
+use evo_domain::biology::cell::{Cell, CellId};
+use evo_domain::environment::trajectories::TrajectoryRecorder;
+use evo_domain::physics::shapes::Circle;
+use evo_domain::world::World;
+use glium::{implement_vertex, uniform, Surface};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrailVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+implement_vertex!(TrailVertex, position, color);
+
+// Lineage overlays: motion trails that fade with age, and lines from children to their living
+// parents. Both are colored by the cell's first tag, or by its lineage if it has no tags.
+// Also the world's recorded trajectories, if it has a trajectory recorder, as polylines that
+// fade with age, colored by the recorder's tag.
+pub struct Trails {
+    max_length: usize,
+    show_motion_trails: bool,
+    show_parent_lines: bool,
+    positions: HashMap<CellId, VecDeque<Point>>,
+}
+
+impl Trails {
+    const PARENT_LINE_ALPHA: f32 = 0.6;
+
+    const PALETTE: [[f32; 3]; 6] = [
+        [1.0, 0.4, 0.4],
+        [0.4, 0.6, 1.0],
+        [1.0, 0.8, 0.2],
+        [0.8, 0.4, 1.0],
+        [0.3, 1.0, 0.8],
+        [1.0, 0.6, 0.2],
+    ];
+
+    // A motion trail covers at most the last `max_length` cell positions.
+    pub fn new(max_length: usize) -> Self {
+        assert!(max_length >= 2);
+        Trails {
+            max_length,
+            show_motion_trails: false,
+            show_parent_lines: false,
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn toggle_motion_trails(&mut self) {
+        self.show_motion_trails = !self.show_motion_trails;
+        self.positions.clear();
+    }
+
+    pub fn toggle_parent_lines(&mut self) {
+        self.show_parent_lines = !self.show_parent_lines;
+    }
+
+    // Records the cells' current positions and returns the overlay line segments, as pairs of
+    // vertices.
+    pub fn update(&mut self, world: &World) -> Vec<TrailVertex> {
+        let mut vertices = vec![];
+        if self.show_motion_trails {
+            self.record_positions(world);
+            self.add_motion_trail_vertices(world, &mut vertices);
+        }
+        if self.show_parent_lines {
+            Self::add_parent_line_vertices(world, &mut vertices);
+        }
+        if let Some(recorder) = world.trajectory_recorder() {
+            Self::add_trajectory_vertices(recorder, &mut vertices);
+        }
+        vertices
+    }
+
+    fn record_positions(&mut self, world: &World) {
+        let mut positions = HashMap::with_capacity(world.cells().len());
+        for cell in world.cells() {
+            let mut trail = self.positions.remove(&cell.id()).unwrap_or_default();
+            let center = Self::center(cell);
+            // rendering while paused shouldn't shorten the trail
+            if trail.back() != Some(&center) {
+                trail.push_back(center);
+            }
+            while trail.len() > self.max_length {
+                trail.pop_front();
+            }
+            positions.insert(cell.id(), trail);
+        }
+        self.positions = positions;
+    }
+
+    fn add_motion_trail_vertices(&self, world: &World, vertices: &mut Vec<TrailVertex>) {
+        for cell in world.cells() {
+            let trail = &self.positions[&cell.id()];
+            let rgb = Self::lineage_color(cell);
+            // older segments are fainter, and the newest is opaque however short the trail is
+            let skipped_segments = self.max_length - trail.len();
+            for (i, (start, end)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+                let alpha = (skipped_segments + i + 1) as f32 / (self.max_length - 1) as f32;
+                let color = [rgb[0], rgb[1], rgb[2], alpha];
+                vertices.push(TrailVertex {
+                    position: *start,
+                    color,
+                });
+                vertices.push(TrailVertex {
+                    position: *end,
+                    color,
+                });
+            }
+        }
+    }
+
+    fn add_parent_line_vertices(world: &World, vertices: &mut Vec<TrailVertex>) {
+        let centers: HashMap<CellId, Point> = world
+            .cells()
+            .iter()
+            .map(|cell| (cell.id(), Self::center(cell)))
+            .collect();
+        for cell in world.cells() {
+            if let Some(parent_center) = cell.parent_id().and_then(|id| centers.get(&id)) {
+                let rgb = Self::lineage_color(cell);
+                let color = [rgb[0], rgb[1], rgb[2], Self::PARENT_LINE_ALPHA];
+                vertices.push(TrailVertex {
+                    position: *parent_center,
+                    color,
+                });
+                vertices.push(TrailVertex {
+                    position: Self::center(cell),
+                    color,
+                });
+            }
+        }
+    }
+
+    fn add_trajectory_vertices(recorder: &TrajectoryRecorder, vertices: &mut Vec<TrailVertex>) {
+        let rgb = Self::tag_color(recorder.tag());
+        for (_, trajectory) in recorder.trajectories() {
+            // the newest segment is opaque however short the trajectory is so far
+            let max_segments = recorder.capacity() - 1;
+            let skipped_segments = recorder.capacity() - trajectory.len();
+            for (i, (start, end)) in trajectory.iter().zip(trajectory.iter().skip(1)).enumerate() {
+                let alpha = (skipped_segments + i + 1) as f32 / max_segments as f32;
+                let color = [rgb[0], rgb[1], rgb[2], alpha];
+                vertices.push(TrailVertex {
+                    position: [start.position.x() as f32, start.position.y() as f32],
+                    color,
+                });
+                vertices.push(TrailVertex {
+                    position: [end.position.x() as f32, end.position.y() as f32],
+                    color,
+                });
+            }
+        }
+    }
+
+    fn center(cell: &Cell) -> Point {
+        [cell.center().x() as f32, cell.center().y() as f32]
+    }
+
+    fn lineage_color(cell: &Cell) -> [f32; 3] {
+        match cell.tags().first() {
+            Some(tag) => Self::tag_color(&tag.name),
+            None => Self::PALETTE[cell.lineage() as usize % Self::PALETTE.len()],
+        }
+    }
+
+    fn tag_color(name: &str) -> [f32; 3] {
+        let key = name.bytes().fold(0_usize, |hash, b| {
+            hash.wrapping_mul(31).wrapping_add(b as usize)
+        });
+        Self::PALETTE[key % Self::PALETTE.len()]
+    }
+}
+
+pub struct TrailDrawing {
+    pub shader_program: glium::Program,
+    pub indices: glium::index::NoIndices,
+}
+
+impl TrailDrawing {
+    pub fn new(display: &glium::Display) -> Self {
+        TrailDrawing {
+            shader_program: glium::Program::from_source(
+                display,
+                Self::VERTEX_SHADER_SRC,
+                Self::FRAGMENT_SHADER_SRC,
+                None,
+            )
+            .unwrap(),
+            indices: glium::index::NoIndices(glium::index::PrimitiveType::LinesList),
+        }
+    }
+
+    pub fn draw(
+        &self,
+        frame: &mut glium::Frame,
+        vertex_buffer: &glium::VertexBuffer<TrailVertex>,
+        screen_transform: [[f32; 4]; 4],
+    ) {
+        let uniforms = uniform! {
+            screen_transform: screen_transform,
+        };
+        let draw_parameters = glium::DrawParameters {
+            blend: glium::Blend::alpha_blending(),
+            ..Default::default()
+        };
+        frame
+            .draw(
+                vertex_buffer,
+                self.indices,
+                &self.shader_program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+    }
+
+    const VERTEX_SHADER_SRC: &'static str = r#"
+        #version 330 core
+
+        uniform mat4 screen_transform;
+
+        in vec2 position;
+        in vec4 color;
+
+        out vec4 trail_color;
+
+        void main() {
+            trail_color = color;
+            gl_Position = screen_transform * vec4(position, 0.0, 1.0);
+        }
+    "#;
+
+    const FRAGMENT_SHADER_SRC: &'static str = r#"
+        #version 330 core
+
+        in vec4 trail_color;
+
+        out vec4 color_out;
+
+        void main() {
+            color_out = trail_color;
+        }
+    "#;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evo_domain::physics::quantities::*;
+
+    #[test]
+    fn motion_trail_fades_and_keeps_only_recent_positions() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::new(1.0, 0.0),
+            ));
+        let mut trails = Trails::new(3);
+        trails.toggle_motion_trails();
+
+        let mut vertices = vec![];
+        for _ in 0..4 {
+            vertices = trails.update(&world);
+            world.tick();
+        }
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(vertices[0].color[3], 0.5);
+        assert_eq!(vertices[2].color[3], 1.0);
+        assert_eq!(
+            vertices[3].position[0],
+            world.cells()[0].center().x() as f32 - 1.0
+        );
+    }
+
+    #[test]
+    fn short_motion_trail_is_drawn_newest_segment_opaque() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::new(1.0, 0.0),
+            ));
+        let mut trails = Trails::new(5);
+        trails.toggle_motion_trails();
+
+        let mut vertices = vec![];
+        for _ in 0..3 {
+            vertices = trails.update(&world);
+            world.tick();
+        }
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(vertices[0].color[3], 0.75);
+        assert_eq!(vertices[2].color[3], 1.0);
+    }
+
+    #[test]
+    fn recorded_trajectory_is_drawn_newest_segment_opaque() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(
+                Cell::ball(
+                    Length::new(1.0),
+                    Mass::new(1.0),
+                    Position::ORIGIN,
+                    Velocity::new(1.0, 0.0),
+                )
+                .with_tag("tracked"),
+            )
+            .with_trajectory_recorder(TrajectoryRecorder::new("tracked", 5));
+        for _ in 0..3 {
+            world.tick();
+        }
+
+        let vertices = Trails::new(3).update(&world);
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(vertices[0].color[3], 0.75);
+        assert_eq!(vertices[2].color[3], 1.0);
+        assert_eq!(
+            vertices[3].position[0],
+            world.cells()[0].center().x() as f32
+        );
+    }
+
+    #[test]
+    fn dead_cells_leave_no_trail() {
+        let mut world = World::new(Position::new(-10.0, -10.0), Position::new(10.0, 10.0))
+            .with_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                Position::ORIGIN,
+                Velocity::new(1.0, 0.0),
+            ));
+        let mut trails = Trails::new(3);
+        trails.toggle_motion_trails();
+        trails.update(&world);
+        world.tick();
+
+        world.select_cells_in_rect(Position::new(-10.0, -10.0), Position::new(10.0, 10.0));
+        world.kill_selected_cells();
+
+        assert!(trails.update(&world).is_empty());
+        assert!(trails.positions.is_empty());
+    }
+}
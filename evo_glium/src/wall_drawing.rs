@@ -0,0 +1,78 @@
+use crate::background_drawing::World as Rect;
+use evo_domain::world::World;
+
+// The walls of the world's influences, drawn in world coordinates as bands centered on the
+// walls' edges, so that walls along the world's own perimeter are half visible.
+pub struct WallRects {
+    // Band width as a fraction of the world's larger dimension
+    thickness_fraction: f32,
+}
+
+impl WallRects {
+    const COLOR: [f32; 3] = [0.45, 0.45, 0.5];
+
+    pub fn new(thickness_fraction: f32) -> Self {
+        WallRects { thickness_fraction }
+    }
+
+    pub fn rects(&self, world: &World) -> Vec<Rect> {
+        let world_size = world.max_corner() - world.min_corner();
+        let half_thickness =
+            0.5 * self.thickness_fraction * world_size.x().max(world_size.y()) as f32;
+        let mut rects = vec![];
+        for walls in world.walls() {
+            let min = [walls.min_corner().x() as f32, walls.min_corner().y() as f32];
+            let max = [walls.max_corner().x() as f32, walls.max_corner().y() as f32];
+            let outer = [
+                min[0] - half_thickness,
+                min[1] - half_thickness,
+                max[0] + half_thickness,
+                max[1] + half_thickness,
+            ];
+            let inner = [
+                min[0] + half_thickness,
+                min[1] + half_thickness,
+                max[0] - half_thickness,
+                max[1] - half_thickness,
+            ];
+            // bottom, top, left, right
+            rects.push(Self::rect([outer[0], outer[1], outer[2], inner[1]]));
+            rects.push(Self::rect([outer[0], inner[3], outer[2], outer[3]]));
+            rects.push(Self::rect([outer[0], inner[1], inner[0], inner[3]]));
+            rects.push(Self::rect([inner[2], inner[1], outer[2], inner[3]]));
+        }
+        rects
+    }
+
+    fn rect(corners: [f32; 4]) -> Rect {
+        Rect {
+            corners,
+            top_color: Self::COLOR,
+            bottom_color: Self::COLOR,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evo_domain::physics::quantities::Position;
+
+    #[test]
+    fn perimeter_walls_are_four_bands_centered_on_edges() {
+        let world =
+            World::new(Position::new(0.0, 0.0), Position::new(100.0, 50.0)).with_perimeter_walls();
+
+        let rects = WallRects::new(0.02).rects(&world);
+
+        assert_eq!(rects.len(), 4);
+        assert_eq!(rects[0].corners, [-1.0, -1.0, 101.0, 1.0]);
+        assert_eq!(rects[3].corners, [99.0, 1.0, 101.0, 49.0]);
+    }
+
+    #[test]
+    fn world_without_walls_has_no_rects() {
+        let world = World::new(Position::new(0.0, 0.0), Position::new(100.0, 50.0));
+        assert!(WallRects::new(0.02).rects(&world).is_empty());
+    }
+}
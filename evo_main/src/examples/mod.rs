@@ -0,0 +1,79 @@
+// The worlds of the examples in evo_main/examples, as library code, so that tests can check
+// that they still run after domain changes.
+pub mod ball_chain;
+pub mod ball_drag;
+pub mod big_and_little;
+pub mod bond_cycle_3;
+pub mod bond_cycle_5;
+pub mod bonded_pair;
+pub mod bonded_triple;
+pub mod bouncing_balls;
+pub mod bouncing_pairs;
+pub mod budding;
+pub mod death_by_wall_bounces;
+pub mod decay_healing_and_death;
+pub mod diagonal_triple;
+pub mod double_overlap;
+pub mod duckweed;
+pub mod falling_ball;
+pub mod fixed_depth_seeking;
+pub mod floating_ball;
+pub mod frozen_links;
+pub mod many_balls;
+pub mod neural_net_budding;
+pub mod neural_net_fixed_depth_seeking;
+pub mod thrust_in_square;
+
+#[cfg(test)]
+mod tests {
+    use evo_domain::world::World;
+
+    // Ticks the example's world, checking for non-finite values along the way.
+    fn smoke_test(world: World) {
+        let mut world = world.with_finite_checks();
+        for _ in 0..100 {
+            world.tick();
+        }
+        assert_eq!(world.current_tick(), 100);
+        for cell in world.cells() {
+            assert!(cell.energy().value() >= 0.0);
+        }
+    }
+
+    macro_rules! smoke_tests {
+        ($($example:ident,)*) => {
+            $(
+                #[test]
+                fn $example() {
+                    smoke_test(super::$example::create_world());
+                }
+            )*
+        };
+    }
+
+    smoke_tests!(
+        ball_chain,
+        ball_drag,
+        big_and_little,
+        bond_cycle_3,
+        bond_cycle_5,
+        bonded_pair,
+        bonded_triple,
+        bouncing_balls,
+        bouncing_pairs,
+        budding,
+        death_by_wall_bounces,
+        decay_healing_and_death,
+        diagonal_triple,
+        double_overlap,
+        duckweed,
+        falling_ball,
+        fixed_depth_seeking,
+        floating_ball,
+        frozen_links,
+        many_balls,
+        neural_net_budding,
+        neural_net_fixed_depth_seeking,
+        thrust_in_square,
+    );
+}
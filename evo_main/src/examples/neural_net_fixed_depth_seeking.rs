@@ -0,0 +1,85 @@
+use evo_domain::biology::cell::Cell;
+use evo_domain::biology::control::*;
+use evo_domain::biology::control_requests::*;
+use evo_domain::biology::genome::*;
+use evo_domain::biology::layers::*;
+use evo_domain::environment::influences::*;
+use evo_domain::physics::quantities::*;
+use evo_domain::state_hash::StateHasher;
+use evo_domain::world::World;
+use std::f64::consts::PI;
+
+pub fn create_world() -> World {
+    let mut genome = SparseNeuralNetGenome::new(TransferFn::IDENTITY);
+    genome.connect_node(1, -100.0, &[(0, -1.0)]);
+
+    World::new(Position::new(0.0, -400.0), Position::new(400.0, 0.0))
+        .with_perimeter_walls()
+        .with_influences(vec![
+            Box::new(SimpleForceInfluence::new(Box::new(WeightForce::new(-0.05)))),
+            Box::new(SimpleForceInfluence::new(Box::new(BuoyancyForce::new(
+                -0.03, 0.001,
+            )))),
+            Box::new(SimpleForceInfluence::new(Box::new(DragForce::new(0.005)))),
+        ])
+        .with_cells(vec![Cell::new(
+            Position::new(200.0, -200.0),
+            Velocity::new(0.0, 0.0),
+            vec![
+                simple_cell_layer(Area::new(100.0 * PI), Density::new(0.0004), Color::White),
+                simple_cell_layer(Area::new(300.0 * PI), Density::new(0.00075), Color::Green),
+            ],
+        )
+        .with_control(Box::new(NeuralNetControl::new(genome)))])
+}
+
+fn simple_cell_layer(area: Area, density: Density, color: Color) -> CellLayer {
+    CellLayer::new(
+        area,
+        density,
+        color,
+        Box::new(NullCellLayerSpecialty::new()),
+    )
+}
+
+#[derive(Clone, Debug)]
+pub struct NeuralNetControl {
+    nnet: SparseNeuralNet,
+}
+
+impl NeuralNetControl {
+    pub fn new(genome: SparseNeuralNetGenome) -> Self {
+        NeuralNetControl {
+            nnet: SparseNeuralNet::new(genome),
+        }
+    }
+}
+
+impl CellControl for NeuralNetControl {
+    fn run(&mut self, cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+        self.nnet.set_node_value(0, cell_state.center.y() as f32);
+        self.nnet.run();
+        vec![CellLayer::resize_request(
+            0,
+            AreaDelta::new(self.nnet.node_value(1) as f64),
+        )]
+    }
+
+    fn spawn(&mut self) -> Box<dyn CellControl> {
+        Box::new(self.clone())
+    }
+
+    fn use_fast_math(&mut self) {
+        self.nnet.use_fast_transfer_fns();
+    }
+
+    fn non_finite_state(&self) -> Option<String> {
+        self.nnet
+            .first_non_finite_node()
+            .map(|(index, value)| format!("node {} value {}", index, value))
+    }
+
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.nnet.hash_state(hasher);
+    }
+}
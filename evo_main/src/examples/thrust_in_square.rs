@@ -0,0 +1,83 @@
+use evo_domain::biology::cell::Cell;
+use evo_domain::biology::control::*;
+use evo_domain::biology::control_requests::*;
+use evo_domain::biology::layers::*;
+use evo_domain::environment::influences::*;
+use evo_domain::physics::quantities::*;
+use evo_domain::world::World;
+use std::f64::consts::PI;
+
+pub fn create_world() -> World {
+    World::new(Position::new(0.0, -400.0), Position::new(400.0, 0.0))
+        .with_perimeter_walls()
+        .with_influences(vec![Box::new(SimpleForceInfluence::new(Box::new(
+            DragForce::new(2.0),
+        )))])
+        .with_cells(vec![Cell::new(
+            Position::new(300.0, -300.0),
+            Velocity::new(0.0, 0.0),
+            vec![CellLayer::new(
+                Area::new(200.0 * PI),
+                Density::new(1.0),
+                Color::Green,
+                Box::new(ThrusterCellLayerSpecialty::new(0.0)),
+            )],
+        )
+        // facing left
+        .with_heading(Angle::from_radians(PI))
+        .with_control(Box::new(ThrustInSquareControl::new(0, 70.0, 100, 200)))])
+}
+
+// Thrusts forward, coasts, and turns a quarter turn clockwise, over and over.
+#[derive(Clone, Debug)]
+pub struct ThrustInSquareControl {
+    thruster_layer_index: usize,
+    force: f64,
+    accel_ticks: u32,
+    ticks_before_turn: u32,
+    ticks: u32,
+}
+
+impl ThrustInSquareControl {
+    pub fn new(
+        thruster_layer_index: usize,
+        force: f64,
+        accel_ticks: u32,
+        ticks_before_turn: u32,
+    ) -> Self {
+        ThrustInSquareControl {
+            thruster_layer_index,
+            force,
+            accel_ticks,
+            ticks_before_turn,
+            ticks: 0,
+        }
+    }
+}
+
+impl CellControl for ThrustInSquareControl {
+    fn run(&mut self, _cell_state: &CellStateSnapshot) -> Vec<ControlRequest> {
+        let force = if self.ticks < self.accel_ticks {
+            self.force
+        } else {
+            0.0
+        };
+
+        self.ticks += 1;
+        let turn = if self.ticks >= self.ticks_before_turn {
+            self.ticks = 0;
+            Deflection::from_radians(-PI / 2.0)
+        } else {
+            Deflection::ZERO
+        };
+
+        vec![
+            ThrusterCellLayerSpecialty::forward_request(self.thruster_layer_index, force),
+            ThrusterCellLayerSpecialty::turn_request(self.thruster_layer_index, turn),
+        ]
+    }
+
+    fn spawn(&mut self) -> Box<dyn CellControl> {
+        Box::new(self.clone())
+    }
+}
@@ -1,2 +1,5 @@
+pub mod examples;
 pub mod main_support;
+pub mod telemetry;
+pub mod text_view;
 pub mod view;
@@ -1,18 +1,715 @@
+use clap::{value_t, App, Arg, ArgMatches, SubCommand};
 use evo_domain::biology::cell::Cell;
 use evo_domain::biology::control::*;
 use evo_domain::biology::control_requests::*;
 use evo_domain::biology::genome::*;
 use evo_domain::biology::layers::*;
+use evo_domain::divergence::TwinRun;
+use evo_domain::document::{RunMetadata, WorldPatch};
 use evo_domain::environment::influences::*;
+use evo_domain::environment::trajectories::TrajectoryRecorder;
+use evo_domain::events::{BlowupBounds, ChurnCap, ChurnOverflow, LimitAction, ResourceLimits};
+use evo_domain::parallelism::Parallelism;
 use evo_domain::physics::quantities::*;
-use evo_domain::world::World;
-use evo_main::main_support::init_and_run;
+use evo_domain::state_hash::StateHasher;
+use evo_domain::world::{Breakpoint, World};
+use evo_domain::worldgen::*;
+use evo_main::main_support::*;
+use evo_main::telemetry::*;
+use evo_main::text_view::Glyphs;
+use std::env;
 use std::f64::consts::PI;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process;
 
 type VecIndex = u16;
 
 fn main() {
-    init_and_run(create_world());
+    let matches = App::new("evo")
+        .about("Simulates evolution of simple digital organisms")
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Runs a world interactively")
+                .arg(world_arg("World document to run (default: built-in world)"))
+                .arg(discard_controls_arg())
+                .arg(seed_arg())
+                .arg(plugin_arg())
+                .arg(paused_arg())
+                .arg(keymap_arg())
+                .args(&breakpoint_args())
+                .arg(watch_arg())
+                .args(&trajectory_args())
+                .arg(detect_blowups_arg())
+                .args(&terminal_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("resume")
+                .about("Resumes a saved world interactively")
+                .arg(world_arg("Saved world document").required(true))
+                .arg(discard_controls_arg())
+                .arg(patch_arg())
+                .arg(plugin_arg())
+                .arg(paused_arg())
+                .arg(keymap_arg())
+                .args(&breakpoint_args())
+                .arg(watch_arg())
+                .args(&trajectory_args())
+                .arg(detect_blowups_arg())
+                .args(&terminal_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about("Runs a world without a view and reports the tick rate")
+                .arg(world_arg("World document to run (default: built-in world)"))
+                .arg(discard_controls_arg())
+                .arg(seed_arg())
+                .arg(plugin_arg())
+                .arg(ticks_arg("1000")),
+        )
+        .subcommand(
+            SubCommand::with_name("check-divergence")
+                .about("Runs two copies of a world in lockstep and reports where they first differ")
+                .arg(world_arg("World document to run (default: built-in world)"))
+                .arg(discard_controls_arg())
+                .arg(seed_arg())
+                .arg(plugin_arg())
+                .arg(ticks_arg("1000"))
+                .arg(
+                    Arg::with_name("threads")
+                        .long("threads")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Number of threads to tick the second copy with"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-stats")
+                .about("Runs a world without a view and writes per-tick stats as CSV")
+                .arg(world_arg("World document to run (default: built-in world)"))
+                .arg(discard_controls_arg())
+                .arg(seed_arg())
+                .arg(plugin_arg())
+                .arg(ticks_arg("1000"))
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("CSV file to write (default: stdout)"),
+                )
+                .arg(
+                    Arg::with_name("lineage-output")
+                        .long("lineage-output")
+                        .takes_value(true)
+                        .help("CSV file to write per-lineage stats to"),
+                )
+                .arg(downsample_stats_arg())
+                .arg(detect_blowups_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("export-frames")
+                .about("Runs a world without a view and writes view frames as JSON lines")
+                .arg(world_arg("World document to run (default: built-in world)"))
+                .arg(discard_controls_arg())
+                .arg(seed_arg())
+                .arg(plugin_arg())
+                .arg(ticks_arg("1000"))
+                .arg(interval_arg("1"))
+                .arg(detect_blowups_arg())
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("File to write (default: stdout)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("text")
+                .about("Runs a world without a view and prints character maps of it")
+                .arg(world_arg("World document to run (default: built-in world)"))
+                .arg(discard_controls_arg())
+                .arg(seed_arg())
+                .arg(plugin_arg())
+                .arg(ticks_arg("1000"))
+                .arg(interval_arg("100"))
+                .arg(detect_blowups_arg())
+                .arg(columns_arg())
+                .arg(rows_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("record")
+                .about("Runs a world without a view and records it in a new run directory")
+                .arg(world_arg(
+                    "World document or checkpoint to run (default: built-in world)",
+                ))
+                .arg(discard_controls_arg())
+                .arg(seed_arg())
+                .arg(plugin_arg())
+                .arg(patch_arg())
+                .arg(ticks_arg("1000"))
+                .arg(
+                    Arg::with_name("runs-dir")
+                        .long("runs-dir")
+                        .takes_value(true)
+                        .default_value("runs")
+                        .help("Directory to create the run directory in"),
+                )
+                .arg(
+                    Arg::with_name("checkpoint-interval")
+                        .long("checkpoint-interval")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Ticks between world checkpoints (0 for none)"),
+                )
+                .arg(
+                    Arg::with_name("keyframe-interval")
+                        .long("keyframe-interval")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Checkpoints between full checkpoints; the rest are deltas"),
+                )
+                .arg(
+                    Arg::with_name("statsd")
+                        .long("statsd")
+                        .takes_value(true)
+                        .value_name("HOST:PORT")
+                        .help("StatsD server to send tick metrics to"),
+                )
+                .arg(
+                    Arg::with_name("prometheus-file")
+                        .long("prometheus-file")
+                        .takes_value(true)
+                        .help("File to keep current tick metrics in, for Prometheus"),
+                )
+                .arg(
+                    Arg::with_name("metrics-prefix")
+                        .long("metrics-prefix")
+                        .takes_value(true)
+                        .default_value("evo")
+                        .help("Prefix for metric names"),
+                )
+                .arg(
+                    Arg::with_name("metrics-interval")
+                        .long("metrics-interval")
+                        .takes_value(true)
+                        .default_value("100")
+                        .help("Ticks between metrics reports"),
+                )
+                .arg(downsample_stats_arg())
+                .args(&trajectory_args())
+                .arg(detect_blowups_arg())
+                .args(&resource_limit_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("export-world")
+                .about("Writes the built-in world as a world document")
+                .arg(Arg::with_name("output").required(true)),
+        )
+        .arg(paused_arg())
+        .arg(keymap_arg())
+        .arg(detect_blowups_arg())
+        .get_matches();
+
+    simple_logger::init().unwrap();
+
+    match matches.subcommand() {
+        ("run", Some(args)) | ("resume", Some(args)) if args.is_present("tui") => {
+            // The terminal view can't pause, so breakpoint_args conflict with --tui instead of
+            // being applied here.
+            run_in_terminal(
+                world_from_args(args),
+                number_from_args(args, "interval"),
+                (
+                    number_from_args(args, "columns"),
+                    number_from_args(args, "rows"),
+                ),
+                if args.is_present("unicode") {
+                    Glyphs::Unicode
+                } else {
+                    Glyphs::Ascii
+                },
+                watcher_from_args(args),
+            )
+            .unwrap();
+        }
+        ("run", Some(args)) | ("resume", Some(args)) => {
+            let mut world = world_from_args(args);
+            for breakpoint in breakpoints_from_args(args) {
+                world.add_breakpoint(breakpoint);
+            }
+            run_interactively(
+                world,
+                args.is_present("paused"),
+                keymap_from_args(args),
+                watcher_from_args(args),
+            )
+        }
+        ("bench", Some(args)) => {
+            let num_ticks = ticks_from_args(args);
+            let elapsed = bench(&mut world_from_args(args), num_ticks);
+            println!(
+                "{} ticks in {:.3}s ({:.1} ticks/s)",
+                num_ticks,
+                elapsed.as_secs_f64(),
+                num_ticks as f64 / elapsed.as_secs_f64()
+            );
+        }
+        ("check-divergence", Some(args)) => {
+            let num_ticks = ticks_from_args(args);
+            let num_threads = number_from_args(args, "threads");
+            let mut twins = TwinRun::new(
+                world_from_args(args),
+                world_from_args(args).with_parallelism(Parallelism::Deterministic { num_threads }),
+            );
+            match twins.run(num_ticks) {
+                Some(divergence) => exit_with_error(&format!(
+                    "Diverged in tick {} after {}, in {}",
+                    divergence.tick,
+                    divergence.phase,
+                    divergence.parts.join(", ")
+                )),
+                None => println!("No divergence in {} ticks", num_ticks),
+            }
+        }
+        ("export-stats", Some(args)) => {
+            let mut world = world_from_args(args);
+            let num_ticks = ticks_from_args(args);
+            let mut out: Box<dyn Write> = match args.value_of("output") {
+                Some(path) => Box::new(BufWriter::new(File::create(path).unwrap())),
+                None => Box::new(io::stdout()),
+            };
+            let mut lineage_out = args
+                .value_of("lineage-output")
+                .map(|path| BufWriter::new(File::create(path).unwrap()));
+            export_stats(
+                &mut world,
+                num_ticks,
+                args.is_present("downsample-stats"),
+                &mut out,
+                lineage_out.as_mut().map(|out| out as &mut dyn Write),
+            )
+            .unwrap();
+        }
+        ("export-frames", Some(args)) => {
+            let mut out: Box<dyn Write> = match args.value_of("output") {
+                Some(path) => Box::new(BufWriter::new(File::create(path).unwrap())),
+                None => Box::new(io::stdout()),
+            };
+            export_frames(
+                &mut world_from_args(args),
+                ticks_from_args(args),
+                number_from_args(args, "interval"),
+                &mut out,
+            )
+            .unwrap();
+        }
+        ("text", Some(args)) => {
+            print_text_frames(
+                &mut world_from_args(args),
+                ticks_from_args(args),
+                number_from_args(args, "interval"),
+                (
+                    number_from_args(args, "columns"),
+                    number_from_args(args, "rows"),
+                ),
+                &mut io::stdout(),
+            )
+            .unwrap();
+        }
+        ("record", Some(args)) => {
+            let metadata = RunMetadata {
+                version: RunMetadata::VERSION,
+                started_at_unix_secs: 0,
+                command: env::args().collect(),
+                seed: optional_number_from_args(args, "seed"),
+                git_describe: git_describe(),
+                num_ticks: ticks_from_args(args),
+                checkpoint_interval: number_from_args(args, "checkpoint-interval"),
+                keyframe_interval: number_from_args(args, "keyframe-interval"),
+                resumed_from: args.value_of("world").map(String::from),
+                patches: patches_from_args(args),
+                plugins: plugins_from_args(args),
+            };
+            let world = world_from_args(args).with_resource_limits(resource_limits_from_args(args));
+            let world = with_churn_cap_from_args(world, args);
+            let run_dir = record_run(
+                &mut with_auto_compaction_from_args(world, args),
+                metadata,
+                Path::new(args.value_of("runs-dir").unwrap()),
+                &mut metrics_sinks_from_args(args),
+                number_from_args(args, "metrics-interval"),
+                args.is_present("downsample-stats"),
+            )
+            .unwrap();
+            println!("Recorded run in {}", run_dir.display());
+        }
+        ("export-world", Some(args)) => {
+            save_world(&create_world(), args.value_of("output").unwrap())
+                .unwrap_or_else(|err| exit_with_error(&err));
+        }
+        _ => run_interactively(
+            with_blowup_detection_from_args(create_world(), &matches),
+            matches.is_present("paused"),
+            keymap_from_args(&matches),
+            None,
+        ),
+    }
+}
+
+fn world_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("world").help(help)
+}
+
+fn downsample_stats_arg() -> Arg<'static, 'static> {
+    Arg::with_name("downsample-stats")
+        .long("downsample-stats")
+        .help("Keeps stats rows for recent ticks and every 100th and 10,000th tick, for long runs")
+}
+
+fn detect_blowups_arg() -> Arg<'static, 'static> {
+    Arg::with_name("detect-blowups")
+        .long("detect-blowups")
+        .help(
+            "Stops with a report and a world dump when a cell leaves sane position or speed bounds",
+        )
+}
+
+fn discard_controls_arg() -> Arg<'static, 'static> {
+    Arg::with_name("discard-controls")
+        .long("discard-controls")
+        .help("Loads a saved world even if its cells had controls, which are not saved")
+}
+
+fn paused_arg() -> Arg<'static, 'static> {
+    Arg::with_name("paused")
+        .short("p")
+        .long("paused")
+        .help("Starts with the simulation paused")
+}
+
+fn keymap_arg() -> Arg<'static, 'static> {
+    Arg::with_name("keymap")
+        .short("k")
+        .long("keymap")
+        .takes_value(true)
+        .help("Key bindings file that overrides the default bindings")
+}
+
+fn seed_arg() -> Arg<'static, 'static> {
+    Arg::with_name("seed")
+        .short("s")
+        .long("seed")
+        .takes_value(true)
+        .conflicts_with("world")
+        .help("Generates a random world from this seed")
+}
+
+fn patch_arg() -> Arg<'static, 'static> {
+    Arg::with_name("patch")
+        .long("patch")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("World patch document to apply before starting; can be repeated")
+}
+
+fn plugin_arg() -> Arg<'static, 'static> {
+    Arg::with_name("plugin")
+        .long("plugin")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Named plugin to add to the world; can be repeated")
+}
+
+fn ticks_arg(default: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("ticks")
+        .short("t")
+        .long("ticks")
+        .takes_value(true)
+        .default_value(default)
+        .help("Number of ticks to run")
+}
+
+fn breakpoint_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("break-energy")
+            .long("break-energy")
+            .takes_value(true)
+            .conflicts_with("tui")
+            .help("Pauses when a selected cell's energy drops below this"),
+        Arg::with_name("break-health")
+            .long("break-health")
+            .takes_value(true)
+            .conflicts_with("tui")
+            .help("Pauses when a layer's health in a selected cell drops below this"),
+        Arg::with_name("break-death")
+            .long("break-death")
+            .conflicts_with("tui")
+            .help("Pauses when a selected cell dies"),
+    ]
+}
+
+fn breakpoints_from_args(args: &ArgMatches) -> Vec<Breakpoint> {
+    let mut breakpoints = vec![];
+    if args.is_present("break-energy") {
+        breakpoints.push(Breakpoint::EnergyBelow(number_from_args(
+            args,
+            "break-energy",
+        )));
+    }
+    if args.is_present("break-health") {
+        breakpoints.push(Breakpoint::HealthBelow(number_from_args(
+            args,
+            "break-health",
+        )));
+    }
+    if args.is_present("break-death") {
+        breakpoints.push(Breakpoint::Death);
+    }
+    breakpoints
+}
+
+fn trajectory_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("trajectory-tag")
+            .long("trajectory-tag")
+            .takes_value(true)
+            .help("Records the recent positions of the cells with this tag"),
+        Arg::with_name("trajectory-length")
+            .long("trajectory-length")
+            .takes_value(true)
+            .default_value("500")
+            .help("Positions to keep per cell when recording trajectories"),
+    ]
+}
+
+fn resource_limit_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("max-cells")
+            .long("max-cells")
+            .takes_value(true)
+            .help("Limit on the number of cells"),
+        Arg::with_name("max-bonds")
+            .long("max-bonds")
+            .takes_value(true)
+            .help("Limit on the number of bonds"),
+        Arg::with_name("max-tick-ms")
+            .long("max-tick-ms")
+            .takes_value(true)
+            .help("Limit on the time one tick takes, in milliseconds"),
+        Arg::with_name("on-limit")
+            .long("on-limit")
+            .takes_value(true)
+            .possible_values(&["pause", "cull"])
+            .default_value("pause")
+            .help("Whether to checkpoint and stop or to cull the lowest-energy cells at a limit"),
+        Arg::with_name("compact-below")
+            .long("compact-below")
+            .takes_value(true)
+            .help("Frees cell memory when fewer than this fraction of the cell slots are in use"),
+        Arg::with_name("max-bond-churn")
+            .long("max-bond-churn")
+            .takes_value(true)
+            .help("Cap on the bonds formed and broken in one tick, including buds' bonds"),
+        Arg::with_name("on-churn")
+            .long("on-churn")
+            .takes_value(true)
+            .possible_values(&["report", "drop"])
+            .default_value("report")
+            .help("Whether to only log ticks over the churn cap or to drop the excess changes"),
+    ]
+}
+
+fn resource_limits_from_args(args: &ArgMatches) -> ResourceLimits {
+    ResourceLimits {
+        max_cells: optional_number_from_args(args, "max-cells"),
+        max_bonds: optional_number_from_args(args, "max-bonds"),
+        max_tick_duration: optional_number_from_args(args, "max-tick-ms")
+            .map(std::time::Duration::from_millis),
+        action: match args.value_of("on-limit").unwrap() {
+            "cull" => LimitAction::Cull,
+            _ => LimitAction::Pause,
+        },
+    }
+}
+
+fn with_auto_compaction_from_args(world: World, args: &ArgMatches) -> World {
+    match optional_number_from_args(args, "compact-below") {
+        Some(min_occupancy) => world.with_auto_compaction(min_occupancy),
+        None => world,
+    }
+}
+
+fn with_churn_cap_from_args(world: World, args: &ArgMatches) -> World {
+    match optional_number_from_args(args, "max-bond-churn") {
+        Some(max_bond_changes) => world.with_churn_cap(ChurnCap {
+            max_bond_changes,
+            overflow: match args.value_of("on-churn").unwrap() {
+                "drop" => ChurnOverflow::Drop,
+                _ => ChurnOverflow::Report,
+            },
+        }),
+        None => world,
+    }
+}
+
+fn watch_arg() -> Arg<'static, 'static> {
+    Arg::with_name("watch")
+        .long("watch")
+        .requires("world")
+        .help("Applies edits to the world file's light, gravity, and drag while running")
+}
+
+fn watcher_from_args(args: &ArgMatches) -> Option<ConfigWatcher> {
+    if args.is_present("watch") {
+        args.value_of("world").map(ConfigWatcher::new)
+    } else {
+        None
+    }
+}
+
+// For running in a terminal instead of a window, e.g. over SSH.
+fn terminal_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("tui")
+            .long("tui")
+            .conflicts_with_all(&["paused", "keymap"])
+            .help("Runs in the terminal, drawing a character map instead of opening a window"),
+        Arg::with_name("unicode")
+            .long("unicode")
+            .requires("tui")
+            .help("Draws the terminal map with Unicode shading blocks"),
+        interval_arg("10"),
+        columns_arg(),
+        rows_arg(),
+    ]
+}
+
+fn columns_arg() -> Arg<'static, 'static> {
+    Arg::with_name("columns")
+        .long("columns")
+        .takes_value(true)
+        .default_value("78")
+        .validator(positive_number)
+        .help("Width of the map, in characters")
+}
+
+fn rows_arg() -> Arg<'static, 'static> {
+    Arg::with_name("rows")
+        .long("rows")
+        .takes_value(true)
+        .default_value("22")
+        .validator(positive_number)
+        .help("Height of the map, in characters")
+}
+
+fn interval_arg(default: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("interval")
+        .long("interval")
+        .takes_value(true)
+        .default_value(default)
+        .validator(positive_number)
+        .help("Ticks between frames")
+}
+
+fn positive_number(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(()),
+        _ => Err(format!("expected a positive whole number, not {}", value)),
+    }
+}
+
+fn optional_number_from_args<T: std::str::FromStr>(args: &ArgMatches, name: &str) -> Option<T> {
+    if args.is_present(name) {
+        Some(number_from_args(args, name))
+    } else {
+        None
+    }
+}
+
+fn number_from_args<T: std::str::FromStr>(args: &ArgMatches, name: &str) -> T {
+    value_t!(args, name, T).unwrap_or_else(|err| err.exit())
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+fn world_from_args(args: &ArgMatches) -> World {
+    let mut world = if let Some(seed) = optional_number_from_args(args, "seed") {
+        generate_world(seed)
+    } else {
+        match args.value_of("world") {
+            Some(path) => load_world(path, args.is_present("discard-controls"))
+                .unwrap_or_else(|err| exit_with_error(&err)),
+            None => create_world(),
+        }
+    };
+    for patch in patches_from_args(args) {
+        world
+            .apply_patch(&patch)
+            .unwrap_or_else(|err| exit_with_error(&format!("Cannot apply patch: {}", err)));
+    }
+    add_plugins(&mut world, &plugin_registry(), &plugins_from_args(args));
+    if let Some(tag) = args.value_of("trajectory-tag") {
+        world = world.with_trajectory_recorder(TrajectoryRecorder::new(
+            tag,
+            number_from_args(args, "trajectory-length"),
+        ));
+    }
+    with_blowup_detection_from_args(world, args)
+}
+
+fn with_blowup_detection_from_args(world: World, args: &ArgMatches) -> World {
+    if !args.is_present("detect-blowups") {
+        return world;
+    }
+    let bounds = BlowupBounds::for_world(world.min_corner(), world.max_corner());
+    world.with_blowup_detection(bounds)
+}
+
+fn plugins_from_args(args: &ArgMatches) -> Vec<String> {
+    args.values_of("plugin")
+        .map_or(vec![], |names| names.map(String::from).collect())
+}
+
+fn patches_from_args(args: &ArgMatches) -> Vec<WorldPatch> {
+    args.values_of("patch").map_or(vec![], |paths| {
+        paths
+            .map(|path| load_patch(path).unwrap_or_else(|err| exit_with_error(&err)))
+            .collect()
+    })
+}
+
+fn metrics_sinks_from_args(args: &ArgMatches) -> Vec<Box<dyn MetricsSink>> {
+    let prefix = args.value_of("metrics-prefix").unwrap();
+    let mut sinks: Vec<Box<dyn MetricsSink>> = vec![];
+    if let Some(address) = args.value_of("statsd") {
+        let sink = StatsdSink::new(address, prefix)
+            .unwrap_or_else(|err| panic!("Cannot reach StatsD server {}: {}", address, err));
+        sinks.push(Box::new(sink));
+    }
+    if let Some(path) = args.value_of("prometheus-file") {
+        sinks.push(Box::new(PrometheusFileSink::new(path.into(), prefix)));
+    }
+    sinks
+}
+
+fn keymap_from_args(args: &ArgMatches) -> Keymap {
+    match args.value_of("keymap") {
+        Some(path) => load_keymap(path),
+        None => Keymap::default(),
+    }
+}
+
+fn ticks_from_args(args: &ArgMatches) -> u64 {
+    value_t!(args, "ticks", u64).unwrap_or_else(|err| err.exit())
 }
 
 const FLUID_DENSITY: f64 = 0.001;
@@ -21,6 +718,7 @@ const PHOTO_LAYER_DENSITY: f64 = 0.002;
 const BONDING_LAYER_DENSITY: f64 = 0.002;
 const GRAVITY: f64 = -0.05;
 const OVERLAP_DAMAGE_HEALTH_DELTA: f64 = -0.1;
+const NEWBORN_OVERLAP_IMMUNITY_TICKS: u32 = 20;
 
 const FLOAT_LAYER_INDEX: usize = 0;
 const PHOTO_LAYER_INDEX: usize = 1;
@@ -49,13 +747,25 @@ fn create_world() -> World {
         )
 }
 
-fn create_cell() -> Cell {
-    const SOME_MUTATION: MutationParameters = MutationParameters {
-        weight_mutation_probability: 0.5,
-        weight_mutation_stdev: 1.0,
-        ..MutationParameters::NO_MUTATION
-    };
+fn generate_world(seed: u64) -> World {
+    WorldGenerator::new(
+        seed,
+        WorldGenParameters {
+            gravity: (GRAVITY, GRAVITY),
+            fluid_density: FLUID_DENSITY,
+            ..WorldGenParameters::DEFAULT
+        },
+    )
+    .generate(create_random_cell)
+}
+
+const SOME_MUTATION: MutationParameters = MutationParameters {
+    weight_mutation_probability: 0.5,
+    weight_mutation_stdev: 1.0,
+    ..MutationParameters::NO_MUTATION
+};
 
+fn create_cell() -> Cell {
     Cell::new(
         Position::ORIGIN,
         Velocity::ZERO,
@@ -71,6 +781,24 @@ fn create_cell() -> Cell {
     )))
 }
 
+fn create_random_cell(seed: u64) -> Cell {
+    let mut randomness = SeededMutationRandomness::new(seed, &SOME_MUTATION);
+    let genome = NeuralNetBuddingControl::new_genome().spawn(&mut randomness);
+    Cell::new(
+        Position::ORIGIN,
+        Velocity::ZERO,
+        vec![
+            create_float_layer(),
+            create_photo_layer(),
+            create_bonding_layer(),
+        ],
+    )
+    .with_control(Box::new(NeuralNetBuddingControl::new(
+        genome,
+        randomness.spawn(),
+    )))
+}
+
 fn create_float_layer() -> CellLayer {
     const LAYER_RESIZE_PARAMS: LayerResizeParameters = LayerResizeParameters {
         growth_energy_delta: BioEnergyDelta::new(-0.1),
@@ -82,6 +810,7 @@ fn create_float_layer() -> CellLayer {
         healing_energy_delta: BioEnergyDelta::new(-1.0),
         entropic_damage_health_delta: -0.01,
         overlap_damage_health_delta: OVERLAP_DAMAGE_HEALTH_DELTA,
+        newborn_overlap_immunity_ticks: NEWBORN_OVERLAP_IMMUNITY_TICKS,
     };
 
     CellLayer::new(
@@ -90,8 +819,8 @@ fn create_float_layer() -> CellLayer {
         Color::White,
         Box::new(NullCellLayerSpecialty::new()),
     )
-    .with_resize_parameters(&LAYER_RESIZE_PARAMS)
-    .with_health_parameters(&LAYER_HEALTH_PARAMS)
+    .with_resize_parameters(LAYER_RESIZE_PARAMS)
+    .with_health_parameters(LAYER_HEALTH_PARAMS)
 }
 
 fn create_photo_layer() -> CellLayer {
@@ -105,6 +834,7 @@ fn create_photo_layer() -> CellLayer {
         healing_energy_delta: BioEnergyDelta::new(-1.0),
         entropic_damage_health_delta: -0.01,
         overlap_damage_health_delta: OVERLAP_DAMAGE_HEALTH_DELTA,
+        newborn_overlap_immunity_ticks: NEWBORN_OVERLAP_IMMUNITY_TICKS,
     };
 
     CellLayer::new(
@@ -113,8 +843,8 @@ fn create_photo_layer() -> CellLayer {
         Color::Green,
         Box::new(PhotoCellLayerSpecialty::new(0.1)), // 0.02
     )
-    .with_resize_parameters(&LAYER_RESIZE_PARAMS)
-    .with_health_parameters(&LAYER_HEALTH_PARAMS)
+    .with_resize_parameters(LAYER_RESIZE_PARAMS)
+    .with_health_parameters(LAYER_HEALTH_PARAMS)
 }
 
 fn create_bonding_layer() -> CellLayer {
@@ -128,6 +858,7 @@ fn create_bonding_layer() -> CellLayer {
         healing_energy_delta: BioEnergyDelta::new(-1.0),
         entropic_damage_health_delta: -0.01,
         overlap_damage_health_delta: OVERLAP_DAMAGE_HEALTH_DELTA,
+        newborn_overlap_immunity_ticks: NEWBORN_OVERLAP_IMMUNITY_TICKS,
     };
 
     CellLayer::new(
@@ -136,8 +867,8 @@ fn create_bonding_layer() -> CellLayer {
         Color::Yellow,
         Box::new(BondingCellLayerSpecialty::new()),
     )
-    .with_resize_parameters(&LAYER_RESIZE_PARAMS)
-    .with_health_parameters(&LAYER_HEALTH_PARAMS)
+    .with_resize_parameters(LAYER_RESIZE_PARAMS)
+    .with_health_parameters(LAYER_HEALTH_PARAMS)
 }
 
 #[derive(Debug)]
@@ -208,6 +939,15 @@ impl NeuralNetBuddingControl {
             -100.0,
             &[(Self::CELL_ENERGY_INPUT_INDEX, 0.1)],
         );
+        genome.set_output_nodes(&[
+            Self::FLOAT_LAYER_RESIZE_OUTPUT_INDEX,
+            Self::FLOAT_LAYER_HEALING_OUTPUT_INDEX,
+            Self::PHOTO_LAYER_RESIZE_OUTPUT_INDEX,
+            Self::PHOTO_LAYER_HEALING_OUTPUT_INDEX,
+            Self::BONDING_LAYER_RESIZE_OUTPUT_INDEX,
+            Self::BONDING_LAYER_HEALING_OUTPUT_INDEX,
+            Self::DONATION_ENERGY_OUTPUT_INDEX,
+        ]);
         genome
     }
 }
@@ -258,14 +998,11 @@ impl CellControl for NeuralNetBuddingControl {
         let donation_energy = self.nnet.node_value(Self::DONATION_ENERGY_OUTPUT_INDEX) as f64;
 
         vec![
-            CellLayer::healing_request(FLOAT_LAYER_INDEX, float_layer_healing.max(0.0).min(1.0)),
+            CellLayer::healing_request(FLOAT_LAYER_INDEX, float_layer_healing.clamp(0.0, 1.0)),
             CellLayer::resize_request(FLOAT_LAYER_INDEX, AreaDelta::new(float_layer_area_delta)),
-            CellLayer::healing_request(PHOTO_LAYER_INDEX, photo_layer_healing.max(0.0).min(1.0)),
+            CellLayer::healing_request(PHOTO_LAYER_INDEX, photo_layer_healing.clamp(0.0, 1.0)),
             CellLayer::resize_request(PHOTO_LAYER_INDEX, AreaDelta::new(photo_layer_area_delta)),
-            CellLayer::healing_request(
-                BONDING_LAYER_INDEX,
-                bonding_layer_healing.max(0.0).min(1.0),
-            ),
+            CellLayer::healing_request(BONDING_LAYER_INDEX, bonding_layer_healing.clamp(0.0, 1.0)),
             CellLayer::resize_request(
                 BONDING_LAYER_INDEX,
                 AreaDelta::new(bonding_layer_area_delta),
@@ -294,4 +1031,26 @@ impl CellControl for NeuralNetBuddingControl {
             randomness: self.randomness.clone(),
         })
     }
+
+    fn use_fast_math(&mut self) {
+        self.nnet.use_fast_transfer_fns();
+    }
+
+    fn mutation_rate(&self) -> Option<f64> {
+        Some(self.randomness.weight_mutation_probability() as f64)
+    }
+
+    fn set_mutation_rate(&mut self, rate: f64) {
+        self.randomness.set_weight_mutation_probability(rate as f32);
+    }
+
+    fn non_finite_state(&self) -> Option<String> {
+        self.nnet
+            .first_non_finite_node()
+            .map(|(index, value)| format!("node {} value {}", index, value))
+    }
+
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.nnet.hash_state(hasher);
+    }
 }
@@ -1,22 +1,789 @@
+use crate::telemetry::*;
+use crate::text_view::*;
 use crate::view::*;
-use evo_domain::physics::quantities::Position;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use evo_domain::biology::cell::{Cell, CellId};
+use evo_domain::document::{RunMetadata, WorldDeltaDocument, WorldDocument, WorldPatch};
+use evo_domain::environment::trajectories::TrajectoryRecorder;
+use evo_domain::events::LimitAction;
+use evo_domain::physics::quantities::*;
+use evo_domain::plugins::{PluginRegistry, WorldPlugin};
+use evo_domain::validation::validate_world_json;
+use evo_domain::view_model::ViewFrame;
 use evo_domain::world::World;
 use evo_domain::UserAction;
+pub use evo_glium::Keymap;
+use log::{error, info};
+use std::collections::{BTreeMap, VecDeque};
 use std::env;
+use std::fs;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub fn init_and_run(world: World) {
     simple_logger::init().unwrap();
 
     let args: Vec<String> = env::args().collect();
-    let start_paused = args.len() == 2 && args[1] == "-p".to_string();
+    let start_paused = args.len() == 2 && args[1] == "-p";
 
-    let view = View::new(world.min_corner(), world.max_corner());
-    run(world, view, start_paused);
+    run_interactively(world, start_paused, Keymap::default(), None);
 }
 
-fn run(mut world: World, mut view: View, start_paused: bool) {
+pub fn run_interactively(
+    world: World,
+    start_paused: bool,
+    keymap: Keymap,
+    watcher: Option<ConfigWatcher>,
+) {
+    let view = View::new(world.min_corner(), world.max_corner(), keymap);
+    run(world.with_genealogy(), view, start_paused, watcher);
+}
+
+// Runs the world until the user presses q, Esc or Ctrl-C, redrawing a character map of it on
+// the terminal's alternate screen every `interval` ticks, at most ten times a second.
+pub fn run_in_terminal(
+    mut world: World,
+    interval: u64,
+    (columns, rows): (usize, usize),
+    glyphs: Glyphs,
+    mut watcher: Option<ConfigWatcher>,
+) -> io::Result<()> {
+    assert!(interval > 0);
+    let _terminal = TerminalGuard::enter()?;
+    let mut out = io::stdout();
+    let mut next_frame = Instant::now();
+    loop {
+        let frame = ViewFrame::from_world(&world);
+        queue!(
+            out,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            Print(render_stats(&frame))
+        )?;
+        for (row, line) in render_text(&frame, columns, rows, glyphs)
+            .lines()
+            .enumerate()
+        {
+            queue!(out, MoveTo(0, row as u16 + 1), Print(line))?;
+        }
+        out.flush()?;
+        next_frame += Duration::from_millis(100);
+        for _ in 0..interval {
+            world.tick();
+        }
+        reload_parameters(&mut world, &mut watcher);
+        if await_quit_key(next_frame)? {
+            return Ok(());
+        }
+    }
+}
+
+// Waits until the deadline, or returns true as soon as the user presses a quit key.
+fn await_quit_key(deadline: Instant) -> io::Result<bool> {
+    while event::poll(deadline.saturating_duration_since(Instant::now()))? {
+        if let Event::Key(key) = event::read()? {
+            let is_quit = match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => true,
+                KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+                _ => false,
+            };
+            if is_quit && key.kind == KeyEventKind::Press {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+// Switches the terminal to raw mode on its alternate screen, and back again when dropped, even
+// after an error or a panic.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // Nothing more can be done if restoring the terminal fails
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Watches a world file for edits while the world runs, so that its tunable parameters, e.g.
+// light intensity and drag, can be changed without restarting. Checks the file's modification
+// time at most twice a second.
+pub struct ConfigWatcher {
+    path: String,
+    modified: Option<SystemTime>,
+    next_check: Instant,
+}
+
+impl ConfigWatcher {
+    const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(path: &str) -> Self {
+        ConfigWatcher {
+            path: path.to_string(),
+            modified: Self::modified_time(path),
+            next_check: Instant::now() + Self::CHECK_INTERVAL,
+        }
+    }
+
+    // The file's new contents, if it has changed since the last check. Logs and skips an
+    // edited file that is invalid, e.g. one saved halfway through an edit.
+    pub fn poll(&mut self) -> Option<WorldDocument> {
+        let now = Instant::now();
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + Self::CHECK_INTERVAL;
+        let modified = Self::modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        let json = match fs::read_to_string(&self.path) {
+            Ok(json) => json,
+            Err(err) => {
+                error!("Cannot reload world file {}: {}", self.path, err);
+                return None;
+            }
+        };
+        match validate_world_json(&json) {
+            Ok(document) => Some(document),
+            Err(problems) => {
+                error!("Not reloading invalid world file {}:", self.path);
+                for problem in problems {
+                    error!("  {}", problem);
+                }
+                None
+            }
+        }
+    }
+
+    fn modified_time(path: &str) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+fn reload_parameters(world: &mut World, watcher: &mut Option<ConfigWatcher>) {
+    if let Some(document) = watcher.as_mut().and_then(ConfigWatcher::poll) {
+        for setting in world.reload_parameters(&document) {
+            info!(
+                "Reloaded {:?}: {:.4} at tick {}",
+                setting.parameter,
+                setting.value,
+                world.current_tick()
+            );
+        }
+    }
+}
+
+pub fn load_keymap(path: &str) -> Keymap {
+    Keymap::from_file(path).unwrap_or_else(|err| panic!("Invalid keymap file {}: {}", path, err))
+}
+
+// Also loads delta checkpoints, from their keyframes in the same directory. Cell controls are
+// not saved, so a world whose cells had controls only loads if `discard_controls` is set, and
+// then its cells run without them.
+pub fn load_world(path: &str, discard_controls: bool) -> Result<World, String> {
+    let document = load_world_document(path)?;
+    let num_controlled_cells = document
+        .cells
+        .iter()
+        .filter(|cell| cell.has_control)
+        .count();
+    if num_controlled_cells > 0 && !discard_controls {
+        return Err(format!(
+            "World file {}: {} of its cells had controls, which are not saved \
+             (use --discard-controls to run them without controls)",
+            path, num_controlled_cells
+        ));
+    }
+    World::from_document(&document).map_err(|err| format!("Invalid world file {}: {}", path, err))
+}
+
+fn load_world_document(path: &str) -> Result<WorldDocument, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|err| format!("Cannot read world file {}: {}", path, err))?;
+    if path.ends_with(DELTA_CHECKPOINT_SUFFIX) {
+        let delta = WorldDeltaDocument::from_json(&json)
+            .map_err(|err| format!("Invalid world delta file {}: {}", path, err))?;
+        let keyframe_path =
+            Path::new(path).with_file_name(checkpoint_file_name(delta.keyframe_tick, false));
+        let keyframe = load_world_document(&keyframe_path.to_string_lossy())?;
+        return delta
+            .apply_to(&keyframe)
+            .map_err(|err| format!("Invalid world delta file {}: {}", path, err));
+    }
+    validate_world_json(&json).map_err(|problems| {
+        let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        format!("Invalid world file {}:\n{}", path, problems.join("\n"))
+    })
+}
+
+const DELTA_CHECKPOINT_SUFFIX: &str = ".delta.json";
+
+fn checkpoint_file_name(tick: u64, is_delta: bool) -> String {
+    if is_delta {
+        format!("tick_{}{}", tick, DELTA_CHECKPOINT_SUFFIX)
+    } else {
+        format!("tick_{}.json", tick)
+    }
+}
+
+pub fn load_patch(path: &str) -> Result<WorldPatch, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|err| format!("Cannot read patch file {}: {}", path, err))?;
+    WorldPatch::from_json(&json).map_err(|err| format!("Invalid patch file {}: {}", path, err))
+}
+
+// The plugins that can be named on the command line. A lab's own binary can register more
+// before adding them to a world with add_plugins.
+pub fn plugin_registry() -> PluginRegistry {
+    PluginRegistry::new().with_plugin("census", || Box::new(CensusPlugin::new(1000)))
+}
+
+pub fn add_plugins(world: &mut World, registry: &PluginRegistry, names: &[String]) {
+    for name in names {
+        let plugin = registry.create(name).unwrap_or_else(|| {
+            panic!(
+                "Unknown plugin: {} (known: {})",
+                name,
+                registry.names().collect::<Vec<_>>().join(", ")
+            )
+        });
+        world.add_plugin(plugin);
+    }
+}
+
+// Logs the population and its births and deaths every `interval` ticks.
+struct CensusPlugin {
+    interval: u64,
+    births: u64,
+    deaths: u64,
+}
+
+impl CensusPlugin {
+    fn new(interval: u64) -> Self {
+        CensusPlugin {
+            interval,
+            births: 0,
+            deaths: 0,
+        }
+    }
+}
+
+impl WorldPlugin for CensusPlugin {
+    fn after_controls(&mut self, world: &mut World) {
+        // The world has not yet counted this tick
+        let tick = world.current_tick() + 1;
+        if tick.is_multiple_of(self.interval) {
+            info!(
+                "Tick {}: {} cells, {} births and {} deaths since last census",
+                tick,
+                world.cells().len(),
+                self.births,
+                self.deaths
+            );
+            self.births = 0;
+            self.deaths = 0;
+        }
+    }
+
+    fn on_cell_born(&mut self, _world: &World, _cell: &Cell) {
+        self.births += 1;
+    }
+
+    fn on_cell_died(&mut self, _world: &World, _cell: &Cell) {
+        self.deaths += 1;
+    }
+}
+
+pub fn save_world(world: &World, path: &str) -> Result<(), String> {
+    fs::write(path, world.to_document().to_json())
+        .map_err(|err| format!("Cannot write world file {}: {}", path, err))
+}
+
+pub fn bench(world: &mut World, num_ticks: u64) -> Duration {
+    let start = Instant::now();
+    for _ in 0..num_ticks {
+        world.tick();
+    }
+    start.elapsed()
+}
+
+// Each probe in the world adds light, cell count and average velocity columns. Probe
+// columns are empty in the tick 0 row, since probes take readings at the end of each tick.
+// The injected and dissipated columns are the energy the influences added and removed during
+// the tick. The energy budget columns total the cells' net energy deltas for the tick, by use.
+// Each cell tag present at the start adds a column counting the cells with that tag.
+// If there is a lineage_out, it gets a row per lineage per tick, since lineages come and go.
+// If downsample is set, the stats are downsampled as by StatsWriter.
+pub fn export_stats(
+    world: &mut World,
+    num_ticks: u64,
+    downsample: bool,
+    out: &mut dyn Write,
+    mut lineage_out: Option<&mut dyn Write>,
+) -> io::Result<()> {
+    let mut stats_writer = StatsWriter::new(world, downsample, out)?;
+    stats_writer.write_row(world, 0, out)?;
+    if let Some(lineage_out) = &mut lineage_out {
+        write_lineage_stats_header(lineage_out)?;
+        write_lineage_stats_rows(world, 0, lineage_out)?;
+    }
+    for tick in 1..=num_ticks {
+        world.tick();
+        check_blowup(world, Path::new("."))?;
+        stats_writer.write_row(world, tick, out)?;
+        if let Some(lineage_out) = &mut lineage_out {
+            write_lineage_stats_rows(world, tick, lineage_out)?;
+        }
+    }
+    stats_writer.finish(out)
+}
+
+// Runs a world without a view and records the run in a new directory in runs_dir, named for
+// the start time. The directory holds the run's metadata, the starting world, a checkpoint
+// of the world every checkpoint_interval ticks, the stats and lineage stats as in
+// export_stats, the world's event log, and the recorded trajectories, if the world has a
+// trajectory recorder. Returns the directory. If downsample_stats is set, the stats are
+// downsampled as by StatsWriter and written at the end of the run.
+// Reports metrics to the sinks every `metrics_interval` ticks. If the world blows up, the run
+// stops with an error, after saving the world in the run directory.
+pub fn record_run(
+    world: &mut World,
+    mut metadata: RunMetadata,
+    runs_dir: &Path,
+    metrics_sinks: &mut [Box<dyn MetricsSink>],
+    metrics_interval: u64,
+    downsample_stats: bool,
+) -> io::Result<PathBuf> {
+    metadata.started_at_unix_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let run_dir = create_run_dir(runs_dir, metadata.started_at_unix_secs)?;
+    fs::write(run_dir.join("metadata.json"), metadata.to_json())?;
+    fs::write(run_dir.join("world.json"), world.to_document().to_json())?;
+    let mut checkpoints =
+        CheckpointWriter::new(run_dir.join("checkpoints"), metadata.keyframe_interval)?;
+
+    let mut out = BufWriter::new(fs::File::create(run_dir.join("stats.csv"))?);
+    let mut lineage_out = BufWriter::new(fs::File::create(run_dir.join("lineages.csv"))?);
+    let mut stats_writer = StatsWriter::new(world, downsample_stats, &mut out)?;
+    stats_writer.write_row(world, 0, &mut out)?;
+    write_lineage_stats_header(&mut lineage_out)?;
+    write_lineage_stats_rows(world, 0, &mut lineage_out)?;
+    let mut result = Ok(());
+    for tick in 1..=metadata.num_ticks {
+        let tick_start = Instant::now();
+        let report = world.tick();
+        if !metrics_sinks.is_empty() && tick.is_multiple_of(metrics_interval) {
+            let metrics = TickMetrics::from_world(world, tick_start.elapsed());
+            for sink in metrics_sinks.iter_mut() {
+                sink.report(&metrics)?;
+            }
+        }
+        stats_writer.write_row(world, tick, &mut out)?;
+        write_lineage_stats_rows(world, tick, &mut lineage_out)?;
+        if metadata.checkpoint_interval > 0 && tick % metadata.checkpoint_interval == 0 {
+            checkpoints.write(world, tick)?;
+        }
+        result = check_blowup(world, &run_dir);
+        if result.is_err() {
+            break;
+        }
+        if let Some(exceeded) = report.limit_exceeded {
+            if world.resource_limits().action == LimitAction::Pause {
+                checkpoints.write_keyframe(world, tick)?;
+                info!("Stopped at tick {}: {:?}", tick, exceeded);
+                break;
+            }
+        }
+    }
+
+    stats_writer.finish(&mut out)?;
+    let mut events_out = BufWriter::new(fs::File::create(run_dir.join("events.txt"))?);
+    for event in world.event_log().events() {
+        writeln!(events_out, "{} {:?}", event.tick, event.kind)?;
+    }
+    if let Some(recorder) = world.trajectory_recorder() {
+        let mut trajectories_out =
+            BufWriter::new(fs::File::create(run_dir.join("trajectories.csv"))?);
+        write_trajectories_csv(recorder, &mut trajectories_out)?;
+    }
+    result.map(|()| run_dir)
+}
+
+// Writes the rows of a stats CSV. Without downsampling, each row is written as it comes.
+// With it, the writer keeps the rows of the most recent ticks, of the most recent multiples of
+// MEDIUM_INTERVAL, and of the most recent multiples of LONG_INTERVAL, at most
+// ROWS_PER_INTERVAL of each, and writes them in tick order when finished. So a run of millions
+// of ticks keeps a bounded number of rows, dense recently and sparse long ago.
+pub struct StatsWriter {
+    tag_names: Vec<String>,
+    // The rows kept for each interval, if downsampling
+    downsampled: Option<Vec<DownsampledRows>>,
+}
+
+struct DownsampledRows {
+    interval: u64,
+    // Ticks and their rows, oldest first
+    rows: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl StatsWriter {
+    pub const MEDIUM_INTERVAL: u64 = 100;
+    pub const LONG_INTERVAL: u64 = 10_000;
+    pub const ROWS_PER_INTERVAL: usize = 1000;
+
+    // Writes the header right away.
+    pub fn new(world: &World, downsample: bool, out: &mut dyn Write) -> io::Result<Self> {
+        let tag_names = world.tag_names();
+        write_stats_header(world, &tag_names, out)?;
+        let downsampled = if downsample {
+            Some(
+                [1, Self::MEDIUM_INTERVAL, Self::LONG_INTERVAL]
+                    .iter()
+                    .map(|&interval| DownsampledRows {
+                        interval,
+                        rows: VecDeque::new(),
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        Ok(StatsWriter {
+            tag_names,
+            downsampled,
+        })
+    }
+
+    pub fn write_row(&mut self, world: &World, tick: u64, out: &mut dyn Write) -> io::Result<()> {
+        let downsampled = match &mut self.downsampled {
+            Some(downsampled) => downsampled,
+            None => return write_stats_row(world, &self.tag_names, tick, out),
+        };
+        let mut row = vec![];
+        write_stats_row(world, &self.tag_names, tick, &mut row)?;
+        for downsampled_rows in downsampled {
+            if tick.is_multiple_of(downsampled_rows.interval) {
+                if downsampled_rows.rows.len() == Self::ROWS_PER_INTERVAL {
+                    downsampled_rows.rows.pop_front();
+                }
+                downsampled_rows.rows.push_back((tick, row.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if let Some(downsampled) = &mut self.downsampled {
+            let rows: BTreeMap<u64, Vec<u8>> = downsampled
+                .iter_mut()
+                .flat_map(|downsampled_rows| downsampled_rows.rows.drain(..))
+                .collect();
+            for row in rows.values() {
+                out.write_all(row)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+// One row per recorded position, grouped by cell, oldest first.
+pub fn write_trajectories_csv(
+    recorder: &TrajectoryRecorder,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    writeln!(out, "cell_id,tick,x,y")?;
+    for (cell_id, trajectory) in recorder.trajectories() {
+        for point in trajectory {
+            writeln!(
+                out,
+                "{},{},{},{}",
+                cell_id.value(),
+                point.tick,
+                point.position.x(),
+                point.position.y()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Writes the request histories of the selected cells: what their controls asked for, what
+// they were allowed, and what it cost.
+pub fn write_request_history_csv(world: &World, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "cell_id,cell_age,layer_index,channel_index,value_index,requested_value,allowed_value,cost,budgeted_fraction"
+    )?;
+    for cell in world.cells().iter().filter(|cell| cell.is_selected()) {
+        for recorded in cell.request_history() {
+            let request = &recorded.request;
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                cell.id().value(),
+                recorded.cell_age,
+                request.layer_index(),
+                request.channel_index(),
+                request.value_index(),
+                request.requested_value(),
+                request.allowed_value(),
+                -request.energy_delta().value(),
+                request.budgeted_fraction()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// If the world blew up in its last tick, saves it as blowup-<tick>.json in `dir`, for
+// diagnosis, and returns an error with the blowup report.
+fn check_blowup(world: &World, dir: &Path) -> io::Result<()> {
+    let blowup = match world.blowup() {
+        Some(blowup) => blowup,
+        None => return Ok(()),
+    };
+    let path = dir.join(format!("blowup-{}.json", blowup.tick));
+    fs::write(&path, world.to_document().to_json())?;
+    Err(io::Error::other(format!(
+        "{}World saved in {}",
+        blowup,
+        path.display()
+    )))
+}
+
+// Writes full world documents every `keyframe_interval` checkpoints, and deltas from the last
+// full one in between.
+struct CheckpointWriter {
+    dir: PathBuf,
+    keyframe_interval: u64,
+    num_checkpoints: u64,
+    // The last full checkpoint's tick, document, and cell IDs
+    keyframe: Option<(u64, WorldDocument, Vec<u64>)>,
+}
+
+impl CheckpointWriter {
+    fn new(dir: PathBuf, keyframe_interval: u64) -> io::Result<Self> {
+        fs::create_dir(&dir)?;
+        Ok(CheckpointWriter {
+            dir,
+            keyframe_interval: keyframe_interval.max(1),
+            num_checkpoints: 0,
+            keyframe: None,
+        })
+    }
+
+    fn write(&mut self, world: &World, tick: u64) -> io::Result<()> {
+        let document = world.to_document();
+        let cell_ids: Vec<u64> = world.cells().iter().map(|cell| cell.id().value()).collect();
+        let is_keyframe = self.num_checkpoints.is_multiple_of(self.keyframe_interval);
+        self.num_checkpoints += 1;
+        match &self.keyframe {
+            Some((keyframe_tick, keyframe, keyframe_cell_ids)) if !is_keyframe => {
+                let delta = WorldDeltaDocument::new(
+                    keyframe,
+                    *keyframe_tick,
+                    keyframe_cell_ids,
+                    &document,
+                    &cell_ids,
+                );
+                fs::write(
+                    self.dir.join(checkpoint_file_name(tick, true)),
+                    delta.to_json(),
+                )
+            }
+            _ => {
+                fs::write(
+                    self.dir.join(checkpoint_file_name(tick, false)),
+                    document.to_json(),
+                )?;
+                self.keyframe = Some((tick, document, cell_ids));
+                Ok(())
+            }
+        }
+    }
+
+    // A full checkpoint, whatever the interval, e.g. to resume from after a stop.
+    fn write_keyframe(&mut self, world: &World, tick: u64) -> io::Result<()> {
+        self.keyframe = None;
+        self.write(world, tick)
+    }
+}
+
+fn create_run_dir(runs_dir: &Path, started_at_unix_secs: u64) -> io::Result<PathBuf> {
+    fs::create_dir_all(runs_dir)?;
+    let mut run_dir = runs_dir.join(format!("run_{}", started_at_unix_secs));
+    let mut suffix = 1;
+    while run_dir.exists() {
+        suffix += 1;
+        run_dir = runs_dir.join(format!("run_{}_{}", started_at_unix_secs, suffix));
+    }
+    fs::create_dir(&run_dir)?;
+    Ok(run_dir)
+}
+
+// Writes a view frame as a line of JSON every `interval` ticks, starting before the first.
+pub fn export_frames(
+    world: &mut World,
+    num_ticks: u64,
+    interval: u64,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    for_each_frame(world, num_ticks, interval, |frame| {
+        writeln!(out, "{}", frame.to_json_line())
+    })
+}
+
+// Writes a character map of the world every `interval` ticks, starting before the first.
+pub fn print_text_frames(
+    world: &mut World,
+    num_ticks: u64,
+    interval: u64,
+    (columns, rows): (usize, usize),
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    for_each_frame(world, num_ticks, interval, |frame| {
+        writeln!(out, "{}", render_stats(frame))?;
+        write!(out, "{}", render_text(frame, columns, rows, Glyphs::Ascii))
+    })
+}
+
+fn for_each_frame<F>(world: &mut World, num_ticks: u64, interval: u64, mut f: F) -> io::Result<()>
+where
+    F: FnMut(&ViewFrame) -> io::Result<()>,
+{
+    assert!(interval > 0);
+    f(&ViewFrame::from_world(world))?;
+    for tick in 1..=num_ticks {
+        world.tick();
+        check_blowup(world, Path::new("."))?;
+        if tick % interval == 0 {
+            f(&ViewFrame::from_world(world))?;
+        }
+    }
+    Ok(())
+}
+
+// `git describe` of the current directory's source tree, if it is one.
+pub fn git_describe() -> Option<String> {
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn write_stats_header(world: &World, tag_names: &[String], out: &mut dyn Write) -> io::Result<()> {
+    write!(
+        out,
+        "tick,cells,bonds,energy,injected,dissipated,influences,healing,growth,donation,thrust,other"
+    )?;
+    for tag_name in tag_names {
+        write!(out, ",{}_cells", tag_name)?;
+    }
+    for probe in world.probes() {
+        let name = probe.name();
+        write!(
+            out,
+            ",{}_light,{}_cells,{}_vx,{}_vy",
+            name, name, name, name
+        )?;
+    }
+    writeln!(out)
+}
+
+fn write_lineage_stats_header(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "tick,lineage,cells,biomass,mean_energy,births,deaths")
+}
+
+fn write_lineage_stats_rows(world: &World, tick: u64, out: &mut dyn Write) -> io::Result<()> {
+    for stats in world.lineage_stats() {
+        writeln!(
+            out,
+            "{},{},{},{:.4},{:.4},{},{}",
+            tick,
+            stats.lineage,
+            stats.cells,
+            stats.biomass.value(),
+            stats.mean_energy.value(),
+            stats.births,
+            stats.deaths
+        )?;
+    }
+    Ok(())
+}
+
+fn write_stats_row(
+    world: &World,
+    tag_names: &[String],
+    tick: u64,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let energy: f64 = world.cells().iter().map(|cell| cell.energy().value()).sum();
+    let flux = world.energy_flux();
+    let budget = world.total_energy_budget();
+    write!(
+        out,
+        "{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4}",
+        tick,
+        world.cells().len(),
+        world.bonds().len(),
+        energy,
+        flux.injected,
+        flux.dissipated,
+        budget.influences.value(),
+        budget.healing.value(),
+        budget.growth.value(),
+        budget.donation.value(),
+        budget.thrust.value(),
+        budget.other.value()
+    )?;
+    for tag_name in tag_names {
+        write!(out, ",{}", world.count_cells_with_tag(tag_name))?;
+    }
+    for probe in world.probes() {
+        match probe.latest_reading() {
+            Some(reading) => write!(
+                out,
+                ",{:.4},{},{:.4},{:.4}",
+                reading.light_intensity,
+                reading.cell_count,
+                reading.average_velocity.x(),
+                reading.average_velocity.y()
+            )?,
+            None => write!(out, ",,,,")?,
+        }
+    }
+    writeln!(out)
+}
+
+fn run(mut world: World, mut view: View, start_paused: bool, mut watcher: Option<ConfigWatcher>) {
     view.render(&world);
 
     let mut user_action = if start_paused {
@@ -24,46 +791,284 @@ fn run(mut world: World, mut view: View, start_paused: bool) {
     } else {
         UserAction::PlayToggle
     };
+    // The world stays paused while it is being edited
+    let mut editing = false;
 
     loop {
         match user_action {
-            UserAction::DebugPrint => world.debug_print_cells(),
             UserAction::Exit => return,
             UserAction::None => (),
-            UserAction::PlayToggle => {
-                if normal_speed(&mut world, &mut view) == UserAction::Exit {
-                    return;
+            UserAction::PlayToggle | UserAction::SingleTick if editing => {
+                info!("Leave edit mode to run the world");
+            }
+            UserAction::PlayToggle => match normal_speed(&mut world, &mut view, &mut watcher) {
+                UserAction::Exit => return,
+                UserAction::ToggleEditMode => {
+                    editing = true;
+                    view.set_edit_mode(editing);
+                    view.render(&world);
                 }
+                _ => (),
+            },
+            UserAction::SingleTick => {
+                single_tick(&mut world, &mut view);
             }
-            UserAction::SelectCellToggle { x, y } => {
-                world.toggle_select_cell_at(Position::new(x, y));
+            UserAction::ToggleEditMode => {
+                editing = !editing;
+                view.set_edit_mode(editing);
                 view.render(&world);
             }
-            UserAction::SingleTick => single_tick(&mut world, &mut view),
+            user_action => handle_user_action(&mut world, &mut view, user_action),
         }
         user_action = view.wait_for_user_action();
     }
 }
 
-fn normal_speed(world: &mut World, view: &mut View) -> UserAction {
+// Handles the actions that neither run the world nor change whether it is running or being
+// edited, so that they work the same while it runs as while it is paused.
+fn handle_user_action(world: &mut World, view: &mut View, user_action: UserAction) {
+    match user_action {
+        UserAction::AddCell { position } => {
+            add_cell_at(world, position);
+            view.render(world);
+        }
+        UserAction::BondSelectedCells => {
+            bond_selected_cells(world);
+            view.render(world);
+        }
+        UserAction::DebugPrint => world.debug_print_cells(),
+        UserAction::ExportSelectedCells => export_selected_cells(world),
+        UserAction::ExportWorld => {
+            let path = format!("world-{}.json", world.current_tick());
+            match save_world(world, &path) {
+                Ok(()) => info!("Exported world to {}", path),
+                Err(err) => error!("{}", err),
+            }
+        }
+        UserAction::InspectLayer {
+            cell_id,
+            layer_index,
+        } => inspect_layer(world, cell_id, layer_index),
+        UserAction::KillSelectedCells => {
+            world.kill_selected_cells();
+            view.render(world);
+        }
+        UserAction::MoveCell { cell_id, position } => {
+            world.move_cell(cell_id, position);
+            view.render(world);
+        }
+        UserAction::Screenshot => save_screenshot(world, view),
+        UserAction::SelectCellToggle { cell_id } => {
+            world.toggle_select_cell(cell_id);
+            view.render(world);
+        }
+        UserAction::SelectOnlyCell { cell_id } => {
+            world.select_only_cell(cell_id);
+            view.render(world);
+        }
+        UserAction::SelectCellsInRect {
+            min_corner,
+            max_corner,
+        } => {
+            world.select_cells_in_rect(min_corner, max_corner);
+            view.render(world);
+        }
+        UserAction::SetTunableParameter { parameter, value } => {
+            world.set_tunable_parameter(parameter, value);
+            info!("Set {:?} to {:.4}", parameter, value);
+            view.render(world);
+        }
+        UserAction::TagSelectedCells => {
+            let name = format!("selection-{}", world.current_tick());
+            world.tag_selected_cells(&name);
+            info!("Tagged selected cells {}", name);
+        }
+        UserAction::ToggleBonds => {
+            view.toggle_bonds();
+            view.render(world);
+        }
+        UserAction::ToggleGenealogyBranch { cell_id } => {
+            view.toggle_genealogy_branch(cell_id);
+            view.render(world);
+        }
+        UserAction::ToggleGenealogyPanel => {
+            view.toggle_genealogy_panel();
+            view.render(world);
+        }
+        UserAction::ToggleGussets => {
+            view.toggle_gussets();
+            view.render(world);
+        }
+        UserAction::ToggleHud => {
+            view.toggle_hud();
+            view.render(world);
+        }
+        UserAction::ToggleMotionTrails => {
+            view.toggle_motion_trails();
+            view.render(world);
+        }
+        UserAction::ToggleParameterPanel => {
+            view.toggle_parameter_panel();
+            view.render(world);
+        }
+        UserAction::ToggleParentLines => {
+            view.toggle_parent_lines();
+            view.render(world);
+        }
+        UserAction::Exit
+        | UserAction::None
+        | UserAction::PlayToggle
+        | UserAction::SingleTick
+        | UserAction::ToggleEditMode => (),
+    }
+}
+
+// Logs the layer's state and parameters, and, if its cell is selected, its recent control
+// requests. Indexes past the cell's layers, e.g. of its selection halo, are ignored.
+fn inspect_layer(world: &World, cell_id: CellId, layer_index: usize) {
+    let cell = match world.cell_with_id(cell_id) {
+        Some(cell) => cell,
+        None => return,
+    };
+    let layer = match cell.layers().get(layer_index) {
+        Some(layer) => layer,
+        None => return,
+    };
+    info!(
+        "Cell {} layer {}: area {:.4}, health {:.4}, alive {}",
+        cell_id.value(),
+        layer_index,
+        layer.area().value(),
+        layer.health(),
+        layer.is_alive()
+    );
+    info!("  {:?}", layer.to_document());
+    if !cell.is_selected() {
+        info!("  (select the cell to record its control requests)");
+        return;
+    }
+    for recorded in cell
+        .request_history()
+        .iter()
+        .filter(|recorded| recorded.request.layer_index() == layer_index)
+    {
+        info!("  age {}: {}", recorded.cell_age, recorded.request);
+    }
+}
+
+// Adds a copy of the first selected cell, or if none is selected, of the first cell, or if
+// there are no cells, a plain ball.
+fn add_cell_at(world: &mut World, position: Position) {
+    let template = world
+        .cells()
+        .iter()
+        .find(|cell| cell.is_selected())
+        .or_else(|| world.cells().first())
+        .map(|cell| cell.id());
+    match template {
+        Some(id) => {
+            world.add_copy_of_cell(id, position);
+        }
+        None => {
+            world.add_cell(Cell::ball(
+                Length::new(1.0),
+                Mass::new(1.0),
+                position,
+                Velocity::ZERO,
+            ));
+        }
+    }
+}
+
+fn bond_selected_cells(world: &mut World) {
+    let selected: Vec<CellId> = world
+        .cells()
+        .iter()
+        .filter(|cell| cell.is_selected())
+        .map(|cell| cell.id())
+        .collect();
+    if selected.len() != 2 {
+        info!("Select two cells to bond them");
+    } else if !world.bond_cells(selected[0], selected[1]) {
+        info!("Cannot bond the selected cells");
+    }
+}
+
+fn export_selected_cells(world: &World) {
+    match world.export_selected_cells() {
+        Some(document) => {
+            let path = format!("selection-{}.json", world.current_tick());
+            match fs::write(&path, document.to_json()) {
+                Ok(()) => info!("Exported {} cells to {}", document.cells.len(), path),
+                Err(err) => error!("Cannot write selection file {}: {}", path, err),
+            }
+            let requests_path = format!("selection-{}-requests.csv", world.current_tick());
+            if let Err(err) = fs::File::create(&requests_path)
+                .and_then(|mut file| write_request_history_csv(world, &mut file))
+            {
+                error!("Cannot write request file {}: {}", requests_path, err);
+            }
+        }
+        None => info!("No cells selected"),
+    }
+}
+
+// Names the file for the tick whose frame it shows.
+fn save_screenshot(world: &World, view: &View) {
+    let path = format!("frame-{:08}.png", world.current_tick());
+    match view.save_screenshot(Path::new(&path)) {
+        Ok(()) => info!("Saved screenshot {}", path),
+        Err(err) => error!("{}", err),
+    }
+}
+
+fn normal_speed(
+    world: &mut World,
+    view: &mut View,
+    watcher: &mut Option<ConfigWatcher>,
+) -> UserAction {
     let mut next_tick = Instant::now();
     loop {
         next_tick += Duration::from_millis(16);
         await_next_tick(next_tick);
 
-        if let Some(user_action) = view.check_for_user_action() {
-            if let UserAction::Exit | UserAction::PlayToggle = user_action {
+        match view.check_for_user_action() {
+            Some(
+                user_action @ (UserAction::Exit
+                | UserAction::PlayToggle
+                | UserAction::ToggleEditMode),
+            ) => {
                 return user_action;
             }
+            Some(UserAction::SingleTick) | None => (),
+            Some(user_action) => handle_user_action(world, view, user_action),
         }
 
-        single_tick(world, view);
+        reload_parameters(world, watcher);
+        if single_tick(world, view) {
+            return UserAction::None;
+        }
     }
 }
 
-fn single_tick(world: &mut World, view: &mut View) {
-    world.tick();
+// Returns whether a breakpoint was hit.
+fn single_tick(world: &mut World, view: &mut View) -> bool {
+    let report = world.tick();
     view.render(world);
+    if let Err(err) = check_blowup(world, Path::new(".")) {
+        error!("Paused: {}", err);
+        return true;
+    }
+    if let Some(hit) = report.breakpoint_hit {
+        info!(
+            "Paused at tick {}: cell {} hit breakpoint {:?}",
+            world.current_tick(),
+            hit.cell_id.value(),
+            hit.breakpoint
+        );
+        return true;
+    }
+    false
 }
 
 fn await_next_tick(next_tick: Instant) {
@@ -72,3 +1077,43 @@ fn await_next_tick(next_tick: Instant) {
         thread::sleep(next_tick - now);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evo_domain::physics::quantities::Position;
+
+    #[test]
+    fn unsampled_stats_have_a_row_per_tick() {
+        let ticks = written_stats_ticks(false, 5);
+
+        assert_eq!(ticks, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn downsampled_stats_are_dense_recently_and_sparse_long_ago() {
+        let ticks = written_stats_ticks(true, 2500);
+
+        let recent: Vec<u64> = (1501..=2500).collect();
+        assert_eq!(&ticks[..4], &[0, 100, 200, 300]);
+        assert_eq!(&ticks[15..17], &[1500, 1501]);
+        assert_eq!(&ticks[16..], &recent[..]);
+    }
+
+    // The ticks of the rows written for ticks 0 to last_tick, in order.
+    fn written_stats_ticks(downsample: bool, last_tick: u64) -> Vec<u64> {
+        let world = World::new(Position::ORIGIN, Position::new(10.0, 10.0));
+        let mut out = vec![];
+        let mut writer = StatsWriter::new(&world, downsample, &mut out).unwrap();
+        for tick in 0..=last_tick {
+            writer.write_row(&world, tick, &mut out).unwrap();
+        }
+        writer.finish(&mut out).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap().parse().unwrap())
+            .collect()
+    }
+}
@@ -0,0 +1,170 @@
+use evo_domain::world::World;
+use std::fs;
+use std::io;
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Core numbers about a tick, for monitoring long headless runs.
+#[derive(Clone, Copy, Debug)]
+pub struct TickMetrics {
+    pub tick: u64,
+    pub tick_time: Duration,
+    pub cells: usize,
+    pub bonds: usize,
+    pub cell_energy: f64,
+}
+
+impl TickMetrics {
+    pub fn from_world(world: &World, tick_time: Duration) -> Self {
+        TickMetrics {
+            tick: world.current_tick(),
+            tick_time,
+            cells: world.cells().len(),
+            bonds: world.bonds().len(),
+            cell_energy: world.cells().iter().map(|cell| cell.energy().value()).sum(),
+        }
+    }
+
+    fn gauges(&self) -> [(&'static str, f64); 5] {
+        [
+            ("tick", self.tick as f64),
+            ("tick_seconds", self.tick_time.as_secs_f64()),
+            ("cells", self.cells as f64),
+            ("bonds", self.bonds as f64),
+            ("cell_energy", self.cell_energy),
+        ]
+    }
+}
+
+pub trait MetricsSink {
+    fn report(&mut self, metrics: &TickMetrics) -> io::Result<()>;
+}
+
+// Sends the metrics as StatsD gauges, one UDP packet per report, named "<prefix>.<metric>".
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdSink {
+    pub fn new(address: &str, prefix: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(address)?;
+        Ok(StatsdSink {
+            socket,
+            prefix: prefix.to_string(),
+        })
+    }
+}
+
+impl MetricsSink for StatsdSink {
+    fn report(&mut self, metrics: &TickMetrics) -> io::Result<()> {
+        let packet: String = metrics
+            .gauges()
+            .iter()
+            .map(|(name, value)| format!("{}.{}:{}|g\n", self.prefix, name, value))
+            .collect();
+        // StatsD servers come and go; a lost packet is not worth stopping a run for.
+        let _ = self.socket.send(packet.as_bytes());
+        Ok(())
+    }
+}
+
+// Rewrites a file in the Prometheus text format, named "<prefix>_<metric>", for the node
+// exporter's textfile collector to pick up. The file is replaced in one step, so that the
+// collector never reads half of it.
+pub struct PrometheusFileSink {
+    path: PathBuf,
+    prefix: String,
+}
+
+impl PrometheusFileSink {
+    pub fn new(path: PathBuf, prefix: &str) -> Self {
+        PrometheusFileSink {
+            path,
+            prefix: prefix.to_string(),
+        }
+    }
+}
+
+impl MetricsSink for PrometheusFileSink {
+    fn report(&mut self, metrics: &TickMetrics) -> io::Result<()> {
+        let text: String = metrics
+            .gauges()
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "# TYPE {0}_{1} gauge\n{0}_{1} {2}\n",
+                    self.prefix, name, value
+                )
+            })
+            .collect();
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, text)?;
+        fs::rename(&temp_path, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    const METRICS: TickMetrics = TickMetrics {
+        tick: 7,
+        tick_time: Duration::from_millis(250),
+        cells: 3,
+        bonds: 2,
+        cell_energy: 1.5,
+    };
+
+    #[test]
+    fn statsd_sink_sends_all_gauges_in_one_packet_per_report() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        let mut sink = StatsdSink::new(&address, "evo").unwrap();
+
+        sink.report(&METRICS).unwrap();
+        sink.report(&TickMetrics { tick: 8, ..METRICS }).unwrap();
+
+        let mut buffer = [0u8; 1024];
+        let len = server.recv(&mut buffer).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buffer[..len]).unwrap(),
+            "evo.tick:7|g\nevo.tick_seconds:0.25|g\nevo.cells:3|g\nevo.bonds:2|g\n\
+             evo.cell_energy:1.5|g\n"
+        );
+        let len = server.recv(&mut buffer).unwrap();
+        assert!(std::str::from_utf8(&buffer[..len])
+            .unwrap()
+            .starts_with("evo.tick:8|g\n"));
+    }
+
+    #[test]
+    fn prometheus_file_sink_writes_a_gauge_per_metric() {
+        let dir = env::temp_dir().join(format!("evo_telemetry_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("evo.prom");
+        let mut sink = PrometheusFileSink::new(path.clone(), "evo");
+
+        sink.report(&TickMetrics { tick: 6, ..METRICS }).unwrap();
+        sink.report(&METRICS).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(!path.with_extension("tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            text,
+            "# TYPE evo_tick gauge\nevo_tick 7\n\
+             # TYPE evo_tick_seconds gauge\nevo_tick_seconds 0.25\n\
+             # TYPE evo_cells gauge\nevo_cells 3\n\
+             # TYPE evo_bonds gauge\nevo_bonds 2\n\
+             # TYPE evo_cell_energy gauge\nevo_cell_energy 1.5\n"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use evo_domain::view_model::ViewFrame;
+use std::collections::HashSet;
+
+// The characters a map uses for patches with one cell, a few cells, many cells, and any
+// selected cell.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Glyphs {
+    Ascii,
+    // Shading blocks, for terminals that can show them
+    Unicode,
+}
+
+impl Glyphs {
+    fn chars(self) -> [char; 4] {
+        match self {
+            Glyphs::Ascii => ['o', 'O', '@', '*'],
+            Glyphs::Unicode => ['░', '▒', '▓', '◆'],
+        }
+    }
+}
+
+// A coarse character map of a view frame, for terminals. Each character covers a patch of the
+// world and shows how many cell centers are in it. The map is framed by a border and has
+// north at the top.
+pub fn render_text(frame: &ViewFrame, columns: usize, rows: usize, glyphs: Glyphs) -> String {
+    const FEW_CELLS: usize = 4;
+
+    assert!(columns > 0 && rows > 0);
+    let [one, few, many, selected_char] = glyphs.chars();
+    let mut counts = vec![0_usize; columns * rows];
+    let mut selected = vec![false; columns * rows];
+    let width = frame.max_corner[0] - frame.min_corner[0];
+    let height = frame.max_corner[1] - frame.min_corner[1];
+    for cell in &frame.cells {
+        let column = ((cell.center[0] - frame.min_corner[0]) / width * columns as f64) as isize;
+        let row = ((frame.max_corner[1] - cell.center[1]) / height * rows as f64) as isize;
+        let column = column.clamp(0, columns as isize - 1) as usize;
+        let row = row.clamp(0, rows as isize - 1) as usize;
+        counts[row * columns + column] += 1;
+        selected[row * columns + column] |= cell.selected;
+    }
+
+    let border = format!("+{}+\n", "-".repeat(columns));
+    let mut text = border.clone();
+    for row in 0..rows {
+        text.push('|');
+        for column in 0..columns {
+            let index = row * columns + column;
+            text.push(match counts[index] {
+                _ if selected[index] => selected_char,
+                0 => ' ',
+                1 => one,
+                n if n <= FEW_CELLS => few,
+                _ => many,
+            });
+        }
+        text.push_str("|\n");
+    }
+    text.push_str(&border);
+    text
+}
+
+// One line of key numbers for the frame's world.
+pub fn render_stats(frame: &ViewFrame) -> String {
+    let lineages: HashSet<u32> = frame.cells.iter().map(|cell| cell.lineage).collect();
+    format!(
+        "Tick {}: {} cells, {} bonds, {} lineages, energy {:.1}",
+        frame.tick,
+        frame.cells.len(),
+        frame.bonds.len(),
+        lineages.len(),
+        frame.cells.iter().map(|cell| cell.energy).sum::<f64>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evo_domain::view_model::CellView;
+
+    #[test]
+    fn empty_map_has_a_border_around_the_requested_size() {
+        let text = render_text(&frame(vec![]), 3, 2, Glyphs::Ascii);
+
+        assert_eq!(text, "+---+\n|   |\n|   |\n+---+\n");
+    }
+
+    #[test]
+    fn cells_are_counted_in_their_patches_with_north_at_the_top() {
+        let mut cells = vec![cell(1.0, 3.0, false), cell(7.0, 1.0, false)];
+        cells.extend((0..2).map(|_| cell(3.0, 1.0, false)));
+        cells.extend((0..5).map(|_| cell(5.0, 3.0, false)));
+
+        let text = render_text(&frame(cells), 4, 2, Glyphs::Ascii);
+
+        assert_eq!(text, "+----+\n|o @ |\n| O o|\n+----+\n");
+    }
+
+    #[test]
+    fn cells_on_or_beyond_the_edges_are_put_in_the_edge_patches() {
+        let cells = vec![
+            cell(-5.0, -5.0, false),
+            cell(8.0, 4.0, false),
+            cell(20.0, 1.0, false),
+        ];
+
+        let text = render_text(&frame(cells), 4, 2, Glyphs::Ascii);
+
+        assert_eq!(text, "+----+\n|   o|\n|o  o|\n+----+\n");
+    }
+
+    #[test]
+    fn selected_cell_glyph_replaces_its_patch_count() {
+        let cells = vec![
+            cell(1.0, 3.0, false),
+            cell(1.5, 3.5, true),
+            cell(7.0, 1.0, true),
+        ];
+
+        let text = render_text(&frame(cells), 4, 2, Glyphs::Unicode);
+
+        assert_eq!(text, "+----+\n|◆   |\n|   ◆|\n+----+\n");
+    }
+
+    #[test]
+    fn stats_line_counts_cells_bonds_and_lineages_and_sums_energy() {
+        let mut cells = vec![
+            cell(1.0, 1.0, false),
+            cell(2.0, 1.0, false),
+            cell(3.0, 1.0, false),
+        ];
+        for (cell, (lineage, energy)) in cells.iter_mut().zip(vec![(1, 1.25), (2, 2.5), (1, 0.3)]) {
+            cell.lineage = lineage;
+            cell.energy = energy;
+        }
+        let mut frame = frame(cells);
+        frame.tick = 12;
+        frame.bonds = vec![[0, 1]];
+
+        assert_eq!(
+            render_stats(&frame),
+            "Tick 12: 3 cells, 1 bonds, 2 lineages, energy 4.0"
+        );
+    }
+
+    fn frame(cells: Vec<CellView>) -> ViewFrame {
+        ViewFrame {
+            version: ViewFrame::VERSION,
+            tick: 0,
+            min_corner: [0.0, 0.0],
+            max_corner: [8.0, 4.0],
+            walls: vec![],
+            cells,
+            bonds: vec![],
+        }
+    }
+
+    fn cell(x: f64, y: f64, selected: bool) -> CellView {
+        CellView {
+            id: 0,
+            center: [x, y],
+            half_axis: [0.0, 0.0],
+            velocity: [0.0, 0.0],
+            energy: 0.0,
+            lineage: 0,
+            selected,
+            layers: vec![],
+        }
+    }
+}
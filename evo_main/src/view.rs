@@ -1,19 +1,22 @@
+use evo_domain::biology::cell::CellId;
 use evo_domain::physics::quantities::*;
 use evo_domain::world::World;
 use evo_domain::UserAction;
-use evo_glium::GliumView;
+use evo_glium::{GliumView, Keymap};
+use std::path::Path;
 
 pub struct View {
     view: GliumView,
 }
 
 impl View {
-    pub fn new(world_min_corner: Position, world_max_corner: Position) -> Self {
+    pub fn new(world_min_corner: Position, world_max_corner: Position, keymap: Keymap) -> Self {
         View {
             view: GliumView::new(
                 [world_min_corner.x() as f32, world_min_corner.y() as f32],
                 [world_max_corner.x() as f32, world_max_corner.y() as f32],
-            ),
+            )
+            .with_keymap(keymap),
         }
     }
 
@@ -28,4 +31,44 @@ impl View {
     pub fn render(&mut self, world: &World) {
         self.view.render(world);
     }
+
+    pub fn save_screenshot(&self, path: &Path) -> Result<(), String> {
+        self.view.save_screenshot(path)
+    }
+
+    pub fn toggle_parameter_panel(&mut self) {
+        self.view.toggle_parameter_panel();
+    }
+
+    pub fn toggle_genealogy_panel(&mut self) {
+        self.view.toggle_genealogy_panel();
+    }
+
+    pub fn toggle_genealogy_branch(&mut self, cell_id: CellId) {
+        self.view.toggle_genealogy_branch(cell_id);
+    }
+
+    pub fn set_edit_mode(&mut self, edit_mode: bool) {
+        self.view.set_edit_mode(edit_mode);
+    }
+
+    pub fn toggle_hud(&mut self) {
+        self.view.toggle_hud();
+    }
+
+    pub fn toggle_motion_trails(&mut self) {
+        self.view.toggle_motion_trails();
+    }
+
+    pub fn toggle_parent_lines(&mut self) {
+        self.view.toggle_parent_lines();
+    }
+
+    pub fn toggle_bonds(&mut self) {
+        self.view.toggle_bonds();
+    }
+
+    pub fn toggle_gussets(&mut self) {
+        self.view.toggle_gussets();
+    }
 }